@@ -0,0 +1,1207 @@
+#include "admin.h"
+#include "cli.h"
+#include "webui_json.h"
+#include "prometheus.h"
+#include "upstream.h"
+#include "utils.h"
+#include "logger.h"
+#include "audit.h"
+#include "redact.h"
+#include <sstream>
+#include <algorithm>
+#include <chrono>
+#include <map>
+#include <set>
+#include <fstream>
+#include <ctime>
+#include <deque>
+#include <condition_variable>
+#include <cctype>
+#include <cstdlib>
+
+using namespace network;
+
+// Largest request or response accepted (headers and body)
+static const size_t MAX_MESSAGE_BYTES = 1024 * 1024;
+
+// Connections served at once; more are closed as soon as they are accepted
+static const size_t MAX_CONNECTIONS = 64;
+
+// GET /events: concurrent clients, events buffered per client before it counts as behind,
+// and how often an idle stream gets a comment line (which also notices clients that left)
+static const size_t MAX_EVENT_STREAMS = 8;
+static const size_t MAX_QUEUED_EVENTS = 1000;
+static const int EVENT_KEEPALIVE_SECS = 15;
+
+struct AdminServer::EventStream {
+    socket_t sock;
+    std::deque<std::string> queue; // Formatted events waiting to be sent
+    size_t dropped; // Events lost to a full queue since the last send
+    bool closing; // Asked to stop
+    bool finished; // Its thread no longer uses the socket
+    std::condition_variable wake;
+    std::thread thread;
+    
+    explicit EventStream(socket_t stream_sock) : sock(stream_sock), dropped(0), closing(false), finished(false) {}
+};
+
+static std::string state_name(RunwayState state) {
+    switch (state) {
+        case RunwayState::Unknown: return "unknown";
+        case RunwayState::Accessible: return "accessible";
+        case RunwayState::PartiallyAccessible: return "partially_accessible";
+        case RunwayState::Inaccessible: return "inaccessible";
+        case RunwayState::Testing: return "testing";
+    }
+    return "unknown";
+}
+
+static void set_timeouts(socket_t sock, long secs) {
+    struct timeval timeout;
+    timeout.tv_sec = secs;
+    timeout.tv_usec = 0;
+#ifdef _WIN32
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+#else
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
+#endif
+}
+
+// Read one HTTP message: headers, then Content-Length bytes of body; if absent, a request
+// has no body and a response's body ends at close
+static bool read_message(socket_t sock, std::string& head, std::string& body, bool is_request) {
+    std::string data;
+    char buffer[4096];
+    size_t header_end = std::string::npos;
+    while (header_end == std::string::npos) {
+        ssize_t received = recv_data(sock, buffer, sizeof(buffer));
+        if (received <= 0 || data.size() + static_cast<size_t>(received) > MAX_MESSAGE_BYTES) {
+            return false;
+        }
+        data.append(buffer, static_cast<size_t>(received));
+        header_end = data.find("\r\n\r\n");
+    }
+    head = data.substr(0, header_end);
+    body = data.substr(header_end + 4);
+    
+    // RFC 7230 Section 3.3.3 - message body length
+    bool has_length = false;
+    uint64_t content_length = 0;
+    for (const auto& line : utils::split(head, '\n')) {
+        size_t colon = line.find(':');
+        if (colon != std::string::npos && utils::to_lower(utils::trim(line.substr(0, colon))) == "content-length") {
+            has_length = utils::safe_str_to_uint64(utils::trim(line.substr(colon + 1)), content_length);
+        }
+    }
+    if (has_length && content_length > MAX_MESSAGE_BYTES) {
+        return false;
+    }
+    if (!has_length && is_request) {
+        body.clear();
+        return true;
+    }
+    
+    while (!has_length || body.size() < content_length) {
+        ssize_t received = recv_data(sock, buffer, sizeof(buffer));
+        if (received <= 0) {
+            return !has_length; // Without Content-Length the body ends at close
+        }
+        if (body.size() + static_cast<size_t>(received) > MAX_MESSAGE_BYTES) {
+            return false;
+        }
+        body.append(buffer, static_cast<size_t>(received));
+    }
+    body.resize(static_cast<size_t>(content_length));
+    return true;
+}
+
+AdminServer::AdminServer(std::shared_ptr<RunwayManager> runway_manager,
+                         std::shared_ptr<RoutingEngine> routing_engine,
+                         std::shared_ptr<TargetAccessibilityTracker> tracker,
+                         std::shared_ptr<HealthMonitor> health_monitor,
+                         std::shared_ptr<ProxyServer> proxy_server,
+                         const Config& config,
+                         const std::string& config_path)
+    : runway_manager_(runway_manager)
+    , routing_engine_(routing_engine)
+    , tracker_(tracker)
+    , health_monitor_(health_monitor)
+    , proxy_server_(proxy_server)
+    , config_(config)
+    , config_path_(config_path)
+    , running_(false)
+    , listen_socket_(INVALID_SOCKET_VALUE)
+    , health_listener_id_(0)
+    , request_listener_id_(0) {
+}
+
+AdminServer::~AdminServer() {
+    stop();
+}
+
+bool AdminServer::start() {
+    if (running_) {
+        return false;
+    }
+    
+    if (!config_.admin_tls_cert_path.empty()) {
+        tls::ServerOptions options;
+        options.cert_path = config_.admin_tls_cert_path;
+        options.key_path = config_.admin_tls_key_path;
+        options.client_ca_path = config_.admin_tls_client_ca_path;
+        std::string error;
+        tls_context_ = tls::create_server_context(options, error);
+        if (!tls_context_) {
+            Logger::instance().log(LogLevel::ERROR_LEVEL, "Cannot serve TLS on the admin endpoint: " + error);
+            return false;
+        }
+    }
+    
+    listen_socket_ = create_tcp_socket();
+    if (listen_socket_ == INVALID_SOCKET_VALUE) {
+        return false;
+    }
+    
+    set_socket_option(listen_socket_, SOL_SOCKET, SO_REUSEADDR, 1);
+    
+    if (!bind_socket(listen_socket_, config_.admin_listen_host, config_.admin_listen_port) ||
+        !listen_socket(listen_socket_, 16)) {
+        close_socket(listen_socket_);
+        listen_socket_ = INVALID_SOCKET_VALUE;
+        return false;
+    }
+    
+    running_ = true;
+    
+    // Feed GET /events
+    using namespace webui_json;
+    auto now = []() { return encode_int(static_cast<int64_t>(std::time(nullptr))); };
+    if (health_monitor_) {
+        health_listener_id_ = health_monitor_->add_listener([this, now](const HealthEvent& event) {
+            if (event.type == HealthEvent::Type::StateChanged) {
+                publish("state", build_object({{"type", encode_string("state")},
+                                               {"time", now()},
+                                               {"source", encode_string("probe")},
+                                               {"target", encode_string(event.target)},
+                                               {"runway_id", encode_string(event.runway_id)},
+                                               {"previous_state", encode_string(state_name(event.previous_state))},
+                                               {"state", encode_string(state_name(event.state))}}));
+            } else {
+                publish("probe", build_object({{"type", encode_string("probe")},
+                                               {"time", now()},
+                                               {"target", encode_string(event.target)},
+                                               {"runway_id", encode_string(event.runway_id)},
+                                               {"network_success", encode_bool(event.network_success)},
+                                               {"user_success", encode_bool(event.user_success)},
+                                               {"response_time_ms", encode_number(event.response_time * 1000.0)},
+                                               {"state", encode_string(state_name(event.state))}}));
+            }
+        });
+    }
+    if (proxy_server_) {
+        request_listener_id_ = proxy_server_->add_listener([this, now](const RequestEvent& event) {
+            if (event.type == RequestEvent::Type::StateChanged) {
+                publish("state", build_object({{"type", encode_string("state")},
+                                               {"time", now()},
+                                               {"source", encode_string("request")},
+                                               {"request_id", encode_string(event.request_id)},
+                                               {"target", encode_string(event.target)},
+                                               {"runway_id", encode_string(event.runway_id)},
+                                               {"previous_state", encode_string(state_name(event.previous_state))},
+                                               {"state", encode_string(state_name(event.state))}}));
+                return;
+            }
+            std::vector<std::pair<std::string, std::string>> fields = {
+                {"type", encode_string("request")},
+                {"time", now()},
+                {"request_id", encode_string(event.request_id)},
+                {"target", encode_string(event.target)},
+                {"target_port", encode_int(event.target_port)},
+                {"runway_id", event.runway_id.empty() ? encode_null() : encode_string(event.runway_id)},
+                {"method", encode_string(event.method)},
+                {"status_code", encode_int(event.status_code)},
+                {"success", encode_bool(event.success)},
+                {"duration_ms", encode_number(event.duration * 1000.0)}
+            };
+            if (!event.error.empty()) {
+                fields.push_back({"error", encode_string(event.error)});
+            }
+            publish("request", build_object(fields));
+        });
+    }
+    
+    server_thread_ = std::thread(&AdminServer::server_loop, this);
+    return true;
+}
+
+void AdminServer::stop() {
+    if (!running_) {
+        return;
+    }
+    
+    running_ = false;
+    
+    if (health_monitor_) {
+        health_monitor_->remove_listener(health_listener_id_);
+    }
+    if (proxy_server_) {
+        proxy_server_->remove_listener(request_listener_id_);
+    }
+    
+    if (listen_socket_ != INVALID_SOCKET_VALUE) {
+        shutdown_socket(listen_socket_); // Wake server_loop out of accept
+        close_socket(listen_socket_);
+        listen_socket_ = INVALID_SOCKET_VALUE;
+    }
+    
+    if (server_thread_.joinable()) {
+        server_thread_.join();
+    }
+    
+    // Wake connections waiting on slow clients and let them finish
+    {
+        std::unique_lock<std::mutex> lock(connections_mutex_);
+        for (socket_t client_sock : connections_) {
+            shutdown_socket(client_sock);
+        }
+        connections_done_.wait(lock, [this]() { return connections_.empty(); });
+    }
+    close_event_streams(false);
+}
+
+void AdminServer::server_loop() {
+    while (running_) {
+        std::string client_ip;
+        uint16_t client_port;
+        socket_t client_sock = accept_connection(listen_socket_, client_ip, client_port);
+        
+        if (client_sock == INVALID_SOCKET_VALUE) {
+            if (running_) {
+                std::this_thread::sleep_for(std::chrono::milliseconds(100));
+            }
+            continue;
+        }
+        
+        // A thread per connection, so a slow client cannot hold up /healthz and /readyz;
+        // runtime changes stay serialized by reload_mutex_ and CLI commands by command_mutex_
+        {
+            std::lock_guard<std::mutex> lock(connections_mutex_);
+            if (connections_.size() >= MAX_CONNECTIONS) {
+                close_socket(client_sock);
+                continue;
+            }
+            connections_.insert(client_sock);
+        }
+        std::thread(&AdminServer::serve_connection, this, client_sock, client_ip).detach();
+    }
+}
+
+void AdminServer::serve_connection(socket_t client_sock, const std::string& client_ip) {
+    set_timeouts(client_sock, 10);
+    std::string tls_error;
+    bool handed_off = false;
+    if (tls_context_ && !tls::accept(client_sock, *tls_context_, tls_error)) {
+        // Covers clients without a trusted certificate when admin_tls_client_ca_path is set
+        Logger::instance().log(LogLevel::WARN, "Rejected admin connection from " + client_ip +
+                               ": TLS handshake failed: " + tls_error);
+    } else {
+        handed_off = handle_connection(client_sock, client_ip);
+    }
+    
+    {
+        std::lock_guard<std::mutex> lock(connections_mutex_);
+        connections_.erase(client_sock);
+        connections_done_.notify_all();
+    }
+    // Closed only once stop() can no longer shut it down, so it never touches a reused descriptor
+    if (!handed_off) {
+        close_socket(client_sock);
+    }
+}
+
+// Value of the header name (case-insensitive) in an HTTP head; empty if absent
+static std::string header_value(const std::string& head, const std::string& name) {
+    for (const auto& line : utils::split(head, '\n')) {
+        size_t colon_pos = line.find(':');
+        if (colon_pos != std::string::npos && utils::to_lower(utils::trim(line.substr(0, colon_pos))) == name) {
+            return utils::trim(line.substr(colon_pos + 1));
+        }
+    }
+    return "";
+}
+
+// Who a request's changes are attributed to: "<user>@<address>", with the user named by the client's
+// verified certificate, else the one the client names in USER_HEADER (not verified; a value that is
+// not a plain login name is ignored), or just the address
+static std::string caller(const std::string& head, const std::string& client_ip, const std::string& cert_name) {
+    if (!cert_name.empty()) {
+        return cert_name + "@" + client_ip;
+    }
+    std::string user = header_value(head, utils::to_lower(admin::USER_HEADER));
+    bool plain = !user.empty() && user.size() <= 64;
+    for (char c : user) {
+        plain = plain && (std::isalnum(static_cast<unsigned char>(c)) || c == '-' || c == '_' || c == '.');
+    }
+    return plain ? user + "@" + client_ip : client_ip;
+}
+
+// Why a request may have been sent by a web page rather than a CLI or script, or empty if it was not.
+// Browsers add Origin to cross-site requests, and a page can only send a JSON Content-Type after a CORS
+// preflight (which this endpoint never answers); a Host that names neither an address, localhost nor
+// listen_host is a DNS name pointed here (DNS rebinding)
+static std::string forgery_reason(const std::string& method, const std::string& head, const std::string& listen_host) {
+    if (!header_value(head, "origin").empty()) {
+        return "requests from web pages (with an Origin header) are not accepted";
+    }
+    // "host", "host:port", "[v6]" or "[v6]:port"
+    std::string host = utils::to_lower(header_value(head, "host"));
+    if (!host.empty() && host[0] == '[') {
+        host = host.substr(1, host.find(']') - 1);
+    } else if (std::count(host.begin(), host.end(), ':') == 1) {
+        host = host.substr(0, host.find(':'));
+    }
+    if (!host.empty() && host != "localhost" && host != utils::to_lower(listen_host) &&
+        !utils::is_valid_ipv4(host) && !is_ipv6_address(host)) {
+        return "Host " + host + " does not name this endpoint";
+    }
+    std::string content_type = utils::to_lower(header_value(head, "content-type"));
+    if (method == "POST" && content_type.compare(0, 16, "application/json") != 0) {
+        return "POST needs Content-Type: application/json";
+    }
+    return "";
+}
+
+// HTTP status of a reload() result: 400 when the file was rejected
+static int reload_status(const std::string& result) {
+    std::map<std::string, std::string> reply;
+    size_t pos = 0;
+    return Config::parse_object(result, pos, reply) && utils::trim(reply["reloaded"]) == "true" ? 200 : 400;
+}
+
+bool AdminServer::handle_connection(socket_t client_sock, const std::string& client_ip) {
+    std::string head;
+    std::string body;
+    std::string response;
+    
+    // Request line: "POST /reload HTTP/1.1"
+    std::vector<std::string> request_line;
+    if (read_message(client_sock, head, body, true)) {
+        request_line = utils::split(head.substr(0, head.find('\r')), ' ');
+    }
+    audit::Actor actor(request_line.size() >= 2 && request_line[1] == "/cli" ? "cli" : "api", caller(head, client_ip, tls::peer_name(client_sock)));
+    // One check for every route: admin_listen_host is fixed at startup
+    std::string forgery = request_line.size() >= 2 ? forgery_reason(request_line[0], head, config_.admin_listen_host) : "";
+    
+    if (request_line.size() < 2) {
+        response = build_http_response(400, webui_json::build_object({{"error", webui_json::encode_string("bad request")}}));
+    } else if (!forgery.empty()) {
+        Logger::instance().log(LogLevel::WARN, "Rejected admin request " + request_line[0] + " " +
+                               request_line[1].substr(0, request_line[1].find('?')) + " from " + client_ip + ": " + forgery);
+        response = build_http_response(403, webui_json::build_object({{"error", webui_json::encode_string(forgery)}}));
+    } else if (request_line[1] != "/healthz" && request_line[1] != "/readyz" && !authorized(head)) {
+        Logger::instance().log(LogLevel::WARN, "Rejected admin request without a valid token: " + request_line[0] + " " +
+                               request_line[1].substr(0, request_line[1].find('?')));
+        response = build_http_response(401, webui_json::build_object({{"error", webui_json::encode_string("missing or invalid bearer token")}}));
+    } else if (request_line[1] == "/metrics") {
+        if (request_line[0] != "GET") {
+            response = build_http_response(405, webui_json::build_object({{"error", webui_json::encode_string("use GET")}}));
+        } else {
+            size_t max_targets = 0;
+            {
+                std::lock_guard<std::mutex> lock(reload_mutex_);
+                max_targets = config_.metrics_max_targets;
+            }
+            response = build_http_response(200, prometheus::render(*runway_manager_, *tracker_, max_targets),
+                                           "text/plain; version=0.0.4");
+        }
+    } else if (request_line[1] == "/events") {
+        if (request_line[0] != "GET") {
+            response = build_http_response(405, webui_json::build_object({{"error", webui_json::encode_string("use GET")}}));
+        } else if (open_event_stream(client_sock)) {
+            return true;
+        } else {
+            response = build_http_response(503, webui_json::build_object({{"error", webui_json::encode_string(
+                "too many event streams (at most " + std::to_string(MAX_EVENT_STREAMS) + ")")}}));
+        }
+    } else if (request_line[1] == "/healthz" || request_line[1] == "/readyz") {
+        if (request_line[0] != "GET") {
+            response = build_http_response(405, webui_json::build_object({{"error", webui_json::encode_string("use GET")}}));
+        } else if (request_line[1] == "/healthz") {
+            response = build_http_response(200, webui_json::build_object({{"status", webui_json::encode_string("ok")}}));
+        } else {
+            bool ready = false;
+            std::string reply = readiness(ready);
+            response = build_http_response(ready ? 200 : 503, reply);
+        }
+    } else if (request_line[1].compare(0, 5, "/api/") == 0) {
+        int status = 200;
+        std::string path = request_line[1].substr(0, request_line[1].find('?'));
+        std::string reply = handle_api(request_line[0], path, body, status);
+        response = build_http_response(status, reply);
+    } else if (request_line[1] == "/reload" || request_line[1] == "/cli") {
+        if (request_line[0] != "POST") {
+            response = build_http_response(405, webui_json::build_object({{"error", webui_json::encode_string("use POST")}}));
+        } else if (request_line[1] == "/reload") {
+            std::string result = reload();
+            response = build_http_response(reload_status(result), result);
+        } else {
+            std::vector<std::string> args;
+            if (Config::parse_string_array(body, args)) {
+                response = build_http_response(200, run_cli(args));
+            } else {
+                response = build_http_response(400, webui_json::build_object({{"error", webui_json::encode_string("expected a JSON array of arguments")}}));
+            }
+        }
+    } else {
+        response = build_http_response(404, webui_json::build_object({{"error", webui_json::encode_string("not found")}}));
+    }
+    
+    send_data(client_sock, response.data(), response.size());
+    return false;
+}
+
+void AdminServer::publish(const std::string& type, const std::string& data) {
+    // Server-Sent Events framing (one data line: the JSON has no raw newlines)
+    std::string message = "event: " + type + "\ndata: " + data + "\n\n";
+    std::lock_guard<std::mutex> lock(streams_mutex_);
+    for (const auto& stream : streams_) {
+        if (stream->closing || stream->finished) {
+            continue;
+        }
+        if (stream->queue.size() >= MAX_QUEUED_EVENTS) {
+            stream->dropped++;
+            continue;
+        }
+        stream->queue.push_back(message);
+        stream->wake.notify_one();
+    }
+}
+
+bool AdminServer::open_event_stream(socket_t client_sock) {
+    close_event_streams(true);
+    
+    std::lock_guard<std::mutex> lock(streams_mutex_);
+    if (streams_.size() >= MAX_EVENT_STREAMS) {
+        return false;
+    }
+    
+    // No Content-Length: the body is the stream, ended by either side closing the connection
+    std::string head = "HTTP/1.1 200 OK\r\n"
+                       "Content-Type: text/event-stream\r\n"
+                       "Cache-Control: no-cache\r\n"
+                       "Connection: close\r\n"
+                       "\r\n"
+                       ": connected\n\n";
+    send_data(client_sock, head.data(), head.size());
+    
+    auto stream = std::make_shared<EventStream>(client_sock);
+    stream->thread = std::thread(&AdminServer::stream_events, this, stream);
+    streams_.push_back(stream);
+    return true;
+}
+
+void AdminServer::stream_events(std::shared_ptr<EventStream> stream) {
+    std::unique_lock<std::mutex> lock(streams_mutex_);
+    while (!stream->closing) {
+        stream->wake.wait_for(lock, std::chrono::seconds(EVENT_KEEPALIVE_SECS),
+                              [&stream]() { return stream->closing || !stream->queue.empty(); });
+        if (stream->closing) {
+            break;
+        }
+        
+        std::string data;
+        if (stream->dropped > 0) {
+            data += "event: dropped\ndata: " + webui_json::build_object({
+                {"type", webui_json::encode_string("dropped")},
+                {"count", webui_json::encode_int(static_cast<int64_t>(stream->dropped))}}) + "\n\n";
+            stream->dropped = 0;
+        }
+        while (!stream->queue.empty()) {
+            data += stream->queue.front();
+            stream->queue.pop_front();
+        }
+        if (data.empty()) {
+            data = ": keepalive\n\n";
+        }
+        
+        // Send without the lock, so a slow client does not hold up publishers
+        lock.unlock();
+        bool sent = send_data(stream->sock, data.data(), data.size()) == static_cast<ssize_t>(data.size());
+        lock.lock();
+        if (!sent) {
+            break; // The client left (or stopped reading for longer than the send timeout)
+        }
+    }
+    stream->finished = true;
+}
+
+void AdminServer::close_event_streams(bool finished_only) {
+    std::vector<std::shared_ptr<EventStream>> closed;
+    {
+        std::lock_guard<std::mutex> lock(streams_mutex_);
+        for (auto it = streams_.begin(); it != streams_.end();) {
+            if (!finished_only || (*it)->finished) {
+                (*it)->closing = true;
+                (*it)->wake.notify_one();
+                shutdown_socket((*it)->sock); // Wake a thread blocked in send
+                closed.push_back(*it);
+                it = streams_.erase(it);
+            } else {
+                ++it;
+            }
+        }
+    }
+    for (const auto& stream : closed) {
+        if (stream->thread.joinable()) {
+            stream->thread.join();
+        }
+        close_socket(stream->sock);
+    }
+}
+
+std::string AdminServer::handle_api(const std::string& method, const std::string& path,
+                                    const std::string& body, int& status) {
+    auto error = [&status](int code, const std::string& message) {
+        status = code;
+        return webui_json::build_object({{"error", webui_json::encode_string(message)}});
+    };
+    
+    if (path == "/api/audit") {
+        if (method != "GET") {
+            return error(405, "use GET");
+        }
+        return webui_json::build_object({{"entries", webui_json::build_array(audit::recent())}});
+    }
+    
+    // Read-only views are GET, everything that changes state is POST
+    bool read_only = path == "/api/status" || path == "/api/runways" || path == "/api/targets" || path == "/api/stats";
+    if (!read_only && path != "/api/mode" && path != "/api/reload" && path != "/api/reset") {
+        return error(404, "not found");
+    }
+    if (read_only && method != "GET") {
+        return error(405, "use GET");
+    }
+    if (!read_only && path != "/api/mode" && method != "POST") {
+        return error(405, "use POST");
+    }
+    if (path == "/api/mode" && method != "GET" && method != "POST") {
+        return error(405, "use GET or POST");
+    }
+    
+    if (path == "/api/reload") {
+        std::string result = reload();
+        status = reload_status(result);
+        return result;
+    }
+    
+    // The body of POST /api/mode and /api/reset is an optional JSON object
+    std::map<std::string, std::string> fields;
+    size_t pos = 0;
+    if (method == "POST" && !utils::trim(body).empty() && !Config::parse_object(body, pos, fields)) {
+        return error(400, "expected a JSON object");
+    }
+    auto string_field = [&fields](const std::string& name) {
+        std::string value;
+        size_t value_pos = 0;
+        auto it = fields.find(name);
+        if (it != fields.end()) {
+            Config::parse_string(utils::trim(it->second), value_pos, value);
+        }
+        return value;
+    };
+    
+    std::vector<std::string> args;
+    if (read_only) {
+        args = {path.substr(5)};
+    } else if (path == "/api/mode" && method == "GET") {
+        args = {"status"};
+    } else if (path == "/api/mode") {
+        std::string mode = string_field("mode");
+        if (mode.empty()) {
+            return error(400, "expected {\"mode\": \"latency|first_accessible|round_robin\"}");
+        }
+        args = {"mode", mode};
+        if (utils::trim(fields["persist"]) == "true") {
+            args.push_back("--persist");
+        }
+    } else {
+        args = {"reset-stats"};
+        std::string target = string_field("target");
+        if (!target.empty()) {
+            args.push_back(target);
+        }
+    }
+    args.push_back("--json");
+    
+    std::string output;
+    if (execute(args, output) != 0) {
+        // "Error: <message>" lines
+        std::string message = utils::trim(output);
+        if (message.compare(0, 7, "Error: ") == 0) {
+            message = message.substr(7);
+        }
+        return error(400, message);
+    }
+    
+    if (path == "/api/mode" && method == "GET") {
+        std::map<std::string, std::string> reply;
+        pos = 0;
+        Config::parse_object(output, pos, reply);
+        return webui_json::build_object({{"routing_mode", utils::trim(reply["routing_mode"])}});
+    }
+    return utils::trim(output);
+}
+
+bool AdminServer::authorized(const std::string& head) {
+    std::string token;
+    {
+        std::lock_guard<std::mutex> lock(reload_mutex_);
+        token = config_.admin_token;
+    }
+    if (token.empty()) {
+        return true;
+    }
+    
+    std::string presented;
+    std::string value = header_value(head, "authorization");
+    if (utils::to_lower(value.substr(0, 7)) == "bearer ") {
+        presented = utils::trim(value.substr(7));
+    }
+    
+    // Compare in constant time, so response timing does not reveal how much of a guess was right
+    unsigned char difference = presented.size() == token.size() ? 0 : 1;
+    for (size_t i = 0; i < token.size(); ++i) {
+        difference |= static_cast<unsigned char>(token[i] ^ (i < presented.size() ? presented[i] : 0));
+    }
+    return difference == 0;
+}
+
+std::string AdminServer::readiness(bool& ready) {
+    std::string check;
+    uint64_t window_secs = 0;
+    {
+        std::lock_guard<std::mutex> lock(reload_mutex_);
+        check = config_.readiness_check;
+        window_secs = config_.readiness_window_secs;
+    }
+    
+    // Disabled runways carry no traffic, so they do not count
+    std::set<std::string> enabled_ids;
+    for (const auto& runway : runway_manager_->get_all_runways()) {
+        enabled_ids.insert(runway->id);
+    }
+    
+    std::string reason;
+    if (enabled_ids.empty()) {
+        ready = false;
+        reason = "no usable runways were discovered";
+    } else if (check == "runways") {
+        ready = true;
+        reason = std::to_string(enabled_ids.size()) + " runways discovered";
+    } else {
+        // A target is recent if a request or probe touched it within the window
+        uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+        size_t recent = 0;
+        size_t reachable = 0;
+        for (const auto& target_pair : tracker_->snapshot()) {
+            bool is_recent = false;
+            bool is_reachable = false;
+            for (const auto& runway_pair : target_pair.second) {
+                is_recent = is_recent || runway_pair.second.last_used + window_secs >= now;
+                is_reachable = is_reachable || (runway_pair.second.state == RunwayState::Accessible &&
+                                                enabled_ids.count(runway_pair.first) > 0);
+            }
+            if (is_recent) {
+                recent++;
+                if (is_reachable) {
+                    reachable++;
+                }
+            }
+        }
+        // Nothing to judge by yet (fresh start, idle service): discovery alone decides
+        if (recent == 0) {
+            ready = true;
+            reason = "no recent targets; " + std::to_string(enabled_ids.size()) + " runways discovered";
+        } else {
+            ready = reachable > 0;
+            reason = std::to_string(reachable) + " of " + std::to_string(recent) +
+                     " recent targets have an accessible runway";
+        }
+    }
+    
+    using namespace webui_json;
+    return build_object({{"ready", ready ? "true" : "false"},
+                         {"check", encode_string(check)},
+                         {"reason", encode_string(reason)}});
+}
+
+std::string AdminServer::build_http_response(int status_code, const std::string& body,
+                                             const std::string& content_type) {
+    std::string status_text;
+    switch (status_code) {
+        case 200: status_text = "OK"; break;
+        case 400: status_text = "Bad Request"; break;
+        case 401: status_text = "Unauthorized"; break;
+        case 404: status_text = "Not Found"; break;
+        case 405: status_text = "Method Not Allowed"; break;
+        case 503: status_text = "Service Unavailable"; break;
+        default: status_text = "Internal Server Error"; break;
+    }
+    
+    std::ostringstream oss;
+    oss << "HTTP/1.1 " << status_code << " " << status_text << "\r\n";
+    oss << "Content-Type: " << content_type << "\r\n";
+    if (status_code == 401) {
+        oss << "WWW-Authenticate: Bearer realm=\"smartproxy\"\r\n";
+    }
+    oss << "Content-Length: " << body.length() << "\r\n";
+    oss << "Connection: close\r\n";
+    oss << "\r\n";
+    oss << body;
+    return oss.str();
+}
+
+const std::vector<std::string> AdminServer::LIVE_SETTINGS = {
+    "routing_mode", "success_rate_threshold", "proxy_failure_threshold",
+    "dns_timeout", "health_probe_timeout", "health_check_concurrency"
+};
+
+// A setting's JSON value as the audit log shows it: strings without their quotes, anything else as is
+static std::string audit_text(const std::string& json_value) {
+    std::string text;
+    size_t pos = 0;
+    return Config::parse_string(json_value, pos, text) ? text : json_value;
+}
+
+// Value of key in config as the audit log shows it
+static std::string setting_text(const Config& config, const std::string& key) {
+    std::map<std::string, std::string> settings;
+    size_t pos = 0;
+    Config::parse_object(config.to_json(), pos, settings);
+    return audit_text(utils::trim(settings[key]));
+}
+
+std::string AdminServer::reload() {
+    using namespace webui_json;
+    std::lock_guard<std::mutex> lock(reload_mutex_);
+    
+    if (!utils::file_exists(config_path_)) {
+        return build_object({{"reloaded", "false"},
+                             {"errors", build_array({encode_string(config_path_ + " not found")})}});
+    }
+    
+    // A file that does not parse would load as the defaults; reject it instead
+    std::ifstream file(config_path_, std::ios::binary);
+    std::stringstream contents;
+    contents << file.rdbuf();
+    std::map<std::string, std::string> new_values;
+    size_t pos = 0;
+    if (!Config::parse_object(contents.str(), pos, new_values)) {
+        Logger::instance().log(LogLevel::WARN, "Reload rejected: " + config_path_ + " is not valid JSON");
+        return build_object({{"reloaded", "false"},
+                             {"errors", build_array({encode_string(config_path_ + " is not valid JSON")})}});
+    }
+    Config fresh = Config::parse_json(contents.str());
+    std::vector<std::string> errors = fresh.validate();
+    if (!errors.empty()) {
+        std::vector<std::string> values;
+        for (const auto& error : errors) {
+            values.push_back(encode_string(error));
+        }
+        Logger::instance().log(LogLevel::WARN, "Reload rejected: " + errors.front());
+        return build_object({{"reloaded", "false"}, {"errors", build_array(values)}});
+    }
+    
+    // Settings as they were, as the file has them, and as they are once the live ones are applied
+    Config running = config_;
+    running.routing_mode = routing_engine_->get_mode();
+    std::map<std::string, std::string> old_values;
+    std::map<std::string, std::string> applied_values;
+    pos = 0;
+    Config::parse_object(running.to_json(), pos, old_values);
+    new_values.clear();
+    pos = 0;
+    Config::parse_object(fresh.to_json(), pos, new_values);
+    
+    bool restart_required = false;
+    std::vector<std::string> changes = apply(fresh, restart_required);
+    pos = 0;
+    Config::parse_object(config_.to_json(), pos, applied_values);
+    
+    std::vector<std::string> diff;
+    for (const auto& pair : new_values) {
+        std::string old_value = utils::trim(old_values[pair.first]);
+        std::string new_value = utils::trim(pair.second);
+        if (old_value == new_value) {
+            continue;
+        }
+        // Secrets are never echoed: admin_token is masked outright, upstream_proxies field by field
+        // (a changed password still shows as a change, with both sides masked)
+        if (redact::is_sensitive(pair.first)) {
+            old_value = encode_string(old_value == "\"\"" ? "" : redact::MASK);
+            new_value = encode_string(new_value == "\"\"" ? "" : redact::MASK);
+        } else {
+            old_value = redact::json(old_value);
+            new_value = redact::json(new_value);
+        }
+        bool applied = utils::trim(applied_values[pair.first]) == utils::trim(pair.second);
+        diff.push_back(build_object({{"setting", encode_string(pair.first)},
+                                     {"old", old_value},
+                                     {"new", new_value},
+                                     {"applied", encode_bool(applied)}}));
+        if (applied) {
+            audit::record("reload", pair.first, audit_text(old_value), audit_text(new_value));
+        }
+    }
+    
+    Logger::instance().log(LogLevel::INFO, "Configuration reloaded (" + std::to_string(changes.size()) + " changes" +
+                           (restart_required ? ", other settings need a restart)" : ")"));
+    
+    std::vector<std::string> values;
+    for (const auto& change : changes) {
+        values.push_back(encode_string(change));
+    }
+    return build_object({{"reloaded", "true"},
+                         {"changes", build_array(values)},
+                         {"diff", build_array(diff)},
+                         {"restart_required", restart_required ? "true" : "false"}});
+}
+
+// Parse value into key of config (one of AdminServer::LIVE_SETTINGS); false with a reason if it is out of range
+static bool assign_setting(Config& config, const std::string& key, const std::string& value, std::string& error) {
+    double number = 0.0;
+    if (key == "routing_mode") {
+        std::string mode = utils::to_lower(value);
+        if (mode == "latency") config.routing_mode = RoutingMode::Latency;
+        else if (mode == "first_accessible") config.routing_mode = RoutingMode::FirstAccessible;
+        else if (mode == "round_robin") config.routing_mode = RoutingMode::RoundRobin;
+        else {
+            error = "routing_mode must be latency, first_accessible or round_robin";
+            return false;
+        }
+    } else if (key == "success_rate_threshold") {
+        if (!utils::safe_str_to_double(value, number) || number < 0.0 || number > 1.0) {
+            error = "success_rate_threshold must be between 0 and 1";
+            return false;
+        }
+        config.success_rate_threshold = number;
+    } else if (key == "dns_timeout" || key == "health_probe_timeout") {
+        if (!utils::safe_str_to_double(value, number) || number <= 0.0) {
+            error = key + " must be a number greater than 0";
+            return false;
+        }
+        (key == "dns_timeout" ? config.dns_timeout : config.health_probe_timeout) = number;
+    } else {
+        uint32_t count = 0;
+        if (!utils::safe_str_to_uint32(value, count) || count < 1) {
+            error = key + " must be a whole number of at least 1";
+            return false;
+        }
+        if (key == "proxy_failure_threshold") {
+            config.proxy_failure_threshold = count;
+        } else {
+            config.health_check_concurrency = static_cast<size_t>(count);
+        }
+    }
+    return true;
+}
+
+std::string AdminServer::set(const std::string& key, const std::string& value, bool persist) {
+    using namespace webui_json;
+    std::lock_guard<std::mutex> lock(reload_mutex_);
+    
+    auto rejected = [](const std::string& error) {
+        return build_object({{"applied", "false"}, {"errors", build_array({encode_string(error)})}});
+    };
+    
+    if (key == "upstream_proxies" || key == "dns_servers") {
+        return rejected(key + " is changed with add-proxy, remove-proxy and add-dns, or by editing " +
+                        config_path_ + " and reloading");
+    }
+    if (std::find(LIVE_SETTINGS.begin(), LIVE_SETTINGS.end(), key) == LIVE_SETTINGS.end()) {
+        std::map<std::string, std::string> settings;
+        size_t pos = 0;
+        if (!Config::parse_object(config_.to_json(), pos, settings) || settings.find(key) == settings.end()) {
+            return rejected("Unknown setting '" + key + "'");
+        }
+        return rejected(key + " cannot change while running; edit " + config_path_ + " and restart");
+    }
+    
+    Config fresh = config_;
+    fresh.routing_mode = routing_engine_->get_mode();
+    std::string old_value = setting_text(fresh, key);
+    std::string error;
+    if (!assign_setting(fresh, key, value, error)) {
+        return rejected(error);
+    }
+    std::vector<std::string> errors = fresh.validate();
+    if (!errors.empty()) {
+        return rejected(errors.front());
+    }
+    
+    bool restart_required = false;
+    std::vector<std::string> changes = apply(fresh, restart_required);
+    if (!changes.empty()) {
+        Logger::instance().log(LogLevel::INFO, "Setting changed: " + key + " = " + value);
+        audit::record("set", key, old_value, setting_text(fresh, key));
+    }
+    
+    std::vector<std::string> values;
+    for (const auto& change : changes) {
+        values.push_back(encode_string(change));
+    }
+    if (!persist) {
+        return build_object({{"applied", "true"}, {"changes", build_array(values)}});
+    }
+    
+    // Only this key's value changes in the file's text: other settings, keys this version does not know,
+    // and edits waiting there for a reload are kept as they are. A file that does not parse is left
+    // alone, and a missing one is created holding just this key (the rest stays at the defaults)
+    std::ifstream file(config_path_, std::ios::binary);
+    std::stringstream contents;
+    contents << file.rdbuf();
+    std::string text = file ? contents.str() : "{}\n";
+    std::map<std::string, std::string> settings;
+    size_t pos = 0;
+    Config::parse_object(fresh.to_json(), pos, settings);
+    std::string persist_error;
+    if (!Config::replace_member(text, key, settings[key])) {
+        persist_error = config_path_ + " is not valid JSON; it was not changed";
+    } else if (!utils::write_file_atomic(config_path_, text)) {
+        persist_error = "cannot write " + config_path_;
+    }
+    if (persist_error.empty()) {
+        Logger::instance().log(LogLevel::INFO, "Saved " + key + " = " + value + " to " + config_path_);
+    }
+    std::vector<std::pair<std::string, std::string>> reply = {{"applied", "true"},
+                                                              {"changes", build_array(values)},
+                                                              {"persisted", persist_error.empty() ? "true" : "false"}};
+    if (!persist_error.empty()) {
+        reply.push_back({"errors", build_array({encode_string(persist_error)})});
+    }
+    return build_object(reply);
+}
+
+std::vector<std::string> AdminServer::apply(const Config& fresh, bool& restart_required) {
+    std::vector<std::string> changes;
+    
+    if (fresh.routing_mode != routing_engine_->get_mode()) { // May have been changed by `mode` since
+        routing_engine_->set_mode(fresh.routing_mode);
+        changes.push_back("routing_mode updated");
+    }
+    
+    // Changed servers are removed and re-added; their runways (and metrics) start over
+    for (const auto& proxy : config_.upstream_proxies) {
+        if (std::find(fresh.upstream_proxies.begin(), fresh.upstream_proxies.end(), proxy) == fresh.upstream_proxies.end()) {
+            for (const auto& runway_id : runway_manager_->remove_upstream_proxy(proxy.host, proxy.port)) {
+                tracker_->remove_runway(runway_id);
+            }
+            changes.push_back("removed upstream proxy " + upstream::describe(proxy));
+        }
+    }
+    for (const auto& dns_server : config_.dns_servers) {
+        if (std::find(fresh.dns_servers.begin(), fresh.dns_servers.end(), dns_server) == fresh.dns_servers.end()) {
+            for (const auto& runway_id : runway_manager_->remove_dns_server(dns_server.host, dns_server.port)) {
+                tracker_->remove_runway(runway_id);
+            }
+            changes.push_back("removed DNS server " + dns_server.host + ":" + std::to_string(dns_server.port));
+        }
+    }
+    for (const auto& proxy : fresh.upstream_proxies) {
+        if (std::find(config_.upstream_proxies.begin(), config_.upstream_proxies.end(), proxy) == config_.upstream_proxies.end()) {
+            std::vector<std::string> new_runway_ids;
+            if (runway_manager_->add_upstream_proxy(proxy, new_runway_ids)) {
+                changes.push_back("added upstream proxy " + upstream::describe(proxy) +
+                                  " (" + std::to_string(new_runway_ids.size()) + " runways)");
+            } else {
+                changes.push_back("could not add upstream proxy " + upstream::describe(proxy));
+            }
+        }
+    }
+    for (const auto& dns_server : fresh.dns_servers) {
+        if (std::find(config_.dns_servers.begin(), config_.dns_servers.end(), dns_server) == config_.dns_servers.end()) {
+            std::vector<std::string> new_runway_ids;
+            runway_manager_->add_dns_server(dns_server, new_runway_ids);
+            changes.push_back("added DNS server " + dns_server.host + ":" + std::to_string(dns_server.port) +
+                              " (" + std::to_string(new_runway_ids.size()) + " runways)");
+        }
+    }
+    
+    if (fresh.success_rate_threshold != config_.success_rate_threshold) {
+        tracker_->set_success_rate_threshold(fresh.success_rate_threshold);
+        changes.push_back("success_rate_threshold updated");
+    }
+    if (fresh.proxy_failure_threshold != config_.proxy_failure_threshold) {
+        runway_manager_->set_proxy_failure_threshold(fresh.proxy_failure_threshold);
+        changes.push_back("proxy_failure_threshold updated");
+    }
+    if (fresh.dns_timeout != config_.dns_timeout) {
+        runway_manager_->get_dns_resolver()->set_timeout(fresh.dns_timeout);
+        changes.push_back("dns_timeout updated");
+    }
+    if (health_monitor_ && fresh.health_probe_timeout != config_.health_probe_timeout) {
+        health_monitor_->set_probe_timeout(fresh.health_probe_timeout);
+        changes.push_back("health_probe_timeout updated");
+    }
+    if (health_monitor_ && fresh.health_check_concurrency != config_.health_check_concurrency) {
+        health_monitor_->set_concurrency(fresh.health_check_concurrency);
+        changes.push_back("health_check_concurrency updated");
+    }
+    if (fresh.admin_token != config_.admin_token) {
+        changes.push_back("admin_token updated"); // Checked from config_ on every request
+    }
+    
+    // Anything else differing from the running settings only applies after a restart
+    Config applied = fresh;
+    applied.routing_mode = config_.routing_mode;
+    applied.upstream_proxies = config_.upstream_proxies;
+    applied.dns_servers = config_.dns_servers;
+    applied.success_rate_threshold = config_.success_rate_threshold;
+    applied.proxy_failure_threshold = config_.proxy_failure_threshold;
+    applied.dns_timeout = config_.dns_timeout;
+    applied.health_probe_timeout = config_.health_probe_timeout;
+    applied.health_check_concurrency = config_.health_check_concurrency;
+    applied.admin_token = config_.admin_token;
+    restart_required = applied.to_json() != config_.to_json();
+    
+    config_.routing_mode = fresh.routing_mode;
+    config_.upstream_proxies = fresh.upstream_proxies;
+    config_.dns_servers = fresh.dns_servers;
+    config_.success_rate_threshold = fresh.success_rate_threshold;
+    config_.proxy_failure_threshold = fresh.proxy_failure_threshold;
+    config_.dns_timeout = fresh.dns_timeout;
+    config_.health_probe_timeout = fresh.health_probe_timeout;
+    config_.health_check_concurrency = fresh.health_check_concurrency;
+    config_.admin_token = fresh.admin_token;
+    
+    return changes;
+}
+
+int AdminServer::execute(const std::vector<std::string>& args, std::string& output) {
+    // A fresh ProxyCLI per command: the state it acts on is shared, its output and flags are not
+    std::lock_guard<std::mutex> lock(command_mutex_);
+    ProxyCLI cli(runway_manager_, routing_engine_, tracker_, health_monitor_, proxy_server_);
+    cli.set_reload_handler([this]() { return reload(); });
+    cli.set_setting_handler([this](const std::string& key, const std::string& value, bool persist) {
+        return set(key, value, persist);
+    });
+    int exit_code = cli.execute(args);
+    output = cli.output();
+    return exit_code;
+}
+
+std::string AdminServer::run_cli(const std::vector<std::string>& args) {
+    std::string output;
+    int exit_code = execute(args, output);
+    return webui_json::build_object({{"exit_code", webui_json::encode_int(exit_code)},
+                                     {"output", webui_json::encode_string(output)}});
+}
+
+namespace admin {
+
+bool client_tls(const Config& config, tls::ClientOptions& options) {
+    if (config.admin_tls_cert_path.empty()) {
+        return false;
+    }
+    // The service's own certificate is the only one trusted, so its name need not match admin_listen_host
+    options.server_name = "";
+    options.ca_path = config.admin_tls_cert_path;
+    options.cert_path = config.admin_client_cert_path;
+    options.key_path = config.admin_client_key_path;
+    return true;
+}
+
+bool request(const std::string& host, uint16_t port, const std::string& method, const std::string& path,
+             const std::string& body, int& status, std::string& response_body, long timeout_secs,
+             const std::string& token, const tls::ClientOptions* tls_options) {
+    socket_t sock = create_tcp_socket();
+    if (sock == INVALID_SOCKET_VALUE) {
+        return false;
+    }
+    
+    // Generous by default: some commands (tests, probes) take a while on the service side
+    set_timeouts(sock, timeout_secs);
+    std::string tls_error;
+    if (!connect_socket(sock, host, port, 5000) || (tls_options && !tls::connect(sock, *tls_options, tls_error))) {
+        close_socket(sock);
+        return false;
+    }
+    
+    std::ostringstream oss;
+    oss << method << " " << path << " HTTP/1.1\r\n";
+    oss << "Host: " << host << ":" << port << "\r\n";
+    oss << "Content-Type: application/json\r\n";
+    if (!token.empty()) {
+        oss << "Authorization: Bearer " << token << "\r\n";
+    }
+    // Names the local user in the service's audit log
+    const char* user = std::getenv("USER");
+    if (!user) {
+        user = std::getenv("USERNAME");
+    }
+    if (user && *user) {
+        oss << USER_HEADER << ": " << user << "\r\n";
+    }
+    oss << "Content-Length: " << body.size() << "\r\n";
+    oss << "Connection: close\r\n";
+    oss << "\r\n";
+    oss << body;
+    std::string request_data = oss.str();
+    
+    std::string head;
+    bool ok = send_data(sock, request_data.data(), request_data.size()) == static_cast<ssize_t>(request_data.size()) &&
+              read_message(sock, head, response_body, false);
+    close_socket(sock);
+    if (!ok) {
+        return false;
+    }
+    
+    // Status line: "HTTP/1.1 200 OK"
+    std::vector<std::string> parts = utils::split(head.substr(0, head.find('\r')), ' ');
+    uint16_t code = 0;
+    if (parts.size() < 2 || !utils::safe_str_to_uint16(parts[1], code)) {
+        return false;
+    }
+    status = code;
+    return true;
+}
+
+bool run_command(const std::string& host, uint16_t port, const std::vector<std::string>& args,
+                 int& exit_code, std::string& output, long timeout_secs, const std::string& token,
+                 const tls::ClientOptions* tls_options) {
+    std::vector<std::string> values;
+    for (const auto& arg : args) {
+        values.push_back(webui_json::encode_string(arg));
+    }
+    int status = 0;
+    std::string body;
+    if (!request(host, port, "POST", "/cli", webui_json::build_array(values), status, body, timeout_secs, token,
+                 tls_options)) {
+        return false;
+    }
+    if (status == 401) {
+        exit_code = 1;
+        output = token.empty() ? "Error: The running service requires an admin token; set admin_token in config.json\n"
+                               : "Error: The running service rejected admin_token\n";
+        return true;
+    }
+    if (status != 200) {
+        return false;
+    }
+    
+    size_t pos = 0;
+    std::map<std::string, std::string> reply;
+    uint64_t code = 0;
+    size_t output_pos = 0;
+    if (!Config::parse_object(body, pos, reply) ||
+        !utils::safe_str_to_uint64(utils::trim(reply["exit_code"]), code) ||
+        !Config::parse_string(utils::trim(reply["output"]), output_pos, output)) {
+        return false;
+    }
+    exit_code = static_cast<int>(code);
+    return true;
+}
+
+} // namespace admin
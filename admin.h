@@ -0,0 +1,178 @@
+#ifndef ADMIN_H
+#define ADMIN_H
+
+#include <string>
+#include <vector>
+#include <memory>
+#include <atomic>
+#include <mutex>
+#include <thread>
+#include <set>
+#include <condition_variable>
+#include <cstdint>
+#include "config.h"
+#include "runway_manager.h"
+#include "routing.h"
+#include "tracker.h"
+#include "health.h"
+#include "proxy.h"
+#include "network.h"
+#include "tls.h"
+
+// Local admin endpoint: lets CLI invocations (`smartproxy <command>`) act on the
+// running service instead of on state of their own
+// HTTP/1.1 (RFC 7230) with JSON bodies, one request per connection
+// With admin_token set, every route but /healthz and /readyz needs "Authorization: Bearer <admin_token>"
+// (RFC 6750) and answers 401 without it
+// With admin_tls_cert_path set it serves HTTPS instead, and with admin_tls_client_ca_path only to clients
+// presenting a certificate from that CA (mutual TLS, RFC 8446 Section 4.3.2); the handshake fails otherwise
+//
+// POST /reload - re-read the config file and apply what can change at runtime
+// POST /cli    - run a CLI command against live state; body is the argument list as a JSON
+//                string array, reply is {"exit_code", "output"}
+//
+// JSON API for external tools (replies are the matching CLI command's --json output,
+// or {"error"} with status 400):
+// GET  /api/status, /api/runways, /api/targets, /api/stats
+// GET  /api/mode   - {"routing_mode"}
+// POST /api/mode   - body {"mode"[, "persist"]}; switch the routing mode
+// POST /api/reload - same as POST /reload
+// POST /api/reset  - body {"target"} or empty; forget the metrics of one target, or of all
+// GET  /api/audit  - {"entries"}: the most recent changes made to the running service (see audit.h)
+//
+// GET /metrics - Prometheus text format (see prometheus.h)
+//
+// For orchestrators and load balancers:
+// GET /healthz - liveness; 200 while the service answers at all
+// GET /readyz  - readiness; 200 or 503 with {"ready", "check", "reason"} (readiness_check decides what counts)
+//
+// GET /events - Server-Sent Events stream, open until the client leaves; each event's data is a JSON object:
+//               "request" (a proxied request completed), "probe" (a health probe result),
+//               "state" (a runway changed state for a target, from a request or a probe)
+//               and "dropped" (this client fell behind and missed events)
+
+class AdminServer {
+public:
+    AdminServer(std::shared_ptr<RunwayManager> runway_manager,
+                std::shared_ptr<RoutingEngine> routing_engine,
+                std::shared_ptr<TargetAccessibilityTracker> tracker,
+                std::shared_ptr<HealthMonitor> health_monitor,
+                std::shared_ptr<ProxyServer> proxy_server,
+                const Config& config,
+                const std::string& config_path);
+    
+    ~AdminServer();
+    
+    // Start admin server (runs in background thread)
+    bool start();
+    
+    // Stop admin server
+    void stop();
+    
+    bool is_running() const { return running_; }
+    
+    // Re-read config_path and apply the settings that can change at runtime (LIVE_SETTINGS,
+    // upstream_proxies, dns_servers and admin_token)
+    // Returns a JSON result: {"reloaded", "changes", "diff", "restart_required"} or {"reloaded": false, "errors"};
+    // diff lists every setting that differs from the running one as {"setting", "old", "new", "applied"},
+    // where applied is false for settings that wait for a restart
+    std::string reload();
+    
+    // Change one of LIVE_SETTINGS in the running service; with persist, also write it to config_path
+    // Returns a JSON result: {"applied", "changes"[, "persisted"]} or {"applied": false, "errors"}
+    std::string set(const std::string& key, const std::string& value, bool persist = false);
+    
+    // Settings `set` accepts
+    static const std::vector<std::string> LIVE_SETTINGS;
+
+private:
+    std::shared_ptr<RunwayManager> runway_manager_;
+    std::shared_ptr<RoutingEngine> routing_engine_;
+    std::shared_ptr<TargetAccessibilityTracker> tracker_;
+    std::shared_ptr<HealthMonitor> health_monitor_;
+    std::shared_ptr<ProxyServer> proxy_server_;
+    Config config_; // Settings currently in effect
+    std::string config_path_;
+    std::mutex reload_mutex_;
+    
+    std::atomic<bool> running_;
+    socket_t listen_socket_;
+    std::thread server_thread_;
+    
+    // Connections being served, each on a thread of its own; stop() wakes them and waits until none is left
+    std::mutex connections_mutex_;
+    std::condition_variable connections_done_;
+    std::set<socket_t> connections_;
+    std::mutex command_mutex_; // CLI commands run one at a time, whichever connection sent them
+    std::shared_ptr<tls::ServerContext> tls_context_; // Set when serving over TLS (admin_tls_cert_path)
+    
+    // GET /events clients, each served by a thread of its own
+    struct EventStream;
+    std::mutex streams_mutex_;
+    std::vector<std::shared_ptr<EventStream>> streams_;
+    size_t health_listener_id_;
+    size_t request_listener_id_;
+    
+    // Bring live state in line with fresh; caller holds reload_mutex_
+    // Returns the changes made; restart_required is set if other settings differ
+    std::vector<std::string> apply(const Config& fresh, bool& restart_required);
+    
+    void server_loop();
+    
+    // Handshake (with TLS) and answer one accepted connection, then release it
+    void serve_connection(socket_t client_sock, const std::string& client_ip);
+    
+    // Returns true if the connection was handed to an event stream, which then owns the socket
+    bool handle_connection(socket_t client_sock, const std::string& client_ip);
+    std::string handle_api(const std::string& method, const std::string& path, const std::string& body, int& status);
+    
+    // Evaluate readiness_check; returns the /readyz body
+    std::string readiness(bool& ready);
+    
+    // Queue an event for every connected /events client
+    void publish(const std::string& type, const std::string& data);
+    
+    // Answer GET /events and serve it on a new thread; false if the socket stays with the caller
+    bool open_event_stream(socket_t client_sock);
+    void stream_events(std::shared_ptr<EventStream> stream);
+    
+    // Stop every event stream (all of them, or only those whose client already left) and release it
+    void close_event_streams(bool finished_only);
+    
+    // True if head carries the admin_token (or none is configured)
+    bool authorized(const std::string& head);
+    
+    // Run a CLI command against live state; returns its exit code, output receives what it printed
+    int execute(const std::vector<std::string>& args, std::string& output);
+    std::string run_cli(const std::vector<std::string>& args);
+    
+    std::string build_http_response(int status_code, const std::string& body,
+                                    const std::string& content_type = "application/json");
+};
+
+namespace admin {
+
+// Sent with every request: the local user running the command, for the service's audit log
+static const char* const USER_HEADER = "X-SmartProxy-User";
+
+// How CLI commands connect to the admin endpoint described by config: false if it serves plain HTTP;
+// otherwise options trust only admin_tls_cert_path and present admin_client_cert_path
+bool client_tls(const Config& config, tls::ClientOptions& options);
+
+// Send a request to a running service's admin endpoint, waiting up to timeout_secs for the reply
+// A non-empty token is sent as "Authorization: Bearer <token>"; with tls_options, the request goes over TLS
+// Returns false if the service could not be reached; status and body receive its reply
+bool request(const std::string& host, uint16_t port, const std::string& method, const std::string& path,
+             const std::string& body, int& status, std::string& response_body, long timeout_secs = 60,
+             const std::string& token = "", const tls::ClientOptions* tls_options = nullptr);
+
+// Run a CLI command in a running service (POST /cli), authenticating with token (the admin_token)
+// Returns false if the service could not be reached or gave no usable reply; a rejected token
+// is reported as exit code 1 with an error in output
+bool run_command(const std::string& host, uint16_t port, const std::vector<std::string>& args,
+                 int& exit_code, std::string& output, long timeout_secs = 60, const std::string& token = "",
+                 const tls::ClientOptions* tls_options = nullptr);
+
+} // namespace admin
+
+#endif // ADMIN_H
@@ -0,0 +1,152 @@
+#include "alert.h"
+#include "network.h"
+#include "logger.h"
+#include "utils.h"
+#include "webui_json.h"
+#include <sstream>
+#include <thread>
+#include <ctime>
+
+AlertNotifier::AlertNotifier(const std::string& webhook_url, uint64_t debounce_secs)
+    : port_(80)
+    , path_("/")
+    , valid_(false)
+    , debounce_secs_(debounce_secs) {
+    valid_ = parse_url(webhook_url);
+}
+
+bool AlertNotifier::parse_url(const std::string& url) {
+    // Only plain HTTP is supported (no TLS dependency)
+    const std::string scheme = "http://";
+    if (utils::to_lower(url.substr(0, scheme.length())) != scheme) {
+        return false;
+    }
+    
+    std::string rest = url.substr(scheme.length());
+    size_t path_pos = rest.find('/');
+    std::string authority = (path_pos == std::string::npos) ? rest : rest.substr(0, path_pos);
+    if (path_pos != std::string::npos) {
+        path_ = rest.substr(path_pos);
+    }
+    
+    size_t colon_pos = authority.find(':');
+    if (colon_pos != std::string::npos) {
+        host_ = authority.substr(0, colon_pos);
+        if (!utils::safe_str_to_uint16(authority.substr(colon_pos + 1), port_) || port_ == 0) {
+            return false;
+        }
+    } else {
+        host_ = authority;
+    }
+    
+    return !host_.empty();
+}
+
+void AlertNotifier::notify_target_down(const std::string& target, const std::vector<TargetMetrics>& runways) {
+    if (!valid_) {
+        return;
+    }
+    
+    uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        auto it = last_alert_time_.find(target);
+        if (it != last_alert_time_.end() && now - it->second < debounce_secs_) {
+            return; // Debounced
+        }
+        last_alert_time_[target] = now;
+    }
+    
+    std::string payload = build_payload(target, runways, now);
+    
+    // Deliver in background so request handling is never blocked on the webhook
+    std::string host = host_;
+    uint16_t port = port_;
+    std::string path = path_;
+    std::thread([host, port, path, payload, target]() {
+        if (!post_json(host, port, path, payload)) {
+            Logger::instance().log(LogLevel::WARN, "Alert webhook delivery failed for target " + target, {"", target});
+        }
+    }).detach();
+    
+    Logger::instance().log(LogLevel::WARN, "All runways down for target " + target + ", alert sent", {"", target});
+}
+
+std::string AlertNotifier::build_payload(const std::string& target, const std::vector<TargetMetrics>& runways,
+                                         uint64_t timestamp) const {
+    using namespace webui_json;
+    
+    std::vector<std::string> runway_values;
+    for (const auto& m : runways) {
+        runway_values.push_back(build_object({
+            {"runway_id", encode_string(m.runway_id)},
+            {"consecutive_failures", encode_int(static_cast<int64_t>(m.consecutive_failures))},
+            {"failure_count", encode_int(static_cast<int64_t>(m.failure_count))},
+            {"last_failure_time", encode_int(static_cast<int64_t>(m.last_failure_time))},
+            {"last_success_time", encode_int(static_cast<int64_t>(m.last_success_time))}
+        }));
+    }
+    
+    return build_object({
+        {"event", encode_string("target_down")},
+        {"target", encode_string(target)},
+        {"timestamp", encode_int(static_cast<int64_t>(timestamp))},
+        {"runways", build_array(runway_values)}
+    });
+}
+
+bool AlertNotifier::post_json(const std::string& host, uint16_t port, const std::string& path,
+                              const std::string& body) {
+    std::string ip = host;
+    if (!utils::is_valid_ipv4(ip) && !network::resolve_hostname(host, ip)) {
+        return false;
+    }
+    
+    socket_t sock = network::create_tcp_socket();
+    if (sock == network::INVALID_SOCKET_VALUE) {
+        return false;
+    }
+    
+    struct timeval timeout;
+    timeout.tv_sec = 5;
+    timeout.tv_usec = 0;
+#ifdef _WIN32
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+#else
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
+#endif
+
+    if (!network::connect_socket(sock, ip, port)) {
+        network::close_socket(sock);
+        return false;
+    }
+    
+    // RFC 7230 Section 3 - request message
+    std::ostringstream oss;
+    oss << "POST " << path << " HTTP/1.1\r\n";
+    oss << "Host: " << host;
+    if (port != 80) oss << ":" << port;
+    oss << "\r\n";
+    oss << "Content-Type: application/json\r\n";
+    oss << "Content-Length: " << body.size() << "\r\n";
+    oss << "Connection: close\r\n";
+    oss << "\r\n";
+    oss << body;
+    
+    std::string request = oss.str();
+    ssize_t sent = network::send_data(sock, request.data(), request.size());
+    
+    // Read status line: "HTTP/1.1 2xx ..."
+    char buffer[64];
+    ssize_t received = network::recv_data(sock, buffer, sizeof(buffer) - 1);
+    network::close_socket(sock);
+    
+    if (sent != static_cast<ssize_t>(request.size()) || received <= 0) {
+        return false;
+    }
+    buffer[received] = '\0';
+    std::vector<std::string> parts = utils::split(buffer, ' ');
+    return parts.size() >= 2 && !parts[1].empty() && parts[1][0] == '2';
+}
@@ -0,0 +1,43 @@
+#ifndef ALERT_H
+#define ALERT_H
+
+#include <string>
+#include <vector>
+#include <map>
+#include <mutex>
+#include <cstdint>
+#include "tracker.h"
+
+// Webhook alerting for target outages
+// Posts a JSON payload (RFC 7159) to a plain HTTP endpoint (RFC 7230)
+// when a target loses its last accessible runway
+
+class AlertNotifier {
+public:
+    // webhook_url: http://host[:port][/path]
+    // debounce_secs: minimum time between alerts for the same target
+    AlertNotifier(const std::string& webhook_url, uint64_t debounce_secs);
+    
+    // Returns false if the webhook URL could not be parsed
+    bool is_valid() const { return valid_; }
+    
+    // Fire a target-down alert (non-blocking, debounced per target)
+    void notify_target_down(const std::string& target, const std::vector<TargetMetrics>& runways);
+
+private:
+    std::string host_;
+    uint16_t port_;
+    std::string path_;
+    bool valid_;
+    uint64_t debounce_secs_;
+    std::map<std::string, uint64_t> last_alert_time_; // target -> Unix timestamp
+    std::mutex mutex_;
+    
+    bool parse_url(const std::string& url);
+    std::string build_payload(const std::string& target, const std::vector<TargetMetrics>& runways,
+                              uint64_t timestamp) const;
+    static bool post_json(const std::string& host, uint16_t port, const std::string& path,
+                          const std::string& body);
+};
+
+#endif // ALERT_H
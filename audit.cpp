@@ -0,0 +1,94 @@
+#include "audit.h"
+#include "logger.h"
+#include "utils.h"
+#include "webui_json.h"
+#include "redact.h"
+#include <mutex>
+#include <deque>
+#include <fstream>
+#include <sstream>
+#include <iomanip>
+#include <ctime>
+
+namespace audit {
+
+static std::mutex g_mutex;
+static std::string g_path;
+static std::deque<std::string> g_recent;
+
+// Actors in scope on this thread, innermost last: {channel, name}
+static thread_local std::vector<std::pair<std::string, std::string>> t_actors;
+
+// ISO 8601 in UTC
+static std::string utc_timestamp(std::time_t time_val) {
+    std::tm tm_info;
+#ifdef _WIN32
+    if (gmtime_s(&tm_info, &time_val) != 0) {
+        return "1970-01-01T00:00:00Z";
+    }
+#else
+    if (gmtime_r(&time_val, &tm_info) == nullptr) {
+        return "1970-01-01T00:00:00Z";
+    }
+#endif
+    std::ostringstream oss;
+    oss << std::put_time(&tm_info, "%Y-%m-%dT%H:%M:%SZ");
+    return oss.str();
+}
+
+bool init(const std::string& path, std::string& error) {
+    if (!path.empty() && !utils::ensure_log_file(path)) {
+        error = "cannot create " + path;
+        return false;
+    }
+    std::lock_guard<std::mutex> lock(g_mutex);
+    g_path = path;
+    return true;
+}
+
+Actor::Actor(const std::string& channel, const std::string& name) {
+    t_actors.emplace_back(channel, name);
+}
+
+Actor::~Actor() {
+    t_actors.pop_back();
+}
+
+void record(const std::string& action, const std::string& subject,
+            const std::string& old_value, const std::string& new_value) {
+    using namespace webui_json;
+    // A secret setting is masked outright; others may still hold one, e.g. a proxy's password
+    std::string old_text = redact::json(redact::value(subject, old_value));
+    std::string new_text = redact::json(redact::value(subject, new_value));
+    std::string entry = build_object({
+        {"time", encode_string(utc_timestamp(std::time(nullptr)))},
+        {"channel", encode_string(t_actors.empty() ? "internal" : t_actors.back().first)},
+        {"actor", t_actors.empty() ? encode_null() : encode_string(t_actors.back().second)},
+        {"action", encode_string(action)},
+        {"subject", encode_string(subject)},
+        {"old", old_text.empty() ? encode_null() : encode_string(old_text)},
+        {"new", new_text.empty() ? encode_null() : encode_string(new_text)}
+    });
+    
+    std::lock_guard<std::mutex> lock(g_mutex);
+    g_recent.push_back(entry);
+    if (g_recent.size() > MAX_RECENT_ENTRIES) {
+        g_recent.pop_front();
+    }
+    if (g_path.empty()) {
+        return;
+    }
+    // Opened per entry: changes are rare, and the file may be moved aside between them
+    std::ofstream file(g_path, std::ios::app | std::ios::binary);
+    if (!(file << entry << "\n")) {
+        Logger::instance().log(LogLevel::WARN, "Cannot write to audit log " + g_path + ": " + action + " " + subject);
+    }
+}
+
+std::vector<std::string> recent(size_t limit) {
+    std::lock_guard<std::mutex> lock(g_mutex);
+    size_t skip = g_recent.size() > limit ? g_recent.size() - limit : 0;
+    return std::vector<std::string>(g_recent.begin() + static_cast<std::ptrdiff_t>(skip), g_recent.end());
+}
+
+} // namespace audit
@@ -0,0 +1,44 @@
+#ifndef AUDIT_H
+#define AUDIT_H
+
+#include <string>
+#include <vector>
+
+// Audit trail of changes made to the running service: routing mode, settings (set, reload),
+// runways, upstream proxies, DNS servers, metrics resets and the health monitor
+// Each change is one JSON object: {"time", "channel", "actor", "action", "subject", "old", "new"}
+// (old and new are null where there is nothing to show, e.g. for an added proxy's old value)
+// It is appended as a line to audit_log_file, and the most recent ones are kept for GET /api/audit
+//
+// Who made a change is taken from the Actor in scope on the calling thread, so the code making the
+// change need not know whether it came from the CLI, the JSON API, the TUI or the web UI
+
+namespace audit {
+
+// Most recent entries kept in memory
+static const size_t MAX_RECENT_ENTRIES = 200;
+
+// Append entries to path from now on (empty = memory only); false with error set if it cannot be created
+bool init(const std::string& path, std::string& error);
+
+// Who changes are attributed to while this is in scope on the current thread; scopes nest
+class Actor {
+public:
+    // channel: "cli", "api", "tui" or "webui"; name: e.g. "alice@127.0.0.1"
+    Actor(const std::string& channel, const std::string& name);
+    ~Actor();
+    Actor(const Actor&) = delete;
+    Actor& operator=(const Actor&) = delete;
+};
+
+// Record a change; empty old_value or new_value is written as null
+// Changes made with no Actor in scope are attributed to channel "internal"
+void record(const std::string& action, const std::string& subject,
+            const std::string& old_value, const std::string& new_value);
+
+// Up to limit most recent entries as JSON objects, oldest first
+std::vector<std::string> recent(size_t limit = MAX_RECENT_ENTRIES);
+
+} // namespace audit
+
+#endif // AUDIT_H
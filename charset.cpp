@@ -0,0 +1,272 @@
+#include "charset.h"
+#include "utils.h"
+#include <cctype>
+
+namespace charset {
+
+// Code points for bytes 0x80-0xFF (0xFFFD = undefined)
+static const uint16_t WINDOWS_1252[128] = {
+    0x20AC, 0xFFFD, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021,
+    0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0xFFFD, 0x017D, 0xFFFD,
+    0xFFFD, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0xFFFD, 0x017E, 0x0178,
+    0x00A0, 0x00A1, 0x00A2, 0x00A3, 0x00A4, 0x00A5, 0x00A6, 0x00A7,
+    0x00A8, 0x00A9, 0x00AA, 0x00AB, 0x00AC, 0x00AD, 0x00AE, 0x00AF,
+    0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x00B4, 0x00B5, 0x00B6, 0x00B7,
+    0x00B8, 0x00B9, 0x00BA, 0x00BB, 0x00BC, 0x00BD, 0x00BE, 0x00BF,
+    0x00C0, 0x00C1, 0x00C2, 0x00C3, 0x00C4, 0x00C5, 0x00C6, 0x00C7,
+    0x00C8, 0x00C9, 0x00CA, 0x00CB, 0x00CC, 0x00CD, 0x00CE, 0x00CF,
+    0x00D0, 0x00D1, 0x00D2, 0x00D3, 0x00D4, 0x00D5, 0x00D6, 0x00D7,
+    0x00D8, 0x00D9, 0x00DA, 0x00DB, 0x00DC, 0x00DD, 0x00DE, 0x00DF,
+    0x00E0, 0x00E1, 0x00E2, 0x00E3, 0x00E4, 0x00E5, 0x00E6, 0x00E7,
+    0x00E8, 0x00E9, 0x00EA, 0x00EB, 0x00EC, 0x00ED, 0x00EE, 0x00EF,
+    0x00F0, 0x00F1, 0x00F2, 0x00F3, 0x00F4, 0x00F5, 0x00F6, 0x00F7,
+    0x00F8, 0x00F9, 0x00FA, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF
+};
+
+static const uint16_t WINDOWS_1251[128] = {
+    0x0402, 0x0403, 0x201A, 0x0453, 0x201E, 0x2026, 0x2020, 0x2021,
+    0x20AC, 0x2030, 0x0409, 0x2039, 0x040A, 0x040C, 0x040B, 0x040F,
+    0x0452, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0xFFFD, 0x2122, 0x0459, 0x203A, 0x045A, 0x045C, 0x045B, 0x045F,
+    0x00A0, 0x040E, 0x045E, 0x0408, 0x00A4, 0x0490, 0x00A6, 0x00A7,
+    0x0401, 0x00A9, 0x0404, 0x00AB, 0x00AC, 0x00AD, 0x00AE, 0x0407,
+    0x00B0, 0x00B1, 0x0406, 0x0456, 0x0491, 0x00B5, 0x00B6, 0x00B7,
+    0x0451, 0x2116, 0x0454, 0x00BB, 0x0458, 0x0405, 0x0455, 0x0457,
+    0x0410, 0x0411, 0x0412, 0x0413, 0x0414, 0x0415, 0x0416, 0x0417,
+    0x0418, 0x0419, 0x041A, 0x041B, 0x041C, 0x041D, 0x041E, 0x041F,
+    0x0420, 0x0421, 0x0422, 0x0423, 0x0424, 0x0425, 0x0426, 0x0427,
+    0x0428, 0x0429, 0x042A, 0x042B, 0x042C, 0x042D, 0x042E, 0x042F,
+    0x0430, 0x0431, 0x0432, 0x0433, 0x0434, 0x0435, 0x0436, 0x0437,
+    0x0438, 0x0439, 0x043A, 0x043B, 0x043C, 0x043D, 0x043E, 0x043F,
+    0x0440, 0x0441, 0x0442, 0x0443, 0x0444, 0x0445, 0x0446, 0x0447,
+    0x0448, 0x0449, 0x044A, 0x044B, 0x044C, 0x044D, 0x044E, 0x044F
+};
+
+static const uint16_t WINDOWS_1256[128] = {
+    0x20AC, 0x067E, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021,
+    0x02C6, 0x2030, 0x0679, 0x2039, 0x0152, 0x0686, 0x0698, 0x0688,
+    0x06AF, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x06A9, 0x2122, 0x0691, 0x203A, 0x0153, 0x200C, 0x200D, 0x06BA,
+    0x00A0, 0x060C, 0x00A2, 0x00A3, 0x00A4, 0x00A5, 0x00A6, 0x00A7,
+    0x00A8, 0x00A9, 0x06BE, 0x00AB, 0x00AC, 0x00AD, 0x00AE, 0x00AF,
+    0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x00B4, 0x00B5, 0x00B6, 0x00B7,
+    0x00B8, 0x00B9, 0x061B, 0x00BB, 0x00BC, 0x00BD, 0x00BE, 0x061F,
+    0x06C1, 0x0621, 0x0622, 0x0623, 0x0624, 0x0625, 0x0626, 0x0627,
+    0x0628, 0x0629, 0x062A, 0x062B, 0x062C, 0x062D, 0x062E, 0x062F,
+    0x0630, 0x0631, 0x0632, 0x0633, 0x0634, 0x0635, 0x0636, 0x00D7,
+    0x0637, 0x0638, 0x0639, 0x063A, 0x0640, 0x0641, 0x0642, 0x0643,
+    0x00E0, 0x0644, 0x00E2, 0x0645, 0x0646, 0x0647, 0x0648, 0x00E7,
+    0x00E8, 0x00E9, 0x00EA, 0x00EB, 0x0649, 0x064A, 0x00EE, 0x00EF,
+    0x064B, 0x064C, 0x064D, 0x064E, 0x00F4, 0x064F, 0x0650, 0x00F7,
+    0x0651, 0x00F9, 0x0652, 0x00FB, 0x00FC, 0x200E, 0x200F, 0x06D2
+};
+
+static const uint16_t KOI8_R[128] = {
+    0x2500, 0x2502, 0x250C, 0x2510, 0x2514, 0x2518, 0x251C, 0x2524,
+    0x252C, 0x2534, 0x253C, 0x2580, 0x2584, 0x2588, 0x258C, 0x2590,
+    0x2591, 0x2592, 0x2593, 0x2320, 0x25A0, 0x2219, 0x221A, 0x2248,
+    0x2264, 0x2265, 0x00A0, 0x2321, 0x00B0, 0x00B2, 0x00B7, 0x00F7,
+    0x2550, 0x2551, 0x2552, 0x0451, 0x2553, 0x2554, 0x2555, 0x2556,
+    0x2557, 0x2558, 0x2559, 0x255A, 0x255B, 0x255C, 0x255D, 0x255E,
+    0x255F, 0x2560, 0x2561, 0x0401, 0x2562, 0x2563, 0x2564, 0x2565,
+    0x2566, 0x2567, 0x2568, 0x2569, 0x256A, 0x256B, 0x256C, 0x00A9,
+    0x044E, 0x0430, 0x0431, 0x0446, 0x0434, 0x0435, 0x0444, 0x0433,
+    0x0445, 0x0438, 0x0439, 0x043A, 0x043B, 0x043C, 0x043D, 0x043E,
+    0x043F, 0x044F, 0x0440, 0x0441, 0x0442, 0x0443, 0x0436, 0x0432,
+    0x044C, 0x044B, 0x0437, 0x0448, 0x044D, 0x0449, 0x0447, 0x044A,
+    0x042E, 0x0410, 0x0411, 0x0426, 0x0414, 0x0415, 0x0424, 0x0413,
+    0x0425, 0x0418, 0x0419, 0x041A, 0x041B, 0x041C, 0x041D, 0x041E,
+    0x041F, 0x042F, 0x0420, 0x0421, 0x0422, 0x0423, 0x0416, 0x0412,
+    0x042C, 0x042B, 0x0417, 0x0428, 0x042D, 0x0429, 0x0427, 0x042A
+};
+
+static void append_utf8(std::string& out, uint32_t cp) {
+    if (cp == 0xFFFD) {
+        return; // Lossy: drop undecodable characters
+    }
+    if (cp < 0x20 && cp != '\t' && cp != '\n' && cp != '\r') {
+        return; // Control characters carry no text
+    }
+    if (cp == 0x7F || (cp >= 0x80 && cp < 0xA0)) {
+        return;
+    }
+    
+    // RFC 3629 Section 3 - UTF-8 encoding
+    if (cp < 0x80) {
+        out += static_cast<char>(cp);
+    } else if (cp < 0x800) {
+        out += static_cast<char>(0xC0 | (cp >> 6));
+        out += static_cast<char>(0x80 | (cp & 0x3F));
+    } else if (cp < 0x10000) {
+        out += static_cast<char>(0xE0 | (cp >> 12));
+        out += static_cast<char>(0x80 | ((cp >> 6) & 0x3F));
+        out += static_cast<char>(0x80 | (cp & 0x3F));
+    } else {
+        out += static_cast<char>(0xF0 | (cp >> 18));
+        out += static_cast<char>(0x80 | ((cp >> 12) & 0x3F));
+        out += static_cast<char>(0x80 | ((cp >> 6) & 0x3F));
+        out += static_cast<char>(0x80 | (cp & 0x3F));
+    }
+}
+
+// Value of a "charset=" parameter starting at pos (quotes and trailing junk stripped)
+static std::string read_charset_value(const std::string& text, size_t pos) {
+    while (pos < text.length() && (text[pos] == ' ' || text[pos] == '"' || text[pos] == '\'')) {
+        pos++;
+    }
+    size_t end = pos;
+    while (end < text.length() && (std::isalnum(static_cast<unsigned char>(text[end])) ||
+                                   text[end] == '-' || text[end] == '_' || text[end] == ':' || text[end] == '.')) {
+        end++;
+    }
+    return text.substr(pos, end - pos);
+}
+
+std::string detect(const std::string& content_type, const std::vector<uint8_t>& body) {
+    std::string lower_type = utils::to_lower(content_type);
+    size_t pos = lower_type.find("charset=");
+    if (pos != std::string::npos) {
+        std::string name = read_charset_value(lower_type, pos + 8);
+        if (!name.empty()) {
+            return name;
+        }
+    }
+    
+    // HTML declares its encoding within the first 1024 bytes (WHATWG prescan)
+    size_t scan_length = body.size() < 1024 ? body.size() : 1024;
+    std::string head = utils::to_lower(std::string(body.begin(), body.begin() + scan_length));
+    pos = head.find("<meta");
+    while (pos != std::string::npos) {
+        size_t tag_end = head.find('>', pos);
+        std::string tag = head.substr(pos, tag_end == std::string::npos ? std::string::npos : tag_end - pos);
+        size_t cs = tag.find("charset=");
+        if (cs != std::string::npos) {
+            std::string name = read_charset_value(tag, cs + 8);
+            if (!name.empty()) {
+                return name;
+            }
+        }
+        pos = head.find("<meta", pos + 5);
+    }
+    
+    return "utf-8";
+}
+
+std::string to_utf8(const std::vector<uint8_t>& body, const std::string& charset_name) {
+    std::string name = utils::to_lower(charset_name);
+    const uint16_t* table = nullptr;
+    bool latin1 = false;
+    
+    if (name == "windows-1252" || name == "cp1252" || name == "us-ascii" || name == "ascii") {
+        table = WINDOWS_1252; // WHATWG: ASCII and Latin-1 labels decode as windows-1252
+    } else if (name == "iso-8859-1" || name == "latin1" || name == "iso8859-1" || name == "l1") {
+        latin1 = true;
+    } else if (name == "windows-1251" || name == "cp1251") {
+        table = WINDOWS_1251;
+    } else if (name == "windows-1256" || name == "cp1256") {
+        table = WINDOWS_1256;
+    } else if (name == "koi8-r" || name == "koi8r") {
+        table = KOI8_R;
+    }
+    
+    std::string out;
+    out.reserve(body.size());
+    
+    if (table || latin1) {
+        for (uint8_t byte : body) {
+            if (byte < 0x80) {
+                append_utf8(out, byte);
+            } else if (latin1) {
+                append_utf8(out, byte);
+            } else {
+                append_utf8(out, table[byte - 0x80]);
+            }
+        }
+        return out;
+    }
+    
+    // UTF-8 (RFC 3629), skipping malformed sequences
+    size_t i = 0;
+    while (i < body.size()) {
+        uint8_t lead = body[i];
+        size_t length = 0;
+        uint32_t cp = 0;
+        if (lead < 0x80) {
+            length = 1;
+            cp = lead;
+        } else if ((lead & 0xE0) == 0xC0) {
+            length = 2;
+            cp = lead & 0x1F;
+        } else if ((lead & 0xF0) == 0xE0) {
+            length = 3;
+            cp = lead & 0x0F;
+        } else if ((lead & 0xF8) == 0xF0) {
+            length = 4;
+            cp = lead & 0x07;
+        } else {
+            i++;
+            continue;
+        }
+        
+        if (i + length > body.size()) {
+            break;
+        }
+        
+        bool valid = true;
+        for (size_t k = 1; k < length; ++k) {
+            if ((body[i + k] & 0xC0) != 0x80) {
+                valid = false;
+                break;
+            }
+            cp = (cp << 6) | (body[i + k] & 0x3F);
+        }
+        
+        // Reject overlong forms, surrogates and out-of-range code points
+        static const uint32_t min_cp[5] = {0, 0, 0x80, 0x800, 0x10000};
+        if (!valid || cp < min_cp[length] || (cp >= 0xD800 && cp <= 0xDFFF) || cp > 0x10FFFF) {
+            i++;
+            continue;
+        }
+        
+        append_utf8(out, cp);
+        i += length;
+    }
+    return out;
+}
+
+std::string to_lower_utf8(const std::string& text) {
+    std::string out;
+    out.reserve(text.size());
+    
+    size_t i = 0;
+    while (i < text.size()) {
+        uint8_t lead = static_cast<uint8_t>(text[i]);
+        if (lead < 0x80) {
+            out += static_cast<char>(std::tolower(lead));
+            i++;
+            continue;
+        }
+        
+        // Only two-byte sequences hold the cased letters handled here (U+0080-U+07FF)
+        if ((lead & 0xE0) == 0xC0 && i + 1 < text.size()) {
+            uint32_t cp = ((lead & 0x1F) << 6) | (static_cast<uint8_t>(text[i + 1]) & 0x3F);
+            if ((cp >= 0xC0 && cp <= 0xDE && cp != 0xD7) ||  // Latin-1: A-grave..Thorn
+                (cp >= 0x391 && cp <= 0x3AB) ||               // Greek capitals
+                (cp >= 0x410 && cp <= 0x42F)) {               // Cyrillic A..YA
+                cp += 0x20;
+            } else if (cp >= 0x400 && cp <= 0x40F) {          // Cyrillic IE-grave..DZHE
+                cp += 0x50;
+            }
+            append_utf8(out, cp);
+            i += 2;
+            continue;
+        }
+        
+        out += text[i];
+        i++;
+    }
+    return out;
+}
+
+} // namespace charset
@@ -0,0 +1,30 @@
+#ifndef CHARSET_H
+#define CHARSET_H
+
+#include <string>
+#include <vector>
+#include <cstdint>
+
+// Character set decoding for response bodies
+// RFC 7231 Section 3.1.1.2 - charset parameter of Content-Type
+// WHATWG Encoding Standard - legacy single-byte encodings
+// No external dependencies - decoding tables are built in
+
+namespace charset {
+
+// Determine a body's charset: the Content-Type charset parameter, else a
+// <meta charset> / <meta http-equiv="Content-Type"> declaration near the start
+// of the body, else "utf-8". Returned lowercased
+std::string detect(const std::string& content_type, const std::vector<uint8_t>& body);
+
+// Decode body to UTF-8. Supports utf-8, iso-8859-1 (latin1), windows-1252,
+// windows-1251, windows-1256 and koi8-r; unknown charsets are decoded as UTF-8.
+// Lossy: invalid sequences and control characters (except tab, CR, LF) are dropped
+std::string to_utf8(const std::vector<uint8_t>& body, const std::string& charset_name);
+
+// Lowercase UTF-8 text: ASCII, Latin-1 Supplement, Greek and Cyrillic
+std::string to_lower_utf8(const std::string& text);
+
+} // namespace charset
+
+#endif // CHARSET_H
@@ -1,17 +1,31 @@
 #include "cli.h"
 #include "utils.h"
+#include "upstream.h"
+#include "version.h"
+#include "audit.h"
 #include <sstream>
 #include <iomanip>
 #include <ctime>
+#include <algorithm>
+#include <cmath>
+#include <thread>
+#include <atomic>
+#include <map>
 
 ProxyCLI::ProxyCLI(
     std::shared_ptr<RunwayManager> runway_manager,
     std::shared_ptr<RoutingEngine> routing_engine,
-    std::shared_ptr<TargetAccessibilityTracker> tracker)
+    std::shared_ptr<TargetAccessibilityTracker> tracker,
+    std::shared_ptr<HealthMonitor> health_monitor,
+    std::shared_ptr<ProxyServer> proxy_server)
     : runway_manager_(runway_manager)
     , routing_engine_(routing_engine)
     , tracker_(tracker)
-    , json_output_(false) {
+    , health_monitor_(health_monitor)
+    , proxy_server_(proxy_server)
+    , json_output_(false)
+    , format_(OutputFormat::Text)
+    , exit_code_(0) {
 }
 
 std::string ProxyCLI::escape_json(const std::string& str) {
@@ -38,50 +52,208 @@ std::string ProxyCLI::escape_json(const std::string& str) {
     return oss.str();
 }
 
+std::string ProxyCLI::mode_to_string(RoutingMode mode) {
+    switch (mode) {
+        case RoutingMode::Latency: return "latency";
+        case RoutingMode::FirstAccessible: return "first_accessible";
+        case RoutingMode::RoundRobin: return "round_robin";
+    }
+    return "latency";
+}
+
+std::string ProxyCLI::state_to_string(RunwayState state) {
+    switch (state) {
+        case RunwayState::Unknown: return "unknown";
+        case RunwayState::Accessible: return "accessible";
+        case RunwayState::PartiallyAccessible: return "partially_accessible";
+        case RunwayState::Inaccessible: return "inaccessible";
+        case RunwayState::Testing: return "testing";
+    }
+    return "unknown";
+}
+
+bool ProxyCLI::parse_arguments(const std::vector<std::string>& args, const std::vector<std::string>& names,
+                               std::vector<std::string>& values) {
+    values.assign(names.size(), "");
+    size_t next = 0;
+    for (size_t i = 1; i < args.size(); ++i) { // args[0] is the command
+        if (args[i].compare(0, 2, "--") != 0) {
+            if (next >= names.size()) {
+                return false;
+            }
+            values[next++] = args[i];
+            continue;
+        }
+        auto it = std::find(names.begin(), names.end(), args[i].substr(2));
+        if (it == names.end() || i + 1 >= args.size()) {
+            return false;
+        }
+        values[static_cast<size_t>(it - names.begin())] = args[++i];
+    }
+    return true;
+}
+
+bool ProxyCLI::split_target(const std::string& target, std::string& host, uint16_t& port) {
+    host = target;
+    size_t colon = target.rfind(':');
+    if (colon != std::string::npos && target.find(':') == colon) { // host:port (not a bare IPv6 address)
+        if (!utils::safe_str_to_uint16(target.substr(colon + 1), port) || port == 0) {
+            return false;
+        }
+        host = target.substr(0, colon);
+    }
+    return true;
+}
+
+void ProxyCLI::print_added_runways(const std::string& what, const std::vector<std::string>& runway_ids) {
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"added_runways\": " << runway_ids.size() << ",\n";
+        oss << "  \"runway_ids\": [";
+        for (size_t i = 0; i < runway_ids.size(); ++i) {
+            oss << (i > 0 ? ", " : "") << "\"" << escape_json(runway_ids[i]) << "\"";
+        }
+        oss << "]\n";
+        oss << "}";
+        print_json(oss.str());
+    } else {
+        print("Added " + what + " (" + std::to_string(runway_ids.size()) + " runways)\n");
+        for (const auto& runway_id : runway_ids) {
+            print("  " + runway_id + "\n");
+        }
+    }
+}
+
+void ProxyCLI::print(const std::string& text) {
+    output_ += text;
+}
+
+void ProxyCLI::print_error(const std::string& message) {
+    output_ += "Error: " + message + "\n";
+    exit_code_ = 1;
+}
+
+// Table cell for a rate or a duration in seconds
+static std::string fixed3(double value) {
+    std::ostringstream oss;
+    oss << std::fixed << std::setprecision(3) << value;
+    return oss.str();
+}
+
+void ProxyCLI::print_table(const Table& table) {
+    print(format_ == OutputFormat::Csv ? table.to_csv() : table.to_text());
+}
+
 void ProxyCLI::print_json(const std::string& json) {
     if (json_output_) {
-        utils::safe_print(json);
-        utils::safe_print("\n");
+        print(json);
+        print("\n");
     } else {
         // Pretty print JSON (simplified)
-        utils::safe_print(json);
-        utils::safe_print("\n");
+        print(json);
+        print("\n");
     }
 }
 
 int ProxyCLI::execute(const std::vector<std::string>& args) {
-    if (args.empty()) {
-        utils::safe_print("Smart Proxy CLI\n");
-        utils::safe_print("Usage: smartproxy <command> [options]\n");
-        utils::safe_print("\nCommands:\n");
-        utils::safe_print("  status              Show current status\n");
-        utils::safe_print("  runways             List all runways\n");
-        utils::safe_print("  targets             Show target accessibility matrix\n");
-        utils::safe_print("  stats               Show performance statistics\n");
-        utils::safe_print("  mode <mode>         Switch routing mode (latency/first_accessible/round_robin)\n");
-        utils::safe_print("  test <target> [id]   Test target accessibility\n");
-        utils::safe_print("  reload              Reload configuration\n");
-        utils::safe_print("\nOptions:\n");
-        utils::safe_print("  --json              Output in JSON format\n");
+    if (args.empty() || args[0] == "help") {
+        print("Smart Proxy CLI\n");
+        print("Usage: smartproxy <command> [options]\n");
+        print("\nCommands:\n");
+        print("  status              Show current status\n");
+        print("  runways             List all runways\n");
+        print("  targets             Show target accessibility matrix\n");
+        print("  stats               Show performance statistics\n");
+        print("  dashboard           Show runways and targets at a glance\n");
+        print("  watch [seconds]     Refresh the dashboard live (default every 2 seconds)\n");
+        print("  logs [--follow] [--lines N] [--target host] [--runway id]\n");
+        print("                      Show the end of the log file, optionally only matching lines\n");
+        print("  validate [--config path]\n");
+        print("                      Check a config file without starting the service\n");
+        print("  reset-stats [target]\n");
+        print("                      Forget the metrics of one target, or of all\n");
+        print("  export-metrics [--out <file>]\n");
+        print("                      Write every target's metrics as JSON (default: stdout)\n");
+        print("  mode <mode> [--persist]\n");
+        print("                      Switch routing mode (latency/first_accessible/round_robin);\n");
+        print("                      --persist also saves it to config.json\n");
+        print("  test <target>[:port] [id]\n");
+        print("                      Test target accessibility (port defaults to 80)\n");
+        print("  test-batch <target>[:port]... | --file <file>\n");
+        print("                      Test many targets through every runway at once and show\n");
+        print("                      which runways reach which targets\n");
+        print("  compare <target>    Rank all runways for a target side by side\n");
+        print("  diff <target> <target>\n");
+        print("                      Show per runway where two targets' accessibility or latency differ\n");
+        print("  simulate <target>   Show which runway a request would use now and why, without sending one\n");
+        print("  bench <target>[:port] [--runway id] [--count N]\n");
+        print("                      Time N GET requests (default 10) through each runway, or one,\n");
+        print("                      and show success rate, latency percentiles and status codes\n");
+        print("  dns                 Test each DNS server and show its health\n");
+        print("  dns show            List cached DNS answers with their remaining TTL\n");
+        print("  dns purge [domain]  Drop cached DNS answers (all, or one domain's)\n");
+        print("  reload              Reload configuration\n");
+        print("  set <key> <value> [--persist]\n");
+        print("                      Change a setting while running (--persist saves it to config.json):\n");
+        print("                      routing_mode, success_rate_threshold, proxy_failure_threshold,\n");
+        print("                      dns_timeout, health_probe_timeout, health_check_concurrency\n");
+        print("  add-proxy <type> <host> <port> [user] [--password-file <file>]\n");
+        print("                      Add an upstream proxy (http/https/socks4/socks4a/socks5/socks5h)\n");
+        print("                      and list its new runways; also --type, --host, --port, ...;\n");
+        print("                      with a user and no --password-file (- = stdin), prompts for it\n");
+        print("  remove-proxy <host> <port>\n");
+        print("                      Remove an upstream proxy and its runways\n");
+        print("  add-dns <host> [port] [name]\n");
+        print("                      Add a DNS server and list its new runways\n");
+        print("  remove-runway <id>  Remove a runway\n");
+        print("  disable-runway <id> Take a runway out of rotation\n");
+        print("  enable-runway <id>  Put a disabled runway back into rotation\n");
+        print("  pause-health        Stop health probing (metrics are kept)\n");
+        print("  resume-health       Resume health probing\n");
+        print("  fingerprint <file>  Print the fingerprint_file hash of a saved block page\n");
+        print("  completions <shell> Print a completion script for bash, zsh or fish\n");
+        print("  version             Show build version, commit and supported features\n");
+        print("\nOptions:\n");
+        print("  --json              Output in JSON format\n");
+        print("  --format <format>   text, table, csv or json; table and csv for runways,\n");
+        print("                      targets and stats\n");
         return 0;
     }
     
-    // Check for --json flag (can be anywhere in args)
+    // Check for --json and --format flags (can be anywhere in args)
     std::vector<std::string> filtered_args;
-    for (const auto& arg : args) {
-        if (arg == "--json") {
+    for (size_t i = 0; i < args.size(); ++i) {
+        if (args[i] == "--json") {
             json_output_ = true;
+        } else if (args[i] == "--format") {
+            std::string format = i + 1 < args.size() ? utils::to_lower(args[++i]) : "";
+            if (format == "json") {
+                json_output_ = true;
+            } else if (format == "table") {
+                format_ = OutputFormat::Table;
+            } else if (format == "csv") {
+                format_ = OutputFormat::Csv;
+            } else if (format != "text") {
+                print_error("--format takes text, table, csv or json");
+                return 1;
+            }
         } else {
-            filtered_args.push_back(arg);
+            filtered_args.push_back(args[i]);
         }
     }
     
     if (filtered_args.empty()) {
-        utils::safe_print("Error: No command specified\n");
+        print_error("No command specified");
         return 1;
     }
     
     std::string command = filtered_args[0];
+    if (format_ != OutputFormat::Text && !json_output_ &&
+        command != "runways" && command != "targets" && command != "stats") {
+        print_error("--format table and csv apply to runways, targets and stats");
+        return 1;
+    }
     
     if (command == "status") {
         status();
@@ -91,60 +263,217 @@ int ProxyCLI::execute(const std::vector<std::string>& args) {
         targets();
     } else if (command == "stats") {
         stats();
+    } else if (command == "dashboard") {
+        dashboard();
+    } else if (command == "reset-stats") {
+        reset_stats(filtered_args.size() > 1 ? filtered_args[1] : "");
+    } else if (command == "export-metrics") {
+        export_metrics();
     } else if (command == "mode") {
+        bool persist = std::find(filtered_args.begin(), filtered_args.end(), "--persist") != filtered_args.end();
+        filtered_args.erase(std::remove(filtered_args.begin(), filtered_args.end(), "--persist"), filtered_args.end());
         if (filtered_args.size() < 2) {
-            utils::safe_print("Error: mode requires an argument (latency/first_accessible/round_robin)\n");
+            print_error("mode requires an argument (latency/first_accessible/round_robin)");
             return 1;
         }
-        mode(filtered_args[1]);
+        mode(filtered_args[1], persist);
     } else if (command == "test") {
         if (filtered_args.size() < 2) {
-            utils::safe_print("Error: test requires a target argument\n");
+            print_error("test requires a target argument");
             return 1;
         }
         std::string runway_id = (filtered_args.size() > 2) ? filtered_args[2] : "";
-        test(filtered_args[1], runway_id);
+        std::string target;
+        uint16_t port = 80;
+        if (!split_target(filtered_args[1], target, port)) {
+            print_error("Invalid port in '" + filtered_args[1] + "'");
+            return 1;
+        }
+        test(target, runway_id, port);
+    } else if (command == "test-batch") {
+        std::vector<std::pair<std::string, uint16_t>> targets;
+        for (size_t i = 1; i < filtered_args.size(); ++i) {
+            std::string target;
+            uint16_t port = 80;
+            if (!split_target(filtered_args[i], target, port)) {
+                print_error("Invalid port in '" + filtered_args[i] + "'");
+                return 1;
+            }
+            targets.emplace_back(target, port);
+        }
+        if (targets.empty()) {
+            print_error("test-batch requires targets, or --file with one target per line");
+            return 1;
+        }
+        test_batch(targets);
+    } else if (command == "bench") {
+        std::vector<std::string> values; // target, runway, count
+        std::string target;
+        uint16_t port = 80;
+        uint32_t count = 10;
+        if (!parse_arguments(filtered_args, {"target", "runway", "count"}, values) || values[0].empty() ||
+            (!values[2].empty() && (!utils::safe_str_to_uint32(values[2], count) || count == 0 || count > MAX_BENCH_COUNT))) {
+            print_error("bench requires <target>[:port] [--runway id] [--count N] (N from 1 to " +
+                        std::to_string(MAX_BENCH_COUNT) + ")");
+            return 1;
+        }
+        if (!split_target(values[0], target, port)) {
+            print_error("Invalid port in '" + values[0] + "'");
+            return 1;
+        }
+        bench(target, values[1], port, count);
+    } else if (command == "diff") {
+        if (filtered_args.size() < 3) {
+            print_error("diff requires two targets");
+            return 1;
+        }
+        diff(filtered_args[1], filtered_args[2]);
+    } else if (command == "simulate") {
+        std::string target;
+        uint16_t port = 80;
+        if (filtered_args.size() < 2) {
+            print_error("simulate requires a target argument");
+            return 1;
+        }
+        if (!split_target(filtered_args[1], target, port)) {
+            print_error("Invalid port in '" + filtered_args[1] + "'");
+            return 1;
+        }
+        simulate(target);
+    } else if (command == "compare") {
+        if (filtered_args.size() < 2) {
+            print_error("compare requires a target argument");
+            return 1;
+        }
+        compare(filtered_args[1]);
+    } else if (command == "dns") {
+        if (filtered_args.size() > 1 && filtered_args[1] == "purge") {
+            dns_purge(filtered_args.size() > 2 ? filtered_args[2] : "");
+        } else if (filtered_args.size() > 1 && filtered_args[1] == "show") {
+            dns_show();
+        } else if (filtered_args.size() > 1) {
+            print_error("Unknown dns subcommand '" + filtered_args[1] + "'");
+            return 1;
+        } else {
+            dns();
+        }
     } else if (command == "reload") {
         reload();
+    } else if (command == "set") {
+        bool persist = std::find(filtered_args.begin(), filtered_args.end(), "--persist") != filtered_args.end();
+        filtered_args.erase(std::remove(filtered_args.begin(), filtered_args.end(), "--persist"), filtered_args.end());
+        if (filtered_args.size() < 3) {
+            print_error("set requires <key> <value> [--persist]");
+            return 1;
+        }
+        set(filtered_args[1], filtered_args[2], persist);
+    } else if (command == "add-proxy") {
+        std::vector<std::string> values; // type, host, port, username, password
+        uint16_t port = 0;
+        if (!parse_arguments(filtered_args, {"type", "host", "port", "username", "password"}, values) ||
+            values[0].empty() || values[1].empty() || !utils::safe_str_to_uint16(values[2], port) || port == 0) {
+            print_error("add-proxy requires <type> <host> <port> [user] [password] "
+                        "(or --type, --host, --port, --username, --password)");
+            return 1;
+        }
+        UpstreamProxyConfig proxy(values[0], values[1], port);
+        proxy.username = values[3];
+        proxy.password = values[4];
+        add_proxy(proxy);
+    } else if (command == "remove-proxy") {
+        uint16_t port = 0;
+        if (filtered_args.size() < 3 || !utils::safe_str_to_uint16(filtered_args[2], port)) {
+            print_error("remove-proxy requires <host> <port>");
+            return 1;
+        }
+        remove_proxy(filtered_args[1], port);
+    } else if (command == "add-dns") {
+        std::vector<std::string> values; // host, port, name
+        uint16_t port = 53;
+        if (!parse_arguments(filtered_args, {"host", "port", "name"}, values) || !utils::is_valid_ipv4(values[0]) ||
+            (!values[1].empty() && (!utils::safe_str_to_uint16(values[1], port) || port == 0))) {
+            print_error("add-dns requires an IPv4 <host> [port] [name] (or --host, --port, --name)");
+            return 1;
+        }
+        add_dns(DNSServerConfig(values[0], port, values[2].empty() ? values[0] : values[2]));
+    } else if (command == "remove-runway") {
+        if (filtered_args.size() < 2) {
+            print_error("remove-runway requires a runway id");
+            return 1;
+        }
+        remove_runway(filtered_args[1]);
+    } else if (command == "enable-runway" || command == "disable-runway") {
+        if (filtered_args.size() < 2) {
+            print_error(command + " requires a runway id");
+            return 1;
+        }
+        set_runway_enabled(filtered_args[1], command == "enable-runway");
+    } else if (command == "pause-health" || command == "resume-health") {
+        set_health_paused(command == "pause-health");
     } else {
-        utils::safe_print("Error: Unknown command '" + command + "'\n");
+        print_error("Unknown command '" + command + "'");
         return 1;
     }
     
-    return 0;
+    return exit_code_;
 }
 
 void ProxyCLI::status() {
     auto all_runways = runway_manager_->get_all_runways();
     auto all_targets = tracker_->get_all_targets();
-    RoutingMode current_mode = routing_engine_->get_mode();
-    
-    std::string mode_str;
-    switch (current_mode) {
-        case RoutingMode::Latency: mode_str = "latency"; break;
-        case RoutingMode::FirstAccessible: mode_str = "first_accessible"; break;
-        case RoutingMode::RoundRobin: mode_str = "round_robin"; break;
-    }
+    auto interfaces = runway_manager_->get_interfaces();
+    std::string mode_str = mode_to_string(routing_engine_->get_mode());
     
     if (json_output_) {
         std::ostringstream oss;
         oss << "{\n";
+        oss << "  \"version\": \"" << escape_json(version::number()) << "\",\n";
         oss << "  \"routing_mode\": \"" << escape_json(mode_str) << "\",\n";
         oss << "  \"runways_count\": " << all_runways.size() << ",\n";
         oss << "  \"targets_count\": " << all_targets.size() << ",\n";
+        oss << "  \"interfaces\": [\n";
+        for (size_t i = 0; i < interfaces.size(); ++i) {
+            const auto& iface = interfaces[i];
+            oss << "    {\"name\": \"" << escape_json(iface.name) << "\", ";
+            oss << "\"ip\": " << (iface.ip.empty() ? "null" : "\"" + escape_json(iface.ip) + "\"") << ", ";
+            oss << "\"ipv6\": " << (iface.ipv6.empty() ? "null" : "\"" + escape_json(iface.ipv6) + "\"") << ", ";
+            oss << "\"gateway\": " << (iface.gateway.empty() ? "null" : "\"" + escape_json(iface.gateway) + "\"") << ", ";
+            oss << "\"metric\": " << iface.metric << "}";
+            if (i < interfaces.size() - 1) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ],\n";
+        if (health_monitor_) {
+            oss << "  \"health_monitor\": \"" << (health_monitor_->is_paused() ? "paused" : "running") << "\",\n";
+        }
         oss << "  \"status\": \"running\"\n";
         oss << "}";
         print_json(oss.str());
     } else {
-        utils::safe_print("Routing Mode: " + mode_str + "\n");
-        utils::safe_print("Runways: " + std::to_string(all_runways.size()) + "\n");
-        utils::safe_print("Targets: " + std::to_string(all_targets.size()) + "\n");
-        utils::safe_print("Status: running\n");
+        print(std::string("Version: ") + version::number() + "\n");
+        print("Routing Mode: " + mode_str + "\n");
+        print("Runways: " + std::to_string(all_runways.size()) + "\n");
+        print("Targets: " + std::to_string(all_targets.size()) + "\n");
+        if (health_monitor_) {
+            print(std::string("Health Monitor: ") + (health_monitor_->is_paused() ? "paused" : "running") + "\n");
+        }
+        print("Interfaces: " + std::to_string(interfaces.size()) + "\n");
+        for (const auto& iface : interfaces) {
+            print("  " + iface.name + ": " + (iface.ip.empty() ? "-" : iface.ip) +
+                  (iface.ipv6.empty() ? "" : " " + iface.ipv6) +
+                  " [gateway: " + (iface.gateway.empty() ? "unknown" : iface.gateway) +
+                  ", metric: " + std::to_string(iface.metric) + "]\n");
+        }
+        print("Status: running\n");
     }
 }
 
 void ProxyCLI::runways() {
-    auto all_runways = runway_manager_->get_all_runways();
+    auto all_runways = runway_manager_->get_all_runways(true);
+    std::map<std::string, std::string> gateways; // interface -> default gateway
+    for (const auto& iface : runway_manager_->get_interfaces()) {
+        gateways[iface.name] = iface.gateway;
+    }
     
     if (json_output_) {
         std::ostringstream oss;
@@ -154,13 +483,16 @@ void ProxyCLI::runways() {
             const auto& r = all_runways[i];
             oss << "    {\n";
             oss << "      \"id\": \"" << escape_json(r->id) << "\",\n";
-            oss << "      \"interface\": \"" << escape_json(r->interface) << "\",\n";
+            oss << "      \"interface\": \"" << escape_json(r->interface_name) << "\",\n";
             oss << "      \"source_ip\": " << (r->source_ip.empty() ? "null" : "\"" + escape_json(r->source_ip) + "\"") << ",\n";
             oss << "      \"is_direct\": " << (r->is_direct ? "true" : "false") << ",\n";
+            oss << "      \"enabled\": " << (r->enabled ? "true" : "false") << ",\n";
+            oss << "      \"in_flight\": " << r->get_in_flight() << ",\n";
+            oss << "      \"metric\": " << r->metric << ",\n";
+            const std::string& gateway = gateways[r->interface_name];
+            oss << "      \"gateway\": " << (gateway.empty() ? "null" : "\"" + escape_json(gateway) + "\"") << ",\n";
             if (r->upstream_proxy) {
-                std::string proxy_str = r->upstream_proxy->config.proxy_type + "://" +
-                                       r->upstream_proxy->config.host + ":" +
-                                       std::to_string(r->upstream_proxy->config.port);
+                std::string proxy_str = upstream::describe(r->upstream_proxy->config);
                 oss << "      \"upstream_proxy\": \"" << escape_json(proxy_str) << "\",\n";
             } else {
                 oss << "      \"upstream_proxy\": null,\n";
@@ -180,13 +512,28 @@ void ProxyCLI::runways() {
         oss << "  \"count\": " << all_runways.size() << "\n";
         oss << "}";
         print_json(oss.str());
+    } else if (format_ != OutputFormat::Text) {
+        Table table({"id", "interface", "source_ip", "direct", "enabled", "in_flight", "metric", "gateway",
+                     "upstream_proxy", "dns_server"});
+        for (const auto& r : all_runways) {
+            table.add_row({r->id, r->interface_name, r->source_ip, r->is_direct ? "yes" : "no",
+                           r->enabled ? "yes" : "no", std::to_string(r->get_in_flight()), std::to_string(r->metric),
+                           gateways[r->interface_name],
+                           r->upstream_proxy ? upstream::describe(r->upstream_proxy->config) : "",
+                           r->dns_server ? r->dns_server->config.host + ":" + std::to_string(r->dns_server->config.port) : ""});
+        }
+        print_table(table);
     } else {
         for (const auto& r : all_runways) {
-            utils::safe_print(r->id + ": " + r->interface);
+            print(r->id + ": " + r->interface_name);
             if (!r->source_ip.empty()) {
-                utils::safe_print(" (" + r->source_ip + ")");
+                print(" (" + r->source_ip + ")");
             }
-            utils::safe_print(" [direct: " + std::string(r->is_direct ? "yes" : "no") + "]\n");
+            print(" [direct: " + std::string(r->is_direct ? "yes" : "no") +
+                  (r->enabled ? "" : ", disabled") +
+                  ", in-flight: " + std::to_string(r->get_in_flight()) +
+                  ", metric: " + std::to_string(r->metric) +
+                  ", gateway: " + (gateways[r->interface_name].empty() ? "unknown" : gateways[r->interface_name]) + "]\n");
         }
     }
 }
@@ -216,9 +563,13 @@ void ProxyCLI::targets() {
                 }
                 oss << "        \"state\": \"" << escape_json(state_str) << "\",\n";
                 oss << "        \"success_rate\": " << std::fixed << std::setprecision(3) << m.success_rate << ",\n";
+                oss << "        \"network_success_rate\": " << m.network_success_rate << ",\n";
                 oss << "        \"avg_response_time\": " << m.avg_response_time << ",\n";
+                oss << "        \"avg_dns_time\": " << m.avg_dns_time << ",\n";
                 oss << "        \"total_attempts\": " << m.total_attempts << ",\n";
                 oss << "        \"user_success_count\": " << m.user_success_count << ",\n";
+                oss << "        \"partial_success_count\": " << m.partial_success_count << ",\n";
+                oss << "        \"recovery_count\": " << m.recovery_count << ",\n";
                 oss << "        \"failure_count\": " << m.failure_count << "\n";
                 oss << "      }";
                 if (++j < metrics.size()) oss << ",";
@@ -231,10 +582,25 @@ void ProxyCLI::targets() {
         oss << "  }\n";
         oss << "}";
         print_json(oss.str());
+    } else if (format_ != OutputFormat::Text) {
+        Table table({"target", "runway", "state", "success_rate", "network_success_rate", "avg_response_time",
+                     "avg_dns_time", "total_attempts", "user_success_count", "partial_success_count",
+                     "recovery_count", "failure_count"});
+        for (const auto& target : all_targets) {
+            for (const auto& pair : tracker_->get_target_metrics(target)) {
+                const auto& m = pair.second;
+                table.add_row({target, pair.first, state_to_string(m.state), fixed3(m.success_rate),
+                               fixed3(m.network_success_rate), fixed3(m.avg_response_time), fixed3(m.avg_dns_time),
+                               std::to_string(m.total_attempts), std::to_string(m.user_success_count),
+                               std::to_string(m.partial_success_count), std::to_string(m.recovery_count),
+                               std::to_string(m.failure_count)});
+            }
+        }
+        print_table(table);
     } else {
         for (const auto& target : all_targets) {
             auto metrics = tracker_->get_target_metrics(target);
-            utils::safe_print(target + ": " + std::to_string(metrics.size()) + " runways\n");
+            print(target + ": " + std::to_string(metrics.size()) + " runways\n");
             for (const auto& pair : metrics) {
                 const auto& m = pair.second;
                 std::string state_str;
@@ -245,9 +611,14 @@ void ProxyCLI::targets() {
                     case RunwayState::Inaccessible: state_str = "inaccessible"; break;
                     case RunwayState::Testing: state_str = "testing"; break;
                 }
-                utils::safe_print("  " + pair.first + ": " + state_str +
-                                 " (success: " + std::to_string(m.user_success_count) +
-                                 ", failures: " + std::to_string(m.failure_count) + ")\n");
+                std::ostringstream rates;
+                rates << std::fixed << std::setprecision(1) << " [network: " << m.network_success_rate * 100.0
+                      << "%, user: " << m.success_rate * 100.0 << "%]";
+                print("  " + pair.first + ": " + state_str + rates.str() +
+                     " (success: " + std::to_string(m.user_success_count) +
+                     ", partial: " + std::to_string(m.partial_success_count) +
+                     ", failures: " + std::to_string(m.failure_count) +
+                     ", recoveries: " + std::to_string(m.recovery_count) + ")\n");
             }
         }
     }
@@ -256,18 +627,31 @@ void ProxyCLI::targets() {
 void ProxyCLI::stats() {
     auto all_targets = tracker_->get_all_targets();
     auto all_runways = runway_manager_->get_all_runways();
+    GlobalSummary summary = tracker_->global_summary();
     
     if (json_output_) {
         std::ostringstream oss;
         oss << "{\n";
+        oss << "  \"global\": {\n";
+        oss << "    \"total_requests\": " << summary.total_requests << ",\n";
+        oss << "    \"total_successes\": " << summary.total_successes << ",\n";
+        oss << "    \"total_partial_successes\": " << summary.total_partial_successes << ",\n";
+        oss << "    \"total_failures\": " << summary.total_failures << ",\n";
+        oss << "    \"overall_success_rate\": " << std::fixed << std::setprecision(3) << summary.overall_success_rate << ",\n";
+        oss << "    \"total_bytes\": " << summary.total_bytes << ",\n";
+        oss << "    \"busiest_runway\": " << (summary.busiest_runway.empty() ? "null" : "\"" + escape_json(summary.busiest_runway) + "\"") << ",\n";
+        oss << "    \"busiest_runway_requests\": " << summary.busiest_runway_requests << "\n";
+        oss << "  },\n";
         oss << "  \"total_targets\": " << all_targets.size() << ",\n";
         oss << "  \"total_runways\": " << all_runways.size() << ",\n";
+        oss << "  \"uptime_window_secs\": " << tracker_->get_uptime_window() << ",\n";
         oss << "  \"targets\": {\n";
         for (size_t i = 0; i < all_targets.size(); ++i) {
             const std::string& target = all_targets[i];
             auto metrics = tracker_->get_target_metrics(target);
             size_t accessible = 0, partial = 0, inaccessible = 0;
             uint64_t total_attempts = 0, total_successes = 0;
+            uint64_t total_partial_successes = 0, total_recoveries = 0;
             for (const auto& pair : metrics) {
                 const auto& m = pair.second;
                 switch (m.state) {
@@ -278,13 +662,25 @@ void ProxyCLI::stats() {
                 }
                 total_attempts += m.total_attempts;
                 total_successes += m.user_success_count;
+                total_partial_successes += m.partial_success_count;
+                total_recoveries += m.recovery_count;
             }
             oss << "    \"" << escape_json(target) << "\": {\n";
             oss << "      \"accessible_runways\": " << accessible << ",\n";
             oss << "      \"partially_accessible_runways\": " << partial << ",\n";
             oss << "      \"inaccessible_runways\": " << inaccessible << ",\n";
             oss << "      \"total_attempts\": " << total_attempts << ",\n";
-            oss << "      \"total_successes\": " << total_successes << "\n";
+            oss << "      \"total_successes\": " << total_successes << ",\n";
+            oss << "      \"total_partial_successes\": " << total_partial_successes << ",\n";
+            oss << "      \"total_recoveries\": " << total_recoveries << ",\n";
+            oss << "      \"uptime\": {";
+            size_t j = 0;
+            for (const auto& pair : metrics) {
+                double uptime = tracker_->get_uptime(target, pair.first);
+                oss << "\"" << escape_json(pair.first) << "\": " << std::fixed << std::setprecision(3) << uptime;
+                if (++j < metrics.size()) oss << ", ";
+            }
+            oss << "}\n";
             oss << "    }";
             if (i < all_targets.size() - 1) oss << ",";
             oss << "\n";
@@ -292,12 +688,48 @@ void ProxyCLI::stats() {
         oss << "  }\n";
         oss << "}";
         print_json(oss.str());
+    } else if (format_ != OutputFormat::Text) {
+        // One row per target, as in the JSON "targets" object (the global summary stays in text and JSON)
+        Table table({"target", "accessible_runways", "partially_accessible_runways", "inaccessible_runways",
+                     "total_attempts", "total_successes", "total_partial_successes", "total_recoveries"});
+        for (const auto& target : all_targets) {
+            size_t accessible = 0, partial = 0, inaccessible = 0;
+            uint64_t total_attempts = 0, total_successes = 0;
+            uint64_t total_partial_successes = 0, total_recoveries = 0;
+            for (const auto& pair : tracker_->get_target_metrics(target)) {
+                const auto& m = pair.second;
+                switch (m.state) {
+                    case RunwayState::Accessible: accessible++; break;
+                    case RunwayState::PartiallyAccessible: partial++; break;
+                    case RunwayState::Inaccessible: inaccessible++; break;
+                    default: break;
+                }
+                total_attempts += m.total_attempts;
+                total_successes += m.user_success_count;
+                total_partial_successes += m.partial_success_count;
+                total_recoveries += m.recovery_count;
+            }
+            table.add_row({target, std::to_string(accessible), std::to_string(partial), std::to_string(inaccessible),
+                           std::to_string(total_attempts), std::to_string(total_successes),
+                           std::to_string(total_partial_successes), std::to_string(total_recoveries)});
+        }
+        print_table(table);
     } else {
-        utils::safe_print("Total Targets: " + std::to_string(all_targets.size()) + "\n");
-        utils::safe_print("Total Runways: " + std::to_string(all_runways.size()) + "\n");
+        std::ostringstream rate;
+        rate << std::fixed << std::setprecision(1) << summary.overall_success_rate * 100.0;
+        print("Total Requests: " + std::to_string(summary.total_requests) + "\n");
+        print("Overall Success Rate: " + rate.str() + "%\n");
+        print("Total Bytes: " + utils::format_bytes(summary.total_bytes) + "\n");
+        if (!summary.busiest_runway.empty()) {
+            print("Busiest Runway: " + summary.busiest_runway + " (" +
+                  std::to_string(summary.busiest_runway_requests) + " requests)\n");
+        }
+        print("Total Targets: " + std::to_string(all_targets.size()) + "\n");
+        print("Total Runways: " + std::to_string(all_runways.size()) + "\n");
         for (const auto& target : all_targets) {
             auto metrics = tracker_->get_target_metrics(target);
             size_t accessible = 0, partial = 0, inaccessible = 0;
+            uint64_t total_partial_successes = 0, total_recoveries = 0;
             for (const auto& pair : metrics) {
                 switch (pair.second.state) {
                     case RunwayState::Accessible: accessible++; break;
@@ -305,16 +737,163 @@ void ProxyCLI::stats() {
                     case RunwayState::Inaccessible: inaccessible++; break;
                     default: break;
                 }
+                total_partial_successes += pair.second.partial_success_count;
+                total_recoveries += pair.second.recovery_count;
+            }
+            print("\n" + target + ":\n");
+            print("  Accessible: " + std::to_string(accessible) + "\n");
+            print("  Partially Accessible: " + std::to_string(partial) + "\n");
+            print("  Inaccessible: " + std::to_string(inaccessible) + "\n");
+            print("  Partial Successes: " + std::to_string(total_partial_successes) + "\n");
+            print("  Recoveries: " + std::to_string(total_recoveries) + "\n");
+            print("  Uptime (last " + std::to_string(tracker_->get_uptime_window()) + "s):\n");
+            for (const auto& pair : metrics) {
+                std::ostringstream uptime_oss;
+                uptime_oss << std::fixed << std::setprecision(1) << tracker_->get_uptime(target, pair.first) * 100.0;
+                print("    " + pair.first + ": " + uptime_oss.str() + "%\n");
             }
-            utils::safe_print("\n" + target + ":\n");
-            utils::safe_print("  Accessible: " + std::to_string(accessible) + "\n");
-            utils::safe_print("  Partially Accessible: " + std::to_string(partial) + "\n");
-            utils::safe_print("  Inaccessible: " + std::to_string(inaccessible) + "\n");
         }
     }
 }
 
-void ProxyCLI::mode(const std::string& mode_str) {
+void ProxyCLI::dashboard() {
+    auto all_runways = runway_manager_->get_all_runways(true);
+    auto all_targets = tracker_->get_all_targets();
+    GlobalSummary summary = tracker_->global_summary();
+    
+    std::string mode_str = mode_to_string(routing_engine_->get_mode());
+    
+    // Per-runway totals over all targets
+    struct RunwayTotals {
+        size_t accessible_targets = 0;
+        uint64_t attempts = 0;
+        uint64_t successes = 0;
+        double latency_sum = 0.0;
+        size_t latency_count = 0;
+    };
+    std::map<std::string, RunwayTotals> totals;
+    
+    size_t id_width = 6;
+    size_t target_width = 6;
+    for (const auto& r : all_runways) {
+        id_width = std::max(id_width, r->id.length());
+    }
+    for (const auto& target : all_targets) {
+        target_width = std::max(target_width, target.length());
+    }
+    
+    std::ostringstream targets_table;
+    targets_table << std::left << std::setw(static_cast<int>(target_width + 2)) << "TARGET" << std::right
+                  << std::setw(6) << "UP" << std::setw(9) << "PARTIAL" << std::setw(6) << "DOWN" << "  "
+                  << std::left << std::setw(static_cast<int>(id_width + 2)) << "BEST RUNWAY" << std::right
+                  << std::setw(8) << "SUCCESS" << std::setw(11) << "LATENCY" << "\n";
+    for (const auto& target : all_targets) {
+        auto ranked = tracker_->get_ranked_runways(target);
+        size_t accessible = 0, partial = 0, inaccessible = 0;
+        for (const auto& m : ranked) {
+            switch (m.state) {
+                case RunwayState::Accessible: accessible++; break;
+                case RunwayState::PartiallyAccessible: partial++; break;
+                case RunwayState::Inaccessible: inaccessible++; break;
+                default: break;
+            }
+            RunwayTotals& t = totals[m.runway_id];
+            if (m.state == RunwayState::Accessible) {
+                t.accessible_targets++;
+            }
+            t.attempts += m.total_attempts;
+            t.successes += m.user_success_count;
+            if (m.avg_response_time > 0.0) {
+                t.latency_sum += m.avg_response_time;
+                t.latency_count++;
+            }
+        }
+        
+        std::string best = "-";
+        std::ostringstream success;
+        std::ostringstream latency;
+        success << "-";
+        latency << "-";
+        if (!ranked.empty() && ranked[0].state != RunwayState::Inaccessible) {
+            const auto& m = ranked[0];
+            best = m.runway_id;
+            success.str("");
+            success << std::fixed << std::setprecision(1) << m.success_rate * 100.0 << "%";
+            if (m.avg_response_time > 0.0) {
+                latency.str("");
+                latency << std::fixed << std::setprecision(3) << m.avg_response_time << "s";
+            }
+        }
+        targets_table << std::left << std::setw(static_cast<int>(target_width + 2)) << target << std::right
+                      << std::setw(6) << accessible << std::setw(9) << partial << std::setw(6) << inaccessible << "  "
+                      << std::left << std::setw(static_cast<int>(id_width + 2)) << best << std::right
+                      << std::setw(8) << success.str() << std::setw(11) << latency.str() << "\n";
+    }
+    
+    size_t enabled = 0;
+    std::ostringstream runways_table;
+    runways_table << std::left << std::setw(static_cast<int>(id_width + 2)) << "RUNWAY" << std::setw(10) << "STATUS"
+                  << std::right << std::setw(10) << "IN-FLIGHT" << std::setw(9) << "TARGETS"
+                  << std::setw(8) << "SUCCESS" << std::setw(11) << "LATENCY" << "\n";
+    for (const auto& r : all_runways) {
+        if (r->enabled) {
+            enabled++;
+        }
+        const RunwayTotals& t = totals[r->id];
+        std::ostringstream success;
+        std::ostringstream latency;
+        if (t.attempts > 0) {
+            success << std::fixed << std::setprecision(1) << 100.0 * static_cast<double>(t.successes) / static_cast<double>(t.attempts) << "%";
+        } else {
+            success << "-";
+        }
+        if (t.latency_count > 0) {
+            latency << std::fixed << std::setprecision(3) << t.latency_sum / static_cast<double>(t.latency_count) << "s";
+        } else {
+            latency << "-";
+        }
+        runways_table << std::left << std::setw(static_cast<int>(id_width + 2)) << r->id
+                      << std::setw(10) << (r->enabled ? "enabled" : "disabled") << std::right
+                      << std::setw(10) << r->get_in_flight() << std::setw(9) << t.accessible_targets
+                      << std::setw(8) << success.str() << std::setw(11) << latency.str() << "\n";
+    }
+    
+    std::ostringstream header;
+    header << "Routing Mode: " << mode_str;
+    if (health_monitor_) {
+        header << "   Health Monitor: " << (health_monitor_->is_paused() ? "paused" : "running");
+    }
+    header << "\nRunways: " << enabled << " enabled / " << all_runways.size()
+           << "   Targets: " << all_targets.size()
+           << "   Requests: " << summary.total_requests << " (" << std::fixed << std::setprecision(1)
+           << summary.overall_success_rate * 100.0 << "% success)\n";
+    
+    print(header.str() + "\n" + targets_table.str() + "\n" + runways_table.str());
+}
+
+void ProxyCLI::reset_stats(const std::string& target) {
+    size_t cleared = tracker_->reset(target);
+    if (!target.empty() && cleared == 0) {
+        print_error("No metrics recorded for " + target);
+        return;
+    }
+    if (cleared > 0) {
+        audit::record("reset-stats", target.empty() ? "*" : target, "metrics for " + std::to_string(cleared) + " targets", "");
+    }
+    
+    if (json_output_) {
+        print_json("{\"reset_targets\": " + std::to_string(cleared) + "}");
+    } else {
+        print("Reset metrics for " + std::to_string(cleared) + " targets\n");
+    }
+}
+
+void ProxyCLI::export_metrics() {
+    // Always JSON: the export is meant for other tools
+    print(tracker_->to_json() + "\n");
+}
+
+void ProxyCLI::mode(const std::string& mode_str, bool persist) {
     RoutingMode mode;
     std::string mode_lower = utils::to_lower(mode_str);
     
@@ -325,25 +904,68 @@ void ProxyCLI::mode(const std::string& mode_str) {
     } else if (mode_lower == "round_robin") {
         mode = RoutingMode::RoundRobin;
     } else {
-        utils::safe_print("Error: Invalid routing mode '" + mode_str + "'. Valid modes: latency, first_accessible, round_robin\n");
+        print_error("Invalid routing mode '" + mode_str + "'. Valid modes: latency, first_accessible, round_robin");
         return;
     }
     
-    routing_engine_->set_mode(mode);
-    if (!json_output_) {
-        utils::safe_print("Routing mode changed to " + mode_str + "\n");
+    // Persisting goes through the admin server, which applies the mode and writes the config file
+    bool saved = false;
+    std::vector<std::string> errors;
+    if (persist) {
+        if (!setting_handler_) {
+            print_error("Saving settings is not available here");
+            return;
+        }
+        size_t pos = 0;
+        std::map<std::string, std::string> reply;
+        if (!Config::parse_object(setting_handler_("routing_mode", mode_lower, true), pos, reply)) {
+            print_error("Unexpected set result");
+            return;
+        }
+        saved = utils::trim(reply["persisted"]) == "true";
+        Config::parse_string_array(reply["errors"], errors);
+    } else {
+        std::string previous = mode_to_string(routing_engine_->get_mode());
+        routing_engine_->set_mode(mode);
+        if (previous != mode_lower) {
+            audit::record("mode", "routing_mode", previous, mode_lower);
+        }
+    }
+    
+    // Report the mode actually in effect, read back from the routing engine
+    std::string effective = mode_to_string(routing_engine_->get_mode());
+    if (effective != mode_lower) {
+        errors.push_back("Routing mode is still " + effective);
+    }
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"routing_mode\": \"" << effective << "\",\n";
+        oss << "  \"persisted\": " << (saved ? "true" : "false") << "\n";
+        oss << "}";
+        print_json(oss.str());
+        if (!errors.empty()) {
+            exit_code_ = 1;
+        }
+        return;
+    }
+    if (effective == mode_lower) {
+        print("Routing mode is now " + effective + (saved ? " (saved to the config file)" : "") + "\n");
+    }
+    for (const auto& error : errors) {
+        print_error(error);
     }
 }
 
-void ProxyCLI::test(const std::string& target, const std::string& runway_id) {
+void ProxyCLI::test(const std::string& target, const std::string& runway_id, uint16_t port) {
     if (!runway_id.empty()) {
         auto runway = runway_manager_->get_runway(runway_id);
         if (!runway) {
-            utils::safe_print("Error: Runway " + runway_id + " not found\n");
+            print_error("Runway " + runway_id + " not found");
             return;
         }
         
-        auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0);
+        auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0, port);
         bool net_success = std::get<0>(result);
         bool user_success = std::get<1>(result);
         double response_time = std::get<2>(result);
@@ -359,9 +981,9 @@ void ProxyCLI::test(const std::string& target, const std::string& runway_id) {
             oss << "}";
             print_json(oss.str());
         } else {
-            utils::safe_print("Network: " + std::string(net_success ? "success" : "failed") + "\n");
-            utils::safe_print("User: " + std::string(user_success ? "success" : "failed") + "\n");
-            utils::safe_print("Response Time: " + std::to_string(response_time) + "s\n");
+            print("Network: " + std::string(net_success ? "success" : "failed") + "\n");
+            print("User: " + std::string(user_success ? "success" : "failed") + "\n");
+            print("Response Time: " + std::to_string(response_time) + "s\n");
         }
     } else {
         auto all_runways = runway_manager_->get_all_runways();
@@ -372,7 +994,7 @@ void ProxyCLI::test(const std::string& target, const std::string& runway_id) {
             oss << "  \"results\": [\n";
             for (size_t i = 0; i < all_runways.size(); ++i) {
                 const auto& runway = all_runways[i];
-                auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0);
+                auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0, port);
                 bool net_success = std::get<0>(result);
                 bool user_success = std::get<1>(result);
                 double response_time = std::get<2>(result);
@@ -391,22 +1013,759 @@ void ProxyCLI::test(const std::string& target, const std::string& runway_id) {
             print_json(oss.str());
         } else {
             for (const auto& runway : all_runways) {
-                auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0);
+                auto result = runway_manager_->test_runway_accessibility(target, runway, 5.0, port);
                 bool net_success = std::get<0>(result);
                 bool user_success = std::get<1>(result);
                 double response_time = std::get<2>(result);
-                utils::safe_print(runway->id + ": net=" + (net_success ? "ok" : "fail") +
-                                 ", user=" + (user_success ? "ok" : "fail") +
-                                 ", time=" + std::to_string(response_time) + "s\n");
+                print(runway->id + ": net=" + (net_success ? "ok" : "fail") +
+                     ", user=" + (user_success ? "ok" : "fail") +
+                     ", time=" + std::to_string(response_time) + "s\n");
+            }
+        }
+    }
+}
+
+void ProxyCLI::compare(const std::string& target) {
+    auto ranked = tracker_->get_ranked_runways(target);
+    
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"target\": \"" << escape_json(target) << "\",\n";
+        oss << "  \"runways\": [\n";
+        for (size_t i = 0; i < ranked.size(); ++i) {
+            const auto& m = ranked[i];
+            oss << "    {\n";
+            oss << "      \"rank\": " << (i + 1) << ",\n";
+            oss << "      \"runway_id\": \"" << escape_json(m.runway_id) << "\",\n";
+            oss << "      \"state\": \"" << state_to_string(m.state) << "\",\n";
+            oss << "      \"success_rate\": " << std::fixed << std::setprecision(3) << m.success_rate << ",\n";
+            oss << "      \"network_success_rate\": " << m.network_success_rate << ",\n";
+            oss << "      \"avg_response_time\": " << m.avg_response_time << ",\n";
+            oss << "      \"total_attempts\": " << m.total_attempts << "\n";
+            oss << "    }";
+            if (i + 1 < ranked.size()) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
+    }
+    
+    if (ranked.empty()) {
+        print("No metrics recorded for " + target + "\n");
+        return;
+    }
+    
+    size_t id_width = 6;
+    for (const auto& m : ranked) {
+        id_width = std::max(id_width, m.runway_id.length());
+    }
+    
+    std::ostringstream oss;
+    oss << std::left << std::setw(5) << "RANK" << std::setw(static_cast<int>(id_width + 2)) << "RUNWAY"
+        << std::setw(22) << "STATE" << std::right << std::setw(8) << "SUCCESS"
+        << std::setw(11) << "LATENCY" << std::setw(10) << "ATTEMPTS" << "\n";
+    for (size_t i = 0; i < ranked.size(); ++i) {
+        const auto& m = ranked[i];
+        std::ostringstream success;
+        success << std::fixed << std::setprecision(1) << m.success_rate * 100.0 << "%";
+        std::ostringstream latency;
+        if (m.avg_response_time > 0.0) {
+            latency << std::fixed << std::setprecision(3) << m.avg_response_time << "s";
+        } else {
+            latency << "-";
+        }
+        oss << std::left << std::setw(5) << (i + 1) << std::setw(static_cast<int>(id_width + 2)) << m.runway_id
+            << std::setw(22) << state_to_string(m.state) << std::right << std::setw(8) << success.str()
+            << std::setw(11) << latency.str() << std::setw(10) << m.total_attempts << "\n";
+    }
+    print(oss.str());
+}
+
+void ProxyCLI::test_batch(const std::vector<std::pair<std::string, uint16_t>>& targets) {
+    if (!proxy_server_) {
+        print_error("test-batch sends its requests through the proxy, which is not running in this process");
+        return;
+    }
+    auto all_runways = runway_manager_->get_all_runways();
+    
+    // Row per runway, column per target; each cell a real request, validated like client traffic
+    std::vector<FetchResult> cells(all_runways.size() * targets.size());
+    
+    // A small pool works through every runway/target pair
+    std::atomic<size_t> next(0);
+    std::vector<std::thread> workers;
+    size_t worker_count = std::min(cells.size(), static_cast<size_t>(16));
+    for (size_t w = 0; w < worker_count; ++w) {
+        workers.emplace_back([this, &all_runways, &targets, &cells, &next]() {
+            size_t i;
+            while ((i = next++) < cells.size()) {
+                const auto& target = targets[i % targets.size()];
+                cells[i] = proxy_server_->fetch(target.first, target.second, all_runways[i / targets.size()]);
+            }
+        });
+    }
+    for (auto& worker : workers) {
+        worker.join();
+    }
+    
+    auto target_name = [](const std::pair<std::string, uint16_t>& target) {
+        return target.second == 80 ? target.first : target.first + ":" + std::to_string(target.second);
+    };
+    
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"targets\": [";
+        for (size_t t = 0; t < targets.size(); ++t) {
+            oss << (t > 0 ? ", " : "") << "\"" << escape_json(target_name(targets[t])) << "\"";
+        }
+        oss << "],\n";
+        oss << "  \"results\": [\n";
+        for (size_t i = 0; i < cells.size(); ++i) {
+            oss << "    {\"runway_id\": \"" << escape_json(all_runways[i / targets.size()]->id)
+                << "\", \"target\": \"" << escape_json(target_name(targets[i % targets.size()]))
+                << "\", \"network_success\": " << (cells[i].network_success ? "true" : "false")
+                << ", \"user_success\": " << (cells[i].user_success ? "true" : "false")
+                << ", \"status_code\": " << cells[i].status_code
+                << ", \"response_time\": " << std::fixed << std::setprecision(3) << cells[i].duration << "}";
+            if (i + 1 < cells.size()) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
+    }
+    
+    // Grid: ok = reachable, blocked = connects but the content is not the target's, - = unreachable
+    std::vector<std::string> columns = {"RUNWAY"};
+    for (const auto& target : targets) {
+        columns.push_back(target_name(target));
+    }
+    Table table(columns, false);
+    std::vector<size_t> reachable(targets.size(), 0);
+    for (size_t r = 0; r < all_runways.size(); ++r) {
+        std::vector<std::string> row = {all_runways[r]->id};
+        for (size_t t = 0; t < targets.size(); ++t) {
+            const FetchResult& cell = cells[r * targets.size() + t];
+            row.push_back(cell.user_success ? "ok" : cell.network_success ? "blocked" : "-");
+            if (cell.user_success) {
+                reachable[t]++;
+            }
+        }
+        table.add_row(row);
+    }
+    std::vector<std::string> totals = {"reachable"};
+    for (size_t t = 0; t < targets.size(); ++t) {
+        totals.push_back(std::to_string(reachable[t]) + "/" + std::to_string(all_runways.size()));
+    }
+    table.add_row(totals);
+    print(table.to_text());
+    print("ok: reachable, blocked: connected but not the expected content, -: unreachable\n");
+}
+
+// Nearest-rank percentile of sorted values
+static double percentile(const std::vector<double>& sorted, double p) {
+    if (sorted.empty()) {
+        return 0.0;
+    }
+    size_t rank = static_cast<size_t>(std::ceil(p / 100.0 * static_cast<double>(sorted.size())));
+    return sorted[rank > 0 ? rank - 1 : 0];
+}
+
+void ProxyCLI::bench(const std::string& target, const std::string& runway_id, uint16_t port, uint32_t count) {
+    if (!proxy_server_) {
+        print_error("bench sends its requests through the proxy, which is not running in this process");
+        return;
+    }
+    std::vector<std::shared_ptr<Runway>> runways;
+    if (!runway_id.empty()) {
+        auto runway = runway_manager_->get_runway(runway_id);
+        if (!runway) {
+            print_error("Runway " + runway_id + " not found");
+            return;
+        }
+        runways.push_back(runway);
+    } else {
+        runways = runway_manager_->get_all_runways();
+    }
+    
+    struct BenchResult {
+        uint32_t successes = 0;
+        std::vector<FetchResult> requests; // In the order sent
+        std::vector<double> latencies; // Seconds, of the requests that were answered; sorted
+        std::map<uint16_t, uint32_t> statuses; // Status code -> requests
+    };
+    std::vector<BenchResult> results(runways.size());
+    
+    // Runways in parallel, each runway's requests one after another; every request is a full GET,
+    // timed until its response is read and validated like client traffic
+    std::vector<std::thread> workers;
+    for (size_t i = 0; i < runways.size(); ++i) {
+        workers.emplace_back([this, &target, &runways, &results, port, count, i]() {
+            BenchResult& r = results[i];
+            for (uint32_t n = 0; n < count; ++n) {
+                FetchResult request = proxy_server_->fetch(target, port, runways[i]);
+                r.requests.push_back(request);
+                r.statuses[request.status_code]++;
+                if (request.network_success) {
+                    r.latencies.push_back(request.duration);
+                }
+                if (request.user_success) {
+                    r.successes++;
+                }
+            }
+            std::sort(r.latencies.begin(), r.latencies.end());
+        });
+    }
+    for (auto& worker : workers) {
+        worker.join();
+    }
+    
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"target\": \"" << escape_json(target) << "\",\n";
+        oss << "  \"port\": " << port << ",\n";
+        oss << "  \"count\": " << count << ",\n";
+        oss << "  \"results\": [\n";
+        for (size_t i = 0; i < runways.size(); ++i) {
+            const auto& r = results[i];
+            oss << "    {\n";
+            oss << "      \"runway_id\": \"" << escape_json(runways[i]->id) << "\",\n";
+            oss << "      \"successes\": " << r.successes << ",\n";
+            oss << "      \"success_rate\": " << std::fixed << std::setprecision(3)
+                << static_cast<double>(r.successes) / count << ",\n";
+            if (r.latencies.empty()) {
+                oss << "      \"latency\": null,\n";
+            } else {
+                oss << "      \"latency\": {\"min\": " << r.latencies.front() << ", \"p50\": " << percentile(r.latencies, 50)
+                    << ", \"p90\": " << percentile(r.latencies, 90) << ", \"p99\": " << percentile(r.latencies, 99)
+                    << ", \"max\": " << r.latencies.back() << "},\n";
+            }
+            oss << "      \"requests\": [";
+            for (size_t n = 0; n < r.requests.size(); ++n) {
+                oss << (n > 0 ? ", " : "") << "{\"status_code\": " << r.requests[n].status_code
+                    << ", \"user_success\": " << (r.requests[n].user_success ? "true" : "false")
+                    << ", \"duration\": " << r.requests[n].duration << "}";
+            }
+            oss << "]\n";
+            oss << "    }";
+            if (i + 1 < runways.size()) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
+    }
+    
+    size_t id_width = 6;
+    for (const auto& runway : runways) {
+        id_width = std::max(id_width, runway->id.length());
+    }
+    
+    std::ostringstream oss;
+    oss << "Benchmark of " << target << ":" << port << ", " << count << " requests per runway\n";
+    oss << std::left << std::setw(static_cast<int>(id_width + 2)) << "RUNWAY" << std::right << std::setw(8) << "SUCCESS"
+        << std::setw(9) << "MIN" << std::setw(9) << "P50" << std::setw(9) << "P90" << std::setw(9) << "P99"
+        << std::setw(9) << "MAX" << "  STATUS" << "\n";
+    for (size_t i = 0; i < runways.size(); ++i) {
+        const auto& r = results[i];
+        std::ostringstream success;
+        success << std::fixed << std::setprecision(1) << 100.0 * r.successes / count << "%";
+        oss << std::left << std::setw(static_cast<int>(id_width + 2)) << runways[i]->id << std::right
+            << std::setw(8) << success.str();
+        if (r.latencies.empty()) {
+            oss << std::setw(9) << "-" << std::setw(9) << "-" << std::setw(9) << "-" << std::setw(9) << "-"
+                << std::setw(9) << "-";
+        } else {
+            for (double value : {r.latencies.front(), percentile(r.latencies, 50), percentile(r.latencies, 90),
+                                 percentile(r.latencies, 99), r.latencies.back()}) {
+                std::ostringstream latency;
+                latency << std::fixed << std::setprecision(3) << value << "s";
+                oss << std::setw(9) << latency.str();
+            }
+        }
+        // How often each status came back, e.g. "200x9 502x1"
+        oss << " ";
+        for (const auto& status : r.statuses) {
+            oss << " " << status.first << "x" << status.second;
+        }
+        oss << "\n";
+    }
+    print(oss.str());
+}
+
+void ProxyCLI::diff(const std::string& target_a, const std::string& target_b) {
+    auto metrics_a = tracker_->get_target_metrics(target_a);
+    auto metrics_b = tracker_->get_target_metrics(target_b);
+    if (metrics_a.empty() || metrics_b.empty()) {
+        print_error("No metrics recorded for " + (metrics_a.empty() ? target_a : target_b));
+        return;
+    }
+    
+    // Every runway with metrics for either target
+    std::vector<std::string> runway_ids;
+    for (const auto& pair : metrics_a) {
+        runway_ids.push_back(pair.first);
+    }
+    for (const auto& pair : metrics_b) {
+        if (metrics_a.find(pair.first) == metrics_a.end()) {
+            runway_ids.push_back(pair.first);
+        }
+    }
+    std::sort(runway_ids.begin(), runway_ids.end());
+    
+    // A runway diverges when only one target has metrics, the states differ,
+    // or one target's average latency is at least twice the other's
+    auto diverges = [](const TargetMetrics* a, const TargetMetrics* b) {
+        if (!a || !b) {
+            return true;
+        }
+        if (a->state != b->state) {
+            return true;
+        }
+        double low = std::min(a->avg_response_time, b->avg_response_time);
+        double high = std::max(a->avg_response_time, b->avg_response_time);
+        return low > 0.0 && high >= 2.0 * low;
+    };
+    auto lookup = [](const std::map<std::string, TargetMetrics>& metrics, const std::string& runway_id) {
+        auto it = metrics.find(runway_id);
+        return it == metrics.end() ? nullptr : &it->second;
+    };
+    
+    if (json_output_) {
+        auto side = [this](const TargetMetrics* m) {
+            if (!m) {
+                return std::string("null");
             }
+            std::ostringstream oss;
+            oss << "{\"state\": \"" << state_to_string(m->state) << "\", \"success_rate\": " << std::fixed
+                << std::setprecision(3) << m->success_rate << ", \"avg_response_time\": " << m->avg_response_time << "}";
+            return oss.str();
+        };
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"target_a\": \"" << escape_json(target_a) << "\",\n";
+        oss << "  \"target_b\": \"" << escape_json(target_b) << "\",\n";
+        oss << "  \"runways\": [\n";
+        for (size_t i = 0; i < runway_ids.size(); ++i) {
+            const TargetMetrics* a = lookup(metrics_a, runway_ids[i]);
+            const TargetMetrics* b = lookup(metrics_b, runway_ids[i]);
+            oss << "    {\"runway_id\": \"" << escape_json(runway_ids[i]) << "\", \"differs\": "
+                << (diverges(a, b) ? "true" : "false") << ", \"a\": " << side(a) << ", \"b\": " << side(b) << "}";
+            if (i + 1 < runway_ids.size()) oss << ",";
+            oss << "\n";
         }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
     }
+    
+    auto state_cell = [this](const TargetMetrics* m) { return m ? state_to_string(m->state) : "-"; };
+    auto success_cell = [](const TargetMetrics* m) {
+        if (!m) {
+            return std::string("-");
+        }
+        std::ostringstream oss;
+        oss << std::fixed << std::setprecision(1) << m->success_rate * 100.0 << "%";
+        return oss.str();
+    };
+    auto latency_cell = [](const TargetMetrics* m) {
+        return m && m->avg_response_time > 0.0 ? fixed3(m->avg_response_time) + "s" : std::string("-");
+    };
+    
+    Table table({"", "runway", "state a", "state b", "success a", "success b", "latency a", "latency b"});
+    size_t differing = 0;
+    for (const auto& runway_id : runway_ids) {
+        const TargetMetrics* a = lookup(metrics_a, runway_id);
+        const TargetMetrics* b = lookup(metrics_b, runway_id);
+        bool differs = diverges(a, b);
+        if (differs) {
+            differing++;
+        }
+        table.add_row({differs ? "*" : "", runway_id, state_cell(a), state_cell(b), success_cell(a), success_cell(b),
+                       latency_cell(a), latency_cell(b)});
+    }
+    print("A: " + target_a + "\nB: " + target_b + "\n\n");
+    print(table.to_text());
+    print("\n" + std::to_string(differing) + " of " + std::to_string(runway_ids.size()) +
+          " runways differ (*: only one target has metrics, the states differ, or latency differs 2x or more)\n");
+}
+
+void ProxyCLI::simulate(const std::string& target) {
+    RoutingDecision decision = routing_engine_->explain(target, runway_manager_->get_all_runways(true));
+    
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"target\": \"" << escape_json(target) << "\",\n";
+        oss << "  \"routing_mode\": \"" << mode_to_string(decision.mode) << "\",\n";
+        oss << "  \"selected_runway\": ";
+        if (decision.selected_runway_id.empty()) {
+            oss << "null,\n";
+        } else {
+            oss << "\"" << escape_json(decision.selected_runway_id) << "\",\n";
+        }
+        oss << "  \"reason\": \"" << escape_json(decision.reason) << "\",\n";
+        oss << "  \"candidates\": [\n";
+        for (size_t i = 0; i < decision.candidates.size(); ++i) {
+            const RoutingCandidate& c = decision.candidates[i];
+            oss << "    {\"runway_id\": \"" << escape_json(c.runway_id) << "\", \"eligible\": "
+                << (c.eligible ? "true" : "false") << ", \"state\": ";
+            if (c.has_metrics) {
+                oss << "\"" << state_to_string(c.state) << "\"";
+            } else {
+                oss << "null";
+            }
+            oss << ", \"avg_response_time\": " << fixed3(c.avg_response_time)
+                << ", \"reason\": \"" << escape_json(c.reason) << "\"}";
+            if (i + 1 < decision.candidates.size()) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
+    }
+    
+    print("Target: " + target + "\n");
+    print("Mode: " + mode_to_string(decision.mode) + "\n");
+    if (decision.selected_runway_id.empty()) {
+        print("Would use: none - " + decision.reason + "\n");
+    } else {
+        print("Would use: " + decision.selected_runway_id + " - " + decision.reason + "\n");
+    }
+    if (decision.candidates.empty()) {
+        return;
+    }
+    
+    // Eligible runways are ranked in the order the mode would consider them
+    Table table({"rank", "runway", "state", "latency", "reason"});
+    size_t rank = 0;
+    for (const auto& c : decision.candidates) {
+        table.add_row({c.eligible ? std::to_string(++rank) : "-", c.runway_id,
+                       c.has_metrics ? state_to_string(c.state) : "-",
+                       c.avg_response_time > 0.0 ? fixed3(c.avg_response_time) + "s" : "-", c.reason});
+    }
+    print("\n");
+    print(table.to_text());
 }
 
 void ProxyCLI::reload() {
-    // Note: Full reload would require re-initializing components
-    // For now, just acknowledge the command
+    if (!reload_handler_) {
+        print_error("Reloading is not available here");
+        return;
+    }
+    
+    std::string result = reload_handler_();
+    size_t pos = 0;
+    std::map<std::string, std::string> reply;
+    if (!Config::parse_object(result, pos, reply)) {
+        print_error("Unexpected reload result");
+        return;
+    }
+    bool reloaded = utils::trim(reply["reloaded"]) == "true";
+    if (json_output_) {
+        print_json(result);
+        if (!reloaded) {
+            exit_code_ = 1;
+        }
+        return;
+    }
+    
+    std::vector<std::string> lines;
+    if (!reloaded) {
+        Config::parse_string_array(reply["errors"], lines);
+        for (const auto& error : lines) {
+            print_error("config.json: " + error);
+        }
+        print("Configuration not reloaded\n");
+        return;
+    }
+    
+    Config::parse_string_array(reply["changes"], lines);
+    print("Configuration reloaded" + std::string(lines.empty() ? " (no runtime changes)" : "") + "\n");
+    for (const auto& change : lines) {
+        print("  " + change + "\n");
+    }
+    
+    // Changed settings, old -> new (values as JSON); those not applied wait for a restart
+    std::vector<std::string> diff;
+    pos = 0;
+    Config::parse_array(reply["diff"], pos, diff);
+    for (const auto& entry : diff) {
+        std::map<std::string, std::string> fields;
+        size_t entry_pos = 0;
+        std::string setting;
+        size_t setting_pos = 0;
+        if (!Config::parse_object(entry, entry_pos, fields) ||
+            !Config::parse_string(utils::trim(fields["setting"]), setting_pos, setting)) {
+            continue;
+        }
+        bool applied = utils::trim(fields["applied"]) == "true";
+        print("  " + setting + ": " + utils::trim(fields["old"]) + " -> " + utils::trim(fields["new"]) +
+              (applied ? "" : " (after restart)") + "\n");
+    }
+    if (utils::trim(reply["restart_required"]) == "true") {
+        print("Settings marked (after restart) take effect when the service restarts\n");
+    }
+}
+
+void ProxyCLI::set(const std::string& key, const std::string& value, bool persist) {
+    if (!setting_handler_) {
+        print_error("Changing settings is not available here");
+        return;
+    }
+    
+    std::string result = setting_handler_(key, value, persist);
+    size_t pos = 0;
+    std::map<std::string, std::string> reply;
+    if (!Config::parse_object(result, pos, reply)) {
+        print_error("Unexpected set result");
+        return;
+    }
+    bool applied = utils::trim(reply["applied"]) == "true";
+    bool saved = !persist || utils::trim(reply["persisted"]) == "true";
+    if (json_output_) {
+        print_json(result);
+        if (!applied || !saved) {
+            exit_code_ = 1;
+        }
+        return;
+    }
+    
+    std::vector<std::string> lines;
+    if (applied) {
+        Config::parse_string_array(reply["changes"], lines);
+        print(lines.empty() ? key + " is already " + value + "\n" : key + " set to " + value + "\n");
+        if (persist && saved) {
+            print("Saved to the config file\n");
+        }
+    }
+    lines.clear();
+    Config::parse_string_array(reply["errors"], lines);
+    for (const auto& error : lines) {
+        print_error(error);
+    }
+}
+
+void ProxyCLI::add_proxy(const UpstreamProxyConfig& proxy) {
+    std::vector<std::string> new_runway_ids;
+    if (!runway_manager_->add_upstream_proxy(proxy, new_runway_ids)) {
+        print_error("Cannot add proxy " + proxy.host + ":" + std::to_string(proxy.port) +
+                    " (unsupported type or already configured)");
+        return;
+    }
+    audit::record("add-proxy", "upstream_proxies", "", upstream::describe(proxy));
+    
+    print_added_runways("proxy " + upstream::describe(proxy), new_runway_ids);
+}
+
+void ProxyCLI::remove_proxy(const std::string& host, uint16_t port) {
+    std::vector<std::string> removed = runway_manager_->remove_upstream_proxy(host, port);
+    if (removed.empty()) {
+        print_error("No upstream proxy " + host + ":" + std::to_string(port));
+        return;
+    }
+    
+    for (const auto& runway_id : removed) {
+        tracker_->remove_runway(runway_id);
+    }
+    audit::record("remove-proxy", "upstream_proxies", host + ":" + std::to_string(port), "");
+    if (json_output_) {
+        print_json("{\"removed_runways\": " + std::to_string(removed.size()) + "}");
+    } else {
+        print("Removed proxy " + host + ":" + std::to_string(port) +
+              " (" + std::to_string(removed.size()) + " runways)\n");
+    }
+}
+
+void ProxyCLI::add_dns(const DNSServerConfig& dns_server) {
+    std::vector<std::string> new_runway_ids;
+    if (!runway_manager_->add_dns_server(dns_server, new_runway_ids)) {
+        print_error("DNS server " + dns_server.host + ":" + std::to_string(dns_server.port) +
+                    " is already configured");
+        return;
+    }
+    audit::record("add-dns", "dns_servers", "", dns_server.host + ":" + std::to_string(dns_server.port));
+    
+    print_added_runways("DNS server " + dns_server.host + ":" + std::to_string(dns_server.port), new_runway_ids);
+}
+
+void ProxyCLI::dns() {
+    for (const auto& server : runway_manager_->get_dns_servers()) {
+        runway_manager_->test_dns_server(server.config, 5.0);
+    }
+    std::vector<DNSServer> servers = runway_manager_->get_dns_servers();
+    
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"dns_servers\": [\n";
+        for (size_t i = 0; i < servers.size(); ++i) {
+            const auto& s = servers[i];
+            oss << "    {\n";
+            oss << "      \"host\": \"" << escape_json(s.config.host) << "\",\n";
+            oss << "      \"port\": " << s.config.port << ",\n";
+            oss << "      \"name\": \"" << escape_json(s.config.name) << "\",\n";
+            oss << "      \"accessible\": " << (s.failure_count == 0 ? "true" : "false") << ",\n";
+            oss << "      \"response_time\": " << std::fixed << std::setprecision(3) << s.response_time << ",\n";
+            oss << "      \"failure_count\": " << s.failure_count << ",\n";
+            oss << "      \"last_success\": " << s.last_success << "\n";
+            oss << "    }";
+            if (i < servers.size() - 1) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ]\n";
+        oss << "}";
+        print_json(oss.str());
+    } else {
+        for (const auto& s : servers) {
+            std::ostringstream line;
+            line << s.config.name << " (" << s.config.host << ":" << s.config.port << "): ";
+            if (s.failure_count == 0) {
+                line << "OK, " << std::fixed << std::setprecision(1) << s.response_time * 1000.0 << "ms";
+            } else {
+                line << "FAILED (" << s.failure_count << " consecutive failures)";
+            }
+            print(line.str() + "\n");
+        }
+    }
+}
+
+void ProxyCLI::dns_show() {
+    auto resolver = runway_manager_->get_dns_resolver();
+    if (!resolver) {
+        print_error("No DNS resolver is running");
+        return;
+    }
+    
+    auto records = resolver->get_cache_entries();
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"entries\": [\n";
+        for (size_t i = 0; i < records.size(); ++i) {
+            const auto& record = records[i];
+            oss << "    {\"domain\": \"" << escape_json(record.domain) << "\", \"type\": \""
+                << (record.ipv6 ? "AAAA" : "A") << "\", \"ip\": \"" << escape_json(record.ip)
+                << "\", \"ttl_remaining\": " << record.ttl_remaining
+                << ", \"expired\": " << (record.ttl_remaining == 0 ? "true" : "false") << "}";
+            if (i + 1 < records.size()) oss << ",";
+            oss << "\n";
+        }
+        oss << "  ],\n";
+        oss << "  \"count\": " << records.size() << "\n";
+        oss << "}";
+        print_json(oss.str());
+        return;
+    }
+    
+    if (records.empty()) {
+        print("The DNS cache is empty\n");
+        return;
+    }
+    Table table({"domain", "type", "address", "ttl"});
+    for (const auto& record : records) {
+        table.add_row({record.domain, record.ipv6 ? "AAAA" : "A", record.ip,
+                       record.ttl_remaining == 0 ? "expired" : std::to_string(record.ttl_remaining) + "s"});
+    }
+    print(table.to_text());
+}
+
+void ProxyCLI::dns_purge(const std::string& domain) {
+    auto resolver = runway_manager_->get_dns_resolver();
+    if (!resolver) {
+        print_error("No DNS resolver is running");
+        return;
+    }
+    
+    size_t purged = resolver->clear_cache(domain);
+    if (purged > 0) {
+        audit::record("dns-purge", domain.empty() ? "*" : domain, std::to_string(purged) + " cached entries", "");
+    }
+    if (json_output_) {
+        print_json("{\"purged_entries\": " + std::to_string(purged) + "}");
+    } else {
+        print("Purged " + std::to_string(purged) + " cached DNS entries" + (domain.empty() ? "" : " for " + domain) + "\n");
+    }
+}
+
+void ProxyCLI::remove_runway(const std::string& runway_id) {
+    if (!runway_manager_->remove_runway(runway_id)) {
+        print_error("Runway " + runway_id + " not found");
+        return;
+    }
+    
+    tracker_->remove_runway(runway_id);
+    audit::record("remove-runway", "runways", runway_id, "");
+    size_t remaining = runway_manager_->get_all_runways(true).size();
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"runway_id\": \"" << escape_json(runway_id) << "\",\n";
+        oss << "  \"removed\": true,\n";
+        oss << "  \"remaining_runways\": " << remaining << "\n";
+        oss << "}";
+        print_json(oss.str());
+    } else {
+        print("Removed runway " + runway_id + " (" + std::to_string(remaining) + " runways left)\n");
+    }
+}
+
+void ProxyCLI::set_health_paused(bool paused) {
+    if (!health_monitor_) {
+        print_error("No health monitor is running");
+        return;
+    }
+    
+    bool was_paused = health_monitor_->is_paused();
+    if (paused) {
+        health_monitor_->pause();
+    } else {
+        health_monitor_->resume();
+    }
+    if (was_paused != paused) {
+        audit::record(paused ? "pause-health" : "resume-health", "health_monitor",
+                      was_paused ? "paused" : "running", paused ? "paused" : "running");
+    }
+    
     if (!json_output_) {
-        utils::safe_print("Configuration reloaded\n");
+        print(std::string("Health monitor ") + (paused ? "paused" : "resumed") + "\n");
+    }
+}
+
+void ProxyCLI::set_runway_enabled(const std::string& runway_id, bool enabled) {
+    auto previous = runway_manager_->get_runway(runway_id);
+    bool was_enabled = previous && previous->enabled;
+    if (!runway_manager_->set_runway_enabled(runway_id, enabled)) {
+        print_error("Runway " + runway_id + " not found");
+        return;
+    }
+    if (was_enabled != enabled) {
+        audit::record(enabled ? "enable-runway" : "disable-runway", runway_id,
+                      was_enabled ? "enabled" : "disabled", enabled ? "enabled" : "disabled");
+    }
+    
+    // Report the state the runway is now in; disabling does not cut its in-flight connections
+    auto runway = runway_manager_->get_runway(runway_id);
+    size_t in_flight = runway ? runway->get_in_flight() : 0;
+    if (json_output_) {
+        std::ostringstream oss;
+        oss << "{\n";
+        oss << "  \"runway_id\": \"" << escape_json(runway_id) << "\",\n";
+        oss << "  \"enabled\": " << (enabled ? "true" : "false") << ",\n";
+        oss << "  \"in_flight\": " << in_flight << "\n";
+        oss << "}";
+        print_json(oss.str());
+    } else {
+        print(std::string(enabled ? "Enabled" : "Disabled") + " runway " + runway_id + ": " +
+              (enabled ? "in rotation" : "out of rotation") + ", " + std::to_string(in_flight) + " in flight\n");
     }
 }
@@ -4,41 +4,112 @@
 #include <string>
 #include <vector>
 #include <memory>
+#include <functional>
 #include "config.h"
 #include "runway_manager.h"
 #include "routing.h"
 #include "tracker.h"
+#include "health.h"
+#include "proxy.h"
+#include "table.h"
+
+// Output of list commands (runways, targets, stats) besides --json
+enum class OutputFormat {
+    Text,  // Human-readable lines (default)
+    Table, // Aligned columns
+    Csv    // Comma-separated values with a header line
+};
 
 // CLI interface for managing and monitoring the proxy service
+// Runs inside the service (behind the admin endpoint) so commands see and change live state;
+// output is collected rather than printed so it can be sent back to the caller
 class ProxyCLI {
 public:
     ProxyCLI(std::shared_ptr<RunwayManager> runway_manager,
              std::shared_ptr<RoutingEngine> routing_engine,
-             std::shared_ptr<TargetAccessibilityTracker> tracker);
+             std::shared_ptr<TargetAccessibilityTracker> tracker,
+             std::shared_ptr<HealthMonitor> health_monitor = nullptr,
+             std::shared_ptr<ProxyServer> proxy_server = nullptr);
     
-    // Execute CLI command
+    // Most requests `bench` sends through each runway
+    static const uint32_t MAX_BENCH_COUNT = 100;
+    
+    // Execute CLI command; returns the exit code (1 if the command reported an error)
     int execute(const std::vector<std::string>& args);
     
+    // Everything the executed command printed
+    const std::string& output() const { return output_; }
+    
+    // Used by reload(): re-reads the config file and returns AdminServer::reload's JSON result
+    void set_reload_handler(std::function<std::string()> handler) { reload_handler_ = handler; }
+    
+    // Used by set() and mode --persist: changes one live setting (and with persist, saves it to the
+    // config file) and returns AdminServer::set's JSON result
+    void set_setting_handler(std::function<std::string(const std::string&, const std::string&, bool)> handler) {
+        setting_handler_ = handler;
+    }
+    
     // Command handlers
     void status();
     void runways();
     void targets();
     void stats();
-    void mode(const std::string& mode_str);
-    void test(const std::string& target, const std::string& runway_id = "");
+    void dashboard();
+    void reset_stats(const std::string& target = "");
+    void export_metrics();
+    void mode(const std::string& mode_str, bool persist = false);
+    void test(const std::string& target, const std::string& runway_id = "", uint16_t port = 80);
+    void test_batch(const std::vector<std::pair<std::string, uint16_t>>& targets);
+    void compare(const std::string& target);
+    void diff(const std::string& target_a, const std::string& target_b);
+    void simulate(const std::string& target);
+    void bench(const std::string& target, const std::string& runway_id, uint16_t port, uint32_t count);
     void reload();
+    void set(const std::string& key, const std::string& value, bool persist = false);
+    void add_proxy(const UpstreamProxyConfig& proxy);
+    void remove_proxy(const std::string& host, uint16_t port);
+    void add_dns(const DNSServerConfig& dns_server);
+    void remove_runway(const std::string& runway_id);
+    void set_runway_enabled(const std::string& runway_id, bool enabled);
+    void set_health_paused(bool paused);
+    void dns();
+    void dns_purge(const std::string& domain = "");
+    void dns_show();
     
     // Set JSON output mode
     void set_json_output(bool json) { json_output_ = json; }
     
+    // Set table/CSV output mode (runways, targets and stats)
+    void set_output_format(OutputFormat format) { format_ = format; }
+    
 private:
     std::shared_ptr<RunwayManager> runway_manager_;
     std::shared_ptr<RoutingEngine> routing_engine_;
     std::shared_ptr<TargetAccessibilityTracker> tracker_;
+    std::shared_ptr<HealthMonitor> health_monitor_; // May be null (no health monitor in this process)
+    std::shared_ptr<ProxyServer> proxy_server_; // Sends test-batch requests; may be null (no proxy in this process)
+    std::function<std::string()> reload_handler_;
+    std::function<std::string(const std::string&, const std::string&, bool)> setting_handler_;
     bool json_output_;
+    OutputFormat format_;
+    std::string output_;
+    int exit_code_;
     
+    void print(const std::string& text);
+    void print_error(const std::string& message); // "Error: <message>" and a failing exit code
     void print_json(const std::string& json);
+    void print_table(const Table& table); // In format_ (Table or Csv)
+    void print_added_runways(const std::string& what, const std::vector<std::string>& runway_ids);
+    
+    // Fill values (one per name) from positional arguments after the command, in order,
+    // or from "--<name> <value>" options; false on an unknown option or extra argument
+    static bool parse_arguments(const std::vector<std::string>& args, const std::vector<std::string>& names,
+                                std::vector<std::string>& values);
+    // "host" or "host:port" (a bare IPv6 address has no port); false on an invalid port
+    static bool split_target(const std::string& target, std::string& host, uint16_t& port);
     std::string escape_json(const std::string& str);
+    std::string state_to_string(RunwayState state);
+    std::string mode_to_string(RoutingMode mode);
 };
 
 #endif // CLI_H
@@ -0,0 +1,177 @@
+#include "completions.h"
+#include "admin.h"
+#include <vector>
+#include <sstream>
+
+namespace completions {
+
+struct CommandInfo {
+    const char* name;
+    const char* description;
+    std::vector<std::string> first_arguments; // Fixed choices for the first argument
+    bool runway_argument; // First argument is a runway id
+};
+
+static std::vector<CommandInfo> commands() {
+    std::vector<std::string> live_settings(AdminServer::LIVE_SETTINGS.begin(), AdminServer::LIVE_SETTINGS.end());
+    return {
+        {"status", "Show current status", {}, false},
+        {"runways", "List all runways", {}, false},
+        {"targets", "Show target accessibility matrix", {}, false},
+        {"stats", "Show performance statistics", {}, false},
+        {"dashboard", "Show runways and targets at a glance", {}, false},
+        {"watch", "Refresh the dashboard live", {}, false},
+        {"logs", "Show the end of the log file", {}, false},
+        {"validate", "Check a config file without starting the service", {}, false},
+        {"reset-stats", "Forget the metrics of one target, or of all", {}, false},
+        {"export-metrics", "Write the metrics of every target as JSON", {}, false},
+        {"mode", "Switch routing mode", {"latency", "first_accessible", "round_robin"}, false},
+        {"test", "Test target accessibility", {}, false},
+        {"test-batch", "Test many targets through every runway", {}, false},
+        {"bench", "Time requests through each runway", {}, false},
+        {"compare", "Rank all runways for a target", {}, false},
+        {"diff", "Compare two targets runway by runway", {}, false},
+        {"simulate", "Show which runway routing would pick and why", {}, false},
+        {"dns", "Test DNS servers, or show or purge the cache", {"show", "purge"}, false},
+        {"reload", "Reload configuration", {}, false},
+        {"set", "Change a setting while running", live_settings, false},
+        {"add-proxy", "Add an upstream proxy", {"http", "https", "socks4", "socks4a", "socks5", "socks5h"}, false},
+        {"remove-proxy", "Remove an upstream proxy", {}, false},
+        {"add-dns", "Add a DNS server", {}, false},
+        {"remove-runway", "Remove a runway", {}, true},
+        {"disable-runway", "Take a runway out of rotation", {}, true},
+        {"enable-runway", "Put a disabled runway back into rotation", {}, true},
+        {"pause-health", "Stop health probing", {}, false},
+        {"resume-health", "Resume health probing", {}, false},
+        {"fingerprint", "Print the fingerprint hash of a saved block page", {}, false},
+        {"completions", "Print a shell completion script", {"bash", "zsh", "fish"}, false},
+        {"version", "Show build version and supported features", {}, false},
+        {"help", "Show usage", {}, false},
+    };
+}
+
+// CSV lines end in CRLF; descriptions must not contain single quotes (zsh and fish quote them)
+static const char* RUNWAY_IDS = "smartproxy runways --format csv 2>/dev/null | tail -n +2 | cut -d, -f1 | tr -d \"\\r\"";
+static const char* FORMATS = "text table csv json";
+
+static std::string join(const std::vector<std::string>& words) {
+    std::string joined;
+    for (const auto& word : words) {
+        joined += (joined.empty() ? "" : " ") + word;
+    }
+    return joined;
+}
+
+static std::string bash_script() {
+    std::vector<std::string> names;
+    for (const auto& command : commands()) {
+        names.push_back(command.name);
+    }
+    
+    std::ostringstream oss;
+    oss << "# bash completion for smartproxy; load with: source <(smartproxy completions bash)\n";
+    oss << "_smartproxy() {\n";
+    oss << "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n";
+    oss << "    case \"$prev\" in\n";
+    oss << "        --format) COMPREPLY=($(compgen -W \"" << FORMATS << "\" -- \"$cur\")); return ;;\n";
+    oss << "        --file|--out|--config) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;\n";
+    oss << "    esac\n";
+    oss << "    if [ \"$COMP_CWORD\" -eq 1 ]; then\n";
+    oss << "        COMPREPLY=($(compgen -W \"" << join(names) << "\" -- \"$cur\"))\n";
+    oss << "        return\n";
+    oss << "    fi\n";
+    oss << "    if [ \"$COMP_CWORD\" -eq 2 ]; then\n";
+    oss << "        case \"${COMP_WORDS[1]}\" in\n";
+    for (const auto& command : commands()) {
+        if (!command.first_arguments.empty()) {
+            oss << "            " << command.name << ") COMPREPLY=($(compgen -W \"" << join(command.first_arguments)
+                << "\" -- \"$cur\")); return ;;\n";
+        } else if (command.runway_argument) {
+            oss << "            " << command.name << ") COMPREPLY=($(compgen -W \"$(" << RUNWAY_IDS
+                << ")\" -- \"$cur\")); return ;;\n";
+        }
+    }
+    oss << "        esac\n";
+    oss << "    fi\n";
+    oss << "    COMPREPLY=($(compgen -W \"--json --format\" -- \"$cur\"))\n";
+    oss << "}\n";
+    oss << "complete -F _smartproxy smartproxy\n";
+    return oss.str();
+}
+
+static std::string zsh_script() {
+    std::ostringstream oss;
+    oss << "#compdef smartproxy\n";
+    oss << "# zsh completion for smartproxy; load with: source <(smartproxy completions zsh)\n";
+    oss << "_smartproxy() {\n";
+    oss << "    local -a commands\n";
+    oss << "    commands=(\n";
+    for (const auto& command : commands()) {
+        oss << "        '" << command.name << ":" << command.description << "'\n";
+    }
+    oss << "    )\n";
+    oss << "    case $words[CURRENT-1] in\n";
+    oss << "        --format) compadd " << FORMATS << "; return ;;\n";
+    oss << "        --file|--out|--config) _files; return ;;\n";
+    oss << "    esac\n";
+    oss << "    if (( CURRENT == 2 )); then\n";
+    oss << "        _describe 'command' commands\n";
+    oss << "        return\n";
+    oss << "    fi\n";
+    oss << "    if (( CURRENT == 3 )); then\n";
+    oss << "        case $words[2] in\n";
+    for (const auto& command : commands()) {
+        if (!command.first_arguments.empty()) {
+            oss << "            " << command.name << ") compadd " << join(command.first_arguments) << "; return ;;\n";
+        } else if (command.runway_argument) {
+            oss << "            " << command.name << ") compadd ${(f)\"$(" << RUNWAY_IDS << ")\"}; return ;;\n";
+        }
+    }
+    oss << "        esac\n";
+    oss << "    fi\n";
+    oss << "    compadd -- --json --format\n";
+    oss << "}\n";
+    oss << "compdef _smartproxy smartproxy\n";
+    return oss.str();
+}
+
+static std::string fish_script() {
+    std::ostringstream oss;
+    oss << "# fish completion for smartproxy; load with: smartproxy completions fish | source\n";
+    oss << "complete -c smartproxy -f\n";
+    for (const auto& command : commands()) {
+        oss << "complete -c smartproxy -n __fish_use_subcommand -a " << command.name
+            << " -d '" << command.description << "'\n";
+    }
+    for (const auto& command : commands()) {
+        // Only right after the command: its first argument
+        std::string condition = "'__fish_seen_subcommand_from " + std::string(command.name) +
+                                "; and test (count (commandline -opc)) -eq 2'";
+        if (!command.first_arguments.empty()) {
+            oss << "complete -c smartproxy -n " << condition << " -a '" << join(command.first_arguments) << "'\n";
+        } else if (command.runway_argument) {
+            oss << "complete -c smartproxy -n " << condition << " -a '(" << RUNWAY_IDS << ")'\n";
+        }
+    }
+    oss << "complete -c smartproxy -l json -d 'Output in JSON format'\n";
+    oss << "complete -c smartproxy -l format -x -a '" << FORMATS << "' -d 'Output format'\n";
+    oss << "complete -c smartproxy -l file -r -F -d 'File of targets'\n";
+    oss << "complete -c smartproxy -l out -r -F -d 'Output file'\n";
+    oss << "complete -c smartproxy -l config -r -F -d 'Config file'\n";
+    return oss.str();
+}
+
+bool generate(const std::string& shell, std::string& script) {
+    if (shell == "bash") {
+        script = bash_script();
+    } else if (shell == "zsh") {
+        script = zsh_script();
+    } else if (shell == "fish") {
+        script = fish_script();
+    } else {
+        return false;
+    }
+    return true;
+}
+
+} // namespace completions
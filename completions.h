@@ -0,0 +1,16 @@
+#ifndef COMPLETIONS_H
+#define COMPLETIONS_H
+
+#include <string>
+
+// `smartproxy completions <shell>`: tab-completion scripts for the CLI's commands and options
+// Runway ids are completed by asking the running service (`smartproxy runways --format csv`)
+
+namespace completions {
+
+// Script for shell (bash, zsh or fish); false if the shell is not supported
+bool generate(const std::string& shell, std::string& script);
+
+} // namespace completions
+
+#endif // COMPLETIONS_H
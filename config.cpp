@@ -1,5 +1,9 @@
 #include "config.h"
 #include "utils.h"
+#include "upstream.h"
+#include "tls.h"
+#include "tracing.h"
+#include "webui_json.h"
 #include <fstream>
 #include <sstream>
 #include <iomanip>
@@ -7,65 +11,230 @@
 #include <algorithm>
 #include <map>
 
+// Helper: convert a list of strings to a JSON array
+static std::string string_array_to_json(const std::vector<std::string>& values) {
+    std::ostringstream oss;
+    oss << "[";
+    for (size_t i = 0; i < values.size(); ++i) {
+        oss << webui_json::encode_string(values[i]);
+        if (i + 1 < values.size()) oss << ", ";
+    }
+    oss << "]";
+    return oss.str();
+}
+
+// Helper: convert a name -> list of strings map (certificate pins) to a JSON object
+static std::string string_array_map_to_json(const std::map<std::string, std::vector<std::string>>& values) {
+    std::ostringstream oss;
+    oss << "{";
+    size_t i = 0;
+    for (const auto& entry : values) {
+        oss << webui_json::encode_string(entry.first) << ": " << string_array_to_json(entry.second);
+        if (++i < values.size()) oss << ", ";
+    }
+    oss << "}";
+    return oss.str();
+}
+
+// Helper: convert a name -> number map (interface metrics, check intervals) to a JSON object
+template <typename T>
+static std::string number_map_to_json(const std::map<std::string, T>& values) {
+    std::ostringstream oss;
+    oss << "{";
+    size_t i = 0;
+    for (const auto& entry : values) {
+        oss << webui_json::encode_string(entry.first) << ": " << entry.second;
+        if (++i < values.size()) oss << ", ";
+    }
+    oss << "}";
+    return oss.str();
+}
+
+// Helper: convert per-target validation overrides to a JSON object
+static std::string target_validation_to_json(const std::map<std::string, TargetValidationConfig>& rules) {
+    std::ostringstream oss;
+    oss << "{";
+    size_t i = 0;
+    for (const auto& entry : rules) {
+        oss << webui_json::encode_string(entry.first) << ": {\"expected_statuses\": [";
+        for (size_t j = 0; j < entry.second.expected_statuses.size(); ++j) {
+            oss << entry.second.expected_statuses[j];
+            if (j + 1 < entry.second.expected_statuses.size()) oss << ", ";
+        }
+        oss << "], \"min_body_bytes\": " << entry.second.min_body_bytes;
+        if (!entry.second.block_patterns.empty()) {
+            oss << ", \"block_patterns\": " << string_array_to_json(entry.second.block_patterns);
+        }
+        if (!entry.second.json_pointer.empty()) {
+            oss << ", \"json_pointer\": " << webui_json::encode_string(entry.second.json_pointer);
+            oss << ", \"json_expected\": " << webui_json::encode_string(entry.second.json_expected);
+        }
+        oss << "}";
+        if (++i < rules.size()) oss << ", ";
+    }
+    oss << "}";
+    return oss.str();
+}
+
+// Helper: convert an upstream proxy (and, recursively, its chain hops) to a JSON object
+static std::string upstream_proxy_to_json(const UpstreamProxyConfig& up) {
+    std::ostringstream oss;
+    oss << "{\"type\":" << webui_json::encode_string(up.proxy_type) << ",\"host\":" << webui_json::encode_string(up.host)
+        << ",\"port\":" << up.port;
+    if (!up.username.empty()) {
+        oss << ",\"username\":" << webui_json::encode_string(up.username)
+            << ",\"password\":" << webui_json::encode_string(up.password);
+    }
+    if (!up.tls_server_name.empty()) {
+        oss << ",\"tls_server_name\":" << webui_json::encode_string(up.tls_server_name);
+    }
+    if (up.tls_allow_self_signed) {
+        oss << ",\"tls_allow_self_signed\":true";
+    }
+    if (up.remote_dns) {
+        oss << ",\"remote_dns\":true";
+    }
+    if (up.danger_accept_invalid_certs) {
+        oss << ",\"danger_accept_invalid_certs\":true";
+    }
+    if (!up.chain.empty()) {
+        oss << ",\"chain\":[";
+        for (size_t i = 0; i < up.chain.size(); ++i) {
+            oss << upstream_proxy_to_json(up.chain[i]);
+            if (i + 1 < up.chain.size()) oss << ",";
+        }
+        oss << "]";
+    }
+    if (!up.dns_servers.empty()) {
+        oss << ",\"dns_servers\":" << string_array_to_json(up.dns_servers);
+    }
+    oss << "}";
+    return oss.str();
+}
+
 // Helper: convert config to JSON string
+std::string routing_mode_name(RoutingMode mode) {
+    switch (mode) {
+        case RoutingMode::Latency: return "latency";
+        case RoutingMode::FirstAccessible: return "first_accessible";
+        case RoutingMode::RoundRobin: return "round_robin";
+    }
+    return "latency";
+}
+
 static std::string config_to_json(const Config& config) {
     std::ostringstream oss;
     oss << "{\n";
-    oss << "  \"routing_mode\": \"";
-    switch (config.routing_mode) {
-        case RoutingMode::Latency: oss << "Latency"; break;
-        case RoutingMode::FirstAccessible: oss << "FirstAccessible"; break;
-        case RoutingMode::RoundRobin: oss << "RoundRobin"; break;
-    }
-    oss << "\",\n";
+    oss << "  \"routing_mode\": \"" << routing_mode_name(config.routing_mode) << "\",\n";
     oss << "  \"dns_servers\": [";
     for (size_t i = 0; i < config.dns_servers.size(); ++i) {
         const auto& dns = config.dns_servers[i];
-        oss << "{\"host\":\"" << dns.host << "\",\"port\":" << dns.port << ",\"name\":\"" << dns.name << "\"}";
+        oss << "{\"host\":" << webui_json::encode_string(dns.host) << ",\"port\":" << dns.port
+            << ",\"name\":" << webui_json::encode_string(dns.name) << "}";
         if (i + 1 < config.dns_servers.size()) oss << ", ";
     }
     oss << "],\n";
     oss << "  \"upstream_proxies\": [";
     for (size_t i = 0; i < config.upstream_proxies.size(); ++i) {
-        const auto& up = config.upstream_proxies[i];
-        oss << "{\"proxy_type\":\"" << up.proxy_type << "\",\"host\":\"" << up.host << "\",\"port\":" << up.port << "}";
+        oss << upstream_proxy_to_json(config.upstream_proxies[i]);
         if (i + 1 < config.upstream_proxies.size()) oss << ", ";
     }
     oss << "],\n";
     oss << "  \"interfaces\": [";
     for (size_t i = 0; i < config.interfaces.size(); ++i) {
-        oss << "\"" << config.interfaces[i] << "\"";
+        oss << webui_json::encode_string(config.interfaces[i]);
         if (i + 1 < config.interfaces.size()) oss << ", ";
     }
     oss << "],\n";
+    oss << "  \"interface_exclude\": " << string_array_to_json(config.interface_exclude) << ",\n";
+    oss << "  \"interface_metrics\": " << number_map_to_json(config.interface_metrics) << ",\n";
     oss << "  \"health_check_interval\": " << config.health_check_interval << ",\n";
+    oss << "  \"health_check_jitter\": " << config.health_check_jitter << ",\n";
+    oss << "  \"health_check_intervals\": " << number_map_to_json(config.health_check_intervals) << ",\n";
+    oss << "  \"probe_backoff_base_secs\": " << config.probe_backoff_base_secs << ",\n";
+    oss << "  \"probe_backoff_max_secs\": " << config.probe_backoff_max_secs << ",\n";
+    oss << "  \"accessible_probe_rate\": " << config.accessible_probe_rate << ",\n";
+    oss << "  \"health_max_targets_per_cycle\": " << config.health_max_targets_per_cycle << ",\n";
+    oss << "  \"health_max_failed_probes\": " << config.health_max_failed_probes << ",\n";
+    oss << "  \"health_max_partial_probes\": " << config.health_max_partial_probes << ",\n";
+    oss << "  \"health_probe_timeout\": " << config.health_probe_timeout << ",\n";
+    oss << "  \"health_check_concurrency\": " << config.health_check_concurrency << ",\n";
+    oss << "  \"max_concurrent_probes\": " << config.max_concurrent_probes << ",\n";
+    oss << "  \"warmup_targets\": " << string_array_to_json(config.warmup_targets) << ",\n";
+    oss << "  \"warmup_interval_secs\": " << config.warmup_interval_secs << ",\n";
     oss << "  \"accessibility_timeout\": " << config.accessibility_timeout << ",\n";
     oss << "  \"dns_timeout\": " << config.dns_timeout << ",\n";
     oss << "  \"network_timeout\": " << config.network_timeout << ",\n";
+    oss << "  \"connect_timeout\": " << config.connect_timeout << ",\n";
+    oss << "  \"danger_accept_invalid_certs\": " << (config.danger_accept_invalid_certs ? "true" : "false") << ",\n";
+    oss << "  \"certificate_pins\": " << string_array_map_to_json(config.certificate_pins) << ",\n";
     oss << "  \"user_validation_timeout\": " << config.user_validation_timeout << ",\n";
     oss << "  \"max_concurrent_connections\": " << config.max_concurrent_connections << ",\n";
     oss << "  \"max_connections_per_runway\": " << config.max_connections_per_runway << ",\n";
     oss << "  \"success_rate_threshold\": " << config.success_rate_threshold << ",\n";
     oss << "  \"success_rate_window\": " << config.success_rate_window << ",\n";
-    oss << "  \"log_level\": \"" << config.log_level << "\",\n";
-    oss << "  \"log_file\": \"" << config.log_file << "\",\n";
+    oss << "  \"uptime_window_secs\": " << config.uptime_window_secs << ",\n";
+    oss << "  \"latency_ema_alpha\": " << config.latency_ema_alpha << ",\n";
+    oss << "  \"target_ttl_secs\": " << config.target_ttl_secs << ",\n";
+    oss << "  \"log_level\": " << webui_json::encode_string(config.log_level) << ",\n";
+    oss << "  \"log_file\": " << webui_json::encode_string(config.log_file) << ",\n";
     oss << "  \"log_max_bytes\": " << config.log_max_bytes << ",\n";
     oss << "  \"log_backup_count\": " << config.log_backup_count << ",\n";
-    oss << "  \"proxy_listen_host\": \"" << config.proxy_listen_host << "\",\n";
+    oss << "  \"log_format\": " << webui_json::encode_string(config.log_format) << ",\n";
+    oss << "  \"audit_log_file\": " << webui_json::encode_string(config.audit_log_file) << ",\n";
+    oss << "  \"proxy_listen_host\": " << webui_json::encode_string(config.proxy_listen_host) << ",\n";
     oss << "  \"proxy_listen_port\": " << config.proxy_listen_port << ",\n";
+    oss << "  \"tls_cert_path\": " << webui_json::encode_string(config.tls_cert_path) << ",\n";
+    oss << "  \"tls_key_path\": " << webui_json::encode_string(config.tls_key_path) << ",\n";
+    oss << "  \"tls_client_ca_path\": " << webui_json::encode_string(config.tls_client_ca_path) << ",\n";
+    oss << "  \"tls_client_cert_required\": " << (config.tls_client_cert_required ? "true" : "false") << ",\n";
     oss << "  \"mouse_enabled\": " << (config.mouse_enabled ? "true" : "false") << ",\n";
     oss << "  \"webui_enabled\": " << (config.webui_enabled ? "true" : "false") << ",\n";
-    oss << "  \"webui_listen_host\": \"" << config.webui_listen_host << "\",\n";
-    oss << "  \"webui_listen_port\": " << config.webui_listen_port << "\n";
+    oss << "  \"webui_listen_host\": " << webui_json::encode_string(config.webui_listen_host) << ",\n";
+    oss << "  \"webui_listen_port\": " << config.webui_listen_port << ",\n";
+    oss << "  \"admin_enabled\": " << (config.admin_enabled ? "true" : "false") << ",\n";
+    oss << "  \"admin_listen_host\": " << webui_json::encode_string(config.admin_listen_host) << ",\n";
+    oss << "  \"admin_listen_port\": " << config.admin_listen_port << ",\n";
+    oss << "  \"admin_token\": " << webui_json::encode_string(config.admin_token) << ",\n";
+    oss << "  \"admin_tls_cert_path\": " << webui_json::encode_string(config.admin_tls_cert_path) << ",\n";
+    oss << "  \"admin_tls_key_path\": " << webui_json::encode_string(config.admin_tls_key_path) << ",\n";
+    oss << "  \"admin_tls_client_ca_path\": " << webui_json::encode_string(config.admin_tls_client_ca_path) << ",\n";
+    oss << "  \"admin_client_cert_path\": " << webui_json::encode_string(config.admin_client_cert_path) << ",\n";
+    oss << "  \"admin_client_key_path\": " << webui_json::encode_string(config.admin_client_key_path) << ",\n";
+    oss << "  \"health_listen_addr\": " << webui_json::encode_string(config.health_listen_addr) << ",\n";
+    oss << "  \"metrics_max_targets\": " << config.metrics_max_targets << ",\n";
+    oss << "  \"readiness_check\": " << webui_json::encode_string(config.readiness_check) << ",\n";
+    oss << "  \"readiness_window_secs\": " << config.readiness_window_secs << ",\n";
+    oss << "  \"block_patterns\": " << string_array_to_json(config.block_patterns) << ",\n";
+    oss << "  \"block_headers\": " << string_array_to_json(config.block_headers) << ",\n";
+    oss << "  \"block_regex_enabled\": " << (config.block_regex_enabled ? "true" : "false") << ",\n";
+    oss << "  \"block_regex_patterns\": " << string_array_to_json(config.block_regex_patterns) << ",\n";
+    oss << "  \"target_validation\": " << target_validation_to_json(config.target_validation) << ",\n";
+    oss << "  \"fingerprint_file\": " << webui_json::encode_string(config.fingerprint_file) << ",\n";
+    oss << "  \"body_size_anomaly_ratio\": " << config.body_size_anomaly_ratio << ",\n";
+    oss << "  \"captive_portal_detection\": " << (config.captive_portal_detection ? "true" : "false") << ",\n";
+    oss << "  \"redirect_allow\": " << string_array_to_json(config.redirect_allow) << ",\n";
+    oss << "  \"alert_webhook_url\": " << webui_json::encode_string(config.alert_webhook_url) << ",\n";
+    oss << "  \"alert_debounce_secs\": " << config.alert_debounce_secs << ",\n";
+    oss << "  \"otlp_endpoint\": " << webui_json::encode_string(config.otlp_endpoint) << ",\n";
+    oss << "  \"otlp_service_name\": " << webui_json::encode_string(config.otlp_service_name) << ",\n";
+    oss << "  \"trace_sample_rate\": " << config.trace_sample_rate << ",\n";
+    oss << "  \"statsd_endpoint\": " << webui_json::encode_string(config.statsd_endpoint) << ",\n";
+    oss << "  \"statsd_prefix\": " << webui_json::encode_string(config.statsd_prefix) << ",\n";
+    oss << "  \"statsd_sample_rate\": " << config.statsd_sample_rate << ",\n";
+    oss << "  \"statsd_dogstatsd\": " << (config.statsd_dogstatsd ? "true" : "false") << ",\n";
+    oss << "  \"proxy_failure_threshold\": " << config.proxy_failure_threshold << ",\n";
+    oss << "  \"max_runways\": " << config.max_runways << "\n";
     oss << "}";
     return oss.str();
 }
 
+std::string Config::to_json() const {
+    return config_to_json(*this);
+}
+
 bool Config::save(const std::string& path) const {
-    std::ofstream out(path);
-    if (!out.is_open()) return false;
-    out << config_to_json(*this) << std::endl;
-    return true;
+    return utils::write_file_atomic(path, config_to_json(*this) + "\n");
 }
 
 // RFC 7159 - JSON Data Interchange Format
@@ -74,24 +243,63 @@ bool Config::save(const std::string& path) const {
 Config::Config() 
     : routing_mode(RoutingMode::Latency)
     , health_check_interval(60)
+    , health_check_jitter(10.0)
+    , probe_backoff_base_secs(60)
+    , probe_backoff_max_secs(3600)
+    , accessible_probe_rate(0.1)
+    , health_max_targets_per_cycle(10)
+    , health_max_failed_probes(5)
+    , health_max_partial_probes(3)
+    , health_probe_timeout(5.0)
+    , health_check_concurrency(4)
+    , max_concurrent_probes(16)
+    , warmup_interval_secs(3600)
     , accessibility_timeout(5)
     , dns_timeout(3.0)
     , network_timeout(10)
+    , connect_timeout(5.0)
+    , danger_accept_invalid_certs(false)
     , user_validation_timeout(15)
     , max_concurrent_connections(100)
     , max_connections_per_runway(10)
     , success_rate_threshold(0.5)
     , success_rate_window(10)
+    , uptime_window_secs(3600)
+    , target_ttl_secs(86400)
+    , latency_ema_alpha(0.3)
     , log_level("INFO")
     , log_file("logs/proxy.log")
     , log_max_bytes(10485760)
     , log_backup_count(5)
+    , log_format("text")
+    , audit_log_file("logs/audit.log")
     , proxy_listen_host("127.0.0.1")
     , proxy_listen_port(2123)
+    , tls_cert_path("")
+    , tls_key_path("")
+    , tls_client_ca_path("")
+    , tls_client_cert_required(true)
     , mouse_enabled(false) // Disabled by default
     , webui_enabled(false) // Disabled by default
     , webui_listen_host("127.0.0.1")
     , webui_listen_port(8080)
+    , admin_enabled(true)
+    , admin_listen_host("127.0.0.1")
+    , admin_listen_port(2124)
+    , metrics_max_targets(100)
+    , readiness_check("accessible")
+    , readiness_window_secs(300)
+    , block_regex_enabled(false)
+    , body_size_anomaly_ratio(0.0)
+    , captive_portal_detection(false)
+    , alert_debounce_secs(300)
+    , otlp_service_name("smartproxy")
+    , trace_sample_rate(1.0)
+    , statsd_prefix("smartproxy")
+    , statsd_sample_rate(1.0)
+    , statsd_dogstatsd(false)
+    , proxy_failure_threshold(3)
+    , max_runways(256)
 {
     interfaces.push_back("auto");
     
@@ -102,6 +310,134 @@ Config::Config()
     
     // Default upstream proxy (SOCKS5 on localhost)
     upstream_proxies.push_back(UpstreamProxyConfig{"socks5", "127.0.0.1", 1080});
+    
+    // Loopback and container/virtual interfaces are not real egress paths
+    interface_exclude = {"lo", "lo0", "docker*", "veth*", "br-*", "virbr*"};
+    
+    // Default block page patterns
+    block_patterns = {"blocked", "forbidden", "access denied", "error 403", "error 404"};
+    
+    // Default interception header signals
+    block_headers = {"x-squid-error"};
+}
+
+std::vector<std::string> Config::validate() const {
+    std::vector<std::string> errors;
+    for (const auto& proxy : upstream_proxies) {
+        if (!upstream::is_supported_type(proxy.proxy_type)) {
+            errors.push_back("upstream proxy " + proxy.host + ":" + std::to_string(proxy.port) +
+                             " has unknown type '" + proxy.proxy_type + "' (expected one of: " +
+                             upstream::supported_types() + ")");
+        }
+        if (utils::to_lower(proxy.proxy_type) == "https" && !tls::available()) {
+            errors.push_back("upstream proxy " + proxy.host + ":" + std::to_string(proxy.port) +
+                             " is https, but this build has no TLS support (rebuild with -DSMARTPROXY_TLS=ON)");
+        }
+        for (const auto& hop : proxy.chain) {
+            if (!upstream::is_supported_type(hop.proxy_type)) {
+                errors.push_back("chain hop " + hop.host + ":" + std::to_string(hop.port) + " of upstream proxy " +
+                                 proxy.host + ":" + std::to_string(proxy.port) + " has unknown type '" +
+                                 hop.proxy_type + "' (expected one of: " + upstream::supported_types() + ")");
+            } else if (utils::to_lower(hop.proxy_type) == "https") {
+                // TLS inside a tunnel would need TLS-over-TLS; only the entry hop can be https
+                errors.push_back("chain hop " + hop.host + ":" + std::to_string(hop.port) + " of upstream proxy " +
+                                 proxy.host + ":" + std::to_string(proxy.port) +
+                                 " is https; only the first proxy of a chain can use TLS");
+            }
+        }
+    }
+    if (tls_cert_path.empty() != tls_key_path.empty()) {
+        errors.push_back("tls_cert_path and tls_key_path must be set together");
+    } else if (!tls_cert_path.empty() && !tls::available()) {
+        errors.push_back("tls_cert_path is set, but this build has no TLS support (rebuild with -DSMARTPROXY_TLS=ON)");
+    }
+    if (!tls_client_ca_path.empty() && tls_cert_path.empty()) {
+        errors.push_back("tls_client_ca_path needs tls_cert_path: client certificates are only checked over TLS");
+    }
+    if (!otlp_endpoint.empty() && !tracing::available()) {
+        errors.push_back("otlp_endpoint is set, but this build has no tracing support (rebuild with -DSMARTPROXY_TRACING=ON)");
+    }
+    if (!(trace_sample_rate >= 0.0 && trace_sample_rate <= 1.0)) {
+        errors.push_back("trace_sample_rate must be between 0 and 1");
+    }
+    if (!(statsd_sample_rate > 0.0 && statsd_sample_rate <= 1.0)) {
+        errors.push_back("statsd_sample_rate must be greater than 0 and at most 1");
+    }
+    if (log_format != "text" && log_format != "json") {
+        errors.push_back("log_format '" + log_format + "' is not text or json");
+    }
+    for (const auto& entry : certificate_pins) {
+        for (const auto& pin : entry.second) {
+            // RFC 7469 pin-sha256: a SHA-256 hash is 44 base64 characters
+            if (pin.size() != 51 || pin.compare(0, 7, "sha256/") != 0) {
+                errors.push_back("certificate_pins for '" + entry.first + "': '" + pin + "' is not sha256/<base64 SHA-256>");
+            }
+        }
+    }
+    if (admin_tls_cert_path.empty() != admin_tls_key_path.empty()) {
+        errors.push_back("admin_tls_cert_path and admin_tls_key_path must be set together");
+    } else if (!admin_tls_cert_path.empty() && !tls::available()) {
+        errors.push_back("admin_tls_cert_path is set, but this build has no TLS support (rebuild with -DSMARTPROXY_TLS=ON)");
+    }
+    if (!admin_tls_client_ca_path.empty() && admin_tls_cert_path.empty()) {
+        errors.push_back("admin_tls_client_ca_path needs admin_tls_cert_path: client certificates are only checked over TLS");
+    }
+    if (admin_client_cert_path.empty() != admin_client_key_path.empty()) {
+        errors.push_back("admin_client_cert_path and admin_client_key_path must be set together");
+    }
+    std::string health_host;
+    uint16_t health_port = 0;
+    if (!health_listen_addr.empty() && !parse_listen_addr(health_listen_addr, health_host, health_port)) {
+        errors.push_back("health_listen_addr '" + health_listen_addr + "' is not host:port");
+    }
+    if (readiness_check != "runways" && readiness_check != "accessible") {
+        errors.push_back("readiness_check '" + readiness_check + "' is not runways or accessible");
+    }
+    for (const auto& pattern : block_regex_patterns) {
+        if (utils::has_nested_quantifier(pattern)) {
+            errors.push_back("block regex '" + pattern + "' repeats a group that contains a repetition "
+                             "(e.g. (a+)+), which can take exponential time on a body that almost matches");
+        }
+    }
+    for (const auto& entry : warmup_targets) {
+        std::string host;
+        uint16_t port = 80;
+        if (!parse_warmup_target(entry, host, port)) {
+            errors.push_back("warmup target '" + entry + "' is not host or host:port");
+        }
+    }
+    return errors;
+}
+
+bool Config::parse_warmup_target(const std::string& entry, std::string& host, uint16_t& port) {
+    std::string trimmed = utils::trim(entry);
+    size_t colon_pos = trimmed.find(':');
+    host = trimmed.substr(0, colon_pos);
+    if (host.empty()) {
+        return false;
+    }
+    if (colon_pos == std::string::npos) {
+        return true;
+    }
+    uint16_t parsed;
+    if (!utils::safe_str_to_uint16(trimmed.substr(colon_pos + 1), parsed) || parsed == 0) {
+        return false;
+    }
+    port = parsed;
+    return true;
+}
+
+bool Config::parse_listen_addr(const std::string& addr, std::string& host, uint16_t& port) {
+    std::string trimmed = utils::trim(addr);
+    size_t colon_pos = trimmed.rfind(':');
+    if (colon_pos == std::string::npos || !utils::safe_str_to_uint16(trimmed.substr(colon_pos + 1), port) || port == 0) {
+        return false;
+    }
+    host = trimmed.substr(0, colon_pos);
+    if (host.size() >= 2 && host.front() == '[' && host.back() == ']') {
+        host = host.substr(1, host.size() - 2);
+    }
+    return !host.empty();
 }
 
 Config Config::load(const std::string& path) {
@@ -211,6 +547,85 @@ bool Config::parse_null(const std::string& str, size_t& pos) {
     return false;
 }
 
+void Config::skip_value(const std::string& str, size_t& pos) {
+    int depth = 0;
+    bool in_string = false;
+    bool escaped = false;
+    
+    while (pos < str.length()) {
+        char c = str[pos];
+        if (escaped) {
+            escaped = false;
+            pos++;
+            continue;
+        }
+        if (c == '\\') {
+            escaped = true;
+            pos++;
+            continue;
+        }
+        if (c == '"') {
+            in_string = !in_string;
+            pos++;
+            continue;
+        }
+        if (!in_string) {
+            if (c == '{' || c == '[') depth++;
+            else if (c == '}' || c == ']') {
+                if (depth == 0) break;
+                depth--;
+            } else if (depth == 0 && (c == ',' || c == '}')) {
+                break;
+            }
+        }
+        pos++;
+    }
+}
+
+bool Config::replace_member(std::string& json, const std::string& key, const std::string& value_json) {
+    std::map<std::string, std::string> members;
+    size_t pos = 0;
+    if (!parse_object(json, pos, members)) {
+        return false;
+    }
+    
+    // Walk the top-level members (parse_object accepted them) looking for key
+    pos = 0;
+    skip_whitespace(json, pos);
+    pos++; // Skip '{'
+    while (!members.empty()) {
+        skip_whitespace(json, pos);
+        std::string name;
+        parse_string(json, pos, name);
+        skip_whitespace(json, pos);
+        pos++; // Skip ':'
+        skip_whitespace(json, pos);
+        size_t value_start = pos;
+        skip_value(json, pos);
+        size_t value_end = pos;
+        while (value_end > value_start && std::isspace(static_cast<unsigned char>(json[value_end - 1]))) {
+            value_end--;
+        }
+        if (name == key) {
+            json.replace(value_start, value_end - value_start, value_json);
+            return true;
+        }
+        skip_whitespace(json, pos);
+        if (json[pos] != ',') {
+            break;
+        }
+        pos++;
+    }
+    
+    // Not there: add it as the last member, after the existing ones
+    size_t close = json.rfind('}');
+    size_t last = json.find_last_not_of(" \t\r\n", close - 1);
+    bool empty = json[last] == '{';
+    json.replace(last + 1, close - last - 1,
+                 std::string(empty ? "\n" : ",\n") + "  " + webui_json::encode_string(key) + ": " + value_json + "\n");
+    return true;
+}
+
 bool Config::parse_object(const std::string& str, size_t& pos, std::map<std::string, std::string>& obj) {
     skip_whitespace(str, pos);
     if (pos >= str.length() || str[pos] != '{') return false;
@@ -238,38 +653,7 @@ bool Config::parse_object(const std::string& str, size_t& pos, std::map<std::str
         
         // Parse value as string representation (simplified)
         size_t value_start = pos;
-        int depth = 0;
-        bool in_string = false;
-        bool escaped = false;
-        
-        while (pos < str.length()) {
-            char c = str[pos];
-            if (escaped) {
-                escaped = false;
-                pos++;
-                continue;
-            }
-            if (c == '\\') {
-                escaped = true;
-                pos++;
-                continue;
-            }
-            if (c == '"') {
-                in_string = !in_string;
-                pos++;
-                continue;
-            }
-            if (!in_string) {
-                if (c == '{' || c == '[') depth++;
-                else if (c == '}' || c == ']') {
-                    if (depth == 0) break;
-                    depth--;
-                } else if (depth == 0 && (c == ',' || c == '}')) {
-                    break;
-                }
-            }
-            pos++;
-        }
+        skip_value(str, pos);
         
         std::string value = str.substr(value_start, pos - value_start);
         obj[key] = utils::trim(value);
@@ -355,6 +739,76 @@ bool Config::parse_array(const std::string& str, size_t& pos, std::vector<std::s
     return false;
 }
 
+bool Config::parse_string_array(const std::string& str, std::vector<std::string>& result) {
+    size_t pos = 0;
+    std::vector<std::string> items;
+    if (!parse_array(str, pos, items)) {
+        return false;
+    }
+    
+    result.clear();
+    for (const auto& item : items) {
+        size_t item_pos = 0;
+        std::string value;
+        if (parse_string(item, item_pos, value)) {
+            result.push_back(value);
+        }
+    }
+    return true;
+}
+
+bool Config::parse_target_validation(const std::string& str, std::map<std::string, TargetValidationConfig>& result) {
+    size_t pos = 0;
+    std::map<std::string, std::string> targets;
+    if (!parse_object(str, pos, targets)) {
+        return false;
+    }
+    
+    result.clear();
+    for (const auto& entry : targets) {
+        size_t rule_pos = 0;
+        std::map<std::string, std::string> fields;
+        if (!parse_object(entry.second, rule_pos, fields)) {
+            continue;
+        }
+        
+        TargetValidationConfig rule;
+        if (fields.find("expected_statuses") != fields.end()) {
+            size_t arr_pos = 0;
+            std::vector<std::string> items;
+            if (parse_array(fields["expected_statuses"], arr_pos, items)) {
+                for (const auto& item : items) {
+                    uint16_t status;
+                    if (utils::safe_str_to_uint16(utils::trim(item), status) && status >= 100 && status <= 599) {
+                        rule.expected_statuses.push_back(status);
+                    }
+                }
+            }
+        }
+        if (fields.find("min_body_bytes") != fields.end()) {
+            uint64_t val;
+            if (utils::safe_str_to_uint64(utils::trim(fields["min_body_bytes"]), val)) rule.min_body_bytes = val;
+        }
+        if (fields.find("json_pointer") != fields.end()) {
+            size_t str_pos = 0;
+            parse_string(utils::trim(fields["json_pointer"]), str_pos, rule.json_pointer);
+        }
+        if (fields.find("json_expected") != fields.end()) {
+            // Strings compare by value; numbers/booleans/null by their JSON text
+            std::string raw = utils::trim(fields["json_expected"]);
+            size_t str_pos = 0;
+            if (!parse_string(raw, str_pos, rule.json_expected)) {
+                rule.json_expected = raw;
+            }
+        }
+        if (fields.find("block_patterns") != fields.end()) {
+            parse_string_array(fields["block_patterns"], rule.block_patterns);
+        }
+        result[utils::to_lower(entry.first)] = rule;
+    }
+    return true;
+}
+
 Config Config::parse_json(const std::string& json_str) {
     Config config;
     size_t pos = 0;
@@ -371,9 +825,10 @@ Config Config::parse_json(const std::string& json_str) {
         if (mode.length() >= 2 && mode[0] == '"' && mode[mode.length()-1] == '"') {
             mode = mode.substr(1, mode.length() - 2);
         }
+        // "firstaccessible" and "roundrobin": files saved as "FirstAccessible"/"RoundRobin" by older versions
         if (mode == "latency") config.routing_mode = RoutingMode::Latency;
-        else if (mode == "first_accessible") config.routing_mode = RoutingMode::FirstAccessible;
-        else if (mode == "round_robin") config.routing_mode = RoutingMode::RoundRobin;
+        else if (mode == "first_accessible" || mode == "firstaccessible") config.routing_mode = RoutingMode::FirstAccessible;
+        else if (mode == "round_robin" || mode == "roundrobin") config.routing_mode = RoutingMode::RoundRobin;
     }
     
     // Parse numeric fields
@@ -382,6 +837,67 @@ Config Config::parse_json(const std::string& json_str) {
         std::string s = utils::trim(root["health_check_interval"]);
         if (utils::safe_str_to_uint64(s, val)) config.health_check_interval = val;
     }
+    if (root.find("health_check_jitter") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["health_check_jitter"]);
+        if (utils::safe_str_to_double(s, val) && val >= 0.0 && val <= 100.0) config.health_check_jitter = val;
+    }
+    if (root.find("probe_backoff_base_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["probe_backoff_base_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.probe_backoff_base_secs = val;
+    }
+    if (root.find("probe_backoff_max_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["probe_backoff_max_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.probe_backoff_max_secs = val;
+    }
+    if (root.find("accessible_probe_rate") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["accessible_probe_rate"]);
+        if (utils::safe_str_to_double(s, val) && val >= 0.0 && val <= 1.0) config.accessible_probe_rate = val;
+    }
+    if (root.find("health_max_targets_per_cycle") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["health_max_targets_per_cycle"]);
+        if (utils::safe_str_to_uint64(s, val)) config.health_max_targets_per_cycle = static_cast<size_t>(val);
+    }
+    if (root.find("health_max_failed_probes") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["health_max_failed_probes"]);
+        if (utils::safe_str_to_uint64(s, val)) config.health_max_failed_probes = static_cast<size_t>(val);
+    }
+    if (root.find("health_max_partial_probes") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["health_max_partial_probes"]);
+        if (utils::safe_str_to_uint64(s, val)) config.health_max_partial_probes = static_cast<size_t>(val);
+    }
+    if (root.find("health_probe_timeout") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["health_probe_timeout"]);
+        if (utils::safe_str_to_double(s, val) && val > 0.0) config.health_probe_timeout = val;
+    }
+    if (root.find("health_check_concurrency") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["health_check_concurrency"]);
+        if (utils::safe_str_to_uint64(s, val) && val > 0) config.health_check_concurrency = static_cast<size_t>(val);
+    }
+    if (root.find("max_concurrent_probes") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["max_concurrent_probes"]);
+        if (utils::safe_str_to_uint64(s, val)) config.max_concurrent_probes = static_cast<size_t>(val);
+    }
+    if (root.find("warmup_targets") != root.end()) {
+        std::vector<std::string> targets;
+        if (parse_string_array(root["warmup_targets"], targets)) {
+            config.warmup_targets = targets;
+        }
+    }
+    if (root.find("warmup_interval_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["warmup_interval_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.warmup_interval_secs = val;
+    }
     if (root.find("accessibility_timeout") != root.end()) {
         uint64_t val;
         std::string s = utils::trim(root["accessibility_timeout"]);
@@ -392,11 +908,54 @@ Config Config::parse_json(const std::string& json_str) {
         std::string s = utils::trim(root["dns_timeout"]);
         if (utils::safe_str_to_double(s, val)) config.dns_timeout = val;
     }
+    if (root.find("user_validation_timeout") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["user_validation_timeout"]);
+        if (utils::safe_str_to_uint64(s, val)) config.user_validation_timeout = val;
+    }
     if (root.find("network_timeout") != root.end()) {
         uint64_t val;
         std::string s = utils::trim(root["network_timeout"]);
         if (utils::safe_str_to_uint64(s, val)) config.network_timeout = val;
     }
+    if (root.find("connect_timeout") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["connect_timeout"]);
+        if (utils::safe_str_to_double(s, val) && val > 0.0) config.connect_timeout = val;
+    }
+    if (root.find("danger_accept_invalid_certs") != root.end()) {
+        std::string val = utils::trim(root["danger_accept_invalid_certs"]);
+        config.danger_accept_invalid_certs = (val == "true" || val == "1");
+    }
+    // Parse certificate_pins object: {"bank.example.com": ["sha256/...", "sha256/..."]}
+    if (root.find("certificate_pins") != root.end()) {
+        size_t obj_pos = 0;
+        std::map<std::string, std::string> pins;
+        if (parse_object(utils::trim(root["certificate_pins"]), obj_pos, pins)) {
+            config.certificate_pins.clear();
+            for (const auto& entry : pins) {
+                std::vector<std::string> values;
+                if (parse_string_array(entry.second, values) && !values.empty()) {
+                    config.certificate_pins[utils::to_lower(entry.first)] = values;
+                }
+            }
+        }
+    }
+    if (root.find("uptime_window_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["uptime_window_secs"]);
+        if (utils::safe_str_to_uint64(s, val) && val > 0) config.uptime_window_secs = val;
+    }
+    if (root.find("latency_ema_alpha") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["latency_ema_alpha"]);
+        if (utils::safe_str_to_double(s, val) && val > 0.0 && val <= 1.0) config.latency_ema_alpha = val;
+    }
+    if (root.find("target_ttl_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["target_ttl_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.target_ttl_secs = val;
+    }
     if (root.find("proxy_listen_port") != root.end()) {
         uint16_t val;
         std::string s = utils::trim(root["proxy_listen_port"]);
@@ -408,6 +967,25 @@ Config Config::parse_json(const std::string& json_str) {
             config.proxy_listen_host = host.substr(1, host.length() - 2);
         }
     }
+    if (root.find("tls_cert_path") != root.end()) {
+        std::string value = utils::trim(root["tls_cert_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.tls_cert_path);
+    }
+    if (root.find("tls_key_path") != root.end()) {
+        std::string value = utils::trim(root["tls_key_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.tls_key_path);
+    }
+    if (root.find("tls_client_ca_path") != root.end()) {
+        std::string value = utils::trim(root["tls_client_ca_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.tls_client_ca_path);
+    }
+    if (root.find("tls_client_cert_required") != root.end()) {
+        std::string val = utils::trim(root["tls_client_cert_required"]);
+        config.tls_client_cert_required = (val == "true" || val == "1");
+    }
     
     // Parse mouse_enabled boolean
     if (root.find("mouse_enabled") != root.end()) {
@@ -446,124 +1024,410 @@ Config Config::parse_json(const std::string& json_str) {
         if (utils::safe_str_to_uint16(s, val)) config.webui_listen_port = val;
     }
     
-    // Parse arrays (simplified - would need full array parsing for nested objects)
-    // For now, we'll parse dns_servers and upstream_proxies manually from the JSON string
-    
-    // Simple extraction of DNS servers
-    size_t dns_start = json_str.find("\"dns_servers\"");
-    if (dns_start != std::string::npos) {
-        size_t arr_start = json_str.find('[', dns_start);
-        if (arr_start != std::string::npos) {
-            size_t arr_end = json_str.find(']', arr_start);
-            if (arr_end != std::string::npos) {
-                std::string dns_array = json_str.substr(arr_start + 1, arr_end - arr_start - 1);
-                // Simple parsing: look for host/port pairs
-                size_t host_pos = 0;
-                while ((host_pos = dns_array.find("\"host\"", host_pos)) != std::string::npos) {
-                    size_t colon = dns_array.find(':', host_pos);
-                    if (colon != std::string::npos) {
-                        size_t quote1 = dns_array.find('"', colon);
-                        size_t quote2 = dns_array.find('"', quote1 + 1);
-                        if (quote1 != std::string::npos && quote2 != std::string::npos) {
-                            DNSServerConfig dns;
-                            dns.host = dns_array.substr(quote1 + 1, quote2 - quote1 - 1);
-                            dns.port = 53; // Default
-                            
-                            // Find port
-                            size_t port_pos = dns_array.find("\"port\"", host_pos);
-                            if (port_pos != std::string::npos && port_pos < quote2 + 100) {
-                                size_t port_colon = dns_array.find(':', port_pos);
-                                if (port_colon != std::string::npos) {
-                                    uint16_t port_val;
-                                    std::string port_str = utils::trim(dns_array.substr(port_colon + 1, 10));
-                                    if (utils::safe_str_to_uint16(port_str, port_val)) {
-                                        dns.port = port_val;
-                                    }
-                                }
-                            }
-                            config.dns_servers.push_back(dns);
-                        }
-                    }
-                    host_pos++;
+    // Parse admin endpoint settings
+    if (root.find("admin_enabled") != root.end()) {
+        std::string val = utils::to_lower(utils::trim(root["admin_enabled"]));
+        config.admin_enabled = (val == "true" || val == "1");
+    }
+    if (root.find("admin_listen_host") != root.end()) {
+        std::string host = utils::trim(root["admin_listen_host"]);
+        size_t str_pos = 0;
+        parse_string(host, str_pos, config.admin_listen_host);
+    }
+    if (root.find("admin_listen_port") != root.end()) {
+        uint16_t val;
+        std::string s = utils::trim(root["admin_listen_port"]);
+        if (utils::safe_str_to_uint16(s, val)) config.admin_listen_port = val;
+    }
+    if (root.find("admin_token") != root.end()) {
+        std::string value = utils::trim(root["admin_token"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_token);
+    }
+    if (root.find("admin_tls_cert_path") != root.end()) {
+        std::string value = utils::trim(root["admin_tls_cert_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_tls_cert_path);
+    }
+    if (root.find("admin_tls_key_path") != root.end()) {
+        std::string value = utils::trim(root["admin_tls_key_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_tls_key_path);
+    }
+    if (root.find("admin_tls_client_ca_path") != root.end()) {
+        std::string value = utils::trim(root["admin_tls_client_ca_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_tls_client_ca_path);
+    }
+    if (root.find("admin_client_cert_path") != root.end()) {
+        std::string value = utils::trim(root["admin_client_cert_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_client_cert_path);
+    }
+    if (root.find("admin_client_key_path") != root.end()) {
+        std::string value = utils::trim(root["admin_client_key_path"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.admin_client_key_path);
+    }
+    if (root.find("metrics_max_targets") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["metrics_max_targets"]);
+        if (utils::safe_str_to_uint64(s, val)) config.metrics_max_targets = static_cast<size_t>(val);
+    }
+    if (root.find("health_listen_addr") != root.end()) {
+        std::string value = utils::trim(root["health_listen_addr"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.health_listen_addr);
+    }
+    if (root.find("readiness_check") != root.end()) {
+        std::string value = utils::trim(root["readiness_check"]);
+        std::string check;
+        size_t str_pos = 0;
+        if (parse_string(value, str_pos, check)) {
+            config.readiness_check = utils::to_lower(check);
+        }
+    }
+    if (root.find("readiness_window_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["readiness_window_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.readiness_window_secs = val;
+    }
+    
+    // Parse block_patterns (replaces the defaults when present)
+    if (root.find("block_patterns") != root.end()) {
+        std::vector<std::string> patterns;
+        if (parse_string_array(root["block_patterns"], patterns)) {
+            config.block_patterns = patterns;
+        }
+    }
+    
+    // Parse interface_exclude (replaces the defaults when present)
+    if (root.find("interface_exclude") != root.end()) {
+        std::vector<std::string> exclude;
+        if (parse_string_array(root["interface_exclude"], exclude)) {
+            config.interface_exclude = exclude;
+        }
+    }
+    
+    // Parse interface_metrics object: {"eth0": 100, "wwan*": 600}
+    if (root.find("interface_metrics") != root.end()) {
+        size_t obj_pos = 0;
+        std::map<std::string, std::string> metrics;
+        if (parse_object(utils::trim(root["interface_metrics"]), obj_pos, metrics)) {
+            config.interface_metrics.clear();
+            for (const auto& entry : metrics) {
+                uint32_t metric;
+                if (utils::safe_str_to_uint32(utils::trim(entry.second), metric)) {
+                    config.interface_metrics[entry.first] = metric;
                 }
             }
         }
     }
     
-    // Similar parsing for upstream_proxies
-    size_t proxy_start = json_str.find("\"upstream_proxies\"");
-    if (proxy_start != std::string::npos) {
-        size_t arr_start = json_str.find('[', proxy_start);
-        if (arr_start != std::string::npos) {
-            size_t arr_end = json_str.find(']', arr_start);
-            if (arr_end != std::string::npos) {
-                std::string proxy_array = json_str.substr(arr_start + 1, arr_end - arr_start - 1);
-                size_t type_pos = 0;
-                while ((type_pos = proxy_array.find("\"type\"", type_pos)) != std::string::npos) {
-                    size_t colon = proxy_array.find(':', type_pos);
-                    if (colon != std::string::npos) {
-                        size_t quote1 = proxy_array.find('"', colon);
-                        size_t quote2 = proxy_array.find('"', quote1 + 1);
-                        if (quote1 != std::string::npos && quote2 != std::string::npos) {
-                            UpstreamProxyConfig proxy;
-                            proxy.proxy_type = proxy_array.substr(quote1 + 1, quote2 - quote1 - 1);
-                            
-                            // Find host
-                            size_t host_pos = proxy_array.find("\"host\"", type_pos);
-                            if (host_pos != std::string::npos && host_pos < quote2 + 200) {
-                                size_t host_colon = proxy_array.find(':', host_pos);
-                                if (host_colon != std::string::npos) {
-                                    size_t hq1 = proxy_array.find('"', host_colon);
-                                    size_t hq2 = proxy_array.find('"', hq1 + 1);
-                                    if (hq1 != std::string::npos && hq2 != std::string::npos) {
-                                        proxy.host = proxy_array.substr(hq1 + 1, hq2 - hq1 - 1);
-                                    }
-                                }
-                            }
-                            
-                            // Find port
-                            size_t port_pos = proxy_array.find("\"port\"", type_pos);
-                            if (port_pos != std::string::npos && port_pos < quote2 + 200) {
-                                size_t port_colon = proxy_array.find(':', port_pos);
-                                if (port_colon != std::string::npos) {
-                                    uint16_t port_val;
-                                    std::string port_str = utils::trim(proxy_array.substr(port_colon + 1, 10));
-                                    if (utils::safe_str_to_uint16(port_str, port_val)) {
-                                        proxy.port = port_val;
-                                    }
-                                }
-                            }
-                            config.upstream_proxies.push_back(proxy);
-                        }
-                    }
-                    type_pos++;
+    // Parse health_check_intervals object: {"critical.example.com": 10, "*.example.org": 300}
+    if (root.find("health_check_intervals") != root.end()) {
+        size_t obj_pos = 0;
+        std::map<std::string, std::string> intervals;
+        if (parse_object(utils::trim(root["health_check_intervals"]), obj_pos, intervals)) {
+            config.health_check_intervals.clear();
+            for (const auto& entry : intervals) {
+                uint64_t interval;
+                if (utils::safe_str_to_uint64(utils::trim(entry.second), interval) && interval > 0) {
+                    config.health_check_intervals[utils::to_lower(entry.first)] = interval;
+                }
+            }
+        }
+    }
+    
+    // Parse block_headers (replaces the defaults when present)
+    if (root.find("block_headers") != root.end()) {
+        std::vector<std::string> headers;
+        if (parse_string_array(root["block_headers"], headers)) {
+            config.block_headers = headers;
+        }
+    }
+    
+    // Parse block_regex_enabled boolean
+    if (root.find("block_regex_enabled") != root.end()) {
+        std::string val = utils::to_lower(utils::trim(root["block_regex_enabled"]));
+        // Remove quotes if present
+        if (val.length() >= 2 && val[0] == '"' && val[val.length()-1] == '"') {
+            val = val.substr(1, val.length() - 2);
+        }
+        config.block_regex_enabled = (val == "true" || val == "1");
+    }
+    
+    // Parse block_regex_patterns
+    if (root.find("block_regex_patterns") != root.end()) {
+        std::vector<std::string> patterns;
+        if (parse_string_array(root["block_regex_patterns"], patterns)) {
+            config.block_regex_patterns = patterns;
+        }
+    }
+    
+    // Parse target_validation object
+    if (root.find("target_validation") != root.end()) {
+        parse_target_validation(root["target_validation"], config.target_validation);
+    }
+    
+    // Parse log file settings
+    if (root.find("log_file") != root.end()) {
+        std::string path = utils::trim(root["log_file"]);
+        size_t str_pos = 0;
+        parse_string(path, str_pos, config.log_file);
+    }
+    if (root.find("log_max_bytes") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["log_max_bytes"]);
+        if (utils::safe_str_to_uint64(s, val)) config.log_max_bytes = val;
+    }
+    if (root.find("log_backup_count") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["log_backup_count"]);
+        if (utils::safe_str_to_uint64(s, val)) config.log_backup_count = static_cast<size_t>(val);
+    }
+    
+    // Parse log_format
+    if (root.find("log_format") != root.end()) {
+        std::string value = utils::trim(root["log_format"]);
+        std::string format;
+        size_t str_pos = 0;
+        if (parse_string(value, str_pos, format)) {
+            config.log_format = utils::to_lower(format);
+        }
+    }
+    
+    // Parse audit_log_file
+    if (root.find("audit_log_file") != root.end()) {
+        std::string path = utils::trim(root["audit_log_file"]);
+        size_t str_pos = 0;
+        parse_string(path, str_pos, config.audit_log_file);
+    }
+    
+    // Parse fingerprint_file
+    if (root.find("fingerprint_file") != root.end()) {
+        std::string path = utils::trim(root["fingerprint_file"]);
+        size_t str_pos = 0;
+        parse_string(path, str_pos, config.fingerprint_file);
+    }
+    
+    // Parse body_size_anomaly_ratio (0 disables, otherwise 0 < ratio < 1)
+    if (root.find("body_size_anomaly_ratio") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["body_size_anomaly_ratio"]);
+        if (utils::safe_str_to_double(s, val) && val >= 0.0 && val < 1.0) config.body_size_anomaly_ratio = val;
+    }
+    
+    // Parse captive_portal_detection boolean
+    if (root.find("captive_portal_detection") != root.end()) {
+        std::string val = utils::to_lower(utils::trim(root["captive_portal_detection"]));
+        // Remove quotes if present
+        if (val.length() >= 2 && val[0] == '"' && val[val.length()-1] == '"') {
+            val = val.substr(1, val.length() - 2);
+        }
+        config.captive_portal_detection = (val == "true" || val == "1");
+    }
+    
+    // Parse redirect_allow
+    if (root.find("redirect_allow") != root.end()) {
+        std::vector<std::string> hosts;
+        if (parse_string_array(root["redirect_allow"], hosts)) {
+            config.redirect_allow = hosts;
+        }
+    }
+    
+    // Parse alert_webhook_url
+    if (root.find("alert_webhook_url") != root.end()) {
+        std::string url = utils::trim(root["alert_webhook_url"]);
+        size_t str_pos = 0;
+        parse_string(url, str_pos, config.alert_webhook_url);
+    }
+    
+    // Parse alert_debounce_secs
+    if (root.find("alert_debounce_secs") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["alert_debounce_secs"]);
+        if (utils::safe_str_to_uint64(s, val)) config.alert_debounce_secs = val;
+    }
+    
+    // Parse OpenTelemetry export settings
+    if (root.find("otlp_endpoint") != root.end()) {
+        std::string url = utils::trim(root["otlp_endpoint"]);
+        size_t str_pos = 0;
+        parse_string(url, str_pos, config.otlp_endpoint);
+    }
+    if (root.find("otlp_service_name") != root.end()) {
+        std::string name = utils::trim(root["otlp_service_name"]);
+        size_t str_pos = 0;
+        parse_string(name, str_pos, config.otlp_service_name);
+    }
+    if (root.find("trace_sample_rate") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["trace_sample_rate"]);
+        if (utils::safe_str_to_double(s, val)) config.trace_sample_rate = val;
+    }
+    
+    // Parse StatsD settings
+    if (root.find("statsd_endpoint") != root.end()) {
+        std::string value = utils::trim(root["statsd_endpoint"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.statsd_endpoint);
+    }
+    if (root.find("statsd_prefix") != root.end()) {
+        std::string value = utils::trim(root["statsd_prefix"]);
+        size_t str_pos = 0;
+        parse_string(value, str_pos, config.statsd_prefix);
+    }
+    if (root.find("statsd_sample_rate") != root.end()) {
+        double val;
+        std::string s = utils::trim(root["statsd_sample_rate"]);
+        if (utils::safe_str_to_double(s, val)) config.statsd_sample_rate = val;
+    }
+    if (root.find("statsd_dogstatsd") != root.end()) {
+        std::string val = utils::trim(root["statsd_dogstatsd"]);
+        config.statsd_dogstatsd = (val == "true" || val == "1");
+    }
+    
+    // Parse proxy_failure_threshold
+    if (root.find("proxy_failure_threshold") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["proxy_failure_threshold"]);
+        if (utils::safe_str_to_uint64(s, val) && val > 0 && val <= UINT32_MAX) {
+            config.proxy_failure_threshold = static_cast<uint32_t>(val);
+        }
+    }
+    
+    // Parse max_runways (0 = unlimited)
+    if (root.find("max_runways") != root.end()) {
+        uint64_t val;
+        std::string s = utils::trim(root["max_runways"]);
+        if (utils::safe_str_to_uint64(s, val)) config.max_runways = static_cast<size_t>(val);
+    }
+    
+    // Parse dns_servers array of objects (replaces the defaults when present)
+    if (root.find("dns_servers") != root.end()) {
+        size_t arr_pos = 0;
+        std::vector<std::string> items;
+        if (parse_array(root["dns_servers"], arr_pos, items)) {
+            config.dns_servers.clear();
+            for (const auto& item : items) {
+                size_t obj_pos = 0;
+                std::map<std::string, std::string> fields;
+                if (!parse_object(item, obj_pos, fields)) {
+                    continue;
+                }
+                DNSServerConfig dns;
+                size_t str_pos = 0;
+                parse_string(utils::trim(fields["host"]), str_pos, dns.host);
+                if (fields.find("port") != fields.end()) {
+                    utils::safe_str_to_uint16(utils::trim(fields["port"]), dns.port);
+                }
+                if (fields.find("name") != fields.end()) {
+                    str_pos = 0;
+                    parse_string(utils::trim(fields["name"]), str_pos, dns.name);
+                }
+                if (!dns.host.empty()) {
+                    config.dns_servers.push_back(dns);
                 }
             }
         }
     }
     
-    // Parse interfaces array
-    size_t iface_start = json_str.find("\"interfaces\"");
-    if (iface_start != std::string::npos) {
-        size_t arr_start = json_str.find('[', iface_start);
-        if (arr_start != std::string::npos) {
-            size_t arr_end = json_str.find(']', arr_start);
-            if (arr_end != std::string::npos) {
-                std::string iface_array = json_str.substr(arr_start + 1, arr_end - arr_start - 1);
-                size_t quote_pos = 0;
-                while ((quote_pos = iface_array.find('"', quote_pos)) != std::string::npos) {
-                    size_t quote_end = iface_array.find('"', quote_pos + 1);
-                    if (quote_end != std::string::npos) {
-                        std::string iface = iface_array.substr(quote_pos + 1, quote_end - quote_pos - 1);
-                        config.interfaces.push_back(iface);
-                        quote_pos = quote_end + 1;
-                    } else {
-                        break;
+    // Parse upstream_proxies array of objects (replaces the defaults when present)
+    if (root.find("upstream_proxies") != root.end()) {
+        size_t arr_pos = 0;
+        std::vector<std::string> items;
+        if (parse_array(root["upstream_proxies"], arr_pos, items)) {
+            config.upstream_proxies.clear();
+            
+            // One proxy object; false if it lacks a type, host or port
+            auto parse_proxy = [](const std::string& item, UpstreamProxyConfig& proxy,
+                                  std::map<std::string, std::string>& fields) {
+                size_t obj_pos = 0;
+                if (!parse_object(item, obj_pos, fields)) {
+                    return false;
+                }
+                
+                // String fields ("proxy_type" accepted as an alias of "type")
+                auto get_string = [&fields](const std::string& key) {
+                    std::string value;
+                    if (fields.find(key) != fields.end()) {
+                        size_t str_pos = 0;
+                        parse_string(utils::trim(fields[key]), str_pos, value);
                     }
+                    return value;
+                };
+                
+                proxy.proxy_type = utils::to_lower(get_string("type"));
+                if (proxy.proxy_type.empty()) {
+                    proxy.proxy_type = utils::to_lower(get_string("proxy_type"));
+                }
+                proxy.host = get_string("host");
+                proxy.username = get_string("username");
+                proxy.password = get_string("password");
+                proxy.tls_server_name = get_string("tls_server_name");
+                if (fields.find("tls_allow_self_signed") != fields.end()) {
+                    std::string val = utils::to_lower(utils::trim(fields["tls_allow_self_signed"]));
+                    proxy.tls_allow_self_signed = (val == "true" || val == "1");
+                }
+                if (fields.find("remote_dns") != fields.end()) {
+                    std::string val = utils::to_lower(utils::trim(fields["remote_dns"]));
+                    proxy.remote_dns = (val == "true" || val == "1");
+                }
+                if (fields.find("danger_accept_invalid_certs") != fields.end()) {
+                    std::string val = utils::to_lower(utils::trim(fields["danger_accept_invalid_certs"]));
+                    proxy.danger_accept_invalid_certs = (val == "true" || val == "1");
+                }
+                if (fields.find("port") != fields.end()) {
+                    utils::safe_str_to_uint16(utils::trim(fields["port"]), proxy.port);
+                }
+                return !proxy.proxy_type.empty() && !proxy.host.empty() && proxy.port != 0;
+            };
+            
+            for (const auto& item : items) {
+                UpstreamProxyConfig proxy;
+                std::map<std::string, std::string> fields;
+                if (!parse_proxy(item, proxy, fields)) {
+                    continue;
+                }
+                
+                // Optional "chain": further hops tunnelled through this proxy (entry first, exit last)
+                bool chain_ok = true;
+                if (fields.find("chain") != fields.end()) {
+                    size_t chain_pos = 0;
+                    std::vector<std::string> hops;
+                    if (parse_array(utils::trim(fields["chain"]), chain_pos, hops)) {
+                        for (const auto& hop_item : hops) {
+                            UpstreamProxyConfig hop;
+                            std::map<std::string, std::string> hop_fields;
+                            if (!parse_proxy(hop_item, hop, hop_fields)) {
+                                chain_ok = false; // A half-parsed chain would egress from the wrong hop
+                                break;
+                            }
+                            proxy.chain.push_back(hop);
+                        }
+                    }
+                }
+                
+                // Optional "dns_servers": restrict which DNS servers this proxy is paired with
+                if (fields.find("dns_servers") != fields.end()) {
+                    parse_string_array(fields["dns_servers"], proxy.dns_servers);
+                }
+                
+                if (chain_ok) {
+                    config.upstream_proxies.push_back(proxy);
                 }
             }
         }
     }
     
+    // Parse interfaces array (replaces the default "auto" when present)
+    if (root.find("interfaces") != root.end()) {
+        std::vector<std::string> interfaces;
+        if (parse_string_array(root["interfaces"], interfaces)) {
+            config.interfaces = interfaces;
+        }
+    }
+    
     return config;
 }
@@ -15,6 +15,9 @@ enum class RoutingMode {
     RoundRobin
 };
 
+// Name used in config.json and by `mode`: "latency", "first_accessible" or "round_robin"
+std::string routing_mode_name(RoutingMode mode);
+
 struct DNSServerConfig {
     std::string host;
     uint16_t port;
@@ -26,55 +29,169 @@ struct DNSServerConfig {
 };
 
 struct UpstreamProxyConfig {
-    std::string proxy_type; // http, https, socks4, socks5
+    std::string proxy_type; // http, https, socks4, socks4a, socks5, socks5h
     std::string host;
     uint16_t port;
+    std::string username; // Optional proxy credentials (never logged)
+    std::string password;
+    std::vector<UpstreamProxyConfig> chain; // Further hops reached through this proxy, in order; the last is the exit
+    std::vector<std::string> dns_servers; // Names or hosts of the DNS servers to pair with (empty = all)
+    std::string tls_server_name; // https: name the proxy's certificate must match (empty = host)
+    bool tls_allow_self_signed; // https: accept a self-signed proxy certificate
+    bool remote_dns; // socks4/socks5: send target host names for the proxy to resolve (as socks4a/socks5h)
+    bool danger_accept_invalid_certs; // Skip verifying the certificates of https targets reached through this proxy
     
-    UpstreamProxyConfig() : port(0) {}
+    UpstreamProxyConfig() : port(0), tls_allow_self_signed(false), remote_dns(false), danger_accept_invalid_certs(false) {}
     UpstreamProxyConfig(const std::string& type, const std::string& h, uint16_t p)
-        : proxy_type(type), host(h), port(p) {}
+        : proxy_type(type), host(h), port(p), tls_allow_self_signed(false), remote_dns(false),
+          danger_accept_invalid_certs(false) {}
+};
+
+// Field-by-field equality, used to tell which servers changed when the config is reloaded
+inline bool operator==(const DNSServerConfig& a, const DNSServerConfig& b) {
+    return a.host == b.host && a.port == b.port && a.name == b.name;
+}
+
+inline bool operator==(const UpstreamProxyConfig& a, const UpstreamProxyConfig& b) {
+    return a.proxy_type == b.proxy_type && a.host == b.host && a.port == b.port &&
+           a.username == b.username && a.password == b.password && a.chain == b.chain &&
+           a.dns_servers == b.dns_servers && a.tls_server_name == b.tls_server_name &&
+           a.tls_allow_self_signed == b.tls_allow_self_signed && a.remote_dns == b.remote_dns &&
+           a.danger_accept_invalid_certs == b.danger_accept_invalid_certs;
+}
+
+// Per-target validation overrides (see target_validation)
+struct TargetValidationConfig {
+    std::vector<uint16_t> expected_statuses; // Status codes that mean "up" for this target (e.g. 401 on auth-gated APIs)
+    uint64_t min_body_bytes; // Smaller HTML/text bodies count as user failure (0 = off)
+    std::string json_pointer; // RFC 6901 pointer ("/status") or "$.status"; body must be JSON containing it
+    std::string json_expected; // Expected value at json_pointer (empty = presence only)
+    std::vector<std::string> block_patterns; // Replaces the global block_patterns for this target when set
+    
+    TargetValidationConfig() : min_body_bytes(0) {}
 };
 
 struct Config {
-        // Save config to file as JSON
+        // Save config to file as JSON, atomically (see utils::write_file_atomic)
         bool save(const std::string& path) const;
+        // Serialize as saved by save()
+        std::string to_json() const;
     RoutingMode routing_mode;
     std::vector<DNSServerConfig> dns_servers;
     std::vector<UpstreamProxyConfig> upstream_proxies;
     std::vector<std::string> interfaces;
+    std::vector<std::string> interface_exclude; // Name patterns ('*' wildcard) skipped by "auto" discovery
+    std::map<std::string, uint32_t> interface_metrics; // Name pattern -> metric; lower is preferred (unset = OS route metric)
     uint64_t health_check_interval;
+    double health_check_jitter; // Percentage (0-100) by which each pause between health checks randomly varies
+    std::map<std::string, uint64_t> health_check_intervals; // Host pattern ("example.com", "*.example.com") -> check interval override
+    uint64_t probe_backoff_base_secs; // First wait before re-probing an Inaccessible runway; doubles per failure
+    uint64_t probe_backoff_max_secs; // Cap on that wait
+    double accessible_probe_rate; // Chance per health check that an Accessible runway is re-probed (0 = never)
+    size_t health_max_targets_per_cycle; // Targets checked per health check
+    size_t health_max_failed_probes; // Inaccessible runways re-probed per target per health check
+    size_t health_max_partial_probes; // PartiallyAccessible runways re-probed per target per health check
+    double health_probe_timeout; // Seconds allowed for each health probe
+    size_t health_check_concurrency; // Targets health-checked in parallel
+    size_t max_concurrent_probes; // Accessibility probe connections open at once, across all subsystems (0 = unlimited)
+    std::vector<std::string> warmup_targets; // "host" or "host:port" (default 80) probed on every runway at startup
+    uint64_t warmup_interval_secs; // Repeat the warm-up this often (0 = startup only)
     uint64_t accessibility_timeout;
     double dns_timeout;
     uint64_t network_timeout;
+    double connect_timeout; // Seconds allowed for a TCP handshake (to a target or proxy), separate from network_timeout
+    bool danger_accept_invalid_certs; // Skip verifying the certificates of https targets on every runway
+    std::map<std::string, std::vector<std::string>> certificate_pins; // Host pattern -> "sha256/<base64>" SPKI pins of https targets
     uint64_t user_validation_timeout;
     size_t max_concurrent_connections;
     size_t max_connections_per_runway;
     double success_rate_threshold;
     size_t success_rate_window;
+    uint64_t uptime_window_secs; // Rolling period for runway uptime percentage
+    uint64_t target_ttl_secs; // Evict targets idle longer than this (0 = never)
+    double latency_ema_alpha; // Latency moving-average weight of the newest sample (0 < alpha <= 1)
     std::string log_level;
     std::string log_file;
     uint64_t log_max_bytes;
     size_t log_backup_count;
+    std::string log_format; // "text" or "json" (one JSON object per line)
+    std::string audit_log_file; // JSON lines recording each change made to the running service (empty = not written)
     std::string proxy_listen_host;
     uint16_t proxy_listen_port;
+    std::string tls_cert_path; // PEM certificate for clients connecting to the proxy over TLS (empty = plain HTTP)
+    std::string tls_key_path; // PEM private key of tls_cert_path
+    std::string tls_client_ca_path; // PEM CA certificates; proxy clients' certificates must be issued by one of them
+    bool tls_client_cert_required; // With tls_client_ca_path: refuse clients without a certificate (else optional)
     bool mouse_enabled; // Enable mouse support in TUI
     bool webui_enabled; // Enable web UI server
     std::string webui_listen_host; // Web UI listen host
     uint16_t webui_listen_port; // Web UI listen port
+    bool admin_enabled; // Local admin endpoint used by CLI commands (e.g. reload)
+    std::string admin_listen_host; // Admin listen host (keep on loopback: plain HTTP, and open unless admin_token is set)
+    uint16_t admin_listen_port; // Admin listen port
+    std::string admin_token; // Bearer token required on admin requests (empty = none; CLI commands send it too)
+    std::string admin_tls_cert_path; // PEM certificate for serving the admin endpoint over TLS (empty = plain HTTP)
+    std::string admin_tls_key_path; // PEM private key of admin_tls_cert_path
+    std::string admin_tls_client_ca_path; // PEM CA certificates; admin clients must present a certificate they issued
+    std::string admin_client_cert_path; // PEM certificate CLI commands present to an admin endpoint that asks for one
+    std::string admin_client_key_path; // PEM private key of admin_client_cert_path
+    std::string health_listen_addr; // host:port of the load balancer health check, apart from admin (empty = disabled)
+    size_t metrics_max_targets; // Targets labelled in /metrics, busiest first; the rest are only counted (0 = unlimited)
+    std::string readiness_check; // /readyz: "runways" (discovery found a usable runway) or "accessible" (and one works for recent targets)
+    uint64_t readiness_window_secs; // Targets with activity this recent count for the "accessible" check
+    std::vector<std::string> block_patterns; // Case-insensitive substrings marking a block page
+    std::vector<std::string> block_headers; // "name" (present) or "name: value" (value contains) marking interception
+    bool block_regex_enabled; // Use block_regex_patterns instead of substring matching
+    std::vector<std::string> block_regex_patterns; // ECMAScript regexes marking a block page (case-insensitive)
+    std::map<std::string, TargetValidationConfig> target_validation; // Keyed by host pattern: "example.com" or "*.example.com"
+    std::string fingerprint_file; // Known block page fingerprints, reloaded when modified (empty = disabled)
+    double body_size_anomaly_ratio; // Flag 200 bodies smaller than ratio x learned size for the resource (0 = disabled)
+    bool captive_portal_detection; // Treat captive portal interception pages as user failures
+    std::vector<std::string> redirect_allow; // Host patterns a redirect may leave the requested site for ("*.example.net")
+    std::string alert_webhook_url; // Webhook for target-down alerts (empty = disabled)
+    uint64_t alert_debounce_secs; // Minimum seconds between alerts for the same target
+    std::string otlp_endpoint; // OTLP/HTTP collector for traces, e.g. http://127.0.0.1:4318 (empty = disabled)
+    std::string otlp_service_name; // service.name of exported traces
+    double trace_sample_rate; // Share of requests traced and, when they succeed, logged (0-1; failures are always logged)
+    std::string statsd_endpoint; // StatsD server for pushed request metrics, host[:port] (empty = disabled)
+    std::string statsd_prefix; // Prefix of every metric name
+    double statsd_sample_rate; // Share of requests whose metrics are sent (0 < rate <= 1)
+    bool statsd_dogstatsd; // Send outcome, status and runway as DogStatsD tags
+    uint32_t proxy_failure_threshold; // Consecutive failures before an upstream proxy is marked inaccessible
+    size_t max_runways; // Upper bound on discovered runways, bounding probe load (0 = unlimited)
     
     Config();
     static Config load(const std::string& path);
     static Config parse_json(const std::string& json_str);
     
+    // Check settings that parse but cannot work (e.g. unknown proxy types)
+    // Returns one message per problem; empty if the config is usable
+    std::vector<std::string> validate() const;
+    
+    // Split a warmup_targets entry into host and port (port is left unchanged if absent)
+    static bool parse_warmup_target(const std::string& entry, std::string& host, uint16_t& port);
+    
+    // Split a listen address, "host:port" or "[ipv6]:port", into host and port
+    static bool parse_listen_addr(const std::string& addr, std::string& host, uint16_t& port);
+    
+    // Simple JSON parser helpers, also used to read admin endpoint replies
+    // parse_object leaves member values as raw JSON text
+    static bool parse_string(const std::string& str, size_t& pos, std::string& result);
+    static bool parse_object(const std::string& str, size_t& pos, std::map<std::string, std::string>& obj);
+    static bool parse_string_array(const std::string& str, std::vector<std::string>& result);
+    static bool parse_array(const std::string& str, size_t& pos, std::vector<std::string>& arr); // Items as raw JSON
+    
+    // Set the top-level member key of a JSON object to value_json (raw JSON), leaving the rest of the
+    // text as it is, or add it as the last member; false if json is not an object
+    static bool replace_member(std::string& json, const std::string& key, const std::string& value_json);
+
 private:
-    // Simple JSON parser helpers
     static std::string skip_whitespace(const std::string& str, size_t& pos);
-    static bool parse_string(const std::string& str, size_t& pos, std::string& result);
+    static void skip_value(const std::string& str, size_t& pos); // To the ',', '}' or ']' after a member value
     static bool parse_number(const std::string& str, size_t& pos, double& result);
     static bool parse_boolean(const std::string& str, size_t& pos, bool& result);
     static bool parse_null(const std::string& str, size_t& pos);
-    static bool parse_object(const std::string& str, size_t& pos, std::map<std::string, std::string>& obj);
-    static bool parse_array(const std::string& str, size_t& pos, std::vector<std::string>& arr);
+    static bool parse_target_validation(const std::string& str, std::map<std::string, TargetValidationConfig>& result);
     static std::string unescape_string(const std::string& str);
 };
 
@@ -3,6 +3,7 @@
 #include <cstring>
 #include <algorithm>
 #include <ctime>
+#include <chrono>
 
 #ifdef _WIN32
 #include <winsock2.h>
@@ -11,9 +12,13 @@
 #endif
 
 // RFC 1035 - Domain Names - Implementation and Specification
+// RFC 3596 - DNS Extensions to Support IP Version 6
+
+static const uint16_t DNS_TYPE_A = 1;
+static const uint16_t DNS_TYPE_AAAA = 28;
 
 DNSResolver::DNSResolver(const std::vector<DNSServerConfig>& servers, double timeout_secs)
-    : servers_(servers), timeout_secs_(timeout_secs) {
+    : servers_(servers), timeout_secs_(timeout_secs), cache_hits_(0), cache_misses_(0) {
 }
 
 DNSResolver::~DNSResolver() {
@@ -30,7 +35,7 @@ uint64_t DNSResolver::get_current_time() const {
 }
 
 bool DNSResolver::is_ip_address(const std::string& target) const {
-    return utils::is_valid_ipv4(target);
+    return utils::is_valid_ipv4(target) || network::is_ipv6_address(target);
 }
 
 bool DNSResolver::is_private_ip(const std::string& ip) const {
@@ -50,7 +55,7 @@ void DNSResolver::encode_domain_name(const std::string& domain, std::vector<uint
     buffer.push_back(0); // Null terminator
 }
 
-std::vector<uint8_t> DNSResolver::build_dns_query(const std::string& domain) const {
+std::vector<uint8_t> DNSResolver::build_dns_query(const std::string& domain, uint16_t qtype) const {
     // RFC 1035 Section 4.1.1 - Message format
     std::vector<uint8_t> packet;
     
@@ -82,9 +87,9 @@ std::vector<uint8_t> DNSResolver::build_dns_query(const std::string& domain) con
     // Question section
     encode_domain_name(domain, packet);
     
-    // QTYPE: A record (1)
-    packet.push_back(0x00);
-    packet.push_back(0x01);
+    // QTYPE: A record (1) or AAAA record (28)
+    packet.push_back(static_cast<uint8_t>(qtype >> 8));
+    packet.push_back(static_cast<uint8_t>(qtype & 0xFF));
     
     // QCLASS: IN (1)
     packet.push_back(0x00);
@@ -133,7 +138,7 @@ bool DNSResolver::decode_domain_name(const std::vector<uint8_t>& response, size_
     return true;
 }
 
-bool DNSResolver::parse_dns_response(const std::vector<uint8_t>& response, std::string& ip) const {
+bool DNSResolver::parse_dns_response(const std::vector<uint8_t>& response, uint16_t qtype, std::string& ip) const {
     // RFC 1035 Section 4.1.3 - Response format
     if (response.size() < 12) return false;
     
@@ -169,7 +174,7 @@ bool DNSResolver::parse_dns_response(const std::vector<uint8_t>& response, std::
         pos += 2;
         
         // Check if A record (type 1)
-        if (type == 1 && class_val == 1 && rdlength == 4) {
+        if (qtype == DNS_TYPE_A && type == DNS_TYPE_A && class_val == 1 && rdlength == 4) {
             if (pos + 4 > response.size()) break;
             
             // Extract IP address
@@ -181,79 +186,145 @@ bool DNSResolver::parse_dns_response(const std::vector<uint8_t>& response, std::
             return true;
         }
         
+        // Check if AAAA record (RFC 3596 Section 2.2: 16 octets)
+        if (qtype == DNS_TYPE_AAAA && type == DNS_TYPE_AAAA && class_val == 1 && rdlength == 16) {
+            if (pos + 16 > response.size()) break;
+            
+            char ip_str[INET6_ADDRSTRLEN];
+            if (inet_ntop(AF_INET6, &response[pos], ip_str, sizeof(ip_str)) == nullptr) break;
+            ip = ip_str;
+            return true;
+        }
+        
         pos += rdlength; // Skip RDATA
     }
     
     return false;
 }
 
-std::pair<std::string, double> DNSResolver::resolve(const std::string& domain) {
+std::pair<std::string, double> DNSResolver::resolve(const std::string& domain, bool ipv6) {
     // Skip DNS for IP addresses
     if (is_ip_address(domain)) {
         return std::make_pair(domain, 0.0);
     }
     
-    // Check cache
+    // Check cache (AAAA answers are cached separately from A answers)
+    std::string cache_key = ipv6 ? "AAAA:" + domain : domain;
     uint64_t current_time = get_current_time();
-    auto cache_it = cache_.find(domain);
-    if (cache_it != cache_.end() && !cache_it->second.is_expired(current_time)) {
-        return std::make_pair(cache_it->second.ip, 0.0);
+    {
+        std::lock_guard<std::mutex> lock(cache_mutex_);
+        auto cache_it = cache_.find(cache_key);
+        if (cache_it != cache_.end() && !cache_it->second.is_expired(current_time)) {
+            cache_hits_++;
+            return std::make_pair(cache_it->second.ip, 0.0);
+        }
     }
+    cache_misses_++;
     
-    // Build query packet
-    std::vector<uint8_t> query = build_dns_query(domain);
-    
-    // Try each DNS server
+    // Try each DNS server (reported time includes servers that failed first)
+    auto query_start = std::chrono::steady_clock::now();
     for (const auto& server : servers_) {
-        socket_t sock = network::create_udp_socket();
-        if (sock == network::INVALID_SOCKET_VALUE) continue;
-        
-        // Set timeout
-        struct timeval timeout;
-        timeout.tv_sec = static_cast<long>(timeout_secs_);
-        timeout.tv_usec = static_cast<long>((timeout_secs_ - timeout.tv_sec) * 1000000);
-        
+        std::string ip;
+        double server_ms = 0.0;
+        if (query_server(server, domain, ipv6, ip, server_ms)) {
+            // Cache with TTL (default 300 seconds)
+            uint64_t expiry = current_time + 300;
+            {
+                std::lock_guard<std::mutex> lock(cache_mutex_);
+                cache_[cache_key] = DNSCacheEntry(ip, expiry);
+            }
+            double elapsed_ms = std::chrono::duration<double, std::milli>(
+                std::chrono::steady_clock::now() - query_start).count();
+            return std::make_pair(ip, elapsed_ms);
+        }
+    }
+    
+    return std::make_pair("", 0.0);
+}
+
+size_t DNSResolver::clear_cache(const std::string& domain) {
+    std::lock_guard<std::mutex> lock(cache_mutex_);
+    if (!domain.empty()) {
+        return cache_.erase(domain) + cache_.erase("AAAA:" + domain); // Keys as built in resolve()
+    }
+    size_t dropped = cache_.size();
+    cache_.clear();
+    return dropped;
+}
+
+std::vector<DNSCacheRecord> DNSResolver::get_cache_entries() {
+    uint64_t current_time = get_current_time();
+    std::vector<DNSCacheRecord> records;
+    {
+        std::lock_guard<std::mutex> lock(cache_mutex_);
+        for (const auto& pair : cache_) {
+            DNSCacheRecord record;
+            record.ipv6 = pair.first.compare(0, 5, "AAAA:") == 0; // Keys as built in resolve()
+            record.domain = record.ipv6 ? pair.first.substr(5) : pair.first;
+            record.ip = pair.second.ip;
+            record.ttl_remaining = pair.second.is_expired(current_time) ? 0 : pair.second.expiry_time - current_time;
+            records.push_back(record);
+        }
+    }
+    std::sort(records.begin(), records.end(), [](const DNSCacheRecord& a, const DNSCacheRecord& b) {
+        return a.domain != b.domain ? a.domain < b.domain : !a.ipv6 && b.ipv6;
+    });
+    return records;
+}
+
+bool DNSResolver::query_server(const DNSServerConfig& server, const std::string& domain, bool ipv6,
+                               std::string& ip, double& elapsed_ms) const {
+    uint16_t qtype = ipv6 ? DNS_TYPE_AAAA : DNS_TYPE_A;
+    std::vector<uint8_t> query = build_dns_query(domain, qtype);
+    auto query_start = std::chrono::steady_clock::now();
+    
+    socket_t sock = network::create_udp_socket();
+    if (sock == network::INVALID_SOCKET_VALUE) return false;
+    
+    // Set timeout
+    struct timeval timeout;
+    double timeout_secs = timeout_secs_;
+    timeout.tv_sec = static_cast<long>(timeout_secs);
+    timeout.tv_usec = static_cast<long>((timeout_secs - timeout.tv_sec) * 1000000);
+    
 #ifdef _WIN32
-        setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
 #else
-        setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
 #endif
-        
-        // Send query
-        struct sockaddr_in server_addr;
-        if (!network::ip_to_sockaddr(server.host, server.port, server_addr)) {
-            network::close_socket(sock);
-            continue;
-        }
-        
-        ssize_t sent = sendto(sock, reinterpret_cast<const char*>(query.data()), static_cast<int>(query.size()), 0,
-                              reinterpret_cast<struct sockaddr*>(&server_addr), sizeof(server_addr));
-        if (sent != static_cast<ssize_t>(query.size())) {
-            network::close_socket(sock);
-            continue;
-        }
-        
-        // Receive response
-        std::vector<uint8_t> response(512);
-        struct sockaddr_in from_addr;
-        socklen_t from_len = sizeof(from_addr);
-        
-        ssize_t received = recvfrom(sock, reinterpret_cast<char*>(response.data()), static_cast<int>(response.size()), 0,
-                                    reinterpret_cast<struct sockaddr*>(&from_addr), &from_len);
-        
+    
+    // Send query
+    struct sockaddr_in server_addr;
+    if (!network::ip_to_sockaddr(server.host, server.port, server_addr)) {
         network::close_socket(sock);
-        
-        if (received > 0) {
-            response.resize(static_cast<size_t>(received));
-            std::string ip;
-            if (parse_dns_response(response, ip)) {
-                // Cache with TTL (default 300 seconds)
-                uint64_t expiry = current_time + 300;
-                cache_[domain] = DNSCacheEntry(ip, expiry);
-                return std::make_pair(ip, 0.0); // Simplified timing
-            }
-        }
+        return false;
     }
     
-    return std::make_pair("", 0.0);
+    ssize_t sent = sendto(sock, reinterpret_cast<const char*>(query.data()), static_cast<int>(query.size()), 0,
+                          reinterpret_cast<struct sockaddr*>(&server_addr), sizeof(server_addr));
+    if (sent != static_cast<ssize_t>(query.size())) {
+        network::close_socket(sock);
+        return false;
+    }
+    
+    // Receive response
+    std::vector<uint8_t> response(512);
+    struct sockaddr_in from_addr;
+    socklen_t from_len = sizeof(from_addr);
+    
+    ssize_t received = recvfrom(sock, reinterpret_cast<char*>(response.data()), static_cast<int>(response.size()), 0,
+                                reinterpret_cast<struct sockaddr*>(&from_addr), &from_len);
+    
+    network::close_socket(sock);
+    
+    if (received <= 0) {
+        return false;
+    }
+    response.resize(static_cast<size_t>(received));
+    if (!parse_dns_response(response, qtype, ip)) {
+        return false;
+    }
+    elapsed_ms = std::chrono::duration<double, std::milli>(
+        std::chrono::steady_clock::now() - query_start).count();
+    return true;
 }
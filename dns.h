@@ -5,12 +5,15 @@
 #include <vector>
 #include <map>
 #include <memory>
+#include <mutex>
+#include <atomic>
 #include <cstdint>
 #include "config.h"
 #include "network.h"
 
 // DNS Resolver (RFC 1035 compliant)
 // Reference: RFC 1035 - Domain Names - Implementation and Specification
+// RFC 3596 - DNS Extensions to Support IP Version 6 (AAAA records)
 
 struct DNSCacheEntry {
     std::string ip;
@@ -25,34 +28,65 @@ struct DNSCacheEntry {
     }
 };
 
+// One cached answer, as listed by `dns show`
+struct DNSCacheRecord {
+    std::string domain;
+    bool ipv6; // AAAA answer (otherwise A)
+    std::string ip;
+    uint64_t ttl_remaining; // Seconds until it expires; 0 once expired (kept until looked up again)
+};
+
 class DNSResolver {
 public:
     DNSResolver(const std::vector<DNSServerConfig>& servers, double timeout_secs);
     ~DNSResolver();
     
-    // Check if target is already an IP address
+    // Check if target is already an IP address (IPv4 or IPv6)
     bool is_ip_address(const std::string& target) const;
     
     // Check if IP is private (RFC 1918)
     bool is_private_ip(const std::string& ip) const;
     
-    // Resolve domain to IP address
+    // Resolve domain to IP address (A record, or AAAA record if ipv6)
     // Returns (ip_address, response_time_ms) or ("", 0.0) on failure
-    std::pair<std::string, double> resolve(const std::string& domain);
+    std::pair<std::string, double> resolve(const std::string& domain, bool ipv6 = false);
+    
+    // Query one server directly, bypassing the cache (used to test a server's health)
+    // elapsed_ms receives the round-trip time on success
+    bool query_server(const DNSServerConfig& server, const std::string& domain, bool ipv6,
+                      std::string& ip, double& elapsed_ms) const;
+    
+    // Per-query timeout; can change while running
+    void set_timeout(double timeout_secs) { timeout_secs_ = timeout_secs; }
+    
+    // Drop all cached answers (e.g. after an interface address change, when the
+    // answers may reflect the previous network), or only those for one domain (A and AAAA)
+    // Returns the number of entries dropped
+    size_t clear_cache(const std::string& domain = "");
+    
+    // Cached answers, expired ones included, ordered by domain (A before AAAA)
+    std::vector<DNSCacheRecord> get_cache_entries();
+    
+    // Lookups answered from the cache, and lookups that went to a server (IP addresses count as neither)
+    uint64_t get_cache_hits() const { return cache_hits_; }
+    uint64_t get_cache_misses() const { return cache_misses_; }
     
 private:
     std::vector<DNSServerConfig> servers_;
-    double timeout_secs_;
+    std::atomic<double> timeout_secs_;
     std::map<std::string, DNSCacheEntry> cache_;
+    std::mutex cache_mutex_;
+    std::atomic<uint64_t> cache_hits_;
+    std::atomic<uint64_t> cache_misses_;
     
     // Get current Unix timestamp
     uint64_t get_current_time() const;
     
     // Build DNS query packet (RFC 1035 Section 4.1.1)
-    std::vector<uint8_t> build_dns_query(const std::string& domain) const;
+    std::vector<uint8_t> build_dns_query(const std::string& domain, uint16_t qtype) const;
     
-    // Parse DNS response packet (RFC 1035 Section 4.1.3)
-    bool parse_dns_response(const std::vector<uint8_t>& response, std::string& ip) const;
+    // Parse DNS response packet (RFC 1035 Section 4.1.3), taking the first answer of qtype
+    bool parse_dns_response(const std::vector<uint8_t>& response, uint16_t qtype, std::string& ip) const;
     
     // Encode domain name for DNS (RFC 1035 Section 3.1)
     void encode_domain_name(const std::string& domain, std::vector<uint8_t>& buffer) const;
@@ -1,5 +1,8 @@
 #include "health.h"
+#include "logger.h"
+#include "utils.h"
 #include <chrono>
+#include <ctime>
 #include <thread>
 #include <algorithm>
 
@@ -16,15 +19,72 @@
 HealthMonitor::HealthMonitor(
     std::shared_ptr<RunwayManager> runway_manager,
     std::shared_ptr<TargetAccessibilityTracker> tracker,
-    uint64_t interval_secs)
+    uint64_t interval_secs,
+    uint64_t target_ttl_secs,
+    double accessible_probe_rate,
+    size_t max_targets_per_cycle,
+    size_t max_failed_probes,
+    size_t max_partial_probes,
+    double probe_timeout,
+    double jitter_percent,
+    size_t concurrency,
+    const std::vector<std::string>& warmup_targets,
+    uint64_t warmup_interval_secs,
+    const std::map<std::string, uint64_t>& target_intervals)
     : runway_manager_(runway_manager)
     , tracker_(tracker)
     , interval_secs_(interval_secs)
-    , running_(false) {
+    , target_ttl_secs_(target_ttl_secs)
+    , accessible_probe_rate_(accessible_probe_rate)
+    , max_targets_per_cycle_(max_targets_per_cycle)
+    , max_failed_probes_(max_failed_probes)
+    , max_partial_probes_(max_partial_probes)
+    , probe_timeout_(probe_timeout)
+    , jitter_percent_(jitter_percent)
+    , concurrency_(concurrency)
+    , warmup_targets_(warmup_targets)
+    , warmup_interval_secs_(warmup_interval_secs)
+    , last_warmup_(0)
+    , tick_secs_(interval_secs)
+    , rng_(std::random_device{}())
+    , running_(false)
+    , paused_(false)
+    , interfaces_changed_(false)
+    , next_listener_id_(0) {
+    // Rebuild runways on the next cycle whenever interfaces come, go or change address
+    interface_listener_id_ = runway_manager_->add_interface_listener([this](const InterfaceEvent&) {
+        interfaces_changed_ = true;
+    });
+    
+    // Wake up often enough for the shortest per-target interval
+    for (const auto& entry : target_intervals) {
+        if (entry.second > 0) {
+            target_intervals_[utils::to_lower(entry.first)] = entry.second;
+            tick_secs_ = std::min(tick_secs_, entry.second);
+        }
+    }
+    
+    // Runways present at startup are not "new"; they are classified as targets show up
+    for (const auto& runway : runway_manager_->get_all_runways(true)) {
+        known_runway_ids_.insert(runway->id);
+    }
+}
+
+size_t HealthMonitor::add_listener(HealthEventCallback callback) {
+    std::lock_guard<std::mutex> lock(listeners_mutex_);
+    size_t listener_id = next_listener_id_++;
+    listeners_[listener_id] = callback;
+    return listener_id;
+}
+
+void HealthMonitor::remove_listener(size_t listener_id) {
+    std::lock_guard<std::mutex> lock(listeners_mutex_);
+    listeners_.erase(listener_id);
 }
 
 HealthMonitor::~HealthMonitor() {
     stop();
+    runway_manager_->remove_interface_listener(interface_listener_id_);
 }
 
 void HealthMonitor::start() {
@@ -48,85 +108,323 @@ void HealthMonitor::stop() {
     }
 }
 
+void HealthMonitor::pause() {
+    if (!paused_.exchange(true)) {
+        Logger::instance().log(LogLevel::INFO, "Health monitor paused");
+    }
+}
+
+void HealthMonitor::resume() {
+    if (paused_.exchange(false)) {
+        Logger::instance().log(LogLevel::INFO, "Health monitor resumed");
+    }
+}
+
 void HealthMonitor::monitor_loop() {
     while (running_) {
-        try {
-            health_check_cycle();
-        } catch (...) {
-            // Defensive: continue on errors
+        if (!paused_) {
+            try {
+                health_check_cycle();
+            } catch (...) {
+                // Defensive: continue on errors
+            }
         }
         
-        // Sleep for interval
-        for (uint64_t i = 0; i < interval_secs_ && running_; ++i) {
-            std::this_thread::sleep_for(std::chrono::seconds(1));
+        // Sleep for interval (jittered), in short slices so stop() stays responsive
+        uint64_t remaining_ms = next_sleep_ms();
+        while (remaining_ms > 0 && running_) {
+            uint64_t slice_ms = std::min(remaining_ms, uint64_t(1000));
+            std::this_thread::sleep_for(std::chrono::milliseconds(slice_ms));
+            remaining_ms -= slice_ms;
         }
     }
 }
 
+uint64_t HealthMonitor::interval_for(const std::string& target) const {
+    auto exact = target_intervals_.find(utils::to_lower(target));
+    if (exact != target_intervals_.end()) {
+        return exact->second;
+    }
+    
+    // Most specific (longest) matching pattern wins
+    uint64_t interval = interval_secs_;
+    size_t best_length = 0;
+    for (const auto& entry : target_intervals_) {
+        if (entry.first.length() > best_length && utils::match_host_pattern(target, entry.first)) {
+            interval = entry.second;
+            best_length = entry.first.length();
+        }
+    }
+    return interval;
+}
+
+uint64_t HealthMonitor::next_sleep_ms() {
+    double interval_ms = static_cast<double>(tick_secs_) * 1000.0;
+    if (jitter_percent_ <= 0.0) {
+        return static_cast<uint64_t>(interval_ms);
+    }
+    
+    // Spread instances and cycles apart so their probes don't line up into bursts
+    double spread = std::min(jitter_percent_, 100.0) / 100.0;
+    std::uniform_real_distribution<double> factor(1.0 - spread, 1.0 + spread);
+    std::lock_guard<std::mutex> lock(rng_mutex_);
+    return static_cast<uint64_t>(interval_ms * factor(rng_));
+}
+
 void HealthMonitor::health_check_cycle() {
-    // Refresh interface information
+    // Refresh interface information; rebuild runways when interfaces come or go
     runway_manager_->refresh_interfaces();
+    if (interfaces_changed_.exchange(false)) {
+        std::vector<std::string> previous_ids;
+        for (const auto& runway : runway_manager_->get_all_runways(true)) {
+            previous_ids.push_back(runway->id);
+        }
+        
+        // Runways that did not survive (e.g. their source address changed) take their
+        // pre-change metrics with them, so routing never relies on the old egress path
+        size_t runway_count = runway_manager_->discover_runways().size();
+        for (const auto& runway_id : previous_ids) {
+            if (!runway_manager_->get_runway(runway_id)) {
+                tracker_->remove_runway(runway_id);
+            }
+        }
+        Logger::instance().log(LogLevel::INFO, "Interfaces changed, now " + std::to_string(runway_count) + " runways");
+    }
+    
+    // Drop idle targets so the metrics map stays bounded
+    if (target_ttl_secs_ > 0) {
+        size_t evicted = tracker_->evict_stale_targets(target_ttl_secs_);
+        if (evicted > 0) {
+            Logger::instance().log(LogLevel::DEBUG, "Evicted " + std::to_string(evicted) + " idle targets");
+        }
+    }
+    
+    // Pre-classify the configured popular targets at startup and every warmup_interval
+    uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+    if (!warmup_targets_.empty() &&
+        (last_warmup_ == 0 || (warmup_interval_secs_ > 0 && now - last_warmup_ >= warmup_interval_secs_))) {
+        last_warmup_ = now;
+        warm_up();
+    }
     
-    // Get all known targets
-    std::vector<std::string> targets = tracker_->get_all_targets();
+    // Get all known targets, stalest first, so that with more targets than
+    // max_targets_per_cycle every target still gets its turn
+    std::vector<std::string> targets = tracker_->get_targets_by_staleness();
     if (targets.empty()) {
         return;
     }
     
     // Limit targets per cycle to avoid overload
-    const size_t max_targets_per_cycle = 10;
-    size_t targets_to_check = std::min(targets.size(), max_targets_per_cycle);
+    size_t targets_to_check = std::min(targets.size(), max_targets_per_cycle_);
     
-    for (size_t i = 0; i < targets_to_check; ++i) {
-        const std::string& target = targets[i];
-        
+    // Only targets whose own interval has elapsed are due; due times are rounded to
+    // the nearest tick so jitter never makes a target skip a whole cycle
+    now = static_cast<uint64_t>(std::time(nullptr));
+    std::vector<std::string> due_targets;
+    std::map<std::string, uint64_t> next_check_at;
+    for (const auto& target : targets) {
+        auto it = next_check_at_.find(target);
+        if (it == next_check_at_.end() || it->second <= now + tick_secs_ / 2) {
+            due_targets.push_back(target);
+        } else {
+            next_check_at[target] = it->second;
+        }
+    }
+    size_t due_to_check = std::min(due_targets.size(), max_targets_per_cycle_);
+    for (size_t i = 0; i < due_to_check; ++i) {
+        next_check_at[due_targets[i]] = now + interval_for(due_targets[i]);
+    }
+    next_check_at_.swap(next_check_at); // Also forgets evicted targets
+    
+    // Runways that appeared since the last cycle (interface up, proxy or DNS server added)
+    // start out Unknown; classify them now rather than on the first request that needs them
+    std::vector<std::shared_ptr<Runway>> new_runways;
+    std::set<std::string> current_ids;
+    for (const auto& runway : runway_manager_->get_all_runways(true)) {
+        current_ids.insert(runway->id);
+        if (runway->enabled && known_runway_ids_.find(runway->id) == known_runway_ids_.end()) {
+            new_runways.push_back(runway);
+        }
+    }
+    known_runway_ids_.swap(current_ids);
+    if (!new_runways.empty()) {
+        Logger::instance().log(LogLevel::INFO, "Probing " + std::to_string(new_runways.size()) +
+                               " new runways against " + std::to_string(targets_to_check) + " targets");
+        for_each_target(targets, targets_to_check, [this, &new_runways](const std::string& target) {
+            probe_new_runways(target, new_runways);
+        });
+    }
+    
+    for_each_target(due_targets, due_to_check, [this](const std::string& target) {
+        check_target(target);
+    });
+}
+
+void HealthMonitor::warm_up() {
+    std::vector<std::shared_ptr<Runway>> runways = runway_manager_->get_all_runways();
+    Logger::instance().log(LogLevel::INFO, "Warming up " + std::to_string(warmup_targets_.size()) +
+                           " targets across " + std::to_string(runways.size()) + " runways");
+    
+    for_each_target(warmup_targets_, warmup_targets_.size(), [this, &runways](const std::string& entry) {
+        std::string host;
+        uint16_t port = 80;
+        if (!Config::parse_warmup_target(entry, host, port)) {
+            return; // Rejected by Config::validate()
+        }
         try {
-            auto metrics = tracker_->get_target_metrics(target);
-            
-            // Prioritize recently failed runways
-            std::vector<std::string> failed_runways;
-            for (const auto& pair : metrics) {
-                if (pair.second.state == RunwayState::Inaccessible) {
-                    failed_runways.push_back(pair.first);
-                }
+            for (const auto& runway : runways) {
+                probe(host, runway, port);
             }
-            
-            // Test failed runways (limit to 5 per target)
-            size_t max_failed = std::min(failed_runways.size(), size_t(5));
-            for (size_t j = 0; j < max_failed; ++j) {
-                auto runway = runway_manager_->get_runway(failed_runways[j]);
-                if (runway) {
-                    auto result = runway_manager_->test_runway_accessibility(
-                        target, runway, 5.0);
-                    bool net_success = std::get<0>(result);
-                    bool user_success = std::get<1>(result);
-                    double response_time = std::get<2>(result);
-                    tracker_->update(target, runway->id, net_success, user_success, response_time);
-                }
+        } catch (...) {
+            // Defensive: continue on errors
+        }
+    });
+}
+
+void HealthMonitor::probe(const std::string& target, const std::shared_ptr<Runway>& runway, uint16_t port) {
+    auto before = tracker_->get_metrics(target, runway->id);
+    RunwayState previous_state = before ? before->state : RunwayState::Unknown;
+    
+    auto result = runway_manager_->test_runway_accessibility(target, runway, probe_timeout_, port);
+    HealthEvent event;
+    event.target = target;
+    event.runway_id = runway->id;
+    event.network_success = std::get<0>(result);
+    event.user_success = std::get<1>(result);
+    event.response_time = std::get<2>(result);
+    tracker_->update(target, runway->id, event.network_success, event.user_success, event.response_time);
+    
+    auto after = tracker_->get_metrics(target, runway->id);
+    event.previous_state = previous_state;
+    event.state = after ? after->state : previous_state;
+    
+    std::vector<HealthEventCallback> listeners;
+    {
+        std::lock_guard<std::mutex> lock(listeners_mutex_);
+        for (const auto& pair : listeners_) {
+            listeners.push_back(pair.second);
+        }
+    }
+    if (listeners.empty()) {
+        return;
+    }
+    
+    // Invoke outside the lock so listeners may subscribe or unsubscribe
+    for (const auto& listener : listeners) {
+        listener(event);
+    }
+    if (event.state != event.previous_state) {
+        event.type = HealthEvent::Type::StateChanged;
+        for (const auto& listener : listeners) {
+            listener(event);
+        }
+    }
+}
+
+void HealthMonitor::for_each_target(const std::vector<std::string>& targets, size_t count,
+                                    const std::function<void(const std::string&)>& check) {
+    // Chunks of health_check_concurrency targets, one thread per target,
+    // waiting for the whole chunk before starting the next
+    size_t concurrency = std::max(concurrency_.load(), size_t(1));
+    for (size_t start = 0; start < count && running_ && !paused_; start += concurrency) {
+        size_t end = std::min(start + concurrency, count);
+        if (end - start == 1) {
+            check(targets[start]);
+            continue;
+        }
+        
+        std::vector<std::thread> workers;
+        for (size_t i = start; i < end; ++i) {
+            workers.emplace_back(check, targets[i]);
+        }
+        for (auto& worker : workers) {
+            worker.join();
+        }
+    }
+}
+
+void HealthMonitor::probe_new_runways(const std::string& target,
+                                      const std::vector<std::shared_ptr<Runway>>& runways) {
+    try {
+        uint16_t port = tracker_->get_target_port(target);
+        for (const auto& runway : runways) {
+            probe(target, runway, port);
+        }
+    } catch (...) {
+        // Defensive: continue on errors
+    }
+}
+
+// Order runway ids by last_evaluated, oldest first, so per-target probe limits
+// rotate through the runways instead of re-testing the same ones
+static void stalest_first(std::vector<std::string>& runway_ids, const std::map<std::string, TargetMetrics>& metrics) {
+    std::stable_sort(runway_ids.begin(), runway_ids.end(), [&metrics](const std::string& a, const std::string& b) {
+        return metrics.at(a).last_evaluated < metrics.at(b).last_evaluated;
+    });
+}
+
+void HealthMonitor::check_target(const std::string& target) {
+    try {
+        auto metrics = tracker_->get_target_metrics(target);
+        uint16_t port = tracker_->get_target_port(target); // Where requests go, e.g. 443 for HTTPS-only sites
+        
+        // Prioritize recently failed runways, skipping those whose circuit is open
+        // (backing off); once next_probe_at passes, a single half-open probe decides
+        uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+        std::vector<std::string> failed_runways;
+        for (const auto& pair : metrics) {
+            if (pair.second.state == RunwayState::Inaccessible && now >= pair.second.next_probe_at) {
+                failed_runways.push_back(pair.first);
+            }
+        }
+        stalest_first(failed_runways, metrics);
+        
+        // Test failed runways (limited per target)
+        size_t max_failed = std::min(failed_runways.size(), max_failed_probes_);
+        for (size_t j = 0; j < max_failed; ++j) {
+            auto runway = runway_manager_->get_runway(failed_runways[j]);
+            if (runway && runway->enabled) {
+                probe(target, runway, port);
             }
-            
-            // Also test partially accessible runways (limit to 3 per target)
-            std::vector<std::string> partial_runways;
+        }
+        
+        // Also test partially accessible runways (limited per target)
+        std::vector<std::string> partial_runways;
+        for (const auto& pair : metrics) {
+            if (pair.second.state == RunwayState::PartiallyAccessible) {
+                partial_runways.push_back(pair.first);
+            }
+        }
+        stalest_first(partial_runways, metrics);
+        
+        size_t max_partial = std::min(partial_runways.size(), max_partial_probes_);
+        for (size_t j = 0; j < max_partial; ++j) {
+            auto runway = runway_manager_->get_runway(partial_runways[j]);
+            if (runway && runway->enabled) {
+                probe(target, runway, port);
+            }
+        }
+        
+        // Spot-check a random sample of accessible runways so silent degradation is
+        // noticed before a real request trips over it
+        if (accessible_probe_rate_ > 0.0) {
+            std::bernoulli_distribution sampled(std::min(accessible_probe_rate_, 1.0));
             for (const auto& pair : metrics) {
-                if (pair.second.state == RunwayState::PartiallyAccessible) {
-                    partial_runways.push_back(pair.first);
+                if (pair.second.state != RunwayState::Accessible || !sample(sampled)) {
+                    continue;
                 }
-            }
-            
-            size_t max_partial = std::min(partial_runways.size(), size_t(3));
-            for (size_t j = 0; j < max_partial; ++j) {
-                auto runway = runway_manager_->get_runway(partial_runways[j]);
-                if (runway) {
-                    auto result = runway_manager_->test_runway_accessibility(
-                        target, runway, 5.0);
-                    bool net_success = std::get<0>(result);
-                    bool user_success = std::get<1>(result);
-                    double response_time = std::get<2>(result);
-                    tracker_->update(target, runway->id, net_success, user_success, response_time);
+                auto runway = runway_manager_->get_runway(pair.first);
+                if (runway && runway->enabled) {
+                    probe(target, runway, port);
                 }
             }
-        } catch (...) {
-            // Defensive: continue on errors
         }
+    } catch (...) {
+        // Defensive: continue on errors
     }
 }
+
+bool HealthMonitor::sample(std::bernoulli_distribution& distribution) {
+    std::lock_guard<std::mutex> lock(rng_mutex_);
+    return distribution(rng_);
+}
@@ -4,14 +4,60 @@
 #include <thread>
 #include <atomic>
 #include <memory>
+#include <random>
+#include <mutex>
+#include <string>
+#include <set>
+#include <map>
+#include <vector>
+#include <functional>
 #include "runway_manager.h"
 #include "tracker.h"
 
+// Outcome of one health probe; a probe that moves the runway to another state
+// is reported twice: first as ProbeResult, then as StateChanged
+struct HealthEvent {
+    enum class Type {
+        ProbeResult,
+        StateChanged // e.g. Inaccessible -> Accessible when a runway recovers
+    };
+    
+    Type type;
+    std::string target;
+    std::string runway_id;
+    bool network_success;
+    bool user_success;
+    double response_time; // Seconds
+    RunwayState previous_state; // Before the probe (Unknown if never evaluated)
+    RunwayState state; // After the probe
+    
+    HealthEvent()
+        : type(Type::ProbeResult)
+        , network_success(false)
+        , user_success(false)
+        , response_time(0.0)
+        , previous_state(RunwayState::Unknown)
+        , state(RunwayState::Unknown) {}
+};
+
+using HealthEventCallback = std::function<void(const HealthEvent& event)>;
+
 class HealthMonitor {
 public:
     HealthMonitor(std::shared_ptr<RunwayManager> runway_manager,
                   std::shared_ptr<TargetAccessibilityTracker> tracker,
-                  uint64_t interval_secs);
+                  uint64_t interval_secs,
+                  uint64_t target_ttl_secs = 0,
+                  double accessible_probe_rate = 0.0,
+                  size_t max_targets_per_cycle = 10,
+                  size_t max_failed_probes = 5,
+                  size_t max_partial_probes = 3,
+                  double probe_timeout = 5.0,
+                  double jitter_percent = 0.0,
+                  size_t concurrency = 1,
+                  const std::vector<std::string>& warmup_targets = {},
+                  uint64_t warmup_interval_secs = 0,
+                  const std::map<std::string, uint64_t>& target_intervals = {});
     
     ~HealthMonitor();
     
@@ -23,15 +69,65 @@ public:
     
     bool is_running() const { return running_; }
     
+    // Halt probing (e.g. during maintenance) without stopping the thread or losing metrics;
+    // a cycle in progress finishes its current batch of targets
+    void pause();
+    void resume();
+    bool is_paused() const { return paused_; }
+    
+    // Live-tunable settings, used from the next probe or cycle on
+    void set_probe_timeout(double probe_timeout) { probe_timeout_ = probe_timeout; }
+    void set_concurrency(size_t concurrency) { concurrency_ = concurrency; }
+    
+    // Subscribe to probe results (fired on the probing thread, which may be a per-target worker)
+    // Returns an id for remove_listener()
+    size_t add_listener(HealthEventCallback callback);
+    
+    void remove_listener(size_t listener_id);
+    
 private:
     std::shared_ptr<RunwayManager> runway_manager_;
     std::shared_ptr<TargetAccessibilityTracker> tracker_;
     uint64_t interval_secs_;
+    uint64_t target_ttl_secs_; // 0 disables eviction of idle targets
+    double accessible_probe_rate_; // Chance per cycle that an Accessible runway is re-probed (0 = never)
+    size_t max_targets_per_cycle_; // Targets checked per cycle
+    size_t max_failed_probes_; // Inaccessible runways re-probed per target per cycle
+    size_t max_partial_probes_; // PartiallyAccessible runways re-probed per target per cycle
+    std::atomic<double> probe_timeout_; // Seconds allowed for each probe
+    double jitter_percent_; // Each pause is interval +/- up to this percentage, chosen at random
+    std::atomic<size_t> concurrency_; // Targets checked in parallel
+    std::vector<std::string> warmup_targets_; // "host" or "host:port", probed on every runway
+    uint64_t warmup_interval_secs_; // 0 = warm up at startup only
+    uint64_t last_warmup_; // Unix timestamp (monitor thread only)
+    std::map<std::string, uint64_t> target_intervals_; // Host pattern -> seconds between checks of matching targets
+    uint64_t tick_secs_; // Pause between cycles: the shortest of interval_secs and the overrides
+    std::map<std::string, uint64_t> next_check_at_; // Target -> Unix timestamp it is next due (monitor thread only)
+    std::mt19937 rng_;
+    std::mutex rng_mutex_; // Targets are checked from several threads
     std::atomic<bool> running_;
+    std::atomic<bool> paused_;
     std::thread monitor_thread_;
+    std::atomic<bool> interfaces_changed_; // Set by interface events, consumed by the next cycle
+    std::set<std::string> known_runway_ids_; // Runways seen by the last cycle (monitor thread only)
+    size_t interface_listener_id_;
+    std::map<size_t, HealthEventCallback> listeners_;
+    size_t next_listener_id_;
+    std::mutex listeners_mutex_;
     
     void monitor_loop();
+    uint64_t next_sleep_ms();
+    // Seconds between checks of a target: its target_intervals override, else interval_secs
+    uint64_t interval_for(const std::string& target) const;
     void health_check_cycle();
+    void check_target(const std::string& target);
+    void warm_up();
+    // Test the runway by connecting to port, record the result and notify listeners
+    void probe(const std::string& target, const std::shared_ptr<Runway>& runway, uint16_t port);
+    void probe_new_runways(const std::string& target, const std::vector<std::shared_ptr<Runway>>& runways);
+    void for_each_target(const std::vector<std::string>& targets, size_t count,
+                         const std::function<void(const std::string&)>& check);
+    bool sample(std::bernoulli_distribution& distribution);
 };
 
 #endif // HEALTH_H
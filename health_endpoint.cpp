@@ -0,0 +1,153 @@
+#include "health_endpoint.h"
+#include "utils.h"
+#include <sstream>
+#include <chrono>
+
+using namespace network;
+
+// Load balancers send short requests; anything longer is not a health check
+static const size_t MAX_REQUEST_BYTES = 8192;
+static const long REQUEST_TIMEOUT_SECS = 2;
+
+static void set_timeouts(socket_t sock, long secs) {
+    struct timeval timeout;
+    timeout.tv_sec = secs;
+    timeout.tv_usec = 0;
+#ifdef _WIN32
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+#else
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+    setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
+#endif
+}
+
+HealthEndpoint::HealthEndpoint(std::shared_ptr<RunwayManager> runway_manager,
+                               std::shared_ptr<ProxyServer> proxy_server,
+                               const std::string& host, uint16_t port)
+    : runway_manager_(runway_manager)
+    , proxy_server_(proxy_server)
+    , host_(host)
+    , port_(port)
+    , running_(false)
+    , listen_socket_(INVALID_SOCKET_VALUE) {
+}
+
+HealthEndpoint::~HealthEndpoint() {
+    stop();
+}
+
+bool HealthEndpoint::start() {
+    if (running_) {
+        return false;
+    }
+    
+    listen_socket_ = create_tcp_socket(is_ipv6_address(host_) ? AF_INET6 : AF_INET);
+    if (listen_socket_ == INVALID_SOCKET_VALUE) {
+        return false;
+    }
+    set_socket_option(listen_socket_, SOL_SOCKET, SO_REUSEADDR, 1);
+    
+    if (!bind_socket(listen_socket_, host_, port_) || !listen_socket(listen_socket_, 16)) {
+        close_socket(listen_socket_);
+        listen_socket_ = INVALID_SOCKET_VALUE;
+        return false;
+    }
+    
+    running_ = true;
+    server_thread_ = std::thread(&HealthEndpoint::server_loop, this);
+    return true;
+}
+
+void HealthEndpoint::stop() {
+    if (!running_) {
+        return;
+    }
+    
+    running_ = false;
+    if (listen_socket_ != INVALID_SOCKET_VALUE) {
+        shutdown_socket(listen_socket_); // Wake server_loop out of accept
+        close_socket(listen_socket_);
+        listen_socket_ = INVALID_SOCKET_VALUE;
+    }
+    if (server_thread_.joinable()) {
+        server_thread_.join();
+    }
+}
+
+bool HealthEndpoint::healthy(std::string& reason) {
+    if (!proxy_server_->is_running()) {
+        reason = "proxy is not accepting connections";
+        return false;
+    }
+    for (const auto& runway : runway_manager_->get_all_runways()) {
+        if (!runway->upstream_proxy || runway->upstream_proxy->accessible) {
+            return true;
+        }
+    }
+    reason = "no usable runway";
+    return false;
+}
+
+void HealthEndpoint::server_loop() {
+    while (running_) {
+        std::string client_ip;
+        uint16_t client_port;
+        socket_t client_sock = accept_connection(listen_socket_, client_ip, client_port);
+        
+        if (client_sock == INVALID_SOCKET_VALUE) {
+            if (running_) {
+                std::this_thread::sleep_for(std::chrono::milliseconds(100));
+            }
+            continue;
+        }
+        
+        // Checks are cheap and answered at once, so one at a time is enough
+        set_timeouts(client_sock, REQUEST_TIMEOUT_SECS);
+        handle_connection(client_sock);
+        close_socket(client_sock);
+    }
+}
+
+void HealthEndpoint::handle_connection(socket_t client_sock) {
+    std::string data;
+    char buffer[1024];
+    while (data.find("\r\n\r\n") == std::string::npos && data.size() < MAX_REQUEST_BYTES) {
+        ssize_t received = recv_data(client_sock, buffer, sizeof(buffer));
+        if (received <= 0) {
+            break;
+        }
+        data.append(buffer, static_cast<size_t>(received));
+    }
+    // Request line: "GET /health HTTP/1.1"; a body, if any, is ignored
+    std::vector<std::string> request_line = utils::split(data.substr(0, data.find('\r')), ' ');
+    if (request_line.size() < 2) {
+        return;
+    }
+    
+    int status = 405;
+    std::string body = "Method Not Allowed\n";
+    std::string reason;
+    if (request_line[0] == "GET" || request_line[0] == "HEAD") {
+        bool ok = healthy(reason);
+        status = ok ? 200 : 503;
+        body = ok ? "OK\n" : "Service Unavailable: " + reason + "\n";
+    }
+    
+    std::ostringstream oss;
+    oss << "HTTP/1.1 " << status << " "
+        << (status == 200 ? "OK" : status == 503 ? "Service Unavailable" : "Method Not Allowed") << "\r\n";
+    oss << "Content-Type: text/plain\r\n";
+    oss << "Content-Length: " << body.size() << "\r\n";
+    oss << "Cache-Control: no-store\r\n";
+    if (status == 405) {
+        oss << "Allow: GET, HEAD\r\n";
+    }
+    oss << "Connection: close\r\n";
+    oss << "\r\n";
+    if (request_line[0] != "HEAD") {
+        oss << body;
+    }
+    std::string response = oss.str();
+    send_data(client_sock, response.data(), response.size());
+}
@@ -0,0 +1,48 @@
+#ifndef HEALTH_ENDPOINT_H
+#define HEALTH_ENDPOINT_H
+
+#include <string>
+#include <memory>
+#include <atomic>
+#include <thread>
+#include <cstdint>
+#include "runway_manager.h"
+#include "proxy.h"
+#include "network.h"
+
+// Health check for load balancers, on a listener of its own (health_listen_addr)
+// Any GET or HEAD answers 200 "OK" while the proxy accepts connections and has a usable runway
+// (enabled, and not through an upstream proxy marked inaccessible), and 503 otherwise
+// It takes no token and reveals nothing else, so it can be exposed where the admin endpoint must not be;
+// unlike /readyz it does not look at recent targets, only at whether traffic could be carried at all
+
+class HealthEndpoint {
+public:
+    HealthEndpoint(std::shared_ptr<RunwayManager> runway_manager,
+                   std::shared_ptr<ProxyServer> proxy_server,
+                   const std::string& host, uint16_t port);
+    ~HealthEndpoint();
+    
+    // Start serving (runs in background thread); false if the address cannot be bound
+    bool start();
+    
+    void stop();
+    
+    // True if a load balancer should send traffic here; reason says why not
+    bool healthy(std::string& reason);
+
+private:
+    std::shared_ptr<RunwayManager> runway_manager_;
+    std::shared_ptr<ProxyServer> proxy_server_;
+    std::string host_;
+    uint16_t port_;
+    
+    std::atomic<bool> running_;
+    socket_t listen_socket_;
+    std::thread server_thread_;
+    
+    void server_loop();
+    void handle_connection(socket_t client_sock);
+};
+
+#endif // HEALTH_ENDPOINT_H
@@ -1,16 +1,19 @@
 #include "logger.h"
 #include "utils.h"
+#include "redact.h"
 #include <ctime>
 #include <sstream>
 #include <iomanip>
 #include <cstring>
+#include <cstdio>
+#include <iostream>
 
 Logger& Logger::instance() {
     static Logger instance;
     return instance;
 }
 
-void Logger::init(const std::string& log_file) {
+void Logger::init(const std::string& log_file, LogFormat format, uint64_t max_bytes, size_t backup_count) {
     std::lock_guard<std::mutex> lock(mutex_);
     
     if (initialized_) {
@@ -18,6 +21,9 @@ void Logger::init(const std::string& log_file) {
     }
     
     log_file_ = log_file;
+    format_ = format;
+    max_bytes_ = max_bytes;
+    backup_count_ = backup_count;
     
     // Ensure log directory exists
     if (!log_file_.empty()) {
@@ -28,6 +34,9 @@ void Logger::init(const std::string& log_file) {
         if (file_stream_.is_open()) {
             // Ensure immediate writes by setting unitbuf (flush after each output operation)
             file_stream_.setf(std::ios::unitbuf);
+            std::ifstream existing(log_file_, std::ios::binary | std::ios::ate);
+            std::streamoff size = existing ? static_cast<std::streamoff>(existing.tellg()) : 0;
+            current_size_ = size > 0 ? static_cast<uint64_t>(size) : 0;
             initialized_ = true;
         } else {
             initialized_ = false;
@@ -35,7 +44,12 @@ void Logger::init(const std::string& log_file) {
     }
 }
 
-void Logger::log(LogLevel level, const std::string& message) {
+void Logger::set_stderr_sink(bool enabled) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    stderr_sink_ = enabled;
+}
+
+void Logger::log(LogLevel level, const std::string& message, const LogContext& context) {
     std::lock_guard<std::mutex> lock(mutex_);
     
     if (!initialized_ || !file_stream_.is_open()) {
@@ -44,11 +58,31 @@ void Logger::log(LogLevel level, const std::string& message) {
     
     uint64_t timestamp = std::time(nullptr);
     std::string level_str = level_to_string(level);
-    std::string time_str = format_timestamp(timestamp);
+    std::string safe_message = redact::text(message); // Secrets never reach the file, whatever the caller put in
     
-    // Format: timestamp level message
-    file_stream_ << time_str << " [" << level_str << "] " << message << "\n";
-    file_stream_.flush(); // Ensure immediate write to disk
+    std::ostringstream line;
+    if (format_ == LogFormat::Json) {
+        line << "{\"time\":\"" << format_utc_timestamp(timestamp) << "\",\"level\":\"" << level_str
+             << "\",\"message\":\"" << escape_json_string(safe_message) << "\"";
+        if (!context.runway_id.empty()) {
+            line << ",\"runway_id\":\"" << escape_json_string(context.runway_id) << "\"";
+        }
+        if (!context.target_host.empty()) {
+            line << ",\"target_host\":\"" << escape_json_string(context.target_host) << "\"";
+        }
+        if (!context.request_id.empty()) {
+            line << ",\"request_id\":\"" << escape_json_string(context.request_id) << "\"";
+        }
+        line << "}\n";
+    } else {
+        // Format: timestamp level message [request_id=<id>]
+        line << format_timestamp(timestamp) << " [" << level_str << "] " << safe_message;
+        if (!context.request_id.empty()) {
+            line << " request_id=" << context.request_id;
+        }
+        line << "\n";
+    }
+    write(line.str());
 }
 
 void Logger::log_connection(const ConnectionLog& conn_log) {
@@ -61,14 +95,29 @@ void Logger::log_connection(const ConnectionLog& conn_log) {
     std::stringstream json;
     json << std::fixed << std::setprecision(2);
     
-    json << format_timestamp(conn_log.timestamp) << " [CONN] {";
+    // Text: "<time> [CONN] {fields}"; JSON: the same fields with time, level and message added
+    if (format_ == LogFormat::Json) {
+        std::string level = conn_log.level.empty() ? "INFO" : conn_log.level;
+        json << "{\"time\":\"" << format_utc_timestamp(conn_log.timestamp) << "\",\"level\":\""
+             << escape_json_string(level) << "\",\"message\":\"connection " << escape_json_string(conn_log.event) << "\",";
+    } else {
+        json << format_timestamp(conn_log.timestamp) << " [CONN] {";
+    }
     json << "\"event\":\"" << escape_json_string(conn_log.event) << "\"";
     
+    if (!conn_log.request_id.empty()) {
+        json << ",\"request_id\":\"" << escape_json_string(conn_log.request_id) << "\"";
+    }
+    
     if (!conn_log.client_ip.empty()) {
         json << ",\"client_ip\":\"" << escape_json_string(conn_log.client_ip) << "\"";
         json << ",\"client_port\":" << conn_log.client_port;
     }
     
+    if (!conn_log.client_cert.empty()) {
+        json << ",\"client_cert\":\"" << escape_json_string(conn_log.client_cert) << "\"";
+    }
+    
     if (!conn_log.target_host.empty()) {
         json << ",\"target_host\":\"" << escape_json_string(conn_log.target_host) << "\"";
         json << ",\"target_port\":" << conn_log.target_port;
@@ -83,7 +132,8 @@ void Logger::log_connection(const ConnectionLog& conn_log) {
     }
     
     if (!conn_log.path.empty()) {
-        json << ",\"path\":\"" << escape_json_string(conn_log.path) << "\"";
+        // Query strings and absolute-form URLs may carry credentials
+        json << ",\"path\":\"" << escape_json_string(redact::text(conn_log.path)) << "\"";
     }
     
     if (conn_log.status_code > 0) {
@@ -100,13 +150,47 @@ void Logger::log_connection(const ConnectionLog& conn_log) {
     }
     
     if (!conn_log.error.empty()) {
-        json << ",\"error\":\"" << escape_json_string(conn_log.error) << "\"";
+        json << ",\"error\":\"" << escape_json_string(redact::text(conn_log.error)) << "\"";
     }
     
     json << "}\n";
     
-    file_stream_ << json.str();
-    file_stream_.flush(); // Ensure immediate write to disk
+    write(json.str());
+}
+
+void Logger::write(const std::string& line) {
+    if (max_bytes_ > 0 && current_size_ > 0 && current_size_ + line.size() > max_bytes_) {
+        rotate();
+    }
+    if (file_stream_.is_open()) {
+        file_stream_ << line;
+        file_stream_.flush(); // Ensure immediate write to disk
+        current_size_ += line.size();
+    }
+    if (stderr_sink_) {
+        std::cerr << line;
+    }
+}
+
+void Logger::rotate() {
+    file_stream_.close();
+    
+    // proxy.log.N is dropped, proxy.log.(i) becomes proxy.log.(i+1), proxy.log becomes proxy.log.1;
+    // without backups the file just starts over
+    if (backup_count_ > 0) {
+        std::remove((log_file_ + "." + std::to_string(backup_count_)).c_str());
+        for (size_t i = backup_count_ - 1; i >= 1; --i) {
+            std::rename((log_file_ + "." + std::to_string(i)).c_str(), (log_file_ + "." + std::to_string(i + 1)).c_str());
+        }
+        std::rename(log_file_.c_str(), (log_file_ + ".1").c_str());
+        file_stream_.open(log_file_, std::ios::app | std::ios::out);
+    } else {
+        file_stream_.open(log_file_, std::ios::trunc | std::ios::out);
+    }
+    if (file_stream_.is_open()) {
+        file_stream_.setf(std::ios::unitbuf);
+    }
+    current_size_ = 0;
 }
 
 void Logger::flush() {
@@ -145,6 +229,25 @@ std::string Logger::format_timestamp(uint64_t timestamp) {
     return ss.str();
 }
 
+std::string Logger::format_utc_timestamp(uint64_t timestamp) {
+    std::time_t time_val = static_cast<std::time_t>(timestamp);
+    std::tm tm_info;
+    
+#ifdef _WIN32
+    if (gmtime_s(&tm_info, &time_val) != 0) {
+        return "1970-01-01T00:00:00Z";
+    }
+#else
+    if (gmtime_r(&time_val, &tm_info) == nullptr) {
+        return "1970-01-01T00:00:00Z";
+    }
+#endif
+    
+    std::stringstream ss;
+    ss << std::put_time(&tm_info, "%Y-%m-%dT%H:%M:%SZ");
+    return ss.str();
+}
+
 std::string Logger::escape_json_string(const std::string& str) {
     std::stringstream escaped;
     for (char c : str) {
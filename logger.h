@@ -19,12 +19,31 @@ enum class LogLevel {
     ERROR_LEVEL
 };
 
+// Line format of the log file
+enum class LogFormat {
+    Text, // "2024-01-01 12:00:00 [INFO] message" (default)
+    Json  // One JSON object per line, for log aggregators
+};
+
+// What a message is about, where known; JSON lines carry these as fields
+struct LogContext {
+    std::string runway_id;
+    std::string target_host;
+    std::string request_id; // Proxied request the message belongs to (X-SmartProxy-Request-Id)
+    
+    LogContext() {}
+    LogContext(const std::string& runway, const std::string& target, const std::string& request = "")
+        : runway_id(runway), target_host(target), request_id(request) {}
+};
+
 struct ConnectionLog {
     uint64_t timestamp;
     std::string level;
     std::string event; // "connect", "disconnect", "error", "request", "response"
+    std::string request_id; // X-SmartProxy-Request-Id of the request, echoed to the client
     std::string client_ip;
     uint16_t client_port;
+    std::string client_cert; // Name in the client's verified TLS certificate (tls_client_ca_path)
     std::string target_host;
     uint16_t target_port;
     std::string runway_id;
@@ -50,24 +69,42 @@ class Logger {
 public:
     static Logger& instance();
     
-    void init(const std::string& log_file);
-    void log(LogLevel level, const std::string& message);
+    // max_bytes: rotate before a line would grow the file past this size (0 = never);
+    // the file becomes log_file.1, older backups shift up, and only backup_count are kept
+    void init(const std::string& log_file, LogFormat format = LogFormat::Text,
+              uint64_t max_bytes = 0, size_t backup_count = 0);
+    
+    // Also write every line to stderr (e.g. when stderr is a terminal and no TUI is drawn)
+    void set_stderr_sink(bool enabled);
+    
+    void log(LogLevel level, const std::string& message, const LogContext& context = LogContext());
     void log_connection(const ConnectionLog& conn_log);
     void flush();
     void close();
     
 private:
-    Logger() : log_file_(), file_stream_(), mutex_(), initialized_(false) {}
+    Logger() : log_file_(), format_(LogFormat::Text), max_bytes_(0), backup_count_(0), current_size_(0),
+               stderr_sink_(false), file_stream_(), mutex_(), initialized_(false) {}
     ~Logger() { close(); }
     Logger(const Logger&) = delete;
     Logger& operator=(const Logger&) = delete;
     
     std::string log_file_;
+    LogFormat format_;
+    uint64_t max_bytes_;
+    size_t backup_count_;
+    uint64_t current_size_; // Size of log_file_ as written so far
+    bool stderr_sink_;
     std::ofstream file_stream_;
     std::mutex mutex_;
     bool initialized_;
     
+    // Append one line (rotating first if needed); caller holds mutex_
+    void write(const std::string& line);
+    void rotate();
+    
     std::string format_timestamp(uint64_t timestamp);
+    std::string format_utc_timestamp(uint64_t timestamp); // ISO 8601, for JSON lines
     std::string escape_json_string(const std::string& str);
     std::string level_to_string(LogLevel level);
 };
@@ -0,0 +1,107 @@
+#include "logs.h"
+#include "utils.h"
+#include <iostream>
+#include <fstream>
+#include <deque>
+#include <thread>
+#include <chrono>
+
+namespace logs {
+
+static bool matches(const std::string& line, const std::vector<std::string>& filters) {
+    for (const auto& filter : filters) {
+        if (line.find(filter) == std::string::npos) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// First bytes of a file; log lines start with a timestamp, so a new file almost always differs here
+static std::string read_head(const std::string& path) {
+    std::ifstream file(path, std::ios::binary);
+    std::string head(256, '\0');
+    file.read(&head[0], static_cast<std::streamsize>(head.size()));
+    head.resize(static_cast<size_t>(file.gcount()));
+    return head;
+}
+
+int tail(const std::string& path, size_t line_count, bool follow, const std::vector<std::string>& filters) {
+    std::ifstream file(path, std::ios::binary);
+    if (!file) {
+        utils::safe_print("Error: Cannot open log file " + path + "\n");
+        return 1;
+    }
+    
+    std::deque<std::string> last;
+    std::string line;
+    while (std::getline(file, line)) {
+        if (line_count > 0 && matches(line, filters)) {
+            last.push_back(line);
+            if (last.size() > line_count) {
+                last.pop_front();
+            }
+        }
+    }
+    for (const auto& entry : last) {
+        utils::safe_print(entry + "\n");
+    }
+    std::cout.flush(); // Also when piped, e.g. into grep
+    if (!follow) {
+        return 0;
+    }
+    
+    // Poll for appended data; a file that shrank was truncated or rotated, so start over from its beginning
+    file.clear();
+    std::streamoff position = file.tellg();
+    if (position < 0) {
+        file.clear();
+        file.seekg(0, std::ios::end);
+        position = file.tellg();
+    }
+    file.close();
+    std::string head = read_head(path); // Start of the file, to tell a rotated-in file from the one being read
+    std::string partial; // Last line until its newline arrives
+    while (true) {
+        std::this_thread::sleep_for(std::chrono::milliseconds(250));
+        
+        // Reopened every time: after rotation the path names a new file
+        std::ifstream current(path, std::ios::binary);
+        if (!current) {
+            continue;
+        }
+        current.seekg(0, std::ios::end);
+        std::streamoff size = current.tellg();
+        std::string current_head = read_head(path);
+        bool replaced = current_head.compare(0, head.size(), head) != 0 && head.compare(0, current_head.size(), current_head) != 0;
+        if (size < position || replaced) {
+            position = 0;
+            partial.clear();
+        }
+        head = current_head;
+        if (size == position) {
+            continue;
+        }
+        
+        current.seekg(position);
+        std::string chunk(static_cast<size_t>(size - position), '\0');
+        current.read(&chunk[0], static_cast<std::streamsize>(chunk.size()));
+        chunk.resize(static_cast<size_t>(current.gcount()));
+        position += static_cast<std::streamoff>(chunk.size());
+        
+        partial += chunk;
+        size_t start = 0;
+        size_t newline;
+        while ((newline = partial.find('\n', start)) != std::string::npos) {
+            std::string entry = partial.substr(start, newline - start);
+            if (matches(entry, filters)) {
+                utils::safe_print(entry + "\n");
+            }
+            start = newline + 1;
+        }
+        partial.erase(0, start);
+        std::cout.flush();
+    }
+}
+
+} // namespace logs
@@ -0,0 +1,19 @@
+#ifndef LOGS_H
+#define LOGS_H
+
+#include <string>
+#include <vector>
+#include <cstddef>
+
+// `smartproxy logs`: print the service's log file (log_file in config.json), like tail
+
+namespace logs {
+
+// Print the last line_count lines that contain every filter (e.g. a target and a runway id);
+// with follow, keep printing matching lines as they are appended until interrupted
+// Returns the process exit code
+int tail(const std::string& path, size_t line_count, bool follow, const std::vector<std::string>& filters);
+
+} // namespace logs
+
+#endif // LOGS_H
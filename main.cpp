@@ -4,6 +4,11 @@
 #include <memory>
 #include <thread>
 #include <chrono>
+#include <algorithm>
+#include <fstream>
+#include <iterator>
+#include <sstream>
+#include <map>
 #include "config.h"
 #include "dns.h"
 #include "runway_manager.h"
@@ -17,6 +22,17 @@
 #include "tui.h"
 #include "webui.h"
 #include "logger.h"
+#include "alert.h"
+#include "admin.h"
+#include "watch.h"
+#include "logs.h"
+#include "completions.h"
+#include "version.h"
+#include "tracing.h"
+#include "audit.h"
+#include "statsd.h"
+#include "health_endpoint.h"
+#include "webui_json.h"
 
 // Defensive terminal handling with double Ctrl+C support
 static volatile sig_atomic_t g_running = 1;
@@ -51,8 +67,322 @@ BOOL WINAPI ConsoleCtrlHandler(DWORD dwCtrlType) {
 }
 #endif
 
-int main(int /*argc*/, char* /*argv*/[]) {
-    // Always run as service with TUI
+static const char* CONFIG_PATH = "config.json";
+
+// Kind of a raw JSON value as kept by Config::parse_object: string, number, bool, array, object or null
+static std::string json_kind(const std::string& raw) {
+    std::string value = utils::trim(raw);
+    if (value.empty()) return "empty";
+    switch (value[0]) {
+        case '"': return "string";
+        case '[': return "array";
+        case '{': return "object";
+        case 't': case 'f': return "bool";
+        case 'n': return "null";
+        default: return "number";
+    }
+}
+
+// `smartproxy validate`: check a config file the way the service would load it, without starting it
+// Errors stop the service from starting; warnings are settings it would silently ignore
+static int validate_config(const std::string& path, bool json_output) {
+    std::vector<std::string> errors;
+    std::vector<std::string> warnings;
+    
+    std::ifstream file(path, std::ios::binary);
+    std::map<std::string, std::string> root;
+    if (!file) {
+        errors.push_back("cannot read " + path);
+    } else {
+        std::stringstream buffer;
+        buffer << file.rdbuf();
+        size_t pos = 0;
+        if (!Config::parse_object(buffer.str(), pos, root)) {
+            errors.push_back("not a valid JSON object");
+        } else {
+            // Compare against a default config: unknown keys are typos, mismatched kinds fall back to the default
+            std::map<std::string, std::string> defaults;
+            size_t default_pos = 0;
+            Config::parse_object(Config().to_json(), default_pos, defaults);
+            for (const auto& entry : root) {
+                auto known = defaults.find(entry.first);
+                if (known == defaults.end()) {
+                    warnings.push_back(entry.first + ": unknown setting, ignored");
+                } else if (json_kind(entry.second) != json_kind(known->second)) {
+                    warnings.push_back(entry.first + ": expected " + json_kind(known->second) + ", got " +
+                                       json_kind(entry.second) + "; the default is used");
+                }
+            }
+            for (const auto& error : Config::parse_json(buffer.str()).validate()) {
+                errors.push_back(error);
+            }
+        }
+    }
+    
+    if (json_output) {
+        std::vector<std::string> error_values;
+        std::vector<std::string> warning_values;
+        for (const auto& error : errors) {
+            error_values.push_back(webui_json::encode_string(error));
+        }
+        for (const auto& warning : warnings) {
+            warning_values.push_back(webui_json::encode_string(warning));
+        }
+        utils::safe_print(webui_json::build_object({{"path", webui_json::encode_string(path)},
+                                                    {"valid", errors.empty() ? "true" : "false"},
+                                                    {"errors", webui_json::build_array(error_values)},
+                                                    {"warnings", webui_json::build_array(warning_values)}}) + "\n");
+        return errors.empty() ? 0 : 1;
+    }
+    
+    for (const auto& error : errors) {
+        utils::safe_print("  error:   " + error + "\n");
+    }
+    for (const auto& warning : warnings) {
+        utils::safe_print("  warning: " + warning + "\n");
+    }
+    std::string summary = std::to_string(errors.size()) + (errors.size() == 1 ? " error, " : " errors, ") +
+                          std::to_string(warnings.size()) + (warnings.size() == 1 ? " warning" : " warnings");
+    utils::safe_print(path + ": " + (errors.empty() ? "OK" : "FAILED") +
+                      (errors.empty() && warnings.empty() ? "" : " (" + summary + ")") + "\n");
+    return errors.empty() ? 0 : 1;
+}
+
+// `smartproxy <command>`: run the command inside the running service through its admin endpoint
+// `smartproxy add-proxy`: the password is read here, from --password-file (- for stdin) or, when a
+// username is given on a terminal, a prompt, and sent on to the service as --password. A password on
+// the command line (positional or --password) still works but shows up in ps and shell history
+static bool read_proxy_password(std::vector<std::string>& args) {
+    std::string file_password;
+    bool from_file = false;
+    auto file_arg = std::find(args.begin(), args.end(), "--password-file");
+    if (file_arg != args.end()) {
+        std::string path = file_arg + 1 == args.end() ? "" : *(file_arg + 1);
+        std::ifstream file(path == "-" ? "" : path);
+        if ((path != "-" && !file) || !std::getline(path == "-" ? std::cin : file, file_password)) {
+            utils::safe_print("Error: --password-file requires a readable file holding the password (- for stdin)\n");
+            return false;
+        }
+        if (!file_password.empty() && file_password.back() == '\r') {
+            file_password.pop_back();
+        }
+        args.erase(file_arg, file_arg + 2);
+        from_file = true;
+    }
+    
+    // type host port [user] [password], or the same as --flags
+    std::string username;
+    bool has_password = false;
+    size_t positional = 0;
+    for (size_t i = 1; i < args.size(); ++i) {
+        if (args[i] == "--username" && i + 1 < args.size()) {
+            username = args[++i];
+        } else if (args[i] == "--password") {
+            has_password = true;
+            ++i;
+        } else if (args[i].compare(0, 2, "--") == 0) {
+            ++i;
+        } else if (++positional == 4) {
+            username = args[i];
+        } else if (positional == 5) {
+            has_password = true;
+        }
+    }
+    
+    if (from_file && has_password) {
+        utils::safe_print("Error: add-proxy takes the password once: --password-file, or (deprecated) on the command line\n");
+        return false;
+    }
+    if (has_password) {
+        std::cerr << "Warning: a password on the command line is visible to other users (ps, shell history); "
+                     "use --password-file or the prompt instead\n";
+        return true;
+    }
+    
+    std::string password = file_password;
+    if (!from_file && !username.empty() && utils::is_stdin_terminal() &&
+        !utils::read_secret("Password for " + username + " (empty for none): ", password)) {
+        return false;
+    }
+    if (!password.empty()) {
+        args.push_back("--password");
+        args.push_back(password);
+    }
+    return true;
+}
+
+static int run_client_command(const std::vector<std::string>& args) {
+    // validate: checks a file, so it needs neither the service nor a loadable config.json
+    if (args[0] == "validate") {
+        std::string path = CONFIG_PATH;
+        bool json_output = false;
+        for (size_t i = 1; i < args.size(); ++i) {
+            if (args[i] == "--config" && i + 1 < args.size()) {
+                path = args[++i];
+            } else if (args[i] == "--json") {
+                json_output = true;
+            } else {
+                utils::safe_print("Error: validate takes [--config path] [--json]\n");
+                return 1;
+            }
+        }
+        return validate_config(path, json_output);
+    }
+    
+    // completions <shell>: printed here, no service involved
+    if (args[0] == "completions") {
+        std::string script;
+        if (args.size() != 2 || !completions::generate(args[1], script)) {
+            utils::safe_print("Error: completions takes a shell: bash, zsh or fish\n");
+            return 1;
+        }
+        utils::safe_print(script);
+        return 0;
+    }
+    
+    // fingerprint <file>: the hash fingerprint_file expects for a saved block page, computed here
+    if (args[0] == "fingerprint") {
+        std::ifstream file(args.size() == 2 ? args[1] : "", std::ios::binary);
+        if (!file.is_open()) {
+            utils::safe_print("Error: fingerprint takes a readable file, e.g. a saved block page\n");
+            return 1;
+        }
+        std::vector<uint8_t> body((std::istreambuf_iterator<char>(file)), std::istreambuf_iterator<char>());
+        utils::safe_print(FingerprintValidator::body_fingerprint(body) + "\n");
+        return 0;
+    }
+    
+    Config config = Config::load(CONFIG_PATH);
+    tls::ClientOptions admin_tls_options;
+    const tls::ClientOptions* admin_tls = admin::client_tls(config, admin_tls_options) ? &admin_tls_options : nullptr;
+    
+    // version: this binary's build, and the running service's if it answers
+    if (args[0] == "version" || args[0] == "--version") {
+        bool json_output = std::find(args.begin(), args.end(), "--json") != args.end();
+        int exit_code = 1;
+        std::string output;
+        std::map<std::string, std::string> status;
+        std::string running_version;
+        size_t pos = 0;
+        size_t version_pos = 0;
+        if (admin::run_command(config.admin_listen_host, config.admin_listen_port, {"status", "--json"}, exit_code, output, 5,
+                               config.admin_token, admin_tls) &&
+            Config::parse_object(output, pos, status) && status.find("version") != status.end()) {
+            Config::parse_string(utils::trim(status["version"]), version_pos, running_version);
+        }
+        utils::safe_print(version::report(running_version, json_output));
+        return 0;
+    }
+    
+    // The live dashboard runs here, polling the service
+    if (args[0] == "watch") {
+        uint64_t interval_secs = 2;
+        if (args.size() > 1 && (!utils::safe_str_to_uint64(args[1], interval_secs) || interval_secs == 0)) {
+            utils::safe_print("Error: watch takes a refresh interval in seconds\n");
+            return 1;
+        }
+        return watch::run(config.admin_listen_host, config.admin_listen_port, config.admin_token, admin_tls, interval_secs);
+    }
+    
+    // logs: read the log file directly, so it works even while the service is down
+    if (args[0] == "logs") {
+        uint64_t line_count = 20;
+        bool follow = false;
+        std::vector<std::string> filters;
+        for (size_t i = 1; i < args.size(); ++i) {
+            if (args[i] == "--follow" || args[i] == "-f") {
+                follow = true;
+            } else if ((args[i] == "--lines" || args[i] == "-n") && i + 1 < args.size() &&
+                       utils::safe_str_to_uint64(args[i + 1], line_count)) {
+                ++i;
+            } else if ((args[i] == "--target" || args[i] == "--runway") && i + 1 < args.size()) {
+                filters.push_back(args[++i]);
+            } else {
+                utils::safe_print("Error: logs takes [--follow] [--lines N] [--target host] [--runway id]\n");
+                return 1;
+            }
+        }
+        if (config.log_file.empty()) {
+            utils::safe_print("Error: No log_file is configured\n");
+            return 1;
+        }
+        return logs::tail(config.log_file, static_cast<size_t>(line_count), follow, filters);
+    }
+    
+    // export-metrics --out <file>: the file is written here, not by the service
+    std::vector<std::string> command_args = args;
+    std::string out_path;
+    auto out = std::find(command_args.begin(), command_args.end(), "--out");
+    if (args[0] == "export-metrics" && out != command_args.end()) {
+        if (out + 1 == command_args.end()) {
+            utils::safe_print("Error: --out requires a file name\n");
+            return 1;
+        }
+        out_path = *(out + 1);
+        command_args.erase(out, out + 2);
+    }
+    
+    // test-batch --file <file>: the list is read here and sent as arguments (one target per line, # comments)
+    auto file_arg = std::find(command_args.begin(), command_args.end(), "--file");
+    if (args[0] == "test-batch" && file_arg != command_args.end()) {
+        std::ifstream file(file_arg + 1 == command_args.end() ? "" : *(file_arg + 1));
+        if (!file) {
+            utils::safe_print("Error: --file requires a readable file of targets\n");
+            return 1;
+        }
+        command_args.erase(file_arg, file_arg + 2);
+        std::string line;
+        while (std::getline(file, line)) {
+            line = utils::trim(line.substr(0, line.find('#')));
+            if (!line.empty()) {
+                command_args.push_back(line);
+            }
+        }
+    }
+    
+    if (args[0] == "add-proxy" && !read_proxy_password(command_args)) {
+        return 1;
+    }
+    
+    // bench and test-batch send many probes before they reply
+    long timeout_secs = args[0] == "bench" || args[0] == "test-batch" ? 600 : 60;
+    int exit_code = 1;
+    std::string output;
+    if (!admin::run_command(config.admin_listen_host, config.admin_listen_port, command_args, exit_code, output,
+                            timeout_secs, config.admin_token, admin_tls)) {
+        utils::safe_print("Error: Cannot reach the running service at " + config.admin_listen_host + ":" +
+                          std::to_string(config.admin_listen_port) + " (is it running, with admin_enabled?" +
+                          (admin_tls ? " Over TLS, it must also accept admin_client_cert_path" : "") + ")\n");
+        return 1;
+    }
+    if (out_path.empty() || exit_code != 0) {
+        utils::safe_print(output);
+        return exit_code;
+    }
+    
+    std::ofstream file(out_path, std::ios::binary | std::ios::trunc);
+    file << output;
+    file.close();
+    if (!file) {
+        utils::safe_print("Error: Cannot write " + out_path + "\n");
+        return 1;
+    }
+    utils::safe_print("Metrics written to " + out_path + "\n");
+    return 0;
+}
+
+int main(int argc, char* argv[]) {
+    if (argc > 1) {
+        if (!network::init()) {
+            utils::safe_print("Error: Failed to initialize networking\n");
+            return 1;
+        }
+        int result = run_client_command(std::vector<std::string>(argv + 1, argv + argc));
+        network::cleanup();
+        return result;
+    }
+    
+    // No arguments: run as service with TUI
     // Defensive: Set up output buffering
     setvbuf(stdout, nullptr, _IOLBF, 0);
     setvbuf(stderr, nullptr, _IOLBF, 0);
@@ -72,7 +402,7 @@ int main(int /*argc*/, char* /*argv*/[]) {
     // First Ctrl+C = graceful shutdown, Second Ctrl+C = force kill
     signal(SIGINT, signal_handler);
     signal(SIGTERM, signal_handler);
-    
+
 #ifdef _WIN32
     // Windows: Also handle console close
     SetConsoleCtrlHandler(ConsoleCtrlHandler, TRUE);
@@ -82,15 +412,25 @@ int main(int /*argc*/, char* /*argv*/[]) {
     // POSIX: Handle terminal resize (SIGWINCH) - TUI will detect it automatically
     // We don't need a handler here since TUI polls for size changes
 #endif
-    
+
     // Load configuration
-    bool config_exists = utils::file_exists("config.json");
-    Config config = Config::load("config.json");
+    bool config_exists = utils::file_exists(CONFIG_PATH);
+    Config config = Config::load(CONFIG_PATH);
     if (!config_exists) {
-        config.save("config.json");
+        config.save(CONFIG_PATH);
         utils::safe_print("Created default config.json\n");
     }
     
+    // Reject settings that would otherwise fail later in confusing ways
+    std::vector<std::string> config_errors = config.validate();
+    if (!config_errors.empty()) {
+        for (const auto& error : config_errors) {
+            utils::safe_print("Error: config.json: " + error + "\n");
+        }
+        network::cleanup();
+        return 1;
+    }
+    
     // Ensure log directory and file exist
     if (!config.log_file.empty()) {
         if (!utils::ensure_log_file(config.log_file)) {
@@ -98,7 +438,10 @@ int main(int /*argc*/, char* /*argv*/[]) {
             utils::safe_print("Logging will continue to stdout/stderr\n");
         } else {
             // Initialize logger
-            Logger::instance().init(config.log_file);
+            Logger::instance().init(config.log_file, config.log_format == "json" ? LogFormat::Json : LogFormat::Text,
+                                    config.log_max_bytes, config.log_backup_count);
+            // Without the TUI, a terminal on stderr also shows the log as it is written
+            Logger::instance().set_stderr_sink(!utils::is_terminal() && utils::is_stderr_terminal());
             Logger::instance().log(LogLevel::INFO, "Smart Proxy Service starting");
         }
     }
@@ -109,7 +452,9 @@ int main(int /*argc*/, char* /*argv*/[]) {
     
     // Initialize runway manager
     std::shared_ptr<RunwayManager> runway_manager = std::make_shared<RunwayManager>(
-        config.interfaces, config.upstream_proxies, config.dns_servers, dns_resolver);
+        config.interfaces, config.upstream_proxies, config.dns_servers, dns_resolver,
+        config.proxy_failure_threshold, config.interface_exclude, config.connect_timeout,
+        config.interface_metrics, config.max_runways, config.max_concurrent_probes);
     
     // Discover runways
     runway_manager->discover_runways();
@@ -122,10 +467,43 @@ int main(int /*argc*/, char* /*argv*/[]) {
     
     // Initialize accessibility tracker
     std::shared_ptr<TargetAccessibilityTracker> tracker = std::make_shared<TargetAccessibilityTracker>(
-        config.success_rate_window, config.success_rate_threshold);
+        config.success_rate_window, config.success_rate_threshold, config.uptime_window_secs,
+        config.latency_ema_alpha, config.probe_backoff_base_secs, config.probe_backoff_max_secs);
     
-    // Initialize success validator
-    std::shared_ptr<SuccessValidator> validator = std::make_shared<SuccessValidator>();
+    // Initialize webhook alerting (optional)
+    std::shared_ptr<AlertNotifier> alert_notifier;
+    if (!config.alert_webhook_url.empty()) {
+        alert_notifier = std::make_shared<AlertNotifier>(config.alert_webhook_url, config.alert_debounce_secs);
+        if (alert_notifier->is_valid()) {
+            tracker->set_target_down_callback(
+                [alert_notifier](const std::string& target, const std::vector<TargetMetrics>& runways) {
+                    alert_notifier->notify_target_down(target, runways);
+                });
+        } else {
+            utils::safe_print("Warning: Invalid alert_webhook_url (expected http://host[:port]/path)\n");
+            Logger::instance().log(LogLevel::WARN, "Invalid alert_webhook_url: " + config.alert_webhook_url);
+        }
+    }
+    
+    if (!config.otlp_endpoint.empty()) {
+        std::string error;
+        if (tracing::start(config.otlp_endpoint, config.otlp_service_name, error)) {
+            Logger::instance().log(LogLevel::INFO, "Exporting traces to " + config.otlp_endpoint);
+        } else {
+            utils::safe_print("Warning: Tracing disabled: " + error + "\n");
+            Logger::instance().log(LogLevel::WARN, "Tracing disabled: " + error);
+        }
+    }
+    
+    std::string audit_error;
+    if (!audit::init(config.audit_log_file, audit_error)) {
+        utils::safe_print("Warning: Audit log not written: " + audit_error + "\n");
+        Logger::instance().log(LogLevel::WARN, "Audit log not written: " + audit_error);
+    }
+    
+    // Initialize success validators (additional Validator implementations can be appended to the chain)
+    std::shared_ptr<ValidatorChain> validator = std::make_shared<ValidatorChain>();
+    validator->add(std::make_shared<HttpValidator>(config));
     
     // Initialize routing engine
     RoutingMode routing_mode = config.routing_mode;
@@ -138,7 +516,39 @@ int main(int /*argc*/, char* /*argv*/[]) {
     
     // Initialize health monitor
     std::shared_ptr<HealthMonitor> health_monitor = std::make_shared<HealthMonitor>(
-        runway_manager, tracker, config.health_check_interval);
+        runway_manager, tracker, config.health_check_interval, config.target_ttl_secs,
+        config.accessible_probe_rate, config.health_max_targets_per_cycle, config.health_max_failed_probes,
+        config.health_max_partial_probes, config.health_probe_timeout, config.health_check_jitter,
+        config.health_check_concurrency, config.warmup_targets, config.warmup_interval_secs,
+        config.health_check_intervals);
+    
+    // Log runways that a health probe found recovered or lost
+    health_monitor->add_listener([](const HealthEvent& event) {
+        if (event.type != HealthEvent::Type::StateChanged) {
+            return;
+        }
+        if (event.state == RunwayState::Accessible) {
+            Logger::instance().log(LogLevel::INFO, "Runway " + event.runway_id + " recovered for " + event.target,
+                                   {event.runway_id, event.target});
+        } else if (event.state == RunwayState::Inaccessible) {
+            Logger::instance().log(LogLevel::INFO, "Runway " + event.runway_id + " lost " + event.target,
+                                   {event.runway_id, event.target});
+        }
+    });
+    
+    // Push request metrics to StatsD (optional)
+    std::shared_ptr<StatsdEmitter> statsd;
+    if (!config.statsd_endpoint.empty()) {
+        statsd = std::make_shared<StatsdEmitter>(config.statsd_endpoint, config.statsd_prefix,
+                                                 config.statsd_sample_rate, config.statsd_dogstatsd);
+        if (statsd->is_valid()) {
+            proxy_server->add_listener([statsd](const RequestEvent& event) { statsd->record(event); });
+            Logger::instance().log(LogLevel::INFO, "Sending metrics to StatsD at " + config.statsd_endpoint);
+        } else {
+            utils::safe_print("Warning: Invalid statsd_endpoint (expected host[:port])\n");
+            Logger::instance().log(LogLevel::WARN, "Invalid statsd_endpoint: " + config.statsd_endpoint);
+        }
+    }
     
     // Start proxy server
     if (!proxy_server->start()) {
@@ -176,6 +586,37 @@ int main(int /*argc*/, char* /*argv*/[]) {
         }
     }
     
+    // Start the admin endpoint for CLI commands (optional)
+    std::unique_ptr<AdminServer> admin_server;
+    if (config.admin_enabled) {
+        admin_server = std::make_unique<AdminServer>(runway_manager, routing_engine, tracker, health_monitor, proxy_server,
+                                                      config, CONFIG_PATH);
+        if (admin_server->start()) {
+            Logger::instance().log(LogLevel::INFO, "Admin endpoint listening on " + config.admin_listen_host + ":" +
+                                   std::to_string(config.admin_listen_port));
+        } else {
+            utils::safe_print("Warning: Failed to start admin endpoint on " + config.admin_listen_host + ":" +
+                              std::to_string(config.admin_listen_port) + "\n");
+            utils::safe_flush();
+            admin_server.reset();
+        }
+    }
+    
+    // Start the load balancer health check (optional; validated with the config)
+    std::unique_ptr<HealthEndpoint> health_endpoint;
+    std::string health_host;
+    uint16_t health_port = 0;
+    if (Config::parse_listen_addr(config.health_listen_addr, health_host, health_port)) {
+        health_endpoint = std::make_unique<HealthEndpoint>(runway_manager, proxy_server, health_host, health_port);
+        if (health_endpoint->start()) {
+            Logger::instance().log(LogLevel::INFO, "Health check listening on " + config.health_listen_addr);
+        } else {
+            utils::safe_print("Warning: Failed to start health check on " + config.health_listen_addr + "\n");
+            utils::safe_flush();
+            health_endpoint.reset();
+        }
+    }
+    
     // Create and run TUI
     TUI tui(runway_manager, routing_engine, tracker, proxy_server, config);
     
@@ -197,6 +638,16 @@ int main(int /*argc*/, char* /*argv*/[]) {
         // TUI already displayed shutdown message and stopped, now clean up services
         tui.stop();
         
+        // Stop admin endpoint if running
+        if (admin_server) {
+            admin_server->stop();
+            admin_server.reset();
+        }
+        if (health_endpoint) {
+            health_endpoint->stop();
+            health_endpoint.reset();
+        }
+        
         // Stop WebUI if running
         if (webui) {
             if (utils::is_terminal()) {
@@ -219,6 +670,7 @@ int main(int /*argc*/, char* /*argv*/[]) {
             utils::safe_flush();
         }
         proxy_server->stop();
+        tracing::stop();
         
         if (utils::is_terminal()) {
             utils::safe_print("Smart Proxy Service stopped.\n");
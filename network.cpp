@@ -1,4 +1,5 @@
 #include "network.h"
+#include "tls.h"
 #include <cstring>
 #include <cerrno>
 #include <stdexcept>
@@ -34,8 +35,8 @@ void cleanup() {
 #endif
 }
 
-socket_t create_tcp_socket() {
-    socket_t sock = socket(AF_INET, SOCK_STREAM, IPPROTO_TCP);
+socket_t create_tcp_socket(int family) {
+    socket_t sock = socket(family, SOCK_STREAM, IPPROTO_TCP);
 #ifdef _WIN32
     if (sock == INVALID_SOCKET) return INVALID_SOCKET_VALUE;
 #else
@@ -55,6 +56,22 @@ socket_t create_udp_socket() {
 }
 
 bool bind_socket(socket_t sock, const std::string& host, uint16_t port) {
+    if (is_ipv6_address(host)) {
+        struct sockaddr_in6 addr6;
+        std::memset(&addr6, 0, sizeof(addr6));
+        addr6.sin6_family = AF_INET6;
+        addr6.sin6_port = htons(port);
+        if (inet_pton(AF_INET6, host.c_str(), &addr6.sin6_addr) != 1) {
+            return false;
+        }
+        int result = bind(sock, reinterpret_cast<struct sockaddr*>(&addr6), sizeof(addr6));
+#ifdef _WIN32
+        return result != SOCKET_ERROR;
+#else
+        return result == 0;
+#endif
+    }
+    
     struct sockaddr_in addr;
     std::memset(&addr, 0, sizeof(addr));
     addr.sin_family = AF_INET;
@@ -104,7 +121,68 @@ socket_t accept_connection(socket_t sock, std::string& client_ip, uint16_t& clie
     return client_sock;
 }
 
-bool connect_socket(socket_t sock, const std::string& host, uint16_t port) {
+// Start connect() and, with a timeout, wait for the handshake to finish
+static bool connect_with_timeout(socket_t sock, const struct sockaddr* addr, socklen_t addr_len, int timeout_ms) {
+    if (timeout_ms < 0) {
+        int result = connect(sock, addr, addr_len);
+#ifdef _WIN32
+        return result != SOCKET_ERROR;
+#else
+        return result == 0;
+#endif
+    }
+    
+    if (!set_nonblocking(sock)) {
+        return false;
+    }
+    
+    int result = connect(sock, addr, addr_len);
+    bool connected = (result == 0);
+    if (!connected) {
+#ifdef _WIN32
+        bool in_progress = WSAGetLastError() == WSAEWOULDBLOCK;
+#else
+        bool in_progress = errno == EINPROGRESS;
+#endif
+        if (in_progress) {
+            // Writable once the handshake completes or fails; SO_ERROR tells which
+#ifdef _WIN32
+            WSAPOLLFD pfd;
+            pfd.fd = sock;
+            pfd.events = POLLOUT;
+            pfd.revents = 0;
+            int ready = WSAPoll(&pfd, 1, timeout_ms);
+#else
+            struct pollfd pfd;
+            pfd.fd = sock;
+            pfd.events = POLLOUT;
+            pfd.revents = 0;
+            int ready = poll(&pfd, 1, timeout_ms);
+#endif
+            if (ready > 0) {
+                int error = 0;
+                socklen_t error_len = sizeof(error);
+                getsockopt(sock, SOL_SOCKET, SO_ERROR, reinterpret_cast<char*>(&error), &error_len);
+                connected = (error == 0);
+            }
+        }
+    }
+    
+    return set_blocking(sock) && connected;
+}
+
+bool connect_socket(socket_t sock, const std::string& host, uint16_t port, int timeout_ms) {
+    if (is_ipv6_address(host)) {
+        struct sockaddr_in6 addr6;
+        std::memset(&addr6, 0, sizeof(addr6));
+        addr6.sin6_family = AF_INET6;
+        addr6.sin6_port = htons(port);
+        if (inet_pton(AF_INET6, host.c_str(), &addr6.sin6_addr) != 1) {
+            return false;
+        }
+        return connect_with_timeout(sock, reinterpret_cast<struct sockaddr*>(&addr6), sizeof(addr6), timeout_ms);
+    }
+    
     struct sockaddr_in addr;
     std::memset(&addr, 0, sizeof(addr));
     addr.sin_family = AF_INET;
@@ -114,12 +192,7 @@ bool connect_socket(socket_t sock, const std::string& host, uint16_t port) {
         return false;
     }
     
-    int result = connect(sock, reinterpret_cast<struct sockaddr*>(&addr), sizeof(addr));
-#ifdef _WIN32
-    return result != SOCKET_ERROR;
-#else
-    return result == 0;
-#endif
+    return connect_with_timeout(sock, reinterpret_cast<struct sockaddr*>(&addr), sizeof(addr), timeout_ms);
 }
 
 bool set_nonblocking(socket_t sock) {
@@ -133,6 +206,17 @@ bool set_nonblocking(socket_t sock) {
 #endif
 }
 
+bool set_blocking(socket_t sock) {
+#ifdef _WIN32
+    u_long mode = 0;
+    return ioctlsocket(sock, FIONBIO, &mode) == 0;
+#else
+    int flags = fcntl(sock, F_GETFL, 0);
+    if (flags < 0) return false;
+    return fcntl(sock, F_SETFL, flags & ~O_NONBLOCK) == 0;
+#endif
+}
+
 bool set_socket_option(socket_t sock, int level, int optname, int value) {
     int result = setsockopt(sock, level, optname, reinterpret_cast<const char*>(&value), sizeof(value));
 #ifdef _WIN32
@@ -143,6 +227,7 @@ bool set_socket_option(socket_t sock, int level, int optname, int value) {
 }
 
 void close_socket(socket_t sock) {
+    tls::detach(sock);
 #ifdef _WIN32
     closesocket(sock);
 #else
@@ -150,7 +235,18 @@ void close_socket(socket_t sock) {
 #endif
 }
 
+void shutdown_socket(socket_t sock) {
+#ifdef _WIN32
+    shutdown(sock, SD_BOTH);
+#else
+    shutdown(sock, SHUT_RDWR);
+#endif
+}
+
 ssize_t send_data(socket_t sock, const void* data, size_t len) {
+    if (tls::is_attached(sock)) {
+        return tls::send(sock, data, len);
+    }
 #ifdef _WIN32
     int result = send(sock, reinterpret_cast<const char*>(data), static_cast<int>(len), 0);
     if (result == SOCKET_ERROR) {
@@ -167,6 +263,9 @@ ssize_t send_data(socket_t sock, const void* data, size_t len) {
 }
 
 ssize_t recv_data(socket_t sock, void* buffer, size_t len, int flags) {
+    if (tls::is_attached(sock)) {
+        return tls::recv(sock, buffer, len, (flags & MSG_PEEK) != 0);
+    }
 #ifdef _WIN32
     int result = recv(sock, reinterpret_cast<char*>(buffer), static_cast<int>(len), flags);
     if (result == SOCKET_ERROR) {
@@ -231,10 +330,10 @@ int poll_sockets(socket_t* sockets, int count, int timeout_ms) {
 #endif
 }
 
-bool resolve_hostname(const std::string& hostname, std::string& ip) {
+bool resolve_hostname(const std::string& hostname, std::string& ip, int family) {
     struct addrinfo hints, *result = nullptr;
     std::memset(&hints, 0, sizeof(hints));
-    hints.ai_family = AF_INET;
+    hints.ai_family = family;
     hints.ai_socktype = SOCK_STREAM;
     
     int err = getaddrinfo(hostname.c_str(), nullptr, &hints, &result);
@@ -252,6 +351,14 @@ bool resolve_hostname(const std::string& hostname, std::string& ip) {
                 found = true;
                 break;
             }
+        } else if (rp->ai_family == AF_INET6) {
+            struct sockaddr_in6* addr6 = reinterpret_cast<struct sockaddr_in6*>(rp->ai_addr);
+            char ip_str[INET6_ADDRSTRLEN];
+            if (inet_ntop(AF_INET6, &addr6->sin6_addr, ip_str, INET6_ADDRSTRLEN) != nullptr) {
+                ip = ip_str;
+                found = true;
+                break;
+            }
         }
     }
     
@@ -259,6 +366,11 @@ bool resolve_hostname(const std::string& hostname, std::string& ip) {
     return found;
 }
 
+bool is_ipv6_address(const std::string& ip) {
+    struct in6_addr addr6;
+    return !ip.empty() && inet_pton(AF_INET6, ip.c_str(), &addr6) == 1;
+}
+
 std::string get_last_error() {
 #ifdef _WIN32
     int error = WSAGetLastError();
@@ -47,13 +47,13 @@ bool init();
 // Cleanup networking (Windows: WSACleanup, POSIX: no-op)
 void cleanup();
 
-// Create TCP socket
-socket_t create_tcp_socket();
+// Create TCP socket (family: AF_INET or AF_INET6)
+socket_t create_tcp_socket(int family = AF_INET);
 
 // Create UDP socket
 socket_t create_udp_socket();
 
-// Bind socket to address and port
+// Bind socket to address and port (IPv4 or IPv6 literal; "::" binds any IPv6 address)
 bool bind_socket(socket_t sock, const std::string& host, uint16_t port);
 
 // Listen on socket
@@ -62,19 +62,26 @@ bool listen_socket(socket_t sock, int backlog = 128);
 // Accept connection
 socket_t accept_connection(socket_t sock, std::string& client_ip, uint16_t& client_port);
 
-// Connect to remote host
-bool connect_socket(socket_t sock, const std::string& host, uint16_t port);
+// Connect to remote host (IPv4 or IPv6 literal)
+// timeout_ms >= 0 bounds the TCP handshake (non-blocking connect + poll); -1 blocks until the OS gives up
+bool connect_socket(socket_t sock, const std::string& host, uint16_t port, int timeout_ms = -1);
 
 // Set socket to non-blocking mode
 bool set_nonblocking(socket_t sock);
 
+// Set socket back to blocking mode
+bool set_blocking(socket_t sock);
+
 // Set socket options (SO_REUSEADDR, etc.)
 bool set_socket_option(socket_t sock, int level, int optname, int value);
 
-// Close socket
+// Close socket (releasing its TLS session, if any)
 void close_socket(socket_t sock);
 
-// Send data (returns bytes sent, -1 on error)
+// Shut down both directions; unlike close_socket, this also wakes a thread blocked in accept_connection
+void shutdown_socket(socket_t sock);
+
+// Send data (returns bytes sent, -1 on error); goes through the socket's TLS session if one is attached
 ssize_t send_data(socket_t sock, const void* data, size_t len);
 
 // Receive data (returns bytes received, -1 on error, 0 on connection closed)
@@ -85,7 +92,11 @@ ssize_t recv_data(socket_t sock, void* buffer, size_t len, int flags = 0);
 int poll_sockets(socket_t* sockets, int count, int timeout_ms);
 
 // Resolve hostname to IP address (getaddrinfo)
-bool resolve_hostname(const std::string& hostname, std::string& ip);
+// family: AF_INET for an IPv4 address, AF_INET6 for an IPv6 address
+bool resolve_hostname(const std::string& hostname, std::string& ip, int family = AF_INET);
+
+// True if ip is an IPv6 address literal (RFC 4291 Section 2.2)
+bool is_ipv6_address(const std::string& ip);
 
 // Get last error message
 std::string get_last_error();
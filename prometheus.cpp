@@ -0,0 +1,150 @@
+#include "prometheus.h"
+#include "dns.h"
+#include <sstream>
+#include <algorithm>
+#include <map>
+#include <vector>
+
+namespace prometheus {
+
+// Label values escape backslash, double quote and newline
+static std::string escape_label(const std::string& value) {
+    std::string escaped;
+    for (char c : value) {
+        switch (c) {
+            case '\\': escaped += "\\\\"; break;
+            case '"': escaped += "\\\""; break;
+            case '\n': escaped += "\\n"; break;
+            default: escaped += c; break;
+        }
+    }
+    return escaped;
+}
+
+static std::string state_name(RunwayState state) {
+    switch (state) {
+        case RunwayState::Unknown: return "unknown";
+        case RunwayState::Accessible: return "accessible";
+        case RunwayState::PartiallyAccessible: return "partially_accessible";
+        case RunwayState::Inaccessible: return "inaccessible";
+        case RunwayState::Testing: return "testing";
+    }
+    return "unknown";
+}
+
+static void header(std::ostringstream& oss, const std::string& name, const std::string& type, const std::string& help) {
+    oss << "# HELP " << name << " " << help << "\n";
+    oss << "# TYPE " << name << " " << type << "\n";
+}
+
+std::string render(RunwayManager& runway_manager, TargetAccessibilityTracker& tracker, size_t max_targets) {
+    auto snapshot = tracker.snapshot();
+    
+    // Busiest targets first; past max_targets they are left out
+    std::vector<std::pair<uint64_t, std::string>> by_attempts;
+    for (const auto& target_pair : snapshot) {
+        uint64_t attempts = 0;
+        for (const auto& runway_pair : target_pair.second) {
+            attempts += runway_pair.second.total_attempts;
+        }
+        by_attempts.emplace_back(attempts, target_pair.first);
+    }
+    std::sort(by_attempts.begin(), by_attempts.end(),
+        [](const std::pair<uint64_t, std::string>& a, const std::pair<uint64_t, std::string>& b) {
+            return a.first != b.first ? a.first > b.first : a.second < b.second;
+        });
+    size_t omitted = 0;
+    if (max_targets > 0 && by_attempts.size() > max_targets) {
+        omitted = by_attempts.size() - max_targets;
+        for (size_t i = max_targets; i < by_attempts.size(); ++i) {
+            snapshot.erase(by_attempts[i].second);
+        }
+    }
+    
+    std::ostringstream oss;
+    
+    // One sample per target and runway
+    struct Family {
+        const char* name;
+        const char* type;
+        const char* help;
+        double (*value)(const TargetMetrics&);
+    };
+    const std::vector<Family> families = {
+        {"smartproxy_requests_total", "counter", "Requests and health probes sent through a runway to a target",
+         [](const TargetMetrics& m) { return static_cast<double>(m.total_attempts); }},
+        {"smartproxy_successes_total", "counter", "Attempts that reached the real target content",
+         [](const TargetMetrics& m) { return static_cast<double>(m.user_success_count); }},
+        {"smartproxy_partial_successes_total", "counter", "Attempts that connected but got blocked or unexpected content",
+         [](const TargetMetrics& m) { return static_cast<double>(m.partial_success_count); }},
+        {"smartproxy_failures_total", "counter", "Attempts that failed",
+         [](const TargetMetrics& m) { return static_cast<double>(m.failure_count); }},
+        {"smartproxy_bytes_total", "counter", "Request and response bytes proxied",
+         [](const TargetMetrics& m) { return static_cast<double>(m.bytes_transferred); }},
+        {"smartproxy_latency_seconds", "gauge", "Moving average response time, DNS excluded",
+         [](const TargetMetrics& m) { return m.avg_response_time; }},
+        {"smartproxy_success_rate", "gauge", "Success rate over recent attempts (0-1)",
+         [](const TargetMetrics& m) { return m.success_rate; }},
+    };
+    for (const auto& family : families) {
+        header(oss, family.name, family.type, family.help);
+        for (const auto& target_pair : snapshot) {
+            for (const auto& runway_pair : target_pair.second) {
+                oss << family.name << "{target=\"" << escape_label(target_pair.first) << "\",runway=\""
+                    << escape_label(runway_pair.first) << "\"} ";
+                // Counters in full (the default stream precision would round large ones)
+                if (std::string(family.type) == "counter") {
+                    oss << static_cast<uint64_t>(family.value(runway_pair.second)) << "\n";
+                } else {
+                    oss << family.value(runway_pair.second) << "\n";
+                }
+            }
+        }
+    }
+    
+    // Only the current state is exported, so a state change replaces the series
+    header(oss, "smartproxy_runway_state", "gauge", "Accessibility of a target through a runway (1 for the current state)");
+    for (const auto& target_pair : snapshot) {
+        for (const auto& runway_pair : target_pair.second) {
+            oss << "smartproxy_runway_state{target=\"" << escape_label(target_pair.first) << "\",runway=\""
+                << escape_label(runway_pair.first) << "\",state=\"" << state_name(runway_pair.second.state) << "\"} 1\n";
+        }
+    }
+    
+    // Per runway; their number is bounded by max_runways
+    auto runways = runway_manager.get_all_runways(true);
+    size_t total_in_flight = 0;
+    header(oss, "smartproxy_runway_in_flight", "gauge", "Requests currently being sent through a runway");
+    for (const auto& runway : runways) {
+        total_in_flight += runway->get_in_flight();
+        oss << "smartproxy_runway_in_flight{runway=\"" << escape_label(runway->id) << "\"} " << runway->get_in_flight() << "\n";
+    }
+    header(oss, "smartproxy_runway_enabled", "gauge", "Whether a runway carries traffic (0 after disable-runway)");
+    for (const auto& runway : runways) {
+        oss << "smartproxy_runway_enabled{runway=\"" << escape_label(runway->id) << "\"} " << (runway->enabled ? 1 : 0) << "\n";
+    }
+    header(oss, "smartproxy_in_flight", "gauge", "Requests currently being sent through any runway");
+    oss << "smartproxy_in_flight " << total_in_flight << "\n";
+    
+    auto resolver = runway_manager.get_dns_resolver();
+    if (resolver) {
+        uint64_t hits = resolver->get_cache_hits();
+        uint64_t misses = resolver->get_cache_misses();
+        header(oss, "smartproxy_dns_cache_hits_total", "counter", "DNS lookups answered from the cache");
+        oss << "smartproxy_dns_cache_hits_total " << hits << "\n";
+        header(oss, "smartproxy_dns_cache_misses_total", "counter", "DNS lookups sent to a DNS server");
+        oss << "smartproxy_dns_cache_misses_total " << misses << "\n";
+        header(oss, "smartproxy_dns_cache_hit_ratio", "gauge", "Share of DNS lookups answered from the cache since start");
+        oss << "smartproxy_dns_cache_hit_ratio " << (hits + misses > 0 ? static_cast<double>(hits) / (hits + misses) : 0.0) << "\n";
+        header(oss, "smartproxy_dns_cache_entries", "gauge", "Cached DNS answers, expired ones included");
+        oss << "smartproxy_dns_cache_entries " << resolver->get_cache_entries().size() << "\n";
+    }
+    
+    header(oss, "smartproxy_targets", "gauge", "Targets with metrics");
+    oss << "smartproxy_targets " << by_attempts.size() << "\n";
+    header(oss, "smartproxy_targets_omitted", "gauge", "Targets left out of per-target series by metrics_max_targets");
+    oss << "smartproxy_targets_omitted " << omitted << "\n";
+    return oss.str();
+}
+
+} // namespace prometheus
@@ -0,0 +1,21 @@
+#ifndef PROMETHEUS_H
+#define PROMETHEUS_H
+
+#include <string>
+#include <cstddef>
+#include "runway_manager.h"
+#include "tracker.h"
+
+// `GET /metrics` on the admin endpoint: live state in the Prometheus text exposition format (0.0.4)
+
+namespace prometheus {
+
+// Per target and runway: requests, successes, failures, bytes, latency, success rate and state
+// Per runway: in-flight requests and whether it is enabled; DNS cache hits, misses and hit ratio
+// Only the max_targets busiest targets get series of their own (0 = all), bounding label cardinality;
+// smartproxy_targets_omitted counts the rest
+std::string render(RunwayManager& runway_manager, TargetAccessibilityTracker& tracker, size_t max_targets);
+
+} // namespace prometheus
+
+#endif // PROMETHEUS_H
@@ -1,10 +1,15 @@
 #include "proxy.h"
 #include "utils.h"
 #include "logger.h"
+#include "upstream.h"
+#include "tracing.h"
 #include <sstream>
 #include <algorithm>
 #include <ctime>
 #include <mutex>
+#include <chrono>
+#include <random>
+#include <cctype>
 #ifdef _WIN32
 #include <winsock2.h>
 #else
@@ -14,13 +19,68 @@
 // RFC 7230 - HTTP/1.1 Message Syntax and Routing
 // RFC 7231 - HTTP/1.1 Semantics and Content
 
+// Correlates a client-visible response with the log lines of the request behind it
+static const char* REQUEST_ID_HEADER = "X-SmartProxy-Request-Id";
+
+// 16 random hex digits
+static std::string generate_request_id() {
+    static thread_local std::mt19937_64 generator(std::random_device{}());
+    static const char* digits = "0123456789abcdef";
+    uint64_t value = generator();
+    std::string id(16, '0');
+    for (size_t i = 0; i < id.size(); ++i) {
+        id[i] = digits[(value >> (4 * i)) & 0xF];
+    }
+    return id;
+}
+
+// Head sampling: true for about rate of all calls (0 <= rate <= 1)
+static bool sample(double rate) {
+    if (rate >= 1.0) {
+        return true;
+    }
+    static thread_local std::mt19937 generator(std::random_device{}());
+    return std::uniform_real_distribution<double>(0.0, 1.0)(generator) < rate;
+}
+
+// A client's own id is reused if it is a plain token: it ends up in log lines and headers verbatim
+static bool is_valid_request_id(const std::string& id) {
+    if (id.empty() || id.size() > 128) {
+        return false;
+    }
+    for (char c : id) {
+        if (!std::isalnum(static_cast<unsigned char>(c)) && c != '-' && c != '_' && c != '.' && c != ':') {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Pins of target from certificate_pins: an exact entry, else the most specific (longest) matching pattern
+static const std::vector<std::string>* pins_for(const std::map<std::string, std::vector<std::string>>& pins,
+                                                const std::string& target) {
+    auto exact = pins.find(utils::to_lower(target));
+    if (exact != pins.end()) {
+        return &exact->second;
+    }
+    const std::vector<std::string>* best = nullptr;
+    size_t best_length = 0;
+    for (const auto& entry : pins) {
+        if (entry.first.length() > best_length && utils::match_host_pattern(target, entry.first)) {
+            best = &entry.second;
+            best_length = entry.first.length();
+        }
+    }
+    return best;
+}
+
 ProxyServer::ProxyServer(
     const Config& config,
     std::shared_ptr<RunwayManager> runway_manager,
     std::shared_ptr<RoutingEngine> routing_engine,
     std::shared_ptr<TargetAccessibilityTracker> tracker,
     std::shared_ptr<DNSResolver> dns_resolver,
-    std::shared_ptr<SuccessValidator> validator)
+    std::shared_ptr<Validator> validator)
     : config_(config)
     , runway_manager_(runway_manager)
     , routing_engine_(routing_engine)
@@ -32,7 +92,8 @@ ProxyServer::ProxyServer(
     , active_connections_(0)
     , total_connections_(0)
     , total_bytes_sent_(0)
-    , total_bytes_received_(0) {
+    , total_bytes_received_(0)
+    , next_listener_id_(0) {
 }
 
 ProxyServer::~ProxyServer() {
@@ -48,6 +109,20 @@ bool ProxyServer::start() {
         return false;
     }
     
+    if (!config_.tls_cert_path.empty()) {
+        tls::ServerOptions options;
+        options.cert_path = config_.tls_cert_path;
+        options.key_path = config_.tls_key_path;
+        options.client_ca_path = config_.tls_client_ca_path;
+        options.require_client_cert = config_.tls_client_cert_required;
+        std::string error;
+        tls_context_ = tls::create_server_context(options, error);
+        if (!tls_context_) {
+            Logger::instance().log(LogLevel::ERROR_LEVEL, "Cannot serve TLS on the proxy listener: " + error);
+            return false;
+        }
+    }
+    
     listen_socket_ = network::create_tcp_socket();
     if (listen_socket_ == network::INVALID_SOCKET_VALUE) {
         return false;
@@ -84,6 +159,7 @@ void ProxyServer::stop() {
     running_ = false;
     
     if (listen_socket_ != network::INVALID_SOCKET_VALUE) {
+        network::shutdown_socket(listen_socket_); // Wake server_loop out of accept
         network::close_socket(listen_socket_);
         listen_socket_ = network::INVALID_SOCKET_VALUE;
     }
@@ -361,12 +437,39 @@ std::vector<std::map<std::string, std::string>> ProxyServer::get_active_connecti
     return result;
 }
 
+size_t ProxyServer::add_listener(RequestEventCallback callback) {
+    std::lock_guard<std::mutex> lock(listeners_mutex_);
+    size_t listener_id = next_listener_id_++;
+    listeners_[listener_id] = callback;
+    return listener_id;
+}
+
+void ProxyServer::remove_listener(size_t listener_id) {
+    std::lock_guard<std::mutex> lock(listeners_mutex_);
+    listeners_.erase(listener_id);
+}
+
+void ProxyServer::notify(const RequestEvent& event) {
+    std::vector<RequestEventCallback> listeners;
+    {
+        std::lock_guard<std::mutex> lock(listeners_mutex_);
+        for (const auto& pair : listeners_) {
+            listeners.push_back(pair.second);
+        }
+    }
+    // Invoke outside the lock so listeners may subscribe or unsubscribe
+    for (const auto& listener : listeners) {
+        listener(event);
+    }
+}
+
 void ProxyServer::handle_connection(socket_t client_sock) {
     std::string client_ip;
     uint16_t client_port = 0;
     network::get_peer_address(client_sock, client_ip, client_port);
     
     uint64_t conn_start_time = std::time(nullptr);
+    auto request_start_time = std::chrono::steady_clock::now();
     std::string conn_id = client_ip + ":" + std::to_string(client_port) + "-" + std::to_string(conn_start_time);
     
     active_connections_++;
@@ -387,7 +490,7 @@ void ProxyServer::handle_connection(socket_t client_sock) {
     struct timeval timeout;
     timeout.tv_sec = static_cast<long>(config_.network_timeout);
     timeout.tv_usec = 0;
-    
+
 #ifdef _WIN32
     setsockopt(client_sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
     setsockopt(client_sock, SOL_SOCKET, SO_SNDTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
@@ -395,22 +498,42 @@ void ProxyServer::handle_connection(socket_t client_sock) {
     setsockopt(client_sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
     setsockopt(client_sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
 #endif
+
+    // Replaced by the client's X-SmartProxy-Request-Id once the request is parsed, if it sent one
+    std::string request_id = generate_request_id();
     
     ConnectionLog conn_log;
     conn_log.timestamp = conn_start_time;
     conn_log.level = "INFO";
     conn_log.event = "connect";
+    conn_log.request_id = request_id;
     conn_log.client_ip = client_ip;
     conn_log.client_port = client_port;
     
+    // TLS termination: the handshake (which checks client certificates, with tls_client_ca_path) comes first,
+    // then everything below reads through the session
+    if (tls_context_) {
+        std::string tls_error;
+        if (!tls::accept(client_sock, *tls_context_, tls_error)) {
+            conn_log.event = "error";
+            conn_log.error = "TLS handshake failed: " + tls_error;
+            conn_log.duration_ms = (std::time(nullptr) - conn_start_time) * 1000.0;
+            Logger::instance().log_connection(conn_log);
+            
+            {
+                std::lock_guard<std::mutex> lock(connections_mutex_);
+                active_connections_map_.erase(conn_id);
+            }
+            active_connections_--;
+            return;
+        }
+        conn_log.client_cert = tls::peer_name(client_sock);
+    }
+    
     // Protocol detection: peek at first byte to detect SOCKS5 vs HTTP
     // SOCKS5 starts with 0x05, HTTP starts with ASCII letters (GET, POST, CONNECT, etc.)
     uint8_t first_byte = 0;
-#ifdef _WIN32
-    ssize_t peeked = recv(client_sock, reinterpret_cast<char*>(&first_byte), 1, MSG_PEEK);
-#else
-    ssize_t peeked = recv(client_sock, &first_byte, 1, MSG_PEEK);
-#endif
+    ssize_t peeked = network::recv_data(client_sock, &first_byte, 1, MSG_PEEK);
     
     if (peeked <= 0) {
         // Connection closed or error
@@ -473,11 +596,25 @@ void ProxyServer::handle_connection(socket_t client_sock) {
         error_response.status_code = 400;
         error_response.status_text = "Bad Request";
         error_response.headers["Content-Length"] = "0";
+        error_response.headers[REQUEST_ID_HEADER] = request_id;
         std::vector<uint8_t> response_data = build_http_response(error_response);
         network::send_data(client_sock, response_data.data(), response_data.size());
         return;
     }
     
+    auto request_id_it = request.headers.find(utils::to_lower(REQUEST_ID_HEADER));
+    if (request_id_it != request.headers.end() && is_valid_request_id(request_id_it->second)) {
+        request_id = request_id_it->second;
+        conn_log.request_id = request_id;
+    }
+    {
+        std::lock_guard<std::mutex> lock(connections_mutex_);
+        auto it = active_connections_map_.find(conn_id);
+        if (it != active_connections_map_.end()) {
+            it->second["request_id"] = request_id;
+        }
+    }
+    
     // Extract target from request
     std::string target_host;
     uint16_t target_port = 80;
@@ -490,6 +627,7 @@ void ProxyServer::handle_connection(socket_t client_sock) {
             error_response.status_code = 400;
             error_response.status_text = "Bad Request";
             error_response.headers["Content-Length"] = "0";
+            error_response.headers[REQUEST_ID_HEADER] = request_id;
             std::vector<uint8_t> response_data = build_http_response(error_response);
             network::send_data(client_sock, response_data.data(), response_data.size());
             return;
@@ -508,10 +646,30 @@ void ProxyServer::handle_connection(socket_t client_sock) {
         error_response.status_code = 501;
         error_response.status_text = "Not Implemented";
         error_response.headers["Content-Length"] = "0";
+        error_response.headers[REQUEST_ID_HEADER] = request_id;
         std::vector<uint8_t> response_data = build_http_response(error_response);
         network::send_data(client_sock, response_data.data(), response_data.size());
         return;
     } else {
+        // Absolute-form https:// has this proxy open the TLS connection itself, on port 443 by default
+        if (utils::to_lower(request.path).compare(0, 8, "https://") == 0) {
+            if (!tls::available()) {
+                HTTPResponse error_response;
+                error_response.status_code = 501;
+                error_response.status_text = "Not Implemented";
+                error_response.headers["Content-Length"] = "0";
+                error_response.headers[REQUEST_ID_HEADER] = request_id;
+                std::vector<uint8_t> response_data = build_http_response(error_response);
+                network::send_data(client_sock, response_data.data(), response_data.size());
+                {
+                    std::lock_guard<std::mutex> lock(connections_mutex_);
+                    active_connections_map_.erase(conn_id);
+                }
+                active_connections_--;
+                return;
+            }
+            target_port = 443;
+        }
         // Extract from path or Host header
         auto host_it = request.headers.find("host");
         if (host_it != request.headers.end()) {
@@ -522,9 +680,9 @@ void ProxyServer::handle_connection(socket_t client_sock) {
             }
         } else {
             // Try to extract from path
-            if (request.path.find("http://") == 0) {
+            if (request.path.find("http://") == 0 || request.path.find("https://") == 0) {
                 // Parse URL
-                size_t host_start = 7; // Skip "http://"
+                size_t host_start = request.path.find("://") + 3; // Skip the scheme
                 size_t host_end = request.path.find('/', host_start);
                 if (host_end == std::string::npos) {
                     host_end = request.path.find(':', host_start);
@@ -546,6 +704,7 @@ void ProxyServer::handle_connection(socket_t client_sock) {
         error_response.status_code = 400;
         error_response.status_text = "Bad Request";
         error_response.headers["Content-Length"] = "0";
+        error_response.headers[REQUEST_ID_HEADER] = request_id;
         std::vector<uint8_t> response_data = build_http_response(error_response);
         network::send_data(client_sock, response_data.data(), response_data.size());
         active_connections_--;
@@ -554,9 +713,36 @@ void ProxyServer::handle_connection(socket_t client_sock) {
     
     conn_log.target_host = target_host;
     conn_log.target_port = target_port;
+    tracker_->set_target_port(target_host, target_port);
     conn_log.method = request.method;
     conn_log.path = request.path;
     
+    // Reported to listeners once the request is done
+    RequestEvent outcome;
+    outcome.request_id = request_id;
+    outcome.target = target_host;
+    outcome.target_port = target_port;
+    outcome.method = request.method;
+    auto complete = [&](uint16_t status_code, bool success, const std::string& error) {
+        outcome.type = RequestEvent::Type::Completed;
+        outcome.status_code = status_code;
+        outcome.success = success;
+        outcome.error = error;
+        outcome.duration = std::chrono::duration<double>(std::chrono::steady_clock::now() - request_start_time).count();
+        notify(outcome);
+    };
+    
+    // Only a trace_sample_rate share of requests is traced, and logged if it succeeds; failures are
+    // always logged, and metrics (tracker, /metrics, StatsD, /events) count every request
+    bool sampled = sample(config_.trace_sample_rate);
+    
+    // Root span of this request's trace (OpenTelemetry HTTP semantic conventions)
+    tracing::Span request_span("handle_request", tracing::SpanKind::Server, sampled);
+    request_span.set_attribute("http.request.method", request.method);
+    request_span.set_attribute("server.address", target_host);
+    request_span.set_attribute("server.port", static_cast<int64_t>(target_port));
+    request_span.set_attribute("smartproxy.request_id", request_id);
+    
     // Update active connection info
     {
         std::lock_guard<std::mutex> lock(connections_mutex_);
@@ -572,23 +758,34 @@ void ProxyServer::handle_connection(socket_t client_sock) {
     
     // Select runway
     auto all_runways = runway_manager_->get_all_runways();
-    auto runway = routing_engine_->select_runway(target_host, all_runways);
-    
-    if (!runway) {
-        // Test all runways
-        runway = test_all_runways(target_host, all_runways);
+    std::shared_ptr<Runway> runway;
+    {
+        tracing::Span select_span("select_runway");
+        runway = routing_engine_->select_runway(target_host, all_runways);
+        
+        if (!runway) {
+            // Test all runways
+            select_span.set_attribute("smartproxy.tested_all_runways", static_cast<int64_t>(1));
+            runway = test_all_runways(target_host, target_port, all_runways);
+        }
+        if (runway) {
+            select_span.set_attribute("smartproxy.runway_id", runway->id);
+        }
     }
     
     if (!runway) {
+        request_span.set_error("No accessible runway found");
         conn_log.event = "error";
         conn_log.error = "No accessible runway found";
         conn_log.duration_ms = (std::time(nullptr) - conn_start_time) * 1000.0;
         Logger::instance().log_connection(conn_log);
+        complete(502, false, conn_log.error);
         
         HTTPResponse error_response;
         error_response.status_code = 502;
         error_response.status_text = "Bad Gateway";
         error_response.headers["Content-Length"] = "0";
+        error_response.headers[REQUEST_ID_HEADER] = request_id;
         std::vector<uint8_t> response_data = build_http_response(error_response);
         network::send_data(client_sock, response_data.data(), response_data.size());
         active_connections_--;
@@ -609,16 +806,39 @@ void ProxyServer::handle_connection(socket_t client_sock) {
     // Make request through runway
     const size_t max_retries = 2;
     for (size_t attempt = 0; attempt < max_retries; ++attempt) {
-        auto result = make_http_request(request, target_host, target_port, runway);
+        RunwayInFlightGuard in_flight_guard(runway);
+        double dns_time = 0.0;
+        auto request_start = std::chrono::steady_clock::now();
+        tracing::Span upstream_span("upstream_request", tracing::SpanKind::Client);
+        upstream_span.set_attribute("smartproxy.runway_id", runway->id);
+        upstream_span.set_attribute("smartproxy.attempt", static_cast<int64_t>(attempt + 1));
+        auto result = make_http_request(request, target_host, target_port, runway, dns_time, request_id);
+        double elapsed = std::chrono::duration<double>(std::chrono::steady_clock::now() - request_start).count();
+        outcome.dns_time += dns_time;
         bool network_success = std::get<0>(result);
         bool user_success = std::get<1>(result);
         uint16_t status = std::get<2>(result);
         auto& response_headers = std::get<3>(result);
         auto& response_body = std::get<4>(result);
+        upstream_span.set_attribute("http.response.status_code", static_cast<int64_t>(status));
+        if (!network_success) {
+            upstream_span.set_error("Request through runway failed");
+        }
         
-        // Update tracker
-        double response_time = 0.0; // Simplified
-        tracker_->update(target_host, runway->id, network_success, user_success, response_time);
+        // Update tracker (DNS time is tracked separately from the upstream round trip)
+        double response_time = std::max(0.0, elapsed - dns_time);
+        auto before = tracker_->get_metrics(target_host, runway->id);
+        tracker_->update(target_host, runway->id, network_success, user_success, response_time, dns_time);
+        auto after = tracker_->get_metrics(target_host, runway->id);
+        RunwayState previous_state = before ? before->state : RunwayState::Unknown;
+        if (after && after->state != previous_state) {
+            RequestEvent state_change = outcome;
+            state_change.type = RequestEvent::Type::StateChanged;
+            state_change.runway_id = runway->id;
+            state_change.previous_state = previous_state;
+            state_change.state = after->state;
+            notify(state_change);
+        }
         
         if (network_success) {
             // Send response to client
@@ -628,6 +848,8 @@ void ProxyServer::handle_connection(socket_t client_sock) {
             http_response.headers = response_headers;
             http_response.body = response_body;
             http_response.headers["Content-Length"] = std::to_string(response_body.size());
+            http_response.headers.erase(utils::to_lower(REQUEST_ID_HEADER)); // Our id, not a copy the target echoed
+            http_response.headers[REQUEST_ID_HEADER] = request_id;
             
             std::vector<uint8_t> response_data = build_http_response(http_response);
             size_t sent = network::send_data(client_sock, response_data.data(), response_data.size());
@@ -647,15 +869,23 @@ void ProxyServer::handle_connection(socket_t client_sock) {
                 }
             }
             
+            request_span.set_attribute("http.response.status_code", static_cast<int64_t>(status));
+            request_span.set_attribute("smartproxy.runway_id", runway->id);
+            
             conn_log.event = "disconnect";
             conn_log.status_code = status;
             conn_log.bytes_sent = sent;
             conn_log.bytes_received = request.body.size();
             conn_log.duration_ms = duration;
-            Logger::instance().log_connection(conn_log);
+            if (sampled) {
+                Logger::instance().log_connection(conn_log);
+            }
+            outcome.runway_id = runway->id;
+            complete(status, true, "");
             
             total_bytes_sent_ += sent;
             total_bytes_received_ += request.body.size();
+            tracker_->record_bytes(target_host, runway->id, sent + request.body.size());
             
             // Remove from active connections
             {
@@ -688,16 +918,22 @@ void ProxyServer::handle_connection(socket_t client_sock) {
         }
     }
     
+    request_span.set_attribute("http.response.status_code", static_cast<int64_t>(502));
+    request_span.set_error("All runway attempts failed");
+    
     conn_log.event = "error";
     conn_log.error = "All runway attempts failed";
     conn_log.status_code = 502;
     conn_log.duration_ms = duration;
     Logger::instance().log_connection(conn_log);
+    outcome.runway_id = runway->id;
+    complete(502, false, conn_log.error);
     
     HTTPResponse error_response;
     error_response.status_code = 502;
     error_response.status_text = "Bad Gateway";
     error_response.headers["Content-Length"] = "0";
+    error_response.headers[REQUEST_ID_HEADER] = request_id;
     std::vector<uint8_t> response_data = build_http_response(error_response);
     network::send_data(client_sock, response_data.data(), response_data.size());
     
@@ -711,23 +947,52 @@ void ProxyServer::handle_connection(socket_t client_sock) {
 
 std::tuple<bool, bool, uint16_t, std::map<std::string, std::string>, std::vector<uint8_t>>
 ProxyServer::make_http_request(const HTTPRequest& request, const std::string& target_host,
-                               uint16_t target_port, std::shared_ptr<Runway> /*runway*/) {
-    // Resolve target
+                               uint16_t target_port, std::shared_ptr<Runway> runway,
+                               double& dns_time_secs, const std::string& request_id) {
+    // Proxy runways reach the target through their upstream proxy
+    std::shared_ptr<UpstreamProxy> proxy = runway ? runway->upstream_proxy : nullptr;
+    if (proxy && !proxy->accessible) {
+        return std::make_tuple(false, false, static_cast<uint16_t>(502),
+                              std::map<std::string, std::string>(),
+                              std::vector<uint8_t>());
+    }
+    // With a chain, the exit hop is the one that talks to the target
+    const UpstreamProxyConfig* exit_hop = proxy ? &upstream::exit_hop(proxy->config) : nullptr;
+    bool remote_dns = exit_hop && upstream::resolves_remotely(*exit_hop);
+    
+    // Absolute-form https:// (RFC 7230 Section 5.3.2): TLS to the target is made here, end to end
+    bool target_tls = utils::to_lower(request.path).compare(0, 8, "https://") == 0;
+    if (target_tls && proxy && utils::to_lower(proxy->config.proxy_type) == "https") {
+        // A socket carries one TLS session, so TLS to the target cannot run inside TLS to the proxy
+        Logger::instance().log(LogLevel::DEBUG, "Runway " + runway->id + " cannot reach https targets (https proxy)",
+                               {runway->id, target_host, request_id});
+        return std::make_tuple(false, false, static_cast<uint16_t>(502),
+                              std::map<std::string, std::string>(),
+                              std::vector<uint8_t>());
+    }
+    
+    // Resolve target (unless the proxy resolves it)
+    dns_time_secs = 0.0;
     std::string resolved_ip;
-    if (dns_resolver_->is_ip_address(target_host) || dns_resolver_->is_private_ip(target_host)) {
+    if (remote_dns || dns_resolver_->is_ip_address(target_host) || dns_resolver_->is_private_ip(target_host)) {
         resolved_ip = target_host;
     } else {
-        auto dns_result = dns_resolver_->resolve(target_host);
+        tracing::Span dns_span("dns_resolve", tracing::SpanKind::Client);
+        dns_span.set_attribute("dns.question.name", target_host);
+        auto dns_result = dns_resolver_->resolve(target_host, runway && runway->is_direct && runway->is_ipv6());
         if (dns_result.first.empty()) {
+            dns_span.set_error("No answer");
             return std::make_tuple(false, false, static_cast<uint16_t>(502), 
                                   std::map<std::string, std::string>(), 
                                   std::vector<uint8_t>());
         }
         resolved_ip = dns_result.first;
+        dns_time_secs = dns_result.second / 1000.0;
     }
     
     // Connect to target
-    socket_t sock = network::create_tcp_socket();
+    int family = (runway && runway->is_ipv6()) ? AF_INET6 : AF_INET;
+    socket_t sock = network::create_tcp_socket(family);
     if (sock == network::INVALID_SOCKET_VALUE) {
         return std::make_tuple(false, false, static_cast<uint16_t>(502),
                               std::map<std::string, std::string>(),
@@ -738,7 +1003,7 @@ ProxyServer::make_http_request(const HTTPRequest& request, const std::string& ta
     struct timeval timeout;
     timeout.tv_sec = static_cast<long>(config_.network_timeout);
     timeout.tv_usec = 0;
-    
+
 #ifdef _WIN32
     setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
     setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
@@ -746,8 +1011,42 @@ ProxyServer::make_http_request(const HTTPRequest& request, const std::string& ta
     setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
     setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
 #endif
+
+    // Egress through the runway's interface: bind to its source address (port chosen by the OS)
+    if (runway && !runway->source_ip.empty() && !network::bind_socket(sock, runway->source_ip, 0)) {
+        Logger::instance().log(LogLevel::DEBUG, "Cannot bind to source " + runway->source_ip + " for runway " + runway->id,
+                               {runway->id, target_host, request_id});
+        network::close_socket(sock);
+        return std::make_tuple(false, false, static_cast<uint16_t>(502),
+                              std::map<std::string, std::string>(),
+                              std::vector<uint8_t>());
+    }
     
-    bool connected = network::connect_socket(sock, resolved_ip, target_port);
+    // Fail fast on dead paths; network_timeout still governs the transfer itself
+    int connect_timeout_ms = static_cast<int>(config_.connect_timeout * 1000.0);
+    bool connected = false;
+    {
+        tracing::Span connect_span("connect");
+        if (proxy) {
+            // Only an unreachable proxy counts against it; a refused tunnel may be the target's fault
+            bool proxy_reached = upstream::connect_to_proxy(sock, proxy->config, family, connect_timeout_ms) &&
+                                 upstream::open_chain(sock, proxy->config);
+            // An HTTP exit hop is asked for a CONNECT tunnel when the target needs TLS, instead of absolute-form requests
+            connected = proxy_reached &&
+                        (target_tls && upstream::is_http_type(exit_hop->proxy_type)
+                             ? upstream::http_connect(sock, resolved_ip, target_port, upstream::proxy_authorization(*exit_hop))
+                             : upstream::open_tunnel(sock, *exit_hop, resolved_ip, target_port));
+            // Nor does a proxy without an address in the runway's family (e.g. an A-only name for a v6 runway)
+            if (connected || (!proxy_reached && upstream::has_address(proxy->config, family))) {
+                runway_manager_->record_proxy_result(proxy, connected);
+            }
+        } else {
+            connected = network::connect_socket(sock, resolved_ip, target_port, connect_timeout_ms);
+        }
+        if (!connected) {
+            connect_span.set_error(proxy ? "Cannot connect through upstream proxy" : "Cannot connect to target");
+        }
+    }
     if (!connected) {
         network::close_socket(sock);
         return std::make_tuple(false, false, static_cast<uint16_t>(502),
@@ -755,17 +1054,65 @@ ProxyServer::make_http_request(const HTTPRequest& request, const std::string& ta
                               std::vector<uint8_t>());
     }
     
+    // The certificate must be valid for target_host, never for the address it resolved to,
+    // unless danger_accept_invalid_certs is set globally or on the runway's proxy
+    if (target_tls) {
+        tls::ClientOptions options;
+        options.server_name = target_host;
+        options.accept_invalid_certs = config_.danger_accept_invalid_certs ||
+                                       (proxy && proxy->config.danger_accept_invalid_certs);
+        std::string tls_error;
+        if (!tls::connect(sock, options, tls_error)) {
+            // The path reached something, but not provably the target: counted like a block page
+            Logger::instance().log(LogLevel::WARN, "TLS to " + target_host + " through runway " + runway->id +
+                                   " failed: " + tls_error, {runway->id, target_host, request_id});
+            network::close_socket(sock);
+            return std::make_tuple(true, false, static_cast<uint16_t>(502),
+                                  std::map<std::string, std::string>(),
+                                  std::vector<uint8_t>());
+        }
+        
+        // A pinned target must present one of its pinned keys, whoever signed the certificate
+        const std::vector<std::string>* pins = pins_for(config_.certificate_pins, target_host);
+        std::string pin = pins ? tls::peer_key_pin(sock) : "";
+        if (pins && std::find(pins->begin(), pins->end(), pin) == pins->end()) {
+            Logger::instance().log(LogLevel::ERROR_LEVEL, "Certificate pin mismatch for " + target_host +
+                                   " through runway " + runway->id + ": got " + pin +
+                                   ", which is not pinned; the connection may be intercepted",
+                                   {runway->id, target_host, request_id});
+            network::close_socket(sock);
+            return std::make_tuple(true, false, static_cast<uint16_t>(502),
+                                  std::map<std::string, std::string>(),
+                                  std::vector<uint8_t>());
+        }
+    }
+    
     // Build request
+    // HTTP proxies need the absolute-form request target (RFC 7230 Section 5.3.2); over TLS the
+    // target itself is addressed, in origin-form
+    std::string request_target = request.path;
+    if (target_tls) {
+        size_t path_start = request_target.find('/', 8);
+        request_target = path_start != std::string::npos ? request_target.substr(path_start) : "/";
+    } else if (exit_hop && upstream::is_http_type(exit_hop->proxy_type) && request_target.compare(0, 7, "http://") != 0) {
+        request_target = "http://" + target_host + (target_port != 80 ? ":" + std::to_string(target_port) : "") + request.path;
+    }
     std::ostringstream request_oss;
-    request_oss << request.method << " " << request.path << " " << request.version << "\r\n";
+    request_oss << request.method << " " << request_target << " " << request.version << "\r\n";
     
     // Copy headers (remove hop-by-hop headers, RFC 7230 Section 6.1)
     for (const auto& pair : request.headers) {
         std::string name = utils::to_lower(pair.first);
-        if (name != "host" && name != "connection" && name != "proxy-connection") {
+        if (name != "host" && name != "connection" && name != "proxy-connection" &&
+            name != "proxy-authorization" && name != utils::to_lower(REQUEST_ID_HEADER)) {
             request_oss << pair.first << ": " << pair.second << "\r\n";
         }
     }
+    // The target (or its logs) sees the same id as the client and this service's log
+    request_oss << REQUEST_ID_HEADER << ": " << request_id << "\r\n";
+    if (!target_tls && exit_hop && upstream::is_http_type(exit_hop->proxy_type) && !exit_hop->username.empty()) {
+        request_oss << "Proxy-Authorization: " << upstream::proxy_authorization(*exit_hop) << "\r\n";
+    }
     request_oss << "Host: " << target_host;
     if (target_port != 80 && target_port != 443) {
         request_oss << ":" << target_port;
@@ -826,20 +1173,37 @@ ProxyServer::make_http_request(const HTTPRequest& request, const std::string& ta
     
     network::close_socket(sock);
     
-    // Validate response
-    bool network_success = (status_code >= 200 && status_code < 400);
-    bool user_success = false;
-    if (network_success) {
-        auto validation = validator_->validate_http(status_code, response_body);
-        user_success = validation.second;
-    }
+    // Validate response (validators know per-target expected statuses)
+    ValidationContext validation_context{target_host, request.path, status_code, response_headers, response_body};
+    auto validation = validator_->validate(validation_context);
+    bool network_success = validation.first;
+    bool user_success = validation.second;
     
     return std::make_tuple(network_success, user_success, status_code,
                           response_headers, response_body);
 }
 
+FetchResult ProxyServer::fetch(const std::string& target, uint16_t port, std::shared_ptr<Runway> runway) {
+    bool https = port == 443 && tls::available();
+    std::string host = target.find(':') != std::string::npos ? "[" + target + "]" : target; // IPv6 literal
+    HTTPRequest request;
+    request.method = "GET";
+    request.path = (https ? "https://" : "http://") + host +
+                   (port != (https ? 443 : 80) ? ":" + std::to_string(port) : "") + "/";
+    
+    FetchResult result;
+    double dns_time = 0.0;
+    auto start = std::chrono::steady_clock::now();
+    auto response = make_http_request(request, target, port, runway, dns_time, generate_request_id());
+    result.duration = std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
+    result.network_success = std::get<0>(response);
+    result.user_success = std::get<1>(response);
+    result.status_code = std::get<2>(response);
+    return result;
+}
+
 std::shared_ptr<Runway> ProxyServer::test_all_runways(
-    const std::string& target,
+    const std::string& target, uint16_t target_port,
     const std::vector<std::shared_ptr<Runway>>& runways) {
     
     // Prioritize direct runways
@@ -860,7 +1224,8 @@ std::shared_ptr<Runway> ProxyServer::test_all_runways(
     
     // Test runways
     for (const auto& runway : prioritized) {
-        auto result = runway_manager_->test_runway_accessibility(target, runway, static_cast<double>(config_.accessibility_timeout));
+        auto result = runway_manager_->test_runway_accessibility(target, runway, static_cast<double>(config_.accessibility_timeout),
+                                                                 target_port);
         bool net_success = std::get<0>(result);
         bool user_success = std::get<1>(result);
         double response_time = std::get<2>(result);
@@ -882,7 +1247,10 @@ std::shared_ptr<Runway> ProxyServer::get_alternative_runway(
     
     for (const auto& id : accessible_ids) {
         if (id != current_runway_id) {
-            return runway_manager_->get_runway(id);
+            auto runway = runway_manager_->get_runway(id);
+            if (runway && runway->enabled) {
+                return runway;
+            }
         }
     }
     
@@ -7,6 +7,8 @@
 #include <memory>
 #include <thread>
 #include <atomic>
+#include <mutex>
+#include <functional>
 #include <cstdint>
 #include "config.h"
 #include "runway.h"
@@ -15,6 +17,7 @@
 #include "tracker.h"
 #include "dns.h"
 #include "validator.h"
+#include "tls.h"
 #include "network.h"
 
 // HTTP Proxy Server
@@ -41,6 +44,51 @@ struct HTTPResponse {
     HTTPResponse() : version("HTTP/1.1"), status_code(200), status_text("OK") {}
 };
 
+// Outcome of one proxied request; an attempt that moves its runway to another state
+// is also reported as StateChanged (before the request completes)
+struct RequestEvent {
+    enum class Type {
+        Completed,
+        StateChanged // Real traffic changed a runway's state for the target
+    };
+    
+    Type type;
+    std::string request_id;
+    std::string target;
+    uint16_t target_port;
+    std::string runway_id; // Empty if no runway could be found
+    std::string method;
+    uint16_t status_code; // Sent to the client (Completed)
+    bool success; // The target's response reached the client (Completed)
+    std::string error;
+    double duration; // Seconds (Completed)
+    double dns_time; // Seconds spent on DNS lookups over all attempts; 0 if none was made (Completed)
+    RunwayState previous_state; // StateChanged
+    RunwayState state; // StateChanged
+    
+    RequestEvent()
+        : type(Type::Completed)
+        , target_port(0)
+        , status_code(0)
+        , success(false)
+        , duration(0.0)
+        , dns_time(0.0)
+        , previous_state(RunwayState::Unknown)
+        , state(RunwayState::Unknown) {}
+};
+
+using RequestEventCallback = std::function<void(const RequestEvent& event)>;
+
+// Outcome of one request sent by ProxyServer::fetch()
+struct FetchResult {
+    bool network_success; // The target answered
+    bool user_success; // The answer passed validation (e.g. not a block page)
+    uint16_t status_code; // As received; 502 without an answer, as a client would get
+    double duration; // Seconds until the whole response was read
+    
+    FetchResult() : network_success(false), user_success(false), status_code(0), duration(0.0) {}
+};
+
 class ProxyServer {
 public:
     ProxyServer(const Config& config,
@@ -48,7 +96,7 @@ public:
                 std::shared_ptr<RoutingEngine> routing_engine,
                 std::shared_ptr<TargetAccessibilityTracker> tracker,
                 std::shared_ptr<DNSResolver> dns_resolver,
-                std::shared_ptr<SuccessValidator> validator);
+                std::shared_ptr<Validator> validator);
     
     ~ProxyServer();
     
@@ -70,17 +118,28 @@ public:
     // Get active connections list (for TUI)
     std::vector<std::map<std::string, std::string>> get_active_connections_info() const;
     
+    // Subscribe to request outcomes (fired on the connection's thread)
+    // Returns an id for remove_listener()
+    size_t add_listener(RequestEventCallback callback);
+    
+    void remove_listener(size_t listener_id);
+    
+    // GET / from target:port through runway as a client's request would go (https for port 443 in TLS
+    // builds) and validate the response; the tracker records nothing. For test-batch and bench
+    FetchResult fetch(const std::string& target, uint16_t port, std::shared_ptr<Runway> runway);
+
 private:
     Config config_;
     std::shared_ptr<RunwayManager> runway_manager_;
     std::shared_ptr<RoutingEngine> routing_engine_;
     std::shared_ptr<TargetAccessibilityTracker> tracker_;
     std::shared_ptr<DNSResolver> dns_resolver_;
-    std::shared_ptr<SuccessValidator> validator_;
+    std::shared_ptr<Validator> validator_;
     
     socket_t listen_socket_;
     std::atomic<bool> running_;
     std::thread server_thread_;
+    std::shared_ptr<tls::ServerContext> tls_context_; // Set when clients connect over TLS (tls_cert_path)
     
     // Connection tracking
     mutable std::mutex stats_mutex_;
@@ -93,6 +152,12 @@ private:
     // Active connections map: conn_id -> connection info
     std::map<std::string, std::map<std::string, std::string>> active_connections_map_;
     
+    std::mutex listeners_mutex_;
+    std::map<size_t, RequestEventCallback> listeners_;
+    size_t next_listener_id_;
+    
+    void notify(const RequestEvent& event);
+    
     // Server main loop
     void server_loop();
     
@@ -106,12 +171,15 @@ private:
     std::vector<uint8_t> build_http_response(const HTTPResponse& response);
     
     // Make HTTP request through runway
+    // dns_time_secs receives the time spent resolving target_host (0 if cached or an IP)
+    // request_id is sent upstream as X-SmartProxy-Request-Id and tags this request's log lines
     std::tuple<bool, bool, uint16_t, std::map<std::string, std::string>, std::vector<uint8_t>>
     make_http_request(const HTTPRequest& request, const std::string& target_host,
-                     uint16_t target_port, std::shared_ptr<Runway> runway);
+                     uint16_t target_port, std::shared_ptr<Runway> runway,
+                     double& dns_time_secs, const std::string& request_id);
     
-    // Test all runways to find accessible one
-    std::shared_ptr<Runway> test_all_runways(const std::string& target,
+    // Test all runways to find accessible one (probing the port the client asked for)
+    std::shared_ptr<Runway> test_all_runways(const std::string& target, uint16_t target_port,
                                              const std::vector<std::shared_ptr<Runway>>& runways);
     
     // Get alternative runway
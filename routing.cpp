@@ -22,20 +22,7 @@ std::shared_ptr<Runway> RoutingEngine::select_runway(
     std::lock_guard<std::mutex> lock(mode_mutex_);
     RoutingMode current_mode = mode_;
     
-    // Get accessible runways
-    std::vector<std::string> accessible_ids = tracker_->get_accessible_runways(target);
-    if (accessible_ids.empty()) {
-        return nullptr;
-    }
-    
-    // Filter runways to only accessible ones
-    std::vector<std::shared_ptr<Runway>> accessible_runways;
-    for (const auto& runway : runways) {
-        if (std::find(accessible_ids.begin(), accessible_ids.end(), runway->id) != accessible_ids.end()) {
-            accessible_runways.push_back(runway);
-        }
-    }
-    
+    std::vector<std::shared_ptr<Runway>> accessible_runways = eligible_runways(target, runways);
     if (accessible_runways.empty()) {
         return nullptr;
     }
@@ -52,6 +39,127 @@ std::shared_ptr<Runway> RoutingEngine::select_runway(
     }
 }
 
+std::vector<std::shared_ptr<Runway>> RoutingEngine::eligible_runways(
+    const std::string& target,
+    const std::vector<std::shared_ptr<Runway>>& runways) {
+    
+    // Get accessible runways
+    std::vector<std::string> accessible_ids = tracker_->get_accessible_runways(target);
+    
+    // Filter runways to only accessible, enabled ones
+    std::vector<std::shared_ptr<Runway>> accessible_runways;
+    for (const auto& runway : runways) {
+        if (runway->enabled &&
+            std::find(accessible_ids.begin(), accessible_ids.end(), runway->id) != accessible_ids.end()) {
+            accessible_runways.push_back(runway);
+        }
+    }
+    
+    if (accessible_runways.empty()) {
+        return accessible_runways;
+    }
+    
+    // Prefer the lowest interface metric (e.g. wired over cellular); higher-metric
+    // runways only carry traffic while no preferred runway is accessible
+    uint32_t best_metric = accessible_runways[0]->metric;
+    for (const auto& runway : accessible_runways) {
+        best_metric = std::min<uint32_t>(best_metric, runway->metric);
+    }
+    accessible_runways.erase(std::remove_if(accessible_runways.begin(), accessible_runways.end(),
+        [best_metric](const std::shared_ptr<Runway>& runway) { return runway->metric != best_metric; }),
+        accessible_runways.end());
+    return accessible_runways;
+}
+
+RoutingDecision RoutingEngine::explain(
+    const std::string& target,
+    const std::vector<std::shared_ptr<Runway>>& runways) {
+    
+    RoutingDecision decision;
+    decision.mode = get_mode();
+    std::vector<std::shared_ptr<Runway>> eligible = eligible_runways(target, runways);
+    std::map<std::string, TargetMetrics> metrics = tracker_->get_target_metrics(target);
+    
+    auto candidate = [&metrics](const std::shared_ptr<Runway>& runway, bool is_eligible, const std::string& reason) {
+        RoutingCandidate c;
+        c.runway_id = runway->id;
+        auto it = metrics.find(runway->id);
+        c.has_metrics = it != metrics.end();
+        c.state = c.has_metrics ? it->second.state : RunwayState::Unknown;
+        c.avg_response_time = c.has_metrics ? it->second.avg_response_time : 0.0;
+        c.eligible = is_eligible;
+        c.reason = reason;
+        return c;
+    };
+    
+    // Eligible runways in the order the mode would consider them, the pick first
+    std::vector<std::shared_ptr<Runway>> ranked = eligible;
+    std::string next_reason;
+    switch (decision.mode) {
+        case RoutingMode::Latency: {
+            // Measured runways fastest first (as select_by_latency), unmeasured ones after in runway order
+            auto latency = [&metrics](const std::shared_ptr<Runway>& runway) {
+                auto it = metrics.find(runway->id);
+                return it != metrics.end() && it->second.avg_response_time > 0.0 ? it->second.avg_response_time : 1e9;
+            };
+            std::stable_sort(ranked.begin(), ranked.end(),
+                [&latency](const std::shared_ptr<Runway>& a, const std::shared_ptr<Runway>& b) {
+                    return latency(a) < latency(b);
+                });
+            if (!ranked.empty()) {
+                decision.reason = latency(ranked[0]) < 1e9
+                    ? "lowest average latency (" + std::to_string(latency(ranked[0])) + "s)"
+                    : "no latency measured yet, so the first accessible runway";
+            }
+            next_reason = "slower";
+            break;
+        }
+        case RoutingMode::FirstAccessible:
+            decision.reason = "first accessible runway";
+            next_reason = "later in runway order";
+            break;
+        case RoutingMode::RoundRobin: {
+            size_t index = 0;
+            {
+                std::lock_guard<std::mutex> lock(rr_mutex_);
+                auto it = round_robin_index_.find(target);
+                index = it != round_robin_index_.end() && !ranked.empty() ? it->second % ranked.size() : 0;
+            }
+            std::rotate(ranked.begin(), ranked.begin() + static_cast<std::ptrdiff_t>(index), ranked.end());
+            decision.reason = "next in rotation (" + std::to_string(index + 1) + " of " + std::to_string(ranked.size()) + ")";
+            next_reason = "later in rotation";
+            break;
+        }
+    }
+    
+    if (ranked.empty()) {
+        decision.reason = "no runway is known to reach this target; a request would test every enabled runway";
+    } else {
+        decision.selected_runway_id = ranked[0]->id;
+    }
+    for (size_t i = 0; i < ranked.size(); ++i) {
+        decision.candidates.push_back(candidate(ranked[i], true, i == 0 ? "selected" : next_reason));
+    }
+    
+    // The rest, with the first filter that ruled each out
+    std::vector<std::string> accessible_ids = tracker_->get_accessible_runways(target);
+    for (const auto& runway : runways) {
+        if (std::find(eligible.begin(), eligible.end(), runway) != eligible.end()) {
+            continue;
+        }
+        std::string reason;
+        if (!runway->enabled) {
+            reason = "disabled";
+        } else if (std::find(accessible_ids.begin(), accessible_ids.end(), runway->id) == accessible_ids.end()) {
+            reason = metrics.find(runway->id) == metrics.end() ? "no metrics for this target" : "not accessible";
+        } else {
+            reason = "interface metric " + std::to_string(runway->metric) + " (a lower-metric runway is accessible)";
+        }
+        decision.candidates.push_back(candidate(runway, false, reason));
+    }
+    return decision;
+}
+
 std::shared_ptr<Runway> RoutingEngine::select_by_latency(
     const std::string& target,
     const std::vector<std::shared_ptr<Runway>>& runways) {
@@ -9,6 +9,23 @@
 #include "tracker.h"
 #include "config.h"
 
+// One runway's part in a routing decision (see RoutingEngine::explain)
+struct RoutingCandidate {
+    std::string runway_id;
+    bool has_metrics; // Whether the tracker has seen this runway for the target
+    RunwayState state;
+    double avg_response_time; // Seconds; 0 if not measured
+    bool eligible; // Enabled, accessible and on the preferred interface metric
+    std::string reason; // Why it is or is not the pick
+};
+
+struct RoutingDecision {
+    RoutingMode mode;
+    std::string selected_runway_id; // Empty if no runway is known to reach the target
+    std::string reason;
+    std::vector<RoutingCandidate> candidates; // Pick first, then eligible runways as the mode ranks them, then the rest
+};
+
 class RoutingEngine {
 public:
     RoutingEngine(std::shared_ptr<TargetAccessibilityTracker> tracker, RoutingMode mode);
@@ -20,6 +37,10 @@ public:
     std::shared_ptr<Runway> select_runway(const std::string& target, 
                                           const std::vector<std::shared_ptr<Runway>>& runways);
     
+    // What select_runway would pick for target right now, and why; nothing changes
+    // (round-robin does not advance)
+    RoutingDecision explain(const std::string& target, const std::vector<std::shared_ptr<Runway>>& runways);
+    
 private:
     std::shared_ptr<TargetAccessibilityTracker> tracker_;
     mutable RoutingMode mode_;
@@ -27,6 +48,10 @@ private:
     std::map<std::string, size_t> round_robin_index_;
     mutable std::mutex rr_mutex_;
     
+    // Enabled runways accessible for target, on the lowest interface metric among them
+    std::vector<std::shared_ptr<Runway>> eligible_runways(const std::string& target,
+                                                          const std::vector<std::shared_ptr<Runway>>& runways);
+    
     std::shared_ptr<Runway> select_by_latency(const std::string& target,
                                                const std::vector<std::shared_ptr<Runway>>& runways);
     std::shared_ptr<Runway> select_first_accessible(const std::string& target,
@@ -4,6 +4,7 @@
 #include <string>
 #include <cstdint>
 #include <memory>
+#include <atomic>
 #include "config.h"
 
 enum class RunwayState {
@@ -16,9 +17,9 @@ enum class RunwayState {
 
 struct DNSServer {
     DNSServerConfig config;
-    double response_time;
+    double response_time; // Seconds, from the last successful test
     uint64_t last_success; // Unix timestamp
-    uint32_t failure_count;
+    uint32_t failure_count; // Consecutive failed tests since the last success
     
     DNSServer() : response_time(0.0), last_success(0), failure_count(0) {}
     DNSServer(const DNSServerConfig& cfg) 
@@ -27,38 +28,63 @@ struct DNSServer {
 
 struct UpstreamProxy {
     UpstreamProxyConfig config;
-    bool accessible;
+    std::atomic<bool> accessible; // Written under the runway manager's lock, read without it (e.g. the TUI)
     uint64_t last_success; // Unix timestamp
-    uint32_t failure_count;
+    uint32_t failure_count; // Consecutive failures since the last success
+    std::atomic<double> response_time; // Seconds to reach the target through the proxy, from the last successful probe
     
-    UpstreamProxy() : accessible(true), last_success(0), failure_count(0) {}
+    UpstreamProxy() : accessible(true), last_success(0), failure_count(0), response_time(0.0) {}
     UpstreamProxy(const UpstreamProxyConfig& cfg)
-        : config(cfg), accessible(true), last_success(0), failure_count(0) {}
+        : config(cfg), accessible(true), last_success(0), failure_count(0), response_time(0.0) {}
 };
 
 struct Runway {
     std::string id;
     std::string interface_name;
-    std::string source_ip; // IPv4 address as string
+    std::string source_ip; // IPv4 or IPv6 address as string
     std::shared_ptr<UpstreamProxy> upstream_proxy;
     std::shared_ptr<DNSServer> dns_server;
     std::string resolved_ip; // Resolved target IP
     bool is_direct;
+    std::atomic<size_t> in_flight; // Requests currently being sent through this runway
+    std::atomic<uint32_t> metric; // Interface metric stamped at discovery; routing prefers the lowest
+    std::atomic<bool> enabled; // Disabled runways are kept (with their metrics) but carry no traffic and are not probed
     
-    Runway() : is_direct(true) {}
+    Runway() : is_direct(true), in_flight(0), metric(0), enabled(true) {}
     Runway(const std::string& id, const std::string& interface_name, 
            const std::string& source_ip,
            std::shared_ptr<UpstreamProxy> proxy,
            std::shared_ptr<DNSServer> dns)
         : id(id), interface_name(interface_name), source_ip(source_ip),
           upstream_proxy(proxy), dns_server(dns),
-          is_direct(proxy == nullptr) {}
+          is_direct(proxy == nullptr), in_flight(0), metric(0), enabled(true) {}
+    
+    size_t get_in_flight() const { return in_flight.load(); }
+    
+    // IPv6 runways egress from an IPv6 source address and resolve AAAA records
+    bool is_ipv6() const { return source_ip.find(':') != std::string::npos; }
     
     bool operator==(const Runway& other) const {
         return id == other.id;
     }
 };
 
+// RAII guard counting a request as in flight on a runway for its lifetime
+class RunwayInFlightGuard {
+public:
+    explicit RunwayInFlightGuard(std::shared_ptr<Runway> runway) : runway_(runway) {
+        if (runway_) runway_->in_flight++;
+    }
+    ~RunwayInFlightGuard() {
+        if (runway_) runway_->in_flight--;
+    }
+    RunwayInFlightGuard(const RunwayInFlightGuard&) = delete;
+    RunwayInFlightGuard& operator=(const RunwayInFlightGuard&) = delete;
+    
+private:
+    std::shared_ptr<Runway> runway_;
+};
+
 // Hash function for Runway (for use in unordered_map)
 struct RunwayHash {
     std::size_t operator()(const Runway& r) const {
@@ -1,9 +1,15 @@
 #include "runway_manager.h"
 #include "network.h"
 #include "utils.h"
+#include "logger.h"
+#include "upstream.h"
+#include "tls.h"
 #include <sstream>
 #include <ctime>
 #include <algorithm>
+#include <chrono>
+#include <fstream>
+#include <cstdlib>
 
 #ifdef _WIN32
 #include <winsock2.h>
@@ -16,16 +22,48 @@
 #include <arpa/inet.h>
 #endif
 
+#ifdef _WIN32
+// UTF-8 form of a wide (UTF-16) string such as an adapter's FriendlyName
+static std::string to_utf8(const wchar_t* text) {
+    int size = WideCharToMultiByte(CP_UTF8, 0, text, -1, nullptr, 0, nullptr, nullptr);
+    if (size <= 1) {
+        return "";
+    }
+    std::string result(static_cast<size_t>(size - 1), '\0');
+    WideCharToMultiByte(CP_UTF8, 0, text, -1, &result[0], size, nullptr, nullptr);
+    return result;
+}
+#endif
+
 RunwayManager::RunwayManager(
     const std::vector<std::string>& interfaces,
     const std::vector<UpstreamProxyConfig>& upstream_proxies,
     const std::vector<DNSServerConfig>& dns_servers,
-    std::shared_ptr<DNSResolver> dns_resolver)
+    std::shared_ptr<DNSResolver> dns_resolver,
+    uint32_t proxy_failure_threshold,
+    const std::vector<std::string>& interface_exclude,
+    double connect_timeout_secs,
+    const std::map<std::string, uint32_t>& interface_metrics,
+    size_t max_runways,
+    size_t max_concurrent_probes)
     : interfaces_(interfaces)
-    , dns_resolver_(dns_resolver) {
+    , interface_exclude_(interface_exclude)
+    , interface_metrics_(interface_metrics)
+    , dns_resolver_(dns_resolver)
+    , proxy_failure_threshold_(proxy_failure_threshold > 0 ? proxy_failure_threshold : 1)
+    , connect_timeout_secs_(connect_timeout_secs)
+    , max_runways_(max_runways)
+    , probe_slots_(max_concurrent_probes)
+    , next_runway_id_(0)
+    , next_listener_id_(0) {
     
     // Convert configs to runtime objects
     for (const auto& proxy_cfg : upstream_proxies) {
+        if (!is_supported_chain(proxy_cfg)) {
+            Logger::instance().log(LogLevel::WARN, "Skipping upstream proxy " + upstream::describe(proxy_cfg) +
+                                   ": unsupported proxy_type");
+            continue;
+        }
         upstream_proxies_.push_back(std::make_shared<UpstreamProxy>(proxy_cfg));
     }
     
@@ -49,8 +87,94 @@ uint64_t RunwayManager::get_current_time() const {
 #endif
 }
 
+int RunwayManager::probe_connect_timeout_ms(double timeout_secs) const {
+    double secs = (connect_timeout_secs_ > 0.0 && connect_timeout_secs_ < timeout_secs) ? connect_timeout_secs_ : timeout_secs;
+    return static_cast<int>(secs * 1000.0);
+}
+
+bool RunwayManager::is_excluded_interface(const std::string& name) const {
+    if (std::find(interfaces_.begin(), interfaces_.end(), name) != interfaces_.end()) {
+        return false;
+    }
+    for (const auto& pattern : interface_exclude_) {
+        if (utils::match_wildcard(name, pattern)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+uint32_t RunwayManager::interface_metric(const std::string& name, uint32_t os_metric) const {
+    auto exact = interface_metrics_.find(name);
+    if (exact != interface_metrics_.end()) {
+        return exact->second;
+    }
+    for (const auto& entry : interface_metrics_) {
+        if (utils::match_wildcard(name, entry.first)) {
+            return entry.second;
+        }
+    }
+    return os_metric;
+}
+
+std::map<std::string, RunwayManager::DefaultRoute> RunwayManager::read_default_routes() {
+    std::map<std::string, DefaultRoute> routes;
+#ifdef __linux__
+    // /proc/net/route columns: Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+    // Destination 00000000 is a default route; keep the lowest metric per interface.
+    // Addresses are the raw in_addr value in hex, so they convert back without byte swapping
+    std::ifstream route_file("/proc/net/route");
+    std::string line;
+    std::getline(route_file, line); // Header
+    while (std::getline(route_file, line)) {
+        std::istringstream iss(line);
+        std::string iface, destination, gateway_hex, flags, refcnt, use;
+        uint32_t metric;
+        if (!(iss >> iface >> destination >> gateway_hex >> flags >> refcnt >> use >> metric) ||
+            destination != "00000000") {
+            continue;
+        }
+        auto it = routes.find(iface);
+        if (it != routes.end() && it->second.metric <= metric) {
+            continue;
+        }
+        
+        DefaultRoute route;
+        route.metric = metric;
+        struct in_addr gateway_addr;
+        gateway_addr.s_addr = static_cast<uint32_t>(std::strtoul(gateway_hex.c_str(), nullptr, 16));
+        char ip_str[INET_ADDRSTRLEN];
+        if (gateway_addr.s_addr != 0 && inet_ntop(AF_INET, &gateway_addr, ip_str, INET_ADDRSTRLEN)) {
+            route.gateway = ip_str;
+        }
+        routes[iface] = route;
+    }
+#endif
+    return routes;
+}
+
 void RunwayManager::discover_interfaces() {
+    std::vector<InterfaceInfo> found;
+    if (list_interfaces(found)) {
+        set_interfaces(found);
+    }
+}
+
+void RunwayManager::set_interfaces(const std::vector<InterfaceInfo>& found) {
     std::lock_guard<std::mutex> lock(mutex_);
+    std::map<std::string, InterfaceInfo> current_interfaces;
+    for (const auto& found_info : found) {
+        if (is_excluded_interface(found_info.name)) continue;
+        InterfaceInfo& info = current_interfaces[found_info.name];
+        info = found_info;
+        info.metric = interface_metric(info.name, found_info.metric);
+        info.last_seen = get_current_time();
+    }
+    interface_info_ = current_interfaces;
+}
+
+bool RunwayManager::list_interfaces(std::vector<InterfaceInfo>& found) {
+    std::map<std::string, InterfaceInfo> current_interfaces;
     
 #ifdef _WIN32
     // Windows: Use GetAdaptersAddresses
@@ -58,88 +182,188 @@ void RunwayManager::discover_interfaces() {
     std::vector<uint8_t> buffer(buffer_size);
     PIP_ADAPTER_ADDRESSES adapters = reinterpret_cast<PIP_ADAPTER_ADDRESSES>(buffer.data());
     
-    ULONG result = GetAdaptersAddresses(AF_INET, GAA_FLAG_INCLUDE_PREFIX, nullptr, adapters, &buffer_size);
+    const ULONG flags = GAA_FLAG_INCLUDE_PREFIX | GAA_FLAG_INCLUDE_GATEWAYS;
+    ULONG result = GetAdaptersAddresses(AF_UNSPEC, flags, nullptr, adapters, &buffer_size);
     if (result == ERROR_BUFFER_OVERFLOW) {
         buffer.resize(buffer_size);
         adapters = reinterpret_cast<PIP_ADAPTER_ADDRESSES>(buffer.data());
-        result = GetAdaptersAddresses(AF_INET, GAA_FLAG_INCLUDE_PREFIX, nullptr, adapters, &buffer_size);
+        result = GetAdaptersAddresses(AF_UNSPEC, flags, nullptr, adapters, &buffer_size);
     }
     
-    if (result == NO_ERROR) {
-        std::map<std::string, InterfaceInfo> current_interfaces;
-        
-        for (PIP_ADAPTER_ADDRESSES adapter = adapters; adapter != nullptr; adapter = adapter->Next) {
-            if (adapter->IfType == IF_TYPE_ETHERNET_CSMACD || adapter->IfType == IF_TYPE_IEEE80211) {
-                for (PIP_ADAPTER_UNICAST_ADDRESS addr = adapter->FirstUnicastAddress;
-                     addr != nullptr; addr = addr->Next) {
-                    if (addr->Address.lpSockaddr->sa_family == AF_INET) {
-                        struct sockaddr_in* sin = reinterpret_cast<struct sockaddr_in*>(addr->Address.lpSockaddr);
-                        char ip_str[INET_ADDRSTRLEN];
-                        inet_ntop(AF_INET, &sin->sin_addr, ip_str, INET_ADDRSTRLEN);
-                        
-                        InterfaceInfo info;
-                        info.name = adapter->AdapterName;
-                        info.ip = ip_str;
-                        info.last_seen = get_current_time();
-                        current_interfaces[info.name] = info;
+    if (result != NO_ERROR) {
+        return false;
+    }
+    
+    for (PIP_ADAPTER_ADDRESSES adapter = adapters; adapter != nullptr; adapter = adapter->Next) {
+        if (adapter->IfType == IF_TYPE_ETHERNET_CSMACD || adapter->IfType == IF_TYPE_IEEE80211) {
+            // Named as the OS shows them ("Ethernet", "Wi-Fi"); AdapterName is a GUID, which
+            // interfaces, interface_exclude and interface_metrics patterns would never match
+            std::string name = to_utf8(adapter->FriendlyName);
+            for (PIP_ADAPTER_UNICAST_ADDRESS addr = adapter->FirstUnicastAddress;
+                 addr != nullptr; addr = addr->Next) {
+                InterfaceInfo& info = current_interfaces[name];
+                info.name = name;
+                info.metric = adapter->Ipv4Metric;
+                for (PIP_ADAPTER_GATEWAY_ADDRESS gw = adapter->FirstGatewayAddress;
+                     gw != nullptr && info.gateway.empty(); gw = gw->Next) {
+                    if (gw->Address.lpSockaddr->sa_family == AF_INET) {
+                        struct sockaddr_in* sin = reinterpret_cast<struct sockaddr_in*>(gw->Address.lpSockaddr);
+                        char gw_str[INET_ADDRSTRLEN];
+                        inet_ntop(AF_INET, &sin->sin_addr, gw_str, INET_ADDRSTRLEN);
+                        info.gateway = gw_str;
                     }
                 }
+                if (addr->Address.lpSockaddr->sa_family == AF_INET && info.ip.empty()) {
+                    struct sockaddr_in* sin = reinterpret_cast<struct sockaddr_in*>(addr->Address.lpSockaddr);
+                    char ip_str[INET_ADDRSTRLEN];
+                    inet_ntop(AF_INET, &sin->sin_addr, ip_str, INET_ADDRSTRLEN);
+                    info.ip = ip_str;
+                } else if (addr->Address.lpSockaddr->sa_family == AF_INET6 && info.ipv6.empty()) {
+                    struct sockaddr_in6* sin6 = reinterpret_cast<struct sockaddr_in6*>(addr->Address.lpSockaddr);
+                    if (IN6_IS_ADDR_LINKLOCAL(&sin6->sin6_addr)) {
+                        continue; // Needs a scope id to be usable
+                    }
+                    char ip_str[INET6_ADDRSTRLEN];
+                    inet_ntop(AF_INET6, &sin6->sin6_addr, ip_str, INET6_ADDRSTRLEN);
+                    info.ipv6 = ip_str;
+                }
             }
         }
-        
-        interface_info_ = current_interfaces;
     }
 #else
     // POSIX: Use getifaddrs
     struct ifaddrs* ifaddr = nullptr;
     if (getifaddrs(&ifaddr) == -1) {
-        return;
+        return false;
     }
     
-    std::map<std::string, InterfaceInfo> current_interfaces;
+    std::map<std::string, DefaultRoute> default_routes = read_default_routes();
     
+    // getifaddrs lists each address separately; merge them per interface name
     for (struct ifaddrs* ifa = ifaddr; ifa != nullptr; ifa = ifa->ifa_next) {
         if (ifa->ifa_addr == nullptr) continue;
-        if (ifa->ifa_addr->sa_family != AF_INET) continue;
+        int family = ifa->ifa_addr->sa_family;
+        if (family != AF_INET && family != AF_INET6) continue;
         
-        struct sockaddr_in* sin = reinterpret_cast<struct sockaddr_in*>(ifa->ifa_addr);
-        char ip_str[INET_ADDRSTRLEN];
-        inet_ntop(AF_INET, &sin->sin_addr, ip_str, INET_ADDRSTRLEN);
+        if (family == AF_INET6) {
+            struct sockaddr_in6* sin6 = reinterpret_cast<struct sockaddr_in6*>(ifa->ifa_addr);
+            if (IN6_IS_ADDR_LINKLOCAL(&sin6->sin6_addr)) {
+                continue; // fe80::/10 needs a scope id to be usable
+            }
+        }
         
-        InterfaceInfo info;
+        InterfaceInfo& info = current_interfaces[ifa->ifa_name];
         info.name = ifa->ifa_name;
-        info.ip = ip_str;
-        if (ifa->ifa_netmask) {
-            struct sockaddr_in* mask = reinterpret_cast<struct sockaddr_in*>(ifa->ifa_netmask);
-            char mask_str[INET_ADDRSTRLEN];
-            inet_ntop(AF_INET, &mask->sin_addr, mask_str, INET_ADDRSTRLEN);
-            info.netmask = mask_str;
+        auto route_it = default_routes.find(info.name);
+        if (route_it != default_routes.end()) {
+            info.metric = route_it->second.metric;
+            info.gateway = route_it->second.gateway;
+        }
+        
+        if (family == AF_INET && info.ip.empty()) {
+            struct sockaddr_in* sin = reinterpret_cast<struct sockaddr_in*>(ifa->ifa_addr);
+            char ip_str[INET_ADDRSTRLEN];
+            inet_ntop(AF_INET, &sin->sin_addr, ip_str, INET_ADDRSTRLEN);
+            info.ip = ip_str;
+            if (ifa->ifa_netmask) {
+                struct sockaddr_in* mask = reinterpret_cast<struct sockaddr_in*>(ifa->ifa_netmask);
+                char mask_str[INET_ADDRSTRLEN];
+                inet_ntop(AF_INET, &mask->sin_addr, mask_str, INET_ADDRSTRLEN);
+                info.netmask = mask_str;
+            }
+        } else if (family == AF_INET6 && info.ipv6.empty()) {
+            struct sockaddr_in6* sin6 = reinterpret_cast<struct sockaddr_in6*>(ifa->ifa_addr);
+            char ip_str[INET6_ADDRSTRLEN];
+            inet_ntop(AF_INET6, &sin6->sin6_addr, ip_str, INET6_ADDRSTRLEN);
+            info.ipv6 = ip_str;
         }
-        info.last_seen = get_current_time();
-        current_interfaces[info.name] = info;
     }
     
     freeifaddrs(ifaddr);
-    interface_info_ = current_interfaces;
 #endif
+    
+    for (const auto& pair : current_interfaces) {
+        found.push_back(pair.second);
+    }
+    return true;
 }
 
-void RunwayManager::refresh_interfaces() {
-    std::map<std::string, InterfaceInfo> old_interfaces = interface_info_;
+bool RunwayManager::refresh_interfaces() {
+    std::map<std::string, InterfaceInfo> old_interfaces;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        old_interfaces = interface_info_;
+    }
     discover_interfaces();
     
-    // Log changes (defensive: check terminal before logging)
-    for (const auto& pair : interface_info_) {
-        if (old_interfaces.find(pair.first) == old_interfaces.end()) {
-            // New interface
+    std::vector<InterfaceEvent> events;
+    std::vector<InterfaceEventCallback> listeners;
+    bool egress_changed = false;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& pair : interface_info_) {
+            auto old_it = old_interfaces.find(pair.first);
+            if (old_it == old_interfaces.end()) {
+                events.push_back(InterfaceEvent(InterfaceEvent::Type::Added, pair.second));
+            } else if (old_it->second.ip != pair.second.ip || old_it->second.ipv6 != pair.second.ipv6) {
+                events.push_back(InterfaceEvent(InterfaceEvent::Type::AddressChanged, pair.second));
+            }
+        }
+        
+        for (const auto& pair : old_interfaces) {
+            if (interface_info_.find(pair.first) == interface_info_.end()) {
+                events.push_back(InterfaceEvent(InterfaceEvent::Type::Removed, pair.second));
+            }
+        }
+        
+        // Resolutions made over a path that changed or vanished may be stale (roaming, VPN down)
+        for (const auto& event : events) {
+            if (event.type == InterfaceEvent::Type::Added) {
+                continue;
+            }
+            egress_changed = true;
+            for (const auto& runway_pair : runways_) {
+                if (runway_pair.second->interface_name == event.name) {
+                    runway_pair.second->resolved_ip.clear();
+                }
+            }
         }
+        
+        for (const auto& pair : interface_listeners_) {
+            listeners.push_back(pair.second);
+        }
+    }
+    
+    // The DNS cache is shared by all runways, so it is purged as a whole
+    if (egress_changed && dns_resolver_) {
+        size_t dropped = dns_resolver_->clear_cache();
+        Logger::instance().log(LogLevel::INFO, "Interface address changed, purged " + std::to_string(dropped) +
+                               " cached DNS resolutions");
     }
     
-    for (const auto& pair : old_interfaces) {
-        if (interface_info_.find(pair.first) == interface_info_.end()) {
-            // Removed interface
+    // Invoke outside the lock so listeners may call back into the manager
+    for (const auto& event : events) {
+        const char* what = event.type == InterfaceEvent::Type::Added ? "added" :
+                           event.type == InterfaceEvent::Type::Removed ? "removed" : "address changed";
+        Logger::instance().log(LogLevel::INFO, "Interface " + std::string(what) + ": " + event.name +
+                               (event.ip.empty() ? "" : " " + event.ip) + (event.ipv6.empty() ? "" : " " + event.ipv6));
+        for (const auto& listener : listeners) {
+            listener(event);
         }
     }
+    return !events.empty();
+}
+
+size_t RunwayManager::add_interface_listener(InterfaceEventCallback callback) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    size_t listener_id = next_listener_id_++;
+    interface_listeners_[listener_id] = callback;
+    return listener_id;
+}
+
+void RunwayManager::remove_interface_listener(size_t listener_id) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    interface_listeners_.erase(listener_id);
 }
 
 std::vector<std::shared_ptr<Runway>> RunwayManager::discover_runways() {
@@ -158,46 +382,327 @@ std::vector<std::shared_ptr<Runway>> RunwayManager::discover_runways() {
         }
     }
     
+    // Runways whose (interface, source address, proxy, DNS server) combination survives keep
+    // their object and id, so tracker metrics stay attached; new combinations get fresh ids
+    std::map<std::string, std::shared_ptr<Runway>> previous; // runway_key -> runway
+    for (const auto& pair : runways_) {
+        const auto& r = pair.second;
+        previous[runway_key(r->interface_name, r->source_ip, r->upstream_proxy, r->dns_server)] = r;
+    }
+    runways_.clear();
+    
     std::vector<std::shared_ptr<Runway>> runways;
-    size_t runway_id_counter = 0;
     
-    // Create direct runways (no upstream proxy)
+    // Each interface contributes one source address per family it has (IPv4, IPv6);
+    // IPv6 runway ids carry a "_v6" suffix on the interface name
+    struct Source {
+        std::string iface;
+        std::string label;
+        std::string ip;
+        uint32_t metric;
+    };
+    std::vector<Source> sources;
     for (const auto& iface : interfaces_to_use) {
         const auto& info = interface_info_[iface];
+        if (!info.ip.empty()) {
+            sources.push_back(Source{iface, iface, info.ip, info.metric});
+        }
+        if (!info.ipv6.empty()) {
+            sources.push_back(Source{iface, iface + "_v6", info.ipv6, info.metric});
+        }
+    }
+    
+    // Preferred interfaces first, so a max_runways cap drops the least preferred combinations
+    std::stable_sort(sources.begin(), sources.end(),
+                     [](const Source& a, const Source& b) { return a.metric < b.metric; });
+    size_t skipped = 0;
+    auto at_cap = [&]() {
+        if (max_runways_ > 0 && runways.size() >= max_runways_) {
+            skipped++;
+            return true;
+        }
+        return false;
+    };
+    
+    // Create direct runways (no upstream proxy)
+    for (const auto& source : sources) {
         for (const auto& dns_server : dns_servers_) {
-            std::ostringstream oss;
-            oss << "direct_" << iface << "_" << dns_server->config.host << "_" << runway_id_counter++;
-            std::string runway_id = oss.str();
-            
-            auto runway = std::make_shared<Runway>(
-                runway_id, iface, info.ip, nullptr, dns_server);
+            std::string key = runway_key(source.iface, source.ip, nullptr, dns_server);
+            if (removed_runways_.count(key) || at_cap()) {
+                continue;
+            }
+            auto existing = previous.find(key);
+            auto runway = existing != previous.end() ? existing->second : nullptr;
+            if (!runway) {
+                std::ostringstream oss;
+                oss << "direct_" << source.label << "_" << dns_server->config.host << "_" << next_runway_id_++;
+                runway = std::make_shared<Runway>(
+                    oss.str(), source.iface, source.ip, nullptr, dns_server);
+            }
+            runway->metric = source.metric;
             runways.push_back(runway);
-            runways_[runway_id] = runway;
+            runways_[runway->id] = runway;
         }
     }
     
-    // Create proxy runways (with upstream proxy)
-    for (const auto& iface : interfaces_to_use) {
-        const auto& info = interface_info_[iface];
+    // Create proxy runways (with upstream proxy); a proxy at an address of the other family cannot
+    // be reached from the source. Names are not looked up here, under the lock: a name without an
+    // address in the source's family fails when used, without counting against the proxy
+    for (const auto& source : sources) {
+        int family = source.ip.find(':') != std::string::npos ? AF_INET6 : AF_INET;
         for (const auto& proxy : upstream_proxies_) {
+            if (upstream::has_literal_address(proxy->config) && !upstream::has_address(proxy->config, family)) {
+                continue;
+            }
             for (const auto& dns_server : dns_servers_) {
-                std::ostringstream oss;
-                oss << "proxy_" << iface << "_" << proxy->config.proxy_type 
-                    << "_" << proxy->config.host << "_" << dns_server->config.host 
-                    << "_" << runway_id_counter++;
-                std::string runway_id = oss.str();
-                
-                auto runway = std::make_shared<Runway>(
-                    runway_id, iface, info.ip, proxy, dns_server);
+                if (!pairs_with(proxy->config, dns_server->config)) {
+                    continue;
+                }
+                std::string key = runway_key(source.iface, source.ip, proxy, dns_server);
+                if (removed_runways_.count(key) || at_cap()) {
+                    continue;
+                }
+                auto existing = previous.find(key);
+                auto runway = existing != previous.end() ? existing->second : nullptr;
+                if (!runway) {
+                    std::ostringstream oss;
+                    oss << "proxy_" << source.label << "_" << proxy->config.proxy_type 
+                        << "_" << proxy->config.host << "_" << dns_server->config.host 
+                        << "_" << next_runway_id_++;
+                    runway = std::make_shared<Runway>(
+                        oss.str(), source.iface, source.ip, proxy, dns_server);
+                }
+                runway->metric = source.metric;
                 runways.push_back(runway);
-                runways_[runway_id] = runway;
+                runways_[runway->id] = runway;
             }
         }
     }
     
+    if (skipped > 0) {
+        Logger::instance().log(LogLevel::WARN, "max_runways (" + std::to_string(max_runways_) + ") reached, skipped " +
+                               std::to_string(skipped) + " runway combinations");
+    }
     return runways;
 }
 
+bool RunwayManager::pairs_with(const UpstreamProxyConfig& proxy, const DNSServerConfig& dns_server) {
+    if (proxy.dns_servers.empty()) {
+        return true;
+    }
+    for (const auto& wanted : proxy.dns_servers) {
+        bool name_match = !dns_server.name.empty() && utils::to_lower(wanted) == utils::to_lower(dns_server.name);
+        if (name_match || wanted == dns_server.host ||
+            wanted == dns_server.host + ":" + std::to_string(dns_server.port)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+std::string RunwayManager::runway_key(const std::string& interface_name, const std::string& source_ip,
+                                      const std::shared_ptr<UpstreamProxy>& proxy,
+                                      const std::shared_ptr<DNSServer>& dns_server) {
+    std::string key = interface_name + "|" + source_ip + "|";
+    key += proxy ? upstream::describe(proxy->config) : "direct";
+    if (dns_server) {
+        key += "|" + dns_server->config.host + ":" + std::to_string(dns_server->config.port);
+    }
+    return key;
+}
+
+bool RunwayManager::is_supported_chain(const UpstreamProxyConfig& proxy) {
+    if (!upstream::is_supported_type(proxy.proxy_type) ||
+        (utils::to_lower(proxy.proxy_type) == "https" && !tls::available())) {
+        return false;
+    }
+    for (const auto& hop : proxy.chain) {
+        if (!upstream::is_supported_type(hop.proxy_type) || utils::to_lower(hop.proxy_type) == "https") {
+            return false;
+        }
+    }
+    return true;
+}
+
+std::vector<std::string> RunwayManager::discover_new_runways() {
+    std::set<std::string> existing;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& pair : runways_) {
+            existing.insert(pair.first);
+        }
+    }
+    
+    std::vector<std::string> created;
+    for (const auto& runway : discover_runways()) {
+        if (!existing.count(runway->id)) {
+            created.push_back(runway->id);
+        }
+    }
+    return created;
+}
+
+bool RunwayManager::add_upstream_proxy(const UpstreamProxyConfig& proxy, std::vector<std::string>& new_runway_ids) {
+    if (!is_supported_chain(proxy)) {
+        return false;
+    }
+    
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& existing : upstream_proxies_) {
+            if (utils::to_lower(upstream::describe(existing->config)) == utils::to_lower(upstream::describe(proxy))) {
+                return false;
+            }
+        }
+        upstream_proxies_.push_back(std::make_shared<UpstreamProxy>(proxy));
+    }
+    
+    new_runway_ids = discover_new_runways();
+    Logger::instance().log(LogLevel::INFO, "Added upstream proxy " + upstream::describe(proxy) +
+                           " (" + std::to_string(new_runway_ids.size()) + " runways)");
+    return true;
+}
+
+std::vector<std::string> RunwayManager::remove_upstream_proxy(const std::string& host, uint16_t port) {
+    std::vector<std::string> removed;
+    std::lock_guard<std::mutex> lock(mutex_);
+    
+    auto it = std::find_if(upstream_proxies_.begin(), upstream_proxies_.end(),
+        [&](const std::shared_ptr<UpstreamProxy>& p) { return p->config.host == host && p->config.port == port; });
+    if (it == upstream_proxies_.end()) {
+        return removed;
+    }
+    std::shared_ptr<UpstreamProxy> proxy = *it;
+    upstream_proxies_.erase(it);
+    
+    for (auto runway_it = runways_.begin(); runway_it != runways_.end();) {
+        if (runway_it->second->upstream_proxy == proxy) {
+            removed.push_back(runway_it->first);
+            runway_it = runways_.erase(runway_it);
+        } else {
+            ++runway_it;
+        }
+    }
+    
+    Logger::instance().log(LogLevel::INFO, "Removed upstream proxy " + host + ":" + std::to_string(port) +
+                           " (" + std::to_string(removed.size()) + " runways)");
+    return removed;
+}
+
+bool RunwayManager::add_dns_server(const DNSServerConfig& dns_server, std::vector<std::string>& new_runway_ids) {
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& existing : dns_servers_) {
+            if (existing->config.host == dns_server.host && existing->config.port == dns_server.port) {
+                return false;
+            }
+        }
+        dns_servers_.push_back(std::make_shared<DNSServer>(dns_server));
+    }
+    
+    new_runway_ids = discover_new_runways();
+    Logger::instance().log(LogLevel::INFO, "Added DNS server " + dns_server.host + ":" + std::to_string(dns_server.port) +
+                           " (" + std::to_string(new_runway_ids.size()) + " runways)");
+    return true;
+}
+
+std::vector<std::string> RunwayManager::remove_dns_server(const std::string& host, uint16_t port) {
+    std::vector<std::string> removed;
+    std::lock_guard<std::mutex> lock(mutex_);
+    
+    auto it = std::find_if(dns_servers_.begin(), dns_servers_.end(),
+        [&](const std::shared_ptr<DNSServer>& d) { return d->config.host == host && d->config.port == port; });
+    if (it == dns_servers_.end()) {
+        return removed;
+    }
+    std::shared_ptr<DNSServer> server = *it;
+    dns_servers_.erase(it);
+    
+    for (auto runway_it = runways_.begin(); runway_it != runways_.end();) {
+        if (runway_it->second->dns_server == server) {
+            removed.push_back(runway_it->first);
+            runway_it = runways_.erase(runway_it);
+        } else {
+            ++runway_it;
+        }
+    }
+    
+    Logger::instance().log(LogLevel::INFO, "Removed DNS server " + host + ":" + std::to_string(port) +
+                           " (" + std::to_string(removed.size()) + " runways)");
+    return removed;
+}
+
+bool RunwayManager::test_dns_server(const DNSServerConfig& dns_server, double timeout_secs) {
+    // Name expected to resolve on any working recursive resolver (RFC 2606 reserved domain)
+    static const std::string probe_domain = "example.com";
+    
+    std::shared_ptr<DNSServer> server;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& existing : dns_servers_) {
+            if (existing->config.host == dns_server.host && existing->config.port == dns_server.port) {
+                server = existing;
+                break;
+            }
+        }
+    }
+    if (!server) {
+        return false;
+    }
+    
+    // Query the server directly with the probe timeout; the shared cache must not mask a dead server
+    DNSResolver resolver(std::vector<DNSServerConfig>{server->config}, timeout_secs);
+    std::string ip;
+    double elapsed_ms = 0.0;
+    bool success = resolver.query_server(server->config, probe_domain, false, ip, elapsed_ms);
+    
+    std::lock_guard<std::mutex> lock(mutex_);
+    if (success) {
+        server->response_time = elapsed_ms / 1000.0;
+        server->last_success = get_current_time();
+        server->failure_count = 0;
+    } else {
+        server->failure_count++;
+    }
+    return success;
+}
+
+std::vector<DNSServer> RunwayManager::get_dns_servers() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    std::vector<DNSServer> result;
+    for (const auto& server : dns_servers_) {
+        result.push_back(*server);
+    }
+    return result;
+}
+
+bool RunwayManager::remove_runway(const std::string& runway_id) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    auto it = runways_.find(runway_id);
+    if (it == runways_.end()) {
+        return false;
+    }
+    
+    const auto& r = it->second;
+    removed_runways_.insert(runway_key(r->interface_name, r->source_ip, r->upstream_proxy, r->dns_server));
+    runways_.erase(it);
+    Logger::instance().log(LogLevel::INFO, "Removed runway " + runway_id, {runway_id, ""});
+    return true;
+}
+
+bool RunwayManager::set_runway_enabled(const std::string& runway_id, bool enabled) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    auto it = runways_.find(runway_id);
+    if (it == runways_.end()) {
+        return false;
+    }
+    
+    it->second->enabled = enabled;
+    Logger::instance().log(LogLevel::INFO, std::string(enabled ? "Enabled" : "Disabled") + " runway " + runway_id,
+                           {runway_id, ""});
+    return true;
+}
+
 std::shared_ptr<Runway> RunwayManager::get_runway(const std::string& runway_id) {
     std::lock_guard<std::mutex> lock(mutex_);
     auto it = runways_.find(runway_id);
@@ -207,52 +712,72 @@ std::shared_ptr<Runway> RunwayManager::get_runway(const std::string& runway_id)
     return nullptr;
 }
 
-std::vector<std::shared_ptr<Runway>> RunwayManager::get_all_runways() {
+std::vector<std::shared_ptr<Runway>> RunwayManager::get_all_runways(bool include_disabled) {
     std::lock_guard<std::mutex> lock(mutex_);
     std::vector<std::shared_ptr<Runway>> result;
     for (const auto& pair : runways_) {
+        if (include_disabled || pair.second->enabled) {
+            result.push_back(pair.second);
+        }
+    }
+    return result;
+}
+
+std::vector<InterfaceInfo> RunwayManager::get_interfaces() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    std::vector<InterfaceInfo> result;
+    for (const auto& pair : interface_info_) {
         result.push_back(pair.second);
     }
     return result;
 }
 
 std::tuple<bool, bool, double> RunwayManager::test_runway_accessibility(
-    const std::string& target, std::shared_ptr<Runway> runway, double timeout_secs) {
+    const std::string& target, std::shared_ptr<Runway> runway, double timeout_secs, uint16_t target_port) {
     
-    // Resolve target if needed
+    // Resolve target if needed (AAAA for IPv6 direct runways; proxies are reached over
+    // the runway's family but choose their own egress). Proxies that resolve remotely
+    // get the host name, so probes leak no more DNS than real traffic does
     std::string resolved_ip;
-    if (dns_resolver_->is_ip_address(target) || dns_resolver_->is_private_ip(target)) {
+    bool remote_dns = runway->upstream_proxy &&
+                      upstream::resolves_remotely(upstream::exit_hop(runway->upstream_proxy->config));
+    if (remote_dns || dns_resolver_->is_ip_address(target) || dns_resolver_->is_private_ip(target)) {
         resolved_ip = target;
     } else {
-        auto result = dns_resolver_->resolve(target);
+        auto result = dns_resolver_->resolve(target, runway->is_direct && runway->is_ipv6());
         if (result.first.empty()) {
             return std::make_tuple(false, false, 0.0);
         }
         resolved_ip = result.first;
     }
     
-    // Test connection
+    // Test connection (inaccessible proxies are still probed so they can recover)
+    Semaphore::Guard slot(probe_slots_);
     bool network_success = false;
-    if (runway->upstream_proxy && runway->upstream_proxy->accessible) {
-        network_success = test_proxy_connection(runway, resolved_ip, timeout_secs);
+    double response_time = 0.0;
+    if (runway->upstream_proxy) {
+        network_success = test_proxy_connection(runway, resolved_ip, target_port, timeout_secs, response_time);
     } else {
-        network_success = test_direct_connection(runway, resolved_ip, timeout_secs);
+        network_success = test_direct_connection(runway, resolved_ip, target_port, timeout_secs, response_time);
     }
     
-    double response_time = 0.0; // Simplified
     bool user_success = network_success; // Simplified for now
     return std::make_tuple(network_success, user_success, response_time);
 }
 
 bool RunwayManager::test_direct_connection(
-    std::shared_ptr<Runway> runway, const std::string& target_ip, double timeout_secs) {
+    std::shared_ptr<Runway> runway, const std::string& target_ip, uint16_t target_port, double timeout_secs,
+    double& elapsed_secs) {
     
-    std::lock_guard<std::mutex> lock(mutex_);
-    if (interface_info_.find(runway->interface_name) == interface_info_.end()) {
-        return false;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        if (interface_info_.find(runway->interface_name) == interface_info_.end()) {
+            return false;
+        }
     }
     
-    socket_t sock = network::create_tcp_socket();
+    int family = runway->is_ipv6() ? AF_INET6 : AF_INET;
+    socket_t sock = network::create_tcp_socket(family);
     if (sock == network::INVALID_SOCKET_VALUE) {
         return false;
     }
@@ -270,21 +795,30 @@ bool RunwayManager::test_direct_connection(
     setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
 #endif
     
-    bool success = network::connect_socket(sock, target_ip, 80);
+    // Probe the path through this runway's interface, not the OS default route
+    if (!runway->source_ip.empty() && !network::bind_socket(sock, runway->source_ip, 0)) {
+        network::close_socket(sock);
+        return false;
+    }
+    
+    auto start = std::chrono::steady_clock::now();
+    bool success = network::connect_socket(sock, target_ip, target_port, probe_connect_timeout_ms(timeout_secs));
+    elapsed_secs = std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
     network::close_socket(sock);
     return success;
 }
 
 bool RunwayManager::test_proxy_connection(
-    std::shared_ptr<Runway> runway, const std::string& /*target_ip*/, double timeout_secs) {
+    std::shared_ptr<Runway> runway, const std::string& target_ip, uint16_t target_port, double timeout_secs,
+    double& elapsed_secs) {
     
-    if (!runway->upstream_proxy || !runway->upstream_proxy->accessible) {
+    if (!runway->upstream_proxy) {
         return false;
     }
     
-    // Simplified proxy test - would need full HTTP CONNECT or proxy protocol
-    // For now, just test if we can connect to the proxy
-    socket_t sock = network::create_tcp_socket();
+    // Connect to the proxy and, for tunnelling proxies, open a tunnel to the target
+    int family = runway->is_ipv6() ? AF_INET6 : AF_INET;
+    socket_t sock = network::create_tcp_socket(family);
     if (sock == network::INVALID_SOCKET_VALUE) {
         return false;
     }
@@ -301,9 +835,67 @@ bool RunwayManager::test_proxy_connection(
     setsockopt(sock, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
 #endif
     
-    bool success = network::connect_socket(sock, 
-                                           runway->upstream_proxy->config.host,
-                                           runway->upstream_proxy->config.port);
+    if (!runway->source_ip.empty() && !network::bind_socket(sock, runway->source_ip, 0)) {
+        network::close_socket(sock);
+        return false;
+    }
+    
+    // Measure the proxy, not the target's HTTP behaviour: a tunnel that opens is success.
+    // Chains are walked hop by hop and the exit hop is probed the same way.
+    const UpstreamProxyConfig& proxy = runway->upstream_proxy->config;
+    const UpstreamProxyConfig& exit_hop = upstream::exit_hop(proxy);
+    bool success = false;
+    auto start = std::chrono::steady_clock::now();
+    bool proxy_reached = upstream::connect_to_proxy(sock, proxy, family, probe_connect_timeout_ms(timeout_secs)) &&
+                         upstream::open_chain(sock, proxy);
+    if (proxy_reached) {
+        if (upstream::is_http_type(exit_hop.proxy_type)) {
+            success = upstream::http_connect(sock, target_ip, target_port, upstream::proxy_authorization(exit_hop));
+        } else {
+            success = upstream::open_tunnel(sock, exit_hop, target_ip, target_port);
+        }
+    }
+    elapsed_secs = std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
     network::close_socket(sock);
+    // As for requests: a refused tunnel may be the target's fault, so the proxy's health only
+    // depends on whether it answered (its latency is kept from complete tunnels)
+    if (proxy_reached) {
+        record_proxy_result(runway->upstream_proxy, true, success ? elapsed_secs : 0.0);
+    } else if (upstream::has_address(proxy, family)) {
+        record_proxy_result(runway->upstream_proxy, false);
+    }
     return success;
 }
+
+void RunwayManager::set_proxy_failure_threshold(uint32_t threshold) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    proxy_failure_threshold_ = threshold > 0 ? threshold : 1;
+}
+
+void RunwayManager::record_proxy_result(std::shared_ptr<UpstreamProxy> proxy, bool success, double response_time_secs) {
+    if (!proxy) {
+        return;
+    }
+    
+    std::lock_guard<std::mutex> lock(mutex_);
+    std::string name = upstream::describe(proxy->config);
+    if (success) {
+        if (!proxy->accessible) {
+            Logger::instance().log(LogLevel::INFO, "Upstream proxy " + name + " is accessible again");
+        }
+        proxy->accessible = true;
+        proxy->failure_count = 0;
+        proxy->last_success = get_current_time();
+        if (response_time_secs > 0.0) {
+            proxy->response_time = response_time_secs;
+        }
+        return;
+    }
+    
+    proxy->failure_count++;
+    if (proxy->accessible && proxy->failure_count >= proxy_failure_threshold_) {
+        proxy->accessible = false;
+        Logger::instance().log(LogLevel::WARN, "Upstream proxy " + name + " marked inaccessible after " +
+                               std::to_string(proxy->failure_count) + " consecutive failures");
+    }
+}
@@ -4,9 +4,12 @@
 #include <string>
 #include <vector>
 #include <map>
+#include <set>
 #include <memory>
 #include <mutex>
+#include <functional>
 #include "runway.h"
+#include "semaphore.h"
 #include "config.h"
 #include "dns.h"
 
@@ -16,54 +19,186 @@
 
 struct InterfaceInfo {
     std::string name;
-    std::string ip;
+    std::string ip; // IPv4 address (empty if none)
     std::string netmask;
+    std::string ipv6; // Global IPv6 address (empty if none; link-local addresses are skipped)
+    uint32_t metric; // From interface_metrics, else the OS default-route metric; lower is preferred
+                     // (UINT32_MAX without a default route: a last resort, never preferred over a routed interface)
+    std::string gateway; // IPv4 default gateway (best effort: Linux and Windows; empty if unknown)
     uint64_t last_seen; // Unix timestamp
     
-    InterfaceInfo() : last_seen(0) {}
+    InterfaceInfo() : metric(UINT32_MAX), last_seen(0) {}
 };
 
+// Interface change detected by refresh_interfaces()
+struct InterfaceEvent {
+    enum class Type {
+        Added,
+        Removed,
+        AddressChanged
+    };
+    
+    Type type;
+    std::string name;
+    std::string ip; // IPv4 address (for Removed: the last known address)
+    std::string ipv6;
+    
+    InterfaceEvent() : type(Type::Added) {}
+    InterfaceEvent(Type type, const InterfaceInfo& info)
+        : type(type), name(info.name), ip(info.ip), ipv6(info.ipv6) {}
+};
+
+using InterfaceEventCallback = std::function<void(const InterfaceEvent& event)>;
+
 class RunwayManager {
 public:
     RunwayManager(const std::vector<std::string>& interfaces,
                   const std::vector<UpstreamProxyConfig>& upstream_proxies,
                   const std::vector<DNSServerConfig>& dns_servers,
-                  std::shared_ptr<DNSResolver> dns_resolver);
+                  std::shared_ptr<DNSResolver> dns_resolver,
+                  uint32_t proxy_failure_threshold = 3,
+                  const std::vector<std::string>& interface_exclude = {},
+                  double connect_timeout_secs = 5.0,
+                  const std::map<std::string, uint32_t>& interface_metrics = {},
+                  size_t max_runways = 0,
+                  size_t max_concurrent_probes = 0);
     
     ~RunwayManager();
     
     // Discover available network interfaces
     void discover_interfaces();
     
-    // Refresh interface information
-    void refresh_interfaces();
+    // Take found as the available interfaces instead of asking the OS, filtered and given metrics the
+    // same way (e.g. fixed loopback addresses in tests); the next discover or refresh asks the OS again
+    void set_interfaces(const std::vector<InterfaceInfo>& found);
+    
+    // Refresh interface information, notifying interface listeners of each change
+    // Returns true if an interface was added or removed or its addresses changed
+    // A changed or removed interface also purges the DNS cache and its runways' resolved_ip
+    bool refresh_interfaces();
+    
+    // Subscribe to interface events (fired outside the manager lock, on the refreshing thread)
+    // Returns an id for remove_interface_listener()
+    size_t add_interface_listener(InterfaceEventCallback callback);
+    
+    void remove_interface_listener(size_t listener_id);
     
     // Discover all possible runway combinations
+    // Surviving runways keep their ids; runways whose interface is gone are dropped
+    // Proxies are only paired with their configured dns_servers, and at most max_runways are kept
     std::vector<std::shared_ptr<Runway>> discover_runways();
     
     // Get runway by ID
     std::shared_ptr<Runway> get_runway(const std::string& runway_id);
     
-    // Get all runways
-    std::vector<std::shared_ptr<Runway>> get_all_runways();
+    // Get all runways (disabled runways only if include_disabled)
+    std::vector<std::shared_ptr<Runway>> get_all_runways(bool include_disabled = false);
     
-    // Test runway accessibility
+    // Snapshot of the discovered interfaces (excluded interfaces are not listed)
+    std::vector<InterfaceInfo> get_interfaces();
+    
+    // Test runway accessibility by connecting to target_port (the port the target is served on)
+    // At most max_concurrent_probes probes connect at once, whichever subsystem started them
     // Returns (network_success, user_success, response_time_secs)
     std::tuple<bool, bool, double> test_runway_accessibility(
-        const std::string& target, std::shared_ptr<Runway> runway, double timeout_secs);
+        const std::string& target, std::shared_ptr<Runway> runway, double timeout_secs,
+        uint16_t target_port = 80);
+    
+    // Runtime changes (no restart needed); runways are rebuilt, keeping surviving ids
+    // Returns false if the proxy type is unsupported or the proxy is already configured;
+    // new_runway_ids receives the ids of the runways created for it
+    bool add_upstream_proxy(const UpstreamProxyConfig& proxy, std::vector<std::string>& new_runway_ids);
+    
+    // Remove a proxy (matched by host and port) and its runways
+    // Returns the removed runway ids so callers can drop their tracker metrics
+    std::vector<std::string> remove_upstream_proxy(const std::string& host, uint16_t port);
+    
+    // Returns false if a server with the same host and port is already configured;
+    // new_runway_ids receives the ids of the runways created for it
+    bool add_dns_server(const DNSServerConfig& dns_server, std::vector<std::string>& new_runway_ids);
+    
+    // Remove a DNS server (matched by host and port) and its runways
+    // Returns the removed runway ids so callers can drop their tracker metrics
+    std::vector<std::string> remove_dns_server(const std::string& host, uint16_t port);
+    
+    // Remove a runway; its combination stays removed across re-discovery
+    // Returns false if no runway has this id
+    bool remove_runway(const std::string& runway_id);
+    
+    // Take a runway out of rotation (or put it back) without removing it; survives re-discovery
+    // Returns false if no runway has this id
+    bool set_runway_enabled(const std::string& runway_id, bool enabled);
+    
+    // Test one DNS server in isolation with a lookup of a known-good name through it alone,
+    // recording latency/failures onto its DNSServer entry. Returns false if it failed or is unknown
+    bool test_dns_server(const DNSServerConfig& dns_server, double timeout_secs);
+    
+    // Snapshot of the DNS servers and their recorded health
+    std::vector<DNSServer> get_dns_servers();
+    
+    // Consecutive failures before an upstream proxy is marked inaccessible (at least 1)
+    void set_proxy_failure_threshold(uint32_t threshold);
+    
+    // The resolver runways look targets up with (its cache is shared by all runways)
+    std::shared_ptr<DNSResolver> get_dns_resolver() const { return dns_resolver_; }
+    
+    // Record the outcome of using an upstream proxy
+    // Marks it inaccessible after proxy_failure_threshold consecutive failures;
+    // a single success restores it
+    // response_time_secs (if > 0) is kept as the proxy's latency on success
+    void record_proxy_result(std::shared_ptr<UpstreamProxy> proxy, bool success, double response_time_secs = 0.0);
     
 private:
     std::vector<std::string> interfaces_;
+    std::vector<std::string> interface_exclude_;
+    std::map<std::string, uint32_t> interface_metrics_; // Name pattern -> configured metric
     std::vector<std::shared_ptr<UpstreamProxy>> upstream_proxies_;
     std::vector<std::shared_ptr<DNSServer>> dns_servers_;
     std::shared_ptr<DNSResolver> dns_resolver_;
+    uint32_t proxy_failure_threshold_;
+    double connect_timeout_secs_; // Caps the handshake part of each probe
+    size_t max_runways_; // 0 = unlimited
+    Semaphore probe_slots_; // Shared by every accessibility probe; limit 0 = unlimited
+    size_t next_runway_id_; // Runway id suffix, never reused so ids stay unique across re-discovery
+    std::set<std::string> removed_runways_; // runway_key() of runways removed at runtime
+    std::map<size_t, InterfaceEventCallback> interface_listeners_;
+    size_t next_listener_id_;
     std::map<std::string, std::shared_ptr<Runway>> runways_;
     std::map<std::string, InterfaceInfo> interface_info_;
     std::mutex mutex_;
     
     uint64_t get_current_time() const;
-    bool test_direct_connection(std::shared_ptr<Runway> runway, const std::string& target_ip, double timeout_secs);
-    bool test_proxy_connection(std::shared_ptr<Runway> runway, const std::string& target_ip, double timeout_secs);
+    // discover_runways(), returning the ids it created
+    std::vector<std::string> discover_new_runways();
+    int probe_connect_timeout_ms(double timeout_secs) const;
+    // Identity of a runway's combination, stable across re-discovery
+    static std::string runway_key(const std::string& interface_name, const std::string& source_ip,
+                                  const std::shared_ptr<UpstreamProxy>& proxy,
+                                  const std::shared_ptr<DNSServer>& dns_server);
+    // Configured metric for an interface (exact name first, then '*' patterns), else os_metric
+    uint32_t interface_metric(const std::string& name, uint32_t os_metric) const;
+    struct DefaultRoute {
+        std::string gateway;
+        uint32_t metric;
+    };
+    // Default route (lowest metric) per interface from the OS routing table (Linux; empty elsewhere)
+    static std::map<std::string, DefaultRoute> read_default_routes();
+    // Every interface with an address, with the OS default-route metric (not yet filtered or configured)
+    // Returns false if the OS could not list them
+    static bool list_interfaces(std::vector<InterfaceInfo>& found);
+    // True if the proxy may be paired with the DNS server (its dns_servers names the server, or is empty)
+    static bool pairs_with(const UpstreamProxyConfig& proxy, const DNSServerConfig& dns_server);
+    // True if the proxy and every hop of its chain use a supported type
+    // (https needs a TLS build and is only allowed on the entry proxy)
+    static bool is_supported_chain(const UpstreamProxyConfig& proxy);
+    // True if an interface is dropped from discovery: it matches interface_exclude
+    // and is not named explicitly in the interfaces list
+    bool is_excluded_interface(const std::string& name) const;
+    // Probes; elapsed_secs receives the time to connect (and open the tunnel) on success
+    bool test_direct_connection(std::shared_ptr<Runway> runway, const std::string& target_ip,
+                                uint16_t target_port, double timeout_secs, double& elapsed_secs);
+    bool test_proxy_connection(std::shared_ptr<Runway> runway, const std::string& target_ip,
+                               uint16_t target_port, double timeout_secs, double& elapsed_secs);
 };
 
 #endif // RUNWAY_MANAGER_H
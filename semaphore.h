@@ -0,0 +1,53 @@
+#ifndef SEMAPHORE_H
+#define SEMAPHORE_H
+
+#include <cstddef>
+#include <mutex>
+#include <condition_variable>
+
+// Counting semaphore (C++17 has none); a limit of 0 means unlimited
+class Semaphore {
+public:
+    explicit Semaphore(size_t limit) : limit_(limit), in_use_(0) {}
+    
+    // Block until a permit is free, then take it
+    void acquire() {
+        if (limit_ == 0) {
+            return;
+        }
+        std::unique_lock<std::mutex> lock(mutex_);
+        available_.wait(lock, [this]() { return in_use_ < limit_; });
+        ++in_use_;
+    }
+    
+    void release() {
+        if (limit_ == 0) {
+            return;
+        }
+        {
+            std::lock_guard<std::mutex> lock(mutex_);
+            --in_use_;
+        }
+        available_.notify_one();
+    }
+    
+    // Holds one permit for its lifetime
+    class Guard {
+    public:
+        explicit Guard(Semaphore& semaphore) : semaphore_(semaphore) { semaphore_.acquire(); }
+        ~Guard() { semaphore_.release(); }
+        Guard(const Guard&) = delete;
+        Guard& operator=(const Guard&) = delete;
+    
+    private:
+        Semaphore& semaphore_;
+    };
+
+private:
+    const size_t limit_;
+    size_t in_use_;
+    std::mutex mutex_;
+    std::condition_variable available_;
+};
+
+#endif // SEMAPHORE_H
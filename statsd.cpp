@@ -0,0 +1,111 @@
+#include "statsd.h"
+#include "utils.h"
+#include <sstream>
+#include <random>
+#include <cstring>
+
+static std::string state_name(RunwayState state) {
+    switch (state) {
+        case RunwayState::Unknown: return "unknown";
+        case RunwayState::Accessible: return "accessible";
+        case RunwayState::PartiallyAccessible: return "partially_accessible";
+        case RunwayState::Inaccessible: return "inaccessible";
+        case RunwayState::Testing: return "testing";
+    }
+    return "unknown";
+}
+
+// Tag values cannot contain the separators of the DogStatsD line format
+static std::string tag_value(const std::string& value) {
+    std::string escaped = value;
+    for (char& c : escaped) {
+        if (c == ',' || c == '|' || c == '#' || c == '\n') {
+            c = '_';
+        }
+    }
+    return escaped;
+}
+
+StatsdEmitter::StatsdEmitter(const std::string& endpoint, const std::string& prefix, double sample_rate, bool dogstatsd)
+    : prefix_(prefix)
+    , sample_rate_(sample_rate)
+    , dogstatsd_(dogstatsd)
+    , valid_(false)
+    , sock_(network::INVALID_SOCKET_VALUE) {
+    std::memset(&address_, 0, sizeof(address_));
+    
+    std::string host = endpoint;
+    uint16_t port = 8125;
+    size_t colon_pos = endpoint.rfind(':');
+    if (colon_pos != std::string::npos) {
+        host = endpoint.substr(0, colon_pos);
+        if (!utils::safe_str_to_uint16(endpoint.substr(colon_pos + 1), port) || port == 0) {
+            return;
+        }
+    }
+    std::string ip = host;
+    if (host.empty() || (!utils::is_valid_ipv4(ip) && !network::resolve_hostname(host, ip)) ||
+        !network::ip_to_sockaddr(ip, port, address_)) {
+        return;
+    }
+    sock_ = network::create_udp_socket();
+    if (sock_ == network::INVALID_SOCKET_VALUE) {
+        return;
+    }
+    // Metrics must never hold up a request: a full send buffer drops the datagram instead
+    network::set_nonblocking(sock_);
+    valid_ = true;
+}
+
+StatsdEmitter::~StatsdEmitter() {
+    if (sock_ != network::INVALID_SOCKET_VALUE) {
+        network::close_socket(sock_);
+    }
+}
+
+void StatsdEmitter::record(const RequestEvent& event) {
+    if (!valid_) {
+        return;
+    }
+    
+    // One decision per event, so a request's counter and timers are sampled together
+    if (sample_rate_ < 1.0) {
+        static thread_local std::mt19937 generator(std::random_device{}());
+        if (std::uniform_real_distribution<double>(0.0, 1.0)(generator) >= sample_rate_) {
+            return;
+        }
+    }
+    std::ostringstream rate;
+    if (sample_rate_ < 1.0) {
+        rate << "|@" << sample_rate_;
+    }
+    
+    std::ostringstream oss;
+    if (event.type == RequestEvent::Type::StateChanged) {
+        oss << prefix_ << ".runway.state_changes:1|c" << rate.str();
+        if (dogstatsd_) {
+            oss << "|#runway:" << tag_value(event.runway_id) << ",state:" << state_name(event.state);
+        }
+        send(oss.str());
+        return;
+    }
+    
+    std::string tags;
+    if (dogstatsd_) {
+        tags = "|#outcome:" + std::string(event.success ? "success" : "failure") +
+               ",status:" + std::to_string(event.status_code) +
+               (event.runway_id.empty() ? "" : ",runway:" + tag_value(event.runway_id));
+    }
+    oss << prefix_ << ".requests." << (event.success ? "success" : "failure") << ":1|c" << rate.str() << tags << "\n";
+    oss << prefix_ << ".request.duration:" << static_cast<uint64_t>(event.duration * 1000.0) << "|ms" << rate.str() << tags;
+    if (event.dns_time > 0.0) {
+        oss << "\n" << prefix_ << ".dns.duration:" << static_cast<uint64_t>(event.dns_time * 1000.0) << "|ms" << rate.str() << tags;
+    }
+    send(oss.str());
+}
+
+void StatsdEmitter::send(const std::string& payload) {
+    // Best effort, like StatsD itself: a lost datagram only costs a sample
+    sendto(sock_, payload.data(), static_cast<int>(payload.size()), 0,
+           reinterpret_cast<const struct sockaddr*>(&address_), sizeof(address_));
+}
@@ -0,0 +1,43 @@
+#ifndef STATSD_H
+#define STATSD_H
+
+#include <string>
+#include <cstdint>
+#include "proxy.h"
+#include "network.h"
+
+// Optional StatsD push of request metrics, an alternative to scraping /metrics
+// Sends UDP datagrams (one per sampled request, metrics separated by newlines) to statsd_endpoint:
+//   <prefix>.requests.success / .failure    counters, one per proxied request
+//   <prefix>.request.duration               timer (ms), whole request
+//   <prefix>.dns.duration                   timer (ms), DNS lookups of the request (only when one was made)
+//   <prefix>.runway.state_changes           counter, runway state changes caused by real traffic
+// In DogStatsD mode, outcome, status code and runway are sent as tags instead of being left out
+
+class StatsdEmitter {
+public:
+    // endpoint: host[:port] (port 8125 by default); sample_rate: share of requests sent (0 < rate <= 1),
+    // declared with @rate so the server scales the counts back up
+    StatsdEmitter(const std::string& endpoint, const std::string& prefix, double sample_rate, bool dogstatsd);
+    ~StatsdEmitter();
+    StatsdEmitter(const StatsdEmitter&) = delete;
+    StatsdEmitter& operator=(const StatsdEmitter&) = delete;
+    
+    // Returns false if the endpoint could not be parsed or resolved
+    bool is_valid() const { return valid_; }
+    
+    // Send the metrics of one request event (a ProxyServer listener; non-blocking)
+    void record(const RequestEvent& event);
+
+private:
+    std::string prefix_;
+    double sample_rate_;
+    bool dogstatsd_;
+    bool valid_;
+    socket_t sock_;
+    struct sockaddr_in address_;
+    
+    void send(const std::string& payload);
+};
+
+#endif // STATSD_H
@@ -0,0 +1,100 @@
+#include "table.h"
+#include "utils.h"
+#include <algorithm>
+
+Table::Table(const std::vector<std::string>& columns, bool upper_case_header)
+    : columns_(columns)
+    , upper_case_header_(upper_case_header) {
+}
+
+void Table::add_row(const std::vector<std::string>& cells) {
+    std::vector<std::string> row(cells.begin(), cells.begin() + static_cast<std::ptrdiff_t>(std::min(cells.size(), columns_.size())));
+    row.resize(columns_.size());
+    rows_.push_back(row);
+}
+
+bool Table::is_numeric_column(size_t column) const {
+    bool any = false;
+    for (const auto& row : rows_) {
+        const std::string& cell = row[column];
+        if (cell.empty() || cell == "-") {
+            continue;
+        }
+        double value = 0.0;
+        if (!utils::safe_str_to_double(cell, value)) {
+            return false;
+        }
+        any = true;
+    }
+    return any;
+}
+
+std::string Table::to_text() const {
+    std::vector<size_t> widths;
+    std::vector<bool> numeric;
+    for (size_t i = 0; i < columns_.size(); ++i) {
+        size_t width = columns_[i].length();
+        for (const auto& row : rows_) {
+            width = std::max(width, row[i].length());
+        }
+        widths.push_back(width);
+        numeric.push_back(is_numeric_column(i));
+    }
+    
+    auto render = [&](const std::vector<std::string>& cells) {
+        std::string line;
+        for (size_t i = 0; i < cells.size(); ++i) {
+            std::string padding(widths[i] - cells[i].length(), ' ');
+            bool last = i + 1 == cells.size();
+            line += numeric[i] ? padding + cells[i] : cells[i] + (last ? "" : padding);
+            if (!last) {
+                line += "  ";
+            }
+        }
+        return line + "\n";
+    };
+    
+    std::vector<std::string> header;
+    for (const auto& column : columns_) {
+        std::string name = column;
+        if (upper_case_header_) {
+            std::transform(name.begin(), name.end(), name.begin(), ::toupper);
+        }
+        header.push_back(name);
+    }
+    std::string text = render(header);
+    for (const auto& row : rows_) {
+        text += render(row);
+    }
+    return text;
+}
+
+static std::string csv_cell(const std::string& cell) {
+    if (cell.find_first_of(",\"\r\n") == std::string::npos) {
+        return cell;
+    }
+    std::string quoted = "\"";
+    for (char c : cell) {
+        quoted += c;
+        if (c == '"') {
+            quoted += '"';
+        }
+    }
+    return quoted + "\"";
+}
+
+std::string Table::to_csv() const {
+    auto render = [](const std::vector<std::string>& cells) {
+        std::string line;
+        for (size_t i = 0; i < cells.size(); ++i) {
+            line += (i > 0 ? "," : "") + csv_cell(cells[i]);
+        }
+        return line + "\r\n";
+    };
+    
+    std::string csv = render(columns_);
+    for (const auto& row : rows_) {
+        csv += render(row);
+    }
+    return csv;
+}
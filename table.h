@@ -0,0 +1,32 @@
+#ifndef TABLE_H
+#define TABLE_H
+
+#include <string>
+#include <vector>
+
+// Rows of CLI output rendered as an aligned text table or as CSV (RFC 4180)
+// Column names are snake_case; the aligned form shows them upper-cased unless they are data
+// themselves (e.g. target names), with upper_case_header false
+
+class Table {
+public:
+    explicit Table(const std::vector<std::string>& columns, bool upper_case_header = true);
+    
+    // Cells beyond the column count are dropped, missing ones are left empty
+    void add_row(const std::vector<std::string>& cells);
+    
+    // Columns padded to their widest cell; numeric columns are right-aligned
+    std::string to_text() const;
+    
+    // Header line, then one line per row; cells with commas, quotes or line breaks are quoted
+    std::string to_csv() const;
+
+private:
+    std::vector<std::string> columns_;
+    bool upper_case_header_;
+    std::vector<std::vector<std::string>> rows_;
+    
+    bool is_numeric_column(size_t column) const;
+};
+
+#endif // TABLE_H
@@ -0,0 +1,189 @@
+#include "test.h"
+#include "test_proxy.h"
+#include "admin.h"
+#include "utils.h"
+#include <chrono>
+#include <cstdlib>
+#include <csignal>
+#include <cstdio>
+#include <fstream>
+#include <sstream>
+
+// Status of "method path" on the admin endpoint with extra header lines (each ending in \r\n), 0 if it
+// did not answer
+static int admin_request(uint16_t port, const std::string& method, const std::string& path,
+                         const std::string& headers = "Host: 127.0.0.1\r\n", const std::string& body = "") {
+    socket_t sock = network::create_tcp_socket();
+    set_recv_timeout(sock, 15);
+    std::string request = method + " " + path + " HTTP/1.1\r\n" + headers + "Content-Length: " +
+                          std::to_string(body.size()) + "\r\nConnection: close\r\n\r\n" + body;
+    std::string response;
+    if (network::connect_socket(sock, "127.0.0.1", port, 2000) &&
+        network::send_data(sock, request.data(), request.size()) == static_cast<ssize_t>(request.size())) {
+        response = recv_all(sock);
+    }
+    network::close_socket(sock);
+    return response.compare(0, 5, "HTTP/") == 0 && response.size() >= 12 ? std::atoi(response.substr(9, 3).c_str()) : 0;
+}
+
+static int admin_get(uint16_t port, const std::string& path) {
+    return admin_request(port, "GET", path);
+}
+
+// A client that sent half a request and went quiet
+static socket_t stalled_client(uint16_t port) {
+    socket_t sock = network::create_tcp_socket();
+    std::string partial = "GET /api/status HTTP/1.1\r\n";
+    if (network::connect_socket(sock, "127.0.0.1", port, 2000)) {
+        network::send_data(sock, partial.data(), partial.size());
+    }
+    return sock;
+}
+
+static double seconds_since(std::chrono::steady_clock::time_point start) {
+    return std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
+}
+
+// Stalled clients hold only their own connection: /healthz and /readyz answer at once (not after
+// the 10 s receive timeout), and stop() does not wait for the stalled clients either
+static void test_stalled_clients() {
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    proxy.config.admin_listen_host = "127.0.0.1";
+    proxy.config.admin_listen_port = free_port();
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, proxy.config.routing_mode);
+    AdminServer admin(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server, proxy.config, "");
+    CHECK(admin.start());
+    uint16_t port = proxy.config.admin_listen_port;
+    
+    socket_t first = stalled_client(port);
+    socket_t second = stalled_client(port);
+    auto start = std::chrono::steady_clock::now();
+    CHECK(admin_get(port, "/healthz") == 200);
+    CHECK(admin_get(port, "/readyz") != 0);
+    CHECK(seconds_since(start) < 3.0);
+    
+    start = std::chrono::steady_clock::now();
+    admin.stop();
+    CHECK(seconds_since(start) < 3.0);
+    network::close_socket(first);
+    network::close_socket(second);
+}
+
+// A web page can send a "simple" cross-site POST (text/plain, with an Origin) without a CORS preflight,
+// or reach the endpoint under its own DNS name (rebinding): neither runs a command
+static void test_cross_site_cli_rejected() {
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    proxy.config.admin_listen_host = "127.0.0.1";
+    proxy.config.admin_listen_port = free_port();
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, RoutingMode::Latency);
+    AdminServer admin(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server, proxy.config, "");
+    CHECK(admin.start());
+    uint16_t port = proxy.config.admin_listen_port;
+    std::string command = "[\"mode\", \"round_robin\"]";
+    
+    CHECK(admin_request(port, "POST", "/cli",
+                        "Host: 127.0.0.1\r\nOrigin: https://evil.example\r\nContent-Type: text/plain\r\n",
+                        command) == 403);
+    CHECK(admin_request(port, "POST", "/cli", "Host: 127.0.0.1\r\nContent-Type: text/plain\r\n", command) == 403);
+    CHECK(admin_request(port, "POST", "/cli",
+                        "Host: 127.0.0.1\r\nOrigin: https://evil.example\r\nContent-Type: application/json\r\n",
+                        command) == 403);
+    CHECK(admin_request(port, "POST", "/cli", "Host: evil.example:2124\r\nContent-Type: application/json\r\n",
+                        command) == 403);
+    CHECK(routing_engine->get_mode() == RoutingMode::Latency);
+    
+    // As the CLI sends it
+    CHECK(admin_request(port, "POST", "/cli", "Host: 127.0.0.1:2124\r\nContent-Type: application/json\r\n",
+                        command) == 200);
+    CHECK(admin_request(port, "POST", "/cli", "Host: localhost\r\nContent-Type: application/json; charset=utf-8\r\n",
+                        command) == 200);
+    CHECK(routing_engine->get_mode() == RoutingMode::RoundRobin);
+    admin.stop();
+}
+
+// The same check covers every route: reloads and API calls as well as reads
+static void test_cross_site_requests_rejected() {
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    proxy.config.admin_listen_host = "127.0.0.1";
+    proxy.config.admin_listen_port = free_port();
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, RoutingMode::Latency);
+    AdminServer admin(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server, proxy.config, "");
+    CHECK(admin.start());
+    uint16_t port = proxy.config.admin_listen_port;
+    std::string simple_post = "Host: 127.0.0.1\r\nOrigin: https://evil.example\r\nContent-Type: text/plain\r\n";
+    
+    CHECK(admin_request(port, "POST", "/reload", simple_post) == 403);
+    CHECK(admin_request(port, "POST", "/api/reload", simple_post) == 403);
+    CHECK(admin_request(port, "POST", "/api/mode", simple_post, "{\"mode\": \"round_robin\"}") == 403);
+    CHECK(admin_request(port, "POST", "/api/reset", "Host: 127.0.0.1\r\n") == 403);
+    CHECK(routing_engine->get_mode() == RoutingMode::Latency);
+    CHECK(admin_request(port, "GET", "/api/status", "Host: 127.0.0.1\r\nOrigin: https://evil.example\r\n") == 403);
+    CHECK(admin_request(port, "GET", "/api/runways", "Host: rebound.example:2124\r\n") == 403);
+    
+    CHECK(admin_get(port, "/api/status") == 200);
+    CHECK(admin_request(port, "POST", "/api/mode", "Host: [::1]:2124\r\nContent-Type: application/json\r\n",
+                        "{\"mode\": \"round_robin\"}") == 200);
+    CHECK(routing_engine->get_mode() == RoutingMode::RoundRobin);
+    admin.stop();
+}
+
+static std::string read_file(const std::string& path) {
+    std::ifstream file(path, std::ios::binary);
+    std::stringstream contents;
+    contents << file.rdbuf();
+    return contents.str();
+}
+
+static void write_file(const std::string& path, const std::string& contents) {
+    std::ofstream file(path, std::ios::binary);
+    file << contents;
+}
+
+// set with persist changes only that key's value in the file's text: keys this version does not know,
+// other settings and the layout survive, a missing file gets just the key, and a broken one is left alone
+static void test_persist_changes_only_its_key() {
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, proxy.config.routing_mode);
+    std::string path = "admin_test_config.json";
+    AdminServer admin(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server, proxy.config, path);
+    
+    std::string original = "{\n"
+                           "    \"future_setting\": {\"nested\": [1, 2]},\n"
+                           "    \"health_probe_timeout\": 2.5,\n"
+                           "    \"dns_timeout\": 9.5\n"
+                           "}\n";
+    write_file(path, original);
+    CHECK(admin.set("health_probe_timeout", "7.5", true).find("\"persisted\":true") != std::string::npos);
+    std::string expected = original;
+    expected.replace(expected.find("2.5"), 3, "7.5");
+    CHECK(read_file(path) == expected);
+    CHECK(!utils::file_exists(path + ".tmp"));
+    
+    // Absent from the file: added after the last member
+    CHECK(admin.set("health_check_concurrency", "2", true).find("\"persisted\":true") != std::string::npos);
+    Config saved = Config::load(path);
+    CHECK(saved.health_check_concurrency == 2);
+    CHECK(saved.health_probe_timeout == 7.5 && saved.dns_timeout == 9.5);
+    CHECK(read_file(path).find("\"future_setting\": {\"nested\": [1, 2]}") != std::string::npos);
+    
+    std::remove(path.c_str());
+    CHECK(admin.set("dns_timeout", "4", true).find("\"persisted\":true") != std::string::npos);
+    CHECK(read_file(path) == "{\n  \"dns_timeout\": 4\n}\n");
+    
+    write_file(path, "{ not json");
+    CHECK(admin.set("dns_timeout", "5", true).find("\"persisted\":false") != std::string::npos);
+    CHECK(read_file(path) == "{ not json");
+    std::remove(path.c_str());
+}
+
+int main() {
+    network::init();
+#ifndef _WIN32
+    signal(SIGPIPE, SIG_IGN); // As main() does: answers to clients that left must not end the process
+#endif
+    test_stalled_clients();
+    test_cross_site_cli_rejected();
+    test_cross_site_requests_rejected();
+    test_persist_changes_only_its_key();
+    return test_result("admin");
+}
@@ -0,0 +1,74 @@
+#include "test.h"
+#include "config.h"
+#include "utils.h"
+#include <cstdio>
+#include <fstream>
+#include <sstream>
+
+static std::string read_file(const std::string& path) {
+    std::ifstream file(path, std::ios::binary);
+    std::ostringstream content;
+    content << file.rdbuf();
+    return content.str();
+}
+
+// save() replaces the file through <path>.tmp and a rename: a write that fails leaves the old file
+// whole, and a successful one leaves no temporary file behind
+static void test_save_is_atomic() {
+    std::string path = "config_test_save.json";
+    std::string tmp_path = path + ".tmp";
+    Config config = Config::parse_json("{\"dns_timeout\": 4.5}");
+    CHECK(config.save(path));
+    CHECK(Config::load(path).dns_timeout == 4.5);
+    CHECK(!utils::file_exists(tmp_path));
+    std::string saved = read_file(path);
+    
+    // The temporary file cannot be written (a directory is in its place)
+    CHECK(utils::create_directory(tmp_path));
+    config.dns_timeout = 9.0;
+    CHECK(!config.save(path));
+    CHECK(read_file(path) == saved);
+    
+    std::remove(tmp_path.c_str());
+    CHECK(config.save(path));
+    CHECK(Config::load(path).dns_timeout == 9.0);
+    std::remove(path.c_str());
+}
+
+// to_json() writes back every upstream proxy field, chain hops included, so a saved config
+// parses to the same proxies
+static void test_proxy_round_trip() {
+    Config config = Config::parse_json(R"({"upstream_proxies": [
+        {"type": "https", "host": "entry.example", "port": 443, "username": "u\"1", "password": "p\\w",
+         "tls_server_name": "proxy.example", "tls_allow_self_signed": true, "dns_servers": ["Google"],
+         "chain": [
+            {"type": "socks5", "host": "mid.example", "port": 1080, "remote_dns": true},
+            {"type": "https", "host": "exit.example", "port": 8443, "username": "x", "password": "y",
+             "tls_server_name": "exit.proxy", "tls_allow_self_signed": true, "danger_accept_invalid_certs": true}
+         ]}
+    ]})");
+    CHECK(config.upstream_proxies.size() == 1);
+    CHECK(config.upstream_proxies[0].chain.size() == 2);
+    CHECK(config.upstream_proxies[0].chain[0].remote_dns);
+    CHECK(config.upstream_proxies[0].chain[1].danger_accept_invalid_certs);
+    
+    Config reparsed = Config::parse_json(config.to_json());
+    CHECK(reparsed.upstream_proxies == config.upstream_proxies);
+}
+
+// Free-text settings are escaped on the way out, so a quote or backslash cannot break the saved file
+static void test_strings_escaped() {
+    Config config;
+    config.alert_webhook_url = "https://hooks.example/a\\b?q=\"x\"";
+    config.admin_listen_host = "host\"name";
+    Config reparsed = Config::parse_json(config.to_json());
+    CHECK(reparsed.alert_webhook_url == config.alert_webhook_url);
+    CHECK(reparsed.admin_listen_host == config.admin_listen_host);
+}
+
+int main() {
+    test_save_is_atomic();
+    test_proxy_round_trip();
+    test_strings_escaped();
+    return test_result("config");
+}
@@ -0,0 +1,55 @@
+#include "test.h"
+#include "logger.h"
+#include <cstdio>
+#include <fstream>
+#include <sstream>
+
+static const char* const PASSWORD = "hunter2-upstream";
+
+// An upstream proxy password, in every shape a log message carries one
+static const std::vector<std::string> MESSAGES = {
+    std::string("Loaded upstream proxy {\"type\": \"socks5\", \"username\": \"alice\", \"password\": \"") + PASSWORD + "\"}",
+    std::string("Forwarding request with Proxy-Authorization: ") + PASSWORD,
+    std::string("Probe GET /check?user=alice&password=") + PASSWORD + "&target=example.com",
+    std::string("Cannot reach upstream proxy http://alice:") + PASSWORD + "@proxy.example.net:8080",
+};
+
+static std::string read_file(const std::string& path) {
+    std::ifstream file(path);
+    std::ostringstream content;
+    content << file.rdbuf();
+    return content.str();
+}
+
+// Every line written to the log file passes through redact::text: the password never reaches the file,
+// and each message keeps its place with the mask instead
+static void test_passwords_redacted(LogFormat format, const std::string& path) {
+    std::remove(path.c_str());
+    Logger::instance().init(path, format);
+    for (const auto& message : MESSAGES) {
+        Logger::instance().log(LogLevel::WARN, message);
+    }
+    Logger::instance().flush();
+    Logger::instance().close();
+    
+    std::string content = read_file(path);
+    CHECK(!content.empty());
+    CHECK(content.find(PASSWORD) == std::string::npos);
+    
+    std::istringstream lines(content);
+    std::string line;
+    size_t masked = 0;
+    while (std::getline(lines, line)) {
+        CHECK(line.find("***") != std::string::npos);
+        ++masked;
+    }
+    CHECK(masked == MESSAGES.size());
+    CHECK(content.find("alice") != std::string::npos); // Only the secret is masked
+    std::remove(path.c_str());
+}
+
+int main() {
+    test_passwords_redacted(LogFormat::Text, "logger_test.log");
+    test_passwords_redacted(LogFormat::Json, "logger_test.json.log");
+    return test_result("logger");
+}
@@ -0,0 +1,140 @@
+#include "test.h"
+#include "test_proxy.h"
+#include "health.h"
+#include "cli.h"
+#include <atomic>
+#include <mutex>
+#include <sstream>
+
+// Connections for a runway leave from its source address: both the probe that picks the runway
+// and the request itself. The target sees 127.0.0.2, which only a bind can produce
+static void test_runway_source_address() {
+    std::mutex peers_mutex;
+    std::vector<std::string> peers;
+    LocalServer target([&](socket_t sock, const std::string& peer_ip) {
+        {
+            std::lock_guard<std::mutex> lock(peers_mutex);
+            peers.push_back(peer_ip);
+        }
+        serve_http(sock, "<html>hello</html>");
+    });
+    
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    CHECK(proxy.start());
+    std::vector<std::shared_ptr<Runway>> runways = proxy.runway_manager->get_all_runways();
+    CHECK(runways.size() == 1 && runways[0]->source_ip == "127.0.0.2");
+    
+    std::string url = "http://127.0.0.1:" + std::to_string(target.port()) + "/";
+    CHECK(proxy.get(url) == 200);
+    
+    // The probe path on its own
+    if (!runways.empty()) {
+        auto result = proxy.runway_manager->test_runway_accessibility("127.0.0.1", runways[0], 2.0, target.port());
+        CHECK(std::get<0>(result));
+    }
+    
+    target.stop();
+    CHECK(peers.size() >= 2);
+    for (const auto& peer : peers) {
+        CHECK(peer == "127.0.0.2");
+    }
+}
+
+// Health probes test the port the target was requested on, not 80: a target on another port
+// must not look unreachable to the health monitor
+static void test_health_probe_uses_target_port() {
+    LocalServer target([](socket_t sock, const std::string&) { serve_http(sock, "<html>hello</html>"); });
+    
+    // Only lo survives the monitor's interface refresh, so its runway stays the one under test
+    TestProxy proxy("{\"interface_exclude\": [\"*\"]}", {loopback_interface("lo", "127.0.0.1")});
+    CHECK(proxy.start());
+    CHECK(proxy.get("http://127.0.0.1:" + std::to_string(target.port()) + "/") == 200);
+    CHECK(proxy.tracker->get_target_port("127.0.0.1") == target.port());
+    
+    std::atomic<int> probes(0);
+    std::atomic<int> reached(0);
+    // The request left the runway Accessible: re-probe it on every cycle
+    HealthMonitor monitor(proxy.runway_manager, proxy.tracker, 1, 0, 1.0);
+    monitor.add_listener([&](const HealthEvent& event) {
+        auto runway = proxy.runway_manager->get_runway(event.runway_id);
+        if (event.type == HealthEvent::Type::ProbeResult && event.target == "127.0.0.1" &&
+            runway && runway->is_direct && runway->source_ip == "127.0.0.1") {
+            probes++;
+            if (event.network_success) {
+                reached++;
+            }
+        }
+    });
+    monitor.start();
+    for (int i = 0; i < 100 && probes == 0; ++i) {
+        std::this_thread::sleep_for(std::chrono::milliseconds(50));
+    }
+    monitor.stop();
+    CHECK(probes > 0);
+    CHECK(reached == probes); // Port 80 on loopback has no listener
+}
+
+// test-batch sends real requests and validates them: a block page served with 200 is "blocked"
+// (connecting alone would call it reachable), a working page "ok", a closed port "-"
+static void test_batch_validates_responses() {
+    LocalServer working([](socket_t sock, const std::string&) { serve_http(sock, "<html>hello</html>"); });
+    LocalServer censored([](socket_t sock, const std::string&) {
+        serve_http(sock, "<html>Access denied by order of the regulator</html>");
+    });
+    uint16_t closed_port = free_port();
+    
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, proxy.config.routing_mode);
+    ProxyCLI cli(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server);
+    auto target = [](uint16_t port) { return "127.0.0.1:" + std::to_string(port); };
+    CHECK(cli.execute({"test-batch", target(working.port()), target(censored.port()), target(closed_port)}) == 0);
+    
+    // The grid's only runway row: id, then one cell per target
+    std::string row = cli.output().substr(cli.output().find("test0"));
+    row = row.substr(0, row.find('\n'));
+    std::vector<std::string> cells;
+    std::istringstream fields(row);
+    std::string field;
+    while (fields >> field) {
+        cells.push_back(field);
+    }
+    CHECK((cells == std::vector<std::string>{cells.empty() ? "" : cells[0], "ok", "blocked", "-"}));
+    CHECK(proxy.tracker->get_target_metrics("127.0.0.1").empty());
+}
+
+// bench times whole requests: the target sees a GET for each, and every request's status is reported
+static void test_bench_sends_requests() {
+    std::atomic<int> gets(0);
+    LocalServer target([&](socket_t sock, const std::string&) {
+        std::string head;
+        char c;
+        while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+            head += c;
+        }
+        if (head.compare(0, 4, "GET ") == 0) { // Not a bare connect
+            gets++;
+        }
+        std::string response = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        network::send_data(sock, response.data(), response.size());
+    });
+    
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    auto routing_engine = std::make_shared<RoutingEngine>(proxy.tracker, proxy.config.routing_mode);
+    ProxyCLI cli(proxy.runway_manager, routing_engine, proxy.tracker, nullptr, proxy.server);
+    CHECK(cli.execute({"bench", "127.0.0.1:" + std::to_string(target.port()), "--count", "3", "--json"}) == 0);
+    CHECK(gets == 3);
+    
+    size_t reported = 0;
+    for (size_t at = 0; (at = cli.output().find("\"status_code\": 404", at)) != std::string::npos; ++at) {
+        ++reported;
+    }
+    CHECK(reported == 3);
+}
+
+int main() {
+    test_runway_source_address();
+    test_health_probe_uses_target_port();
+    test_batch_validates_responses();
+    test_bench_sends_requests();
+    return test_result("proxy");
+}
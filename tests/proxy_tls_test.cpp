@@ -0,0 +1,143 @@
+#include "test.h"
+#include "test_proxy.h"
+#include "test_tls.h"
+#include "tls.h"
+#include <csignal>
+
+// https origin on loopback presenting certificate, answering like serve_http
+class TlsOrigin {
+public:
+    explicit TlsOrigin(const TestCertificate& certificate)
+        : context_(server_context(certificate)),
+          server_([this](socket_t sock, const std::string&) { serve(sock); }) {
+        CHECK(context_ != nullptr);
+    }
+    
+    uint16_t port() const { return server_.port(); }
+    
+    std::string url() const { return "https://127.0.0.1:" + std::to_string(port()) + "/"; }
+
+private:
+    std::shared_ptr<tls::ServerContext> context_;
+    LocalServer server_;
+    
+    static std::shared_ptr<tls::ServerContext> server_context(const TestCertificate& certificate) {
+        tls::ServerOptions options;
+        options.cert_path = certificate.cert_path;
+        options.key_path = certificate.key_path;
+        std::string error;
+        return tls::create_server_context(options, error);
+    }
+    
+    void serve(socket_t sock) {
+        std::string error;
+        if (context_ && tls::accept(sock, *context_, error)) {
+            serve_http(sock, "<html>hello over tls</html>");
+        }
+    }
+};
+
+// Copy from one socket to the other until the source closes, then end the other's sending side
+static void pump(socket_t from, socket_t to) {
+    char buffer[4096];
+    ssize_t received;
+    while ((received = network::recv_data(from, buffer, sizeof(buffer))) > 0) {
+        if (network::send_data(to, buffer, static_cast<size_t>(received)) != received) {
+            break;
+        }
+    }
+    network::shutdown_socket(to);
+}
+
+// HTTP proxy handler: CONNECT to the requested host:port, then relay both ways
+static void serve_connect_tunnel(socket_t sock) {
+    std::string head;
+    char c;
+    while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+        head += c;
+    }
+    // "CONNECT host:port HTTP/1.1"
+    if (head.compare(0, 8, "CONNECT ") != 0) {
+        return;
+    }
+    std::string authority = head.substr(8, head.find(' ', 8) - 8);
+    size_t colon = authority.rfind(':');
+    socket_t target = network::create_tcp_socket();
+    set_recv_timeout(target, 5);
+    if (colon == std::string::npos ||
+        !network::connect_socket(target, authority.substr(0, colon),
+                                 static_cast<uint16_t>(std::atoi(authority.substr(colon + 1).c_str())), 2000)) {
+        network::close_socket(target);
+        return;
+    }
+    std::string established = "HTTP/1.1 200 Connection established\r\n\r\n";
+    network::send_data(sock, established.data(), established.size());
+    
+    std::thread back(pump, target, sock);
+    pump(sock, target);
+    back.join();
+    network::close_socket(target);
+}
+
+// A certificate the target name cannot be verified against fails the request with 502 and counts
+// as a user failure: the path reached something, not provably the target
+static void test_invalid_certificate_rejected(const TestCertificate& certificate) {
+    TlsOrigin origin(certificate);
+    TestProxy proxy("{}", {loopback_interface("test0", "127.0.0.2")});
+    CHECK(proxy.start());
+    CHECK(proxy.get(origin.url()) == 502);
+    
+    std::vector<std::shared_ptr<Runway>> runways = proxy.runway_manager->get_all_runways();
+    CHECK(runways.size() == 1);
+    if (!runways.empty()) {
+        std::shared_ptr<TargetMetrics> metrics = proxy.tracker->get_metrics("127.0.0.1", runways[0]->id);
+        CHECK(metrics != nullptr);
+        if (metrics) {
+            CHECK(metrics->partial_success_count >= 1);
+            CHECK(metrics->state == RunwayState::PartiallyAccessible);
+        }
+    }
+}
+
+// danger_accept_invalid_certs set globally lets the request through on any runway
+static void test_accept_invalid_certs_globally(const TestCertificate& certificate) {
+    TlsOrigin origin(certificate);
+    TestProxy proxy("{\"danger_accept_invalid_certs\": true}", {loopback_interface("test0", "127.0.0.2")});
+    CHECK(proxy.start());
+    CHECK(proxy.get(origin.url()) == 200);
+}
+
+// danger_accept_invalid_certs on an upstream proxy covers the targets reached through it
+static void test_accept_invalid_certs_per_proxy(const TestCertificate& certificate) {
+    TlsOrigin origin(certificate);
+    LocalServer tunnel([](socket_t sock, const std::string&) { serve_connect_tunnel(sock); });
+    
+    for (bool accept : {false, true}) {
+        std::string config_json = "{\"upstream_proxies\": [{\"type\": \"http\", \"host\": \"127.0.0.1\", \"port\": " +
+                                  std::to_string(tunnel.port()) + ", \"danger_accept_invalid_certs\": " +
+                                  (accept ? "true" : "false") + "}]}";
+        TestProxy proxy(config_json, {loopback_interface("test0", "127.0.0.2")});
+        // Only the runway through the proxy carries traffic
+        for (const auto& runway : proxy.runway_manager->get_all_runways()) {
+            if (runway->is_direct) {
+                proxy.runway_manager->set_runway_enabled(runway->id, false);
+            }
+        }
+        CHECK(proxy.runway_manager->get_all_runways().size() == 1);
+        CHECK(proxy.start());
+        CHECK(proxy.get(origin.url()) == (accept ? 200 : 502));
+    }
+}
+
+int main() {
+    network::init();
+#ifndef _WIN32
+    signal(SIGPIPE, SIG_IGN); // As main() does: sends to peers that left must not end the process
+#endif
+    // Self-signed, and for another name than the 127.0.0.1 the requests address
+    TestCertificate certificate("proxy_tls_origin", "origin.example.net");
+    test_invalid_certificate_rejected(certificate);
+    test_accept_invalid_certs_globally(certificate);
+    test_accept_invalid_certs_per_proxy(certificate);
+    return test_result("proxy_tls");
+}
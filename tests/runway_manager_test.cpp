@@ -0,0 +1,225 @@
+#include "test.h"
+#include "config.h"
+#include "dns.h"
+#include "runway_manager.h"
+#include "routing.h"
+#include "tracker.h"
+#include <algorithm>
+#include <mutex>
+
+static InterfaceInfo fake_interface(const std::string& name) {
+    InterfaceInfo info;
+    info.name = name;
+    info.ip = "127.0.0.1";
+    return info;
+}
+
+// Names get_interfaces() keeps out of a fake OS listing, sorted
+static std::vector<std::string> kept_interfaces(const std::vector<std::string>& interfaces,
+                                                const std::vector<std::string>& interface_exclude) {
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    RunwayManager manager(interfaces, {}, {}, dns_resolver, 3, interface_exclude);
+    std::vector<InterfaceInfo> found;
+    for (const char* name : {"lo", "lo0", "eth0", "wlan0", "docker0", "veth1a2b3c", "br-5f2e", "virbr0", "tun0"}) {
+        found.push_back(fake_interface(name));
+    }
+    manager.set_interfaces(found);
+    
+    std::vector<std::string> names;
+    for (const auto& info : manager.get_interfaces()) {
+        names.push_back(info.name);
+    }
+    std::sort(names.begin(), names.end());
+    return names;
+}
+
+// interface_exclude: the defaults drop loopback, container and bridge interfaces
+static void test_default_exclusions() {
+    Config config = Config::parse_json("{}");
+    CHECK((kept_interfaces({}, config.interface_exclude) == std::vector<std::string>{"eth0", "tun0", "wlan0"}));
+}
+
+// Patterns are wildcards, and a configured list replaces the defaults
+static void test_exclusion_wildcards() {
+    CHECK((kept_interfaces({}, {"tun*", "*0"}) ==
+           std::vector<std::string>{"br-5f2e", "lo", "veth1a2b3c"}));
+    CHECK((kept_interfaces({}, {"*"}).empty()));
+    CHECK((kept_interfaces({}, {}).size() == 9));
+}
+
+// An interface named in interfaces is used even when an exclusion pattern matches it
+static void test_named_interface_overrides_exclusion() {
+    Config config = Config::parse_json("{}");
+    std::vector<std::string> kept = kept_interfaces({"docker0", "eth0"}, config.interface_exclude);
+    CHECK(std::find(kept.begin(), kept.end(), "docker0") != kept.end());
+    CHECK(std::find(kept.begin(), kept.end(), "veth1a2b3c") == kept.end()); // Still excluded
+    CHECK(std::find(kept.begin(), kept.end(), "lo") == kept.end());
+    
+    CHECK((kept_interfaces({"lo"}, {"*"}) == std::vector<std::string>{"lo"}));
+}
+
+// Scripted HTTP proxy: records the authority of each CONNECT and answers 200
+static void serve_http_connect(socket_t sock, std::mutex& mutex, std::vector<std::string>& authorities) {
+    std::string head;
+    char c;
+    while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+        head += c;
+    }
+    // "CONNECT host:port HTTP/1.1"
+    if (head.compare(0, 8, "CONNECT ") != 0) {
+        return;
+    }
+    {
+        std::lock_guard<std::mutex> lock(mutex);
+        authorities.push_back(head.substr(8, head.find(' ', 8) - 8));
+    }
+    std::string response = "HTTP/1.1 200 Connection established\r\n\r\n";
+    network::send_data(sock, response.data(), response.size());
+}
+
+// A probe through an HTTP proxy opens its tunnel to the port the target is served on
+static void test_proxy_probe_target_port() {
+    std::mutex mutex;
+    std::vector<std::string> authorities;
+    LocalServer proxy([&](socket_t sock, const std::string&) { serve_http_connect(sock, mutex, authorities); });
+    
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    RunwayManager manager({}, {}, {}, dns_resolver);
+    auto runway = std::make_shared<Runway>(
+        "test0_proxy", "test0", "127.0.0.1",
+        std::make_shared<UpstreamProxy>(UpstreamProxyConfig("http", "127.0.0.1", proxy.port())), nullptr);
+    
+    CHECK(std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 8080)));
+    CHECK(std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 443)));
+    
+    proxy.stop();
+    CHECK((authorities == std::vector<std::string>{"127.0.0.1:8080", "127.0.0.1:443"}));
+}
+
+// A probe through a working proxy succeeds and records the proxy's latency; once the proxy is gone
+// the probe fails
+static void test_proxy_probe() {
+    std::mutex mutex;
+    std::vector<std::string> authorities;
+    LocalServer proxy([&](socket_t sock, const std::string&) { serve_http_connect(sock, mutex, authorities); });
+    
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    RunwayManager manager({}, {}, {}, dns_resolver);
+    auto upstream_proxy = std::make_shared<UpstreamProxy>(UpstreamProxyConfig("http", "127.0.0.1", proxy.port()));
+    auto runway = std::make_shared<Runway>("test0_proxy", "test0", "127.0.0.1", upstream_proxy, nullptr);
+    
+    CHECK(upstream_proxy->response_time == 0.0);
+    CHECK(std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 443)));
+    CHECK(upstream_proxy->response_time > 0.0);
+    CHECK(upstream_proxy->accessible);
+    
+    proxy.stop();
+    CHECK(!std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 443)));
+}
+
+// A proxy that answers but refuses the tunnel (the target may be down or blocked) stays accessible;
+// one that cannot be reached is marked inaccessible after proxy_failure_threshold probes
+static void test_refused_tunnel_keeps_proxy_accessible() {
+    LocalServer proxy([](socket_t sock, const std::string&) {
+        std::string head;
+        char c;
+        while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+            head += c;
+        }
+        std::string response = "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n";
+        network::send_data(sock, response.data(), response.size());
+    });
+    
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    RunwayManager manager({}, {}, {}, dns_resolver, 3);
+    auto upstream_proxy = std::make_shared<UpstreamProxy>(UpstreamProxyConfig("http", "127.0.0.1", proxy.port()));
+    auto runway = std::make_shared<Runway>("test0_proxy", "test0", "127.0.0.1", upstream_proxy, nullptr);
+    
+    for (int i = 0; i < 5; ++i) {
+        CHECK(!std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 443)));
+    }
+    CHECK(upstream_proxy->accessible);
+    CHECK(upstream_proxy->failure_count == 0);
+    
+    proxy.stop();
+    for (int i = 0; i < 3; ++i) {
+        CHECK(!std::get<0>(manager.test_runway_accessibility("127.0.0.1", runway, 2.0, 443)));
+    }
+    CHECK(!upstream_proxy->accessible);
+}
+
+// An IPv6 source is only paired with proxies it can reach: not those at an IPv4 address, and the other
+// way round
+static void test_proxy_runway_families() {
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    std::vector<UpstreamProxyConfig> proxies = {UpstreamProxyConfig("socks5", "192.0.2.10", 1080),
+                                                UpstreamProxyConfig("socks5", "2001:db8::10", 1080)};
+    RunwayManager manager({"eth0"}, proxies, {DNSServerConfig("127.0.0.1", 53, "local")}, dns_resolver);
+    InterfaceInfo info;
+    info.name = "eth0";
+    info.ip = "198.51.100.7";
+    info.ipv6 = "2001:db8::7";
+    manager.set_interfaces({info});
+    
+    size_t proxy_runways = 0;
+    for (const auto& runway : manager.discover_runways()) {
+        if (runway->is_direct) {
+            continue;
+        }
+        ++proxy_runways;
+        bool ipv6_proxy = runway->upstream_proxy->config.host.find(':') != std::string::npos;
+        CHECK(runway->is_ipv6() == ipv6_proxy);
+    }
+    CHECK(proxy_runways == 2);
+}
+
+// An interface without a default route is a last resort: routing only uses it while no routed
+// interface has an accessible runway, rather than preferring it as metric 0 would
+static void test_unrouted_interface_last_resort() {
+    auto dns_resolver = std::make_shared<DNSResolver>(std::vector<DNSServerConfig>{}, 1.0);
+    RunwayManager manager({"eth0", "wlan0"}, {}, {DNSServerConfig("127.0.0.1", 53, "local")}, dns_resolver);
+    InterfaceInfo routed = fake_interface("eth0");
+    routed.metric = 100;
+    InterfaceInfo unrouted = fake_interface("wlan0");
+    unrouted.ip = "127.0.0.2";
+    manager.set_interfaces({routed, unrouted});
+    
+    std::shared_ptr<Runway> eth0;
+    std::shared_ptr<Runway> wlan0;
+    for (const auto& runway : manager.discover_runways()) {
+        (runway->interface_name == "eth0" ? eth0 : wlan0) = runway;
+    }
+    CHECK(eth0 && wlan0);
+    if (!eth0 || !wlan0) {
+        return;
+    }
+    CHECK(eth0->metric == 100);
+    CHECK(wlan0->metric == UINT32_MAX);
+    
+    auto tracker = std::make_shared<TargetAccessibilityTracker>(10, 0.5);
+    RoutingEngine routing(tracker, RoutingMode::RoundRobin);
+    std::vector<std::shared_ptr<Runway>> runways = {wlan0, eth0};
+    tracker->update("example.com", wlan0->id, true, true, 0.1);
+    tracker->update("example.com", eth0->id, true, true, 0.1);
+    for (int i = 0; i < 3; ++i) {
+        CHECK(routing.select_runway("example.com", runways) == eth0);
+    }
+    
+    for (int i = 0; i < 10; ++i) {
+        tracker->update("example.com", eth0->id, false, false, 0.0);
+    }
+    CHECK(routing.select_runway("example.com", runways) == wlan0);
+}
+
+int main() {
+    network::init();
+    test_default_exclusions();
+    test_exclusion_wildcards();
+    test_named_interface_overrides_exclusion();
+    test_proxy_probe_target_port();
+    test_proxy_probe();
+    test_refused_tunnel_keeps_proxy_accessible();
+    test_proxy_runway_families();
+    test_unrouted_interface_last_resort();
+    return test_result("runway_manager");
+}
@@ -0,0 +1,143 @@
+#ifndef TEST_H
+#define TEST_H
+
+#include <iostream>
+#include <cmath>
+#include <string>
+#include <vector>
+#include <thread>
+#include <atomic>
+#include <functional>
+#include <chrono>
+#include <cstdint>
+#include "network.h"
+
+// Minimal checks for the tests/ executables (see CMakeLists.txt): a failed CHECK reports
+// file:line and the expression, and the test keeps going so one run shows every failure
+
+static int test_failures = 0;
+
+#define CHECK(condition)                                                                      \
+    do {                                                                                      \
+        if (!(condition)) {                                                                   \
+            std::cerr << __FILE__ << ":" << __LINE__ << ": CHECK failed: " #condition "\n";   \
+            ++test_failures;                                                                  \
+        }                                                                                     \
+    } while (0)
+
+// Floating-point comparison within an absolute tolerance
+#define CHECK_NEAR(actual, expected, tolerance) CHECK(std::fabs((actual) - (expected)) <= (tolerance))
+
+// Exit status for main(): 0 if every CHECK passed
+inline int test_result(const char* name) {
+    if (test_failures > 0) {
+        std::cerr << name << ": " << test_failures << " check(s) failed\n";
+        return 1;
+    }
+    std::cout << name << ": ok\n";
+    return 0;
+}
+
+// Port of a bound socket (0 if unknown)
+inline uint16_t bound_port(socket_t sock) {
+    struct sockaddr_in addr;
+    socklen_t len = sizeof(addr);
+    if (getsockname(sock, reinterpret_cast<struct sockaddr*>(&addr), &len) != 0) {
+        return 0;
+    }
+    return ntohs(addr.sin_port);
+}
+
+// A port nothing listens on right now, for servers that take their port from a config
+inline uint16_t free_port(const std::string& host = "127.0.0.1") {
+    socket_t sock = network::create_tcp_socket();
+    uint16_t port = network::bind_socket(sock, host, 0) ? bound_port(sock) : 0;
+    network::close_socket(sock);
+    return port;
+}
+
+// Read until the peer closes (or the receive timeout expires)
+inline std::string recv_all(socket_t sock) {
+    std::string data;
+    char buffer[4096];
+    ssize_t received;
+    while ((received = network::recv_data(sock, buffer, sizeof(buffer))) > 0) {
+        data.append(buffer, static_cast<size_t>(received));
+    }
+    return data;
+}
+
+inline void set_recv_timeout(socket_t sock, long secs) {
+    struct timeval timeout;
+    timeout.tv_sec = secs;
+    timeout.tv_usec = 0;
+#ifdef _WIN32
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, reinterpret_cast<const char*>(&timeout), sizeof(timeout));
+#else
+    setsockopt(sock, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+#endif
+}
+
+// TCP server on a loopback address for the length of a test: accepted connections are passed to
+// handler one at a time, on the server's thread, and closed when it returns
+class LocalServer {
+public:
+    using Handler = std::function<void(socket_t sock, const std::string& peer_ip)>;
+    
+    explicit LocalServer(Handler handler, const std::string& host = "127.0.0.1")
+        : handler_(handler), running_(true), port_(0) {
+        network::init();
+        listen_socket_ = network::create_tcp_socket();
+        if (network::bind_socket(listen_socket_, host, 0) && network::listen_socket(listen_socket_, 16)) {
+            port_ = bound_port(listen_socket_);
+        }
+        thread_ = std::thread(&LocalServer::serve, this);
+    }
+    
+    ~LocalServer() {
+        stop();
+    }
+    
+    LocalServer(const LocalServer&) = delete;
+    LocalServer& operator=(const LocalServer&) = delete;
+    
+    uint16_t port() const { return port_; }
+    
+    // Stop accepting; connections accepted later are refused by the OS
+    void stop() {
+        if (!running_.exchange(false)) {
+            return;
+        }
+        network::shutdown_socket(listen_socket_); // Wake accept
+        if (thread_.joinable()) {
+            thread_.join();
+        }
+        network::close_socket(listen_socket_);
+    }
+
+private:
+    Handler handler_;
+    std::atomic<bool> running_;
+    socket_t listen_socket_;
+    uint16_t port_;
+    std::thread thread_;
+    
+    void serve() {
+        while (running_) {
+            std::string peer_ip;
+            uint16_t peer_port;
+            socket_t sock = network::accept_connection(listen_socket_, peer_ip, peer_port);
+            if (sock == network::INVALID_SOCKET_VALUE) {
+                if (running_) {
+                    std::this_thread::sleep_for(std::chrono::milliseconds(10));
+                }
+                continue;
+            }
+            set_recv_timeout(sock, 5);
+            handler_(sock, peer_ip);
+            network::close_socket(sock);
+        }
+    }
+};
+
+#endif // TEST_H
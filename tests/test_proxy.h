@@ -0,0 +1,116 @@
+#ifndef TEST_PROXY_H
+#define TEST_PROXY_H
+
+#include <cstdlib>
+#include <memory>
+#include <string>
+#include <vector>
+#include "test.h"
+#include "config.h"
+#include "dns.h"
+#include "runway_manager.h"
+#include "routing.h"
+#include "tracker.h"
+#include "validator.h"
+#include "proxy.h"
+
+// The service's request path as main() wires it up, listening on a free loopback port, with
+// runways over the given interfaces instead of the OS's (so source addresses are known)
+class TestProxy {
+public:
+    Config config;
+    std::shared_ptr<RunwayManager> runway_manager;
+    std::shared_ptr<TargetAccessibilityTracker> tracker;
+    std::shared_ptr<ProxyServer> server;
+    
+    // config_json: settings under test; one DNS server, never asked (targets are IP addresses),
+    // and no upstream proxies unless config_json lists them
+    TestProxy(const std::string& config_json, const std::vector<InterfaceInfo>& interfaces)
+        : config(Config::parse_json(config_json)) {
+        network::init();
+        config.proxy_listen_host = "127.0.0.1";
+        config.proxy_listen_port = free_port();
+        config.dns_servers = {DNSServerConfig("127.0.0.1", 53, "local")};
+        if (config_json.find("\"upstream_proxies\"") == std::string::npos) {
+            config.upstream_proxies.clear(); // Instead of the default config's example proxy
+        }
+        config.interfaces.clear();
+        for (const auto& info : interfaces) {
+            config.interfaces.push_back(info.name);
+        }
+        
+        auto dns_resolver = std::make_shared<DNSResolver>(config.dns_servers, config.dns_timeout);
+        runway_manager = std::make_shared<RunwayManager>(
+            config.interfaces, config.upstream_proxies, config.dns_servers, dns_resolver,
+            config.proxy_failure_threshold, config.interface_exclude, config.connect_timeout);
+        runway_manager->set_interfaces(interfaces);
+        runway_manager->discover_runways();
+        tracker = std::make_shared<TargetAccessibilityTracker>(config.success_rate_window,
+                                                              config.success_rate_threshold);
+        auto validator = std::make_shared<ValidatorChain>();
+        validator->add(std::make_shared<HttpValidator>(config));
+        auto routing_engine = std::make_shared<RoutingEngine>(tracker, config.routing_mode);
+        server = std::make_shared<ProxyServer>(config, runway_manager, routing_engine, tracker, dns_resolver,
+                                               validator);
+    }
+    
+    ~TestProxy() {
+        // Connection threads are detached; let them finish before the server goes away
+        for (int i = 0; i < 500 && server->get_active_connections() > 0; ++i) {
+            std::this_thread::sleep_for(std::chrono::milliseconds(10));
+        }
+        server->stop();
+    }
+    
+    TestProxy(const TestProxy&) = delete;
+    TestProxy& operator=(const TestProxy&) = delete;
+    
+    bool start() { return server->start(); }
+    
+    // Send "GET url" (absolute-form) through the proxy; returns the response status, 0 if none
+    int get(const std::string& url) {
+        size_t host_start = url.find("://") + 3;
+        std::string authority = url.substr(host_start, url.find('/', host_start) - host_start);
+        std::string request = "GET " + url + " HTTP/1.1\r\nHost: " + authority + "\r\nConnection: close\r\n\r\n";
+        
+        socket_t sock = network::create_tcp_socket();
+        set_recv_timeout(sock, 15);
+        std::string response;
+        if (network::connect_socket(sock, "127.0.0.1", config.proxy_listen_port, 2000) &&
+            network::send_data(sock, request.data(), request.size()) == static_cast<ssize_t>(request.size())) {
+            response = recv_all(sock);
+        }
+        network::close_socket(sock);
+        
+        // "HTTP/1.1 200 OK"
+        if (response.compare(0, 5, "HTTP/") != 0 || response.size() < 12) {
+            return 0;
+        }
+        return std::atoi(response.substr(9, 3).c_str());
+    }
+};
+
+// Loopback interface for TestProxy; any 127.0.0.0/8 address works as a source without configuring it
+inline InterfaceInfo loopback_interface(const std::string& name, const std::string& ip) {
+    InterfaceInfo info;
+    info.name = name;
+    info.ip = ip;
+    return info;
+}
+
+// Minimal HTTP/1.1 origin: answers every request with 200 and body, as text/html
+inline void serve_http(socket_t sock, const std::string& body) {
+    std::string head;
+    char c;
+    while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+        head += c;
+    }
+    if (head.empty()) {
+        return; // A probe: connected and left
+    }
+    std::string response = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: " +
+                           std::to_string(body.size()) + "\r\nConnection: close\r\n\r\n" + body;
+    network::send_data(sock, response.data(), response.size());
+}
+
+#endif // TEST_PROXY_H
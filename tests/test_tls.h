@@ -0,0 +1,105 @@
+#ifndef TEST_TLS_H
+#define TEST_TLS_H
+
+#include <cstdio>
+#include <cstdlib>
+#include <string>
+#include <openssl/evp.h>
+#include <openssl/ec.h>
+#include <openssl/pem.h>
+#include <openssl/x509.h>
+#include <openssl/x509v3.h>
+
+// Certificates for TLS tests (SMARTPROXY_TLS builds only), generated at run time so none can expire:
+// an EC P-256 key and a certificate for one DNS name, written as PEM files to the working directory
+class TestCertificate {
+public:
+    std::string cert_path;
+    std::string key_path;
+    
+    // file_prefix: <file_prefix>.crt and <file_prefix>.key; issuer: the CA signing the certificate,
+    // or null for a self-signed one; is_ca: may sign other certificates
+    TestCertificate(const std::string& file_prefix, const std::string& dns_name,
+                    const TestCertificate* issuer = nullptr, bool is_ca = false)
+        : cert_path(file_prefix + ".crt"), key_path(file_prefix + ".key"), cert_(X509_new()), key_(nullptr) {
+        EVP_PKEY_CTX* key_ctx = EVP_PKEY_CTX_new_id(EVP_PKEY_EC, nullptr);
+        if (key_ctx && EVP_PKEY_keygen_init(key_ctx) == 1 &&
+            EVP_PKEY_CTX_set_ec_paramgen_curve_nid(key_ctx, NID_X9_62_prime256v1) == 1) {
+            EVP_PKEY_keygen(key_ctx, &key_);
+        }
+        EVP_PKEY_CTX_free(key_ctx);
+        if (!key_ || !cert_) {
+            return;
+        }
+        
+        static long serial = 1;
+        X509_set_version(cert_, 2); // v3
+        ASN1_INTEGER_set(X509_get_serialNumber(cert_), serial++);
+        X509_gmtime_adj(X509_getm_notBefore(cert_), -3600);
+        X509_gmtime_adj(X509_getm_notAfter(cert_), 24 * 3600);
+        X509_set_pubkey(cert_, key_);
+        X509_NAME* name = X509_get_subject_name(cert_);
+        X509_NAME_add_entry_by_txt(name, "CN", MBSTRING_ASC,
+                                   reinterpret_cast<const unsigned char*>(dns_name.c_str()), -1, -1, 0);
+        const TestCertificate& signer = issuer ? *issuer : *this;
+        X509_set_issuer_name(cert_, X509_get_subject_name(signer.cert_));
+        
+        X509V3_CTX ext_ctx;
+        X509V3_set_ctx(&ext_ctx, signer.cert_, cert_, nullptr, nullptr, 0);
+        add_extension(ext_ctx, NID_basic_constraints, is_ca ? "critical,CA:TRUE" : "CA:FALSE");
+        add_extension(ext_ctx, NID_subject_key_identifier, "hash");
+        if (is_ca) {
+            add_extension(ext_ctx, NID_key_usage, "critical,keyCertSign,cRLSign");
+        } else {
+            add_extension(ext_ctx, NID_subject_alt_name, ("DNS:" + dns_name).c_str());
+        }
+        X509_sign(cert_, signer.key_, EVP_sha256());
+        
+        FILE* cert_file = std::fopen(cert_path.c_str(), "w");
+        if (cert_file) {
+            PEM_write_X509(cert_file, cert_);
+            std::fclose(cert_file);
+        }
+        FILE* key_file = std::fopen(key_path.c_str(), "w");
+        if (key_file) {
+            PEM_write_PrivateKey(key_file, key_, nullptr, nullptr, 0, nullptr, nullptr);
+            std::fclose(key_file);
+        }
+    }
+    
+    ~TestCertificate() {
+        X509_free(cert_);
+        EVP_PKEY_free(key_);
+        std::remove(cert_path.c_str());
+        std::remove(key_path.c_str());
+    }
+    
+    TestCertificate(const TestCertificate&) = delete;
+    TestCertificate& operator=(const TestCertificate&) = delete;
+
+private:
+    X509* cert_;
+    EVP_PKEY* key_;
+    
+    void add_extension(X509V3_CTX& ctx, int nid, const char* value) {
+        X509_EXTENSION* extension = X509V3_EXT_conf_nid(nullptr, &ctx, nid, value);
+        if (extension) {
+            X509_add_ext(cert_, extension, -1);
+            X509_EXTENSION_free(extension);
+        }
+    }
+};
+
+// Make ca the only certificate the system trust store holds for this process; must run before the
+// first TLS client handshake, which loads the store once (OpenSSL reads SSL_CERT_FILE)
+inline void trust_only(const TestCertificate& ca) {
+#ifdef _WIN32
+    _putenv_s("SSL_CERT_FILE", ca.cert_path.c_str());
+    _putenv_s("SSL_CERT_DIR", "");
+#else
+    setenv("SSL_CERT_FILE", ca.cert_path.c_str(), 1);
+    setenv("SSL_CERT_DIR", "", 1);
+#endif
+}
+
+#endif // TEST_TLS_H
@@ -0,0 +1,61 @@
+#include "test.h"
+#include "tracker.h"
+#include <thread>
+#include <chrono>
+
+// Latency moving averages weight the newest sample by latency_ema_alpha; the first sample seeds them
+static void test_latency_ema() {
+    TargetAccessibilityTracker tracker(10, 0.5, 3600, 0.5);
+    tracker.update("example.com", "r1", true, true, 1.0, 0.1);
+    tracker.update("example.com", "r1", true, true, 2.0, 0.3);
+    tracker.update("example.com", "r1", true, true, 4.0);
+    
+    auto metrics = tracker.get_metrics("example.com", "r1");
+    CHECK(metrics != nullptr);
+    if (metrics) {
+        CHECK_NEAR(metrics->avg_response_time, 2.75, 1e-9); // 1.0 -> 1.5 -> 2.75
+        CHECK_NEAR(metrics->avg_dns_time, 0.2, 1e-9); // Cached lookups (0.0) are not samples
+    }
+    
+    // Failed attempts carry no latency sample
+    tracker.update("example.com", "r1", false, false, 9.0);
+    metrics = tracker.get_metrics("example.com", "r1");
+    if (metrics) {
+        CHECK_NEAR(metrics->avg_response_time, 2.75, 1e-9);
+    }
+    
+    // A different alpha weighs the same sequence differently: 1.0 -> 1.1 -> 1.39
+    TargetAccessibilityTracker slow(10, 0.5, 3600, 0.1);
+    slow.update("example.com", "r1", true, true, 1.0);
+    slow.update("example.com", "r1", true, true, 2.0);
+    slow.update("example.com", "r1", true, true, 4.0);
+    metrics = slow.get_metrics("example.com", "r1");
+    if (metrics) {
+        CHECK_NEAR(metrics->avg_response_time, 1.39, 1e-9);
+    }
+}
+
+// Targets whose runways were all idle for longer than the TTL are evicted; a TTL of 0 keeps everything
+static void test_evict_stale_targets() {
+    TargetAccessibilityTracker tracker(10, 0.5);
+    tracker.update("idle.example.com", "r1", true, true, 0.1);
+    tracker.update("idle.example.com", "r2", false, false, 0.0);
+    
+    // Timestamps have one-second resolution, so idle.example.com is now at least 2 s old
+    std::this_thread::sleep_for(std::chrono::milliseconds(2100));
+    tracker.update("busy.example.com", "r1", true, true, 0.1);
+    
+    CHECK(tracker.evict_stale_targets(0) == 0);
+    CHECK(tracker.get_all_targets().size() == 2);
+    
+    CHECK(tracker.evict_stale_targets(1) == 1);
+    std::vector<std::string> targets = tracker.get_all_targets();
+    CHECK(targets.size() == 1 && targets[0] == "busy.example.com");
+    CHECK(tracker.get_metrics("idle.example.com", "r1") == nullptr);
+}
+
+int main() {
+    test_latency_ema();
+    test_evict_stale_targets();
+    return test_result("tracker");
+}
@@ -0,0 +1,164 @@
+#include "test.h"
+#include "upstream.h"
+
+// Read exactly len bytes; false if the client closed early
+static bool read_bytes(socket_t sock, std::vector<uint8_t>& data, size_t len) {
+    data.assign(len, 0);
+    size_t received = 0;
+    while (received < len) {
+        ssize_t n = network::recv_data(sock, data.data() + received, len - received);
+        if (n <= 0) {
+            return false;
+        }
+        received += static_cast<size_t>(n);
+    }
+    return true;
+}
+
+static void send_bytes(socket_t sock, const std::vector<uint8_t>& data) {
+    network::send_data(sock, data.data(), data.size());
+}
+
+// What a scripted SOCKS5 server (RFC 1928) saw, and how it answers
+struct Socks5Script {
+    bool require_auth = false; // Select method 0x02 and check the RFC 1929 sub-negotiation
+    uint8_t auth_status = 0x00; // Sub-negotiation STATUS sent back
+    uint8_t reply_code = 0x00; // REP of the CONNECT reply
+    
+    std::vector<uint8_t> greeting;
+    std::vector<uint8_t> auth_request;
+    std::vector<uint8_t> connect_request;
+};
+
+static void serve_socks5(socket_t sock, Socks5Script& script) {
+    // VER, NMETHODS, METHODS
+    std::vector<uint8_t> head;
+    std::vector<uint8_t> methods;
+    if (!read_bytes(sock, head, 2) || !read_bytes(sock, methods, head[1])) {
+        return;
+    }
+    script.greeting = head;
+    script.greeting.insert(script.greeting.end(), methods.begin(), methods.end());
+    send_bytes(sock, {0x05, static_cast<uint8_t>(script.require_auth ? 0x02 : 0x00)});
+    
+    if (script.require_auth) {
+        // VER (0x01), ULEN, UNAME, PLEN, PASSWD
+        std::vector<uint8_t> ver_ulen, uname, plen, passwd;
+        if (!read_bytes(sock, ver_ulen, 2) || !read_bytes(sock, uname, ver_ulen[1]) || !read_bytes(sock, plen, 1) ||
+            !read_bytes(sock, passwd, plen[0])) {
+            return;
+        }
+        script.auth_request = ver_ulen;
+        script.auth_request.insert(script.auth_request.end(), uname.begin(), uname.end());
+        script.auth_request.insert(script.auth_request.end(), plen.begin(), plen.end());
+        script.auth_request.insert(script.auth_request.end(), passwd.begin(), passwd.end());
+        send_bytes(sock, {0x01, script.auth_status});
+        if (script.auth_status != 0x00) {
+            return;
+        }
+    }
+    
+    // VER, CMD, RSV, ATYP, DST.ADDR, DST.PORT
+    std::vector<uint8_t> request, address, port;
+    if (!read_bytes(sock, request, 4)) {
+        return;
+    }
+    size_t address_len = request[3] == 0x01 ? 4 : request[3] == 0x04 ? 16 : 0;
+    if (request[3] == 0x03) {
+        std::vector<uint8_t> name_len;
+        if (!read_bytes(sock, name_len, 1)) {
+            return;
+        }
+        request.push_back(name_len[0]);
+        address_len = name_len[0];
+    }
+    if (!read_bytes(sock, address, address_len) || !read_bytes(sock, port, 2)) {
+        return;
+    }
+    script.connect_request = request;
+    script.connect_request.insert(script.connect_request.end(), address.begin(), address.end());
+    script.connect_request.insert(script.connect_request.end(), port.begin(), port.end());
+    
+    // VER, REP, RSV, ATYP (IPv4), BND.ADDR, BND.PORT
+    send_bytes(sock, {0x05, script.reply_code, 0x00, 0x01, 127, 0, 0, 1, 0x1F, 0x90});
+}
+
+// Run socks5_connect against a server following script
+static bool socks5_connect_to(Socks5Script& script, const std::string& host, uint16_t port,
+                              const std::string& username = "", const std::string& password = "") {
+    LocalServer server([&script](socket_t sock, const std::string&) { serve_socks5(sock, script); });
+    socket_t sock = network::create_tcp_socket();
+    set_recv_timeout(sock, 5);
+    bool connected = network::connect_socket(sock, "127.0.0.1", server.port(), 2000) &&
+                     upstream::socks5_connect(sock, host, port, username, password);
+    network::close_socket(sock);
+    server.stop();
+    return connected;
+}
+
+static std::vector<uint8_t> bytes(const std::string& text) {
+    return std::vector<uint8_t>(text.begin(), text.end());
+}
+
+// RFC 1928: no-auth greeting, CONNECT with an IPv4 address (ATYP 0x01) or a domain name (ATYP 0x03)
+static void test_socks5_connect() {
+    Socks5Script ipv4;
+    CHECK(socks5_connect_to(ipv4, "93.184.216.34", 443));
+    CHECK((ipv4.greeting == std::vector<uint8_t>{0x05, 0x01, 0x00}));
+    CHECK((ipv4.connect_request == std::vector<uint8_t>{0x05, 0x01, 0x00, 0x01, 93, 184, 216, 34, 0x01, 0xBB}));
+    
+    Socks5Script domain;
+    CHECK(socks5_connect_to(domain, "example.com", 8080));
+    std::vector<uint8_t> expected = {0x05, 0x01, 0x00, 0x03, 11};
+    std::vector<uint8_t> name = bytes("example.com");
+    expected.insert(expected.end(), name.begin(), name.end());
+    expected.insert(expected.end(), {0x1F, 0x90});
+    CHECK(domain.connect_request == expected);
+    
+    // Any REP other than 0x00 (succeeded) is a failure, e.g. 0x05 connection refused
+    Socks5Script refused;
+    refused.reply_code = 0x05;
+    CHECK(!socks5_connect_to(refused, "93.184.216.34", 443));
+    CHECK(!refused.connect_request.empty()); // Failed at the reply, not before
+    
+    Socks5Script unreachable;
+    unreachable.reply_code = 0x04;
+    CHECK(!socks5_connect_to(unreachable, "example.com", 80));
+}
+
+// RFC 1929: with a username, method 0x02 is offered and the credentials are sent when selected
+static void test_socks5_auth() {
+    Socks5Script accepted;
+    accepted.require_auth = true;
+    CHECK(socks5_connect_to(accepted, "93.184.216.34", 80, "alice", "s3cret"));
+    CHECK((accepted.greeting == std::vector<uint8_t>{0x05, 0x02, 0x00, 0x02}));
+    std::vector<uint8_t> expected = {0x01, 5};
+    std::vector<uint8_t> user = bytes("alice");
+    std::vector<uint8_t> password = bytes("s3cret");
+    expected.insert(expected.end(), user.begin(), user.end());
+    expected.push_back(6);
+    expected.insert(expected.end(), password.begin(), password.end());
+    CHECK(accepted.auth_request == expected);
+    CHECK(!accepted.connect_request.empty());
+    
+    // Rejected credentials end the handshake before any CONNECT
+    Socks5Script rejected;
+    rejected.require_auth = true;
+    rejected.auth_status = 0x01;
+    CHECK(!socks5_connect_to(rejected, "93.184.216.34", 80, "alice", "wrong"));
+    CHECK(rejected.connect_request.empty());
+    
+    // A server insisting on authentication gets nowhere without credentials
+    Socks5Script no_credentials;
+    no_credentials.require_auth = true;
+    CHECK(!socks5_connect_to(no_credentials, "93.184.216.34", 80));
+    CHECK((no_credentials.greeting == std::vector<uint8_t>{0x05, 0x01, 0x00}));
+    CHECK(no_credentials.auth_request.empty());
+}
+
+int main() {
+    network::init();
+    test_socks5_connect();
+    test_socks5_auth();
+    return test_result("upstream");
+}
@@ -0,0 +1,101 @@
+#include "test.h"
+#include "test_tls.h"
+#include "tls.h"
+#include "upstream.h"
+
+// https upstream proxy on loopback: TLS with certificate, then a CONNECT answered with 200
+class TlsConnectProxy {
+public:
+    explicit TlsConnectProxy(const TestCertificate& certificate)
+        : context_(server_context(certificate)),
+          server_([this](socket_t sock, const std::string&) { serve(sock); }) {
+        CHECK(context_ != nullptr);
+    }
+    
+    uint16_t port() const { return server_.port(); }
+
+private:
+    std::shared_ptr<tls::ServerContext> context_;
+    LocalServer server_;
+    
+    static std::shared_ptr<tls::ServerContext> server_context(const TestCertificate& certificate) {
+        tls::ServerOptions options;
+        options.cert_path = certificate.cert_path;
+        options.key_path = certificate.key_path;
+        std::string error;
+        return tls::create_server_context(options, error);
+    }
+    
+    void serve(socket_t sock) {
+        std::string error;
+        if (!context_ || !tls::accept(sock, *context_, error)) {
+            return; // The client refused the certificate
+        }
+        std::string head;
+        char c;
+        while (head.find("\r\n\r\n") == std::string::npos && network::recv_data(sock, &c, 1) == 1) {
+            head += c;
+        }
+        if (head.compare(0, 8, "CONNECT ") == 0) {
+            std::string response = "HTTP/1.1 200 Connection established\r\n\r\n";
+            network::send_data(sock, response.data(), response.size());
+        }
+    }
+};
+
+// connect_to_proxy and a CONNECT through proxy
+static bool tunnel_through(const UpstreamProxyConfig& proxy) {
+    socket_t sock = network::create_tcp_socket();
+    set_recv_timeout(sock, 5);
+    bool connected = upstream::connect_to_proxy(sock, proxy, AF_INET, 2000) &&
+                     upstream::http_connect(sock, "example.com", 443);
+    network::close_socket(sock);
+    return connected;
+}
+
+static UpstreamProxyConfig https_proxy(uint16_t port) {
+    return UpstreamProxyConfig("https", "localhost", port);
+}
+
+// The proxy's certificate is verified against the trust store and the proxy's host name
+static void test_trusted_certificate(const TestCertificate& ca) {
+    TestCertificate certificate("upstream_tls_trusted", "localhost", &ca);
+    TlsConnectProxy proxy(certificate);
+    CHECK(tunnel_through(https_proxy(proxy.port())));
+    
+    // tls_server_name replaces the host as the name the certificate must match
+    UpstreamProxyConfig other_name = https_proxy(proxy.port());
+    other_name.tls_server_name = "proxy.example.net";
+    CHECK(!tunnel_through(other_name));
+    
+    TestCertificate named("upstream_tls_named", "proxy.example.net", &ca);
+    TlsConnectProxy named_proxy(named);
+    UpstreamProxyConfig by_name = https_proxy(named_proxy.port());
+    CHECK(!tunnel_through(by_name)); // Issued for proxy.example.net, not localhost
+    by_name.tls_server_name = "proxy.example.net";
+    CHECK(tunnel_through(by_name));
+}
+
+// A self-signed certificate fails verification unless tls_allow_self_signed, and even then its
+// name is checked
+static void test_self_signed_certificate() {
+    TestCertificate certificate("upstream_tls_self_signed", "localhost");
+    TlsConnectProxy proxy(certificate);
+    UpstreamProxyConfig config = https_proxy(proxy.port());
+    CHECK(!tunnel_through(config));
+    
+    config.tls_allow_self_signed = true;
+    CHECK(tunnel_through(config));
+    
+    config.tls_server_name = "proxy.example.net";
+    CHECK(!tunnel_through(config));
+}
+
+int main() {
+    network::init();
+    TestCertificate ca("upstream_tls_ca", "Smart Proxy Test CA", nullptr, true);
+    trust_only(ca);
+    test_trusted_certificate(ca);
+    test_self_signed_certificate();
+    return test_result("upstream_tls");
+}
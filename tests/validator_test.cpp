@@ -0,0 +1,218 @@
+#include "test.h"
+#include "validator.h"
+#include "utils.h"
+#include <chrono>
+#include <cstdio>
+#include <fstream>
+#include <map>
+#include <string>
+#include <vector>
+
+static std::vector<uint8_t> bytes(const std::string& text) {
+    return std::vector<uint8_t>(text.begin(), text.end());
+}
+
+// user_success of a 200 response with body, served as content_type
+static bool passes(HttpValidator& validator, const std::string& body,
+                   const std::string& content_type = "text/html; charset=utf-8") {
+    std::map<std::string, std::string> headers = {{"content-type", content_type}};
+    std::pair<bool, bool> result = validator.validate_for("example.com", 200, headers, bytes(body));
+    CHECK(result.first); // A 200 always reached the target
+    return result.second;
+}
+
+// block_patterns replaces the default patterns; matching is case-insensitive
+static void test_block_patterns() {
+    HttpValidator defaults(Config::parse_json("{}"));
+    CHECK(!passes(defaults, "<html><h1>Access Denied</h1></html>"));
+    CHECK(passes(defaults, "<html><h1>Welcome</h1></html>"));
+    
+    HttpValidator custom(Config::parse_json("{\"block_patterns\": [\"Sitio Restringido\"]}"));
+    CHECK(!passes(custom, "<html><h1>SITIO RESTRINGIDO por orden judicial</h1></html>"));
+    CHECK(passes(custom, "<html><h1>Access Denied</h1></html>"));
+    CHECK(passes(custom, "<html><h1>Welcome</h1></html>"));
+}
+
+// Only text bodies are scanned: the same bytes are a block page as HTML but not as an image
+static void test_text_content_only() {
+    HttpValidator validator(Config::parse_json("{}"));
+    std::string body = std::string("\x89PNG\r\n\x1a\n", 8) + "...blocked...";
+    CHECK(passes(validator, body, "image/png"));
+    CHECK(passes(validator, body, "application/octet-stream"));
+    CHECK(!passes(validator, body, "text/html"));
+    CHECK(!passes(validator, body, "text/plain; charset=utf-8"));
+}
+
+// A scan still running at user_validation_timeout is a user failure, not a pass. The slow step is
+// a long list of regexes that each take milliseconds on the body and never match: run to the end
+// they would take far longer than the 1 s timeout, so only the deadline can end the scan early
+static void test_validation_deadline() {
+    std::string body(60 * 1024, 'a');
+    for (size_t i = 0; i < body.size(); i += 7) {
+        body[i] = ' ';
+    }
+    
+    std::string one_regex = "{\"user_validation_timeout\": 1, \"block_regex_enabled\": true, "
+                            "\"block_regex_patterns\": [\"(a|b)+c\"]}";
+    HttpValidator quick(Config::parse_json(one_regex));
+    CHECK(passes(quick, body)); // Nothing matches
+    
+    std::string slow_regexes = "{\"user_validation_timeout\": 1, \"block_regex_enabled\": true, "
+                               "\"block_regex_patterns\": [";
+    for (int i = 0; i < 2000; ++i) {
+        slow_regexes += (i > 0 ? ", \"" : "\"") + std::string("(a|b)+c") + std::to_string(i) + "\"";
+    }
+    slow_regexes += "]}";
+    HttpValidator slow(Config::parse_json(slow_regexes));
+    
+    auto start = std::chrono::steady_clock::now();
+    CHECK(!passes(slow, body));
+    double elapsed = std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
+    CHECK(elapsed >= 1.0);
+    CHECK(elapsed < 10.0); // Stopped at the deadline rather than after every regex
+}
+
+// One backtracking-heavy regex on a large body: a single search over the whole scanned head would
+// run for minutes, but searches are bounded to windows and the deadline is checked between them
+static void test_regex_deadline_within_one_pattern() {
+    HttpValidator validator(Config::parse_json("{\"user_validation_timeout\": 1, \"block_regex_enabled\": true, "
+                                               "\"block_regex_patterns\": [\"(a|b)+c\"]}"));
+    auto start = std::chrono::steady_clock::now();
+    CHECK(!passes(validator, std::string(1024 * 1024, 'a')));
+    double elapsed = std::chrono::duration<double>(std::chrono::steady_clock::now() - start).count();
+    CHECK(elapsed < 10.0);
+}
+
+// Repeating a group that already repeats can backtrack exponentially: rejected at load and skipped
+static void test_nested_quantifiers_rejected() {
+    for (const char* regex : {"(a+)+", "(\\w+\\s?)*b", "(?:x*y)+", "((ab)*c)+", "(a+){2,}"}) {
+        CHECK(utils::has_nested_quantifier(regex));
+    }
+    for (const char* regex : {"(a|b)+c", "(\\d+\\.){3}\\d+", "[(a+)]+", "\\(a+\\)+", "<b>[^<]*denied</b>"}) {
+        CHECK(!utils::has_nested_quantifier(regex));
+    }
+    
+    Config config = Config::parse_json("{\"block_regex_enabled\": true, \"block_regex_patterns\": [\"(a+)+b\"]}");
+    CHECK(config.validate().size() == 1);
+    HttpValidator validator(config);
+    CHECK(passes(validator, std::string(64, 'a') + "b")); // Skipped, so the substring defaults apply
+}
+
+// A body far smaller than the learned size is flagged while normal ones keep arriving, but a
+// permanent change (every response is small from now on) is relearned and stops being flagged
+static void test_body_size_baseline_relearned() {
+    BodySizeValidator validator(Config::parse_json("{\"body_size_anomaly_ratio\": 0.2}"));
+    for (int i = 0; i < 5; ++i) {
+        CHECK(!validator.is_anomaly("example.com", "/page", 10000));
+    }
+    
+    // Tampering on one runway: stubs interleaved with full pages stay flagged
+    for (int i = 0; i < 20; ++i) {
+        CHECK(validator.is_anomaly("example.com", "/page?tampered", 500));
+        CHECK(!validator.is_anomaly("example.com", "/page", 10000));
+    }
+    
+    // The page shrank for good: flagged until the run is long enough, then the new normal
+    int flagged = 0;
+    for (int i = 0; i < 30; ++i) {
+        if (validator.is_anomaly("example.com", "/page", 1000)) {
+            ++flagged;
+        }
+    }
+    CHECK(flagged == 9);
+    for (int i = 0; i < 5; ++i) {
+        CHECK(!validator.is_anomaly("example.com", "/page", 1000));
+    }
+    CHECK(validator.is_anomaly("example.com", "/page", 50)); // Still checked against the new size
+}
+
+// A redirect off the requested site counts as a block unless its host is in redirect_allow
+static void test_foreign_redirect_blocked() {
+    auto redirect_passes = [](HttpValidator& validator, const std::string& location) {
+        std::map<std::string, std::string> headers = {{"location", location}};
+        std::pair<bool, bool> result = validator.validate_for("example.com", 302, headers, bytes(""));
+        CHECK(result.first);
+        return result.second;
+    };
+    
+    HttpValidator defaults(Config::parse_json("{}"));
+    CHECK(redirect_passes(defaults, "/home"));
+    CHECK(redirect_passes(defaults, "https://www.example.com/"));
+    CHECK(!redirect_passes(defaults, "http://10.10.34.34/"));
+    CHECK(!redirect_passes(defaults, "https://news.unrelated.org/"));
+    
+    HttpValidator allowing(Config::parse_json("{\"redirect_allow\": [\"*.unrelated.org\", \"sso.example.net\"]}"));
+    CHECK(redirect_passes(allowing, "https://news.unrelated.org/"));
+    CHECK(redirect_passes(allowing, "https://sso.example.net/login?next=/"));
+    CHECK(!redirect_passes(allowing, "https://other.example.net/"));
+}
+
+// An empty chain falls back to the status code; added validators can only veto, and the chain
+// stops at the first user failure
+static void test_validator_chain() {
+    std::map<std::string, std::string> headers = {{"content-type", "text/html"}};
+    std::vector<uint8_t> body = bytes("<html><h1>Welcome</h1></html>");
+    
+    ValidatorChain empty;
+    CHECK(empty.validate(ValidationContext{"example.com", "/", 200, headers, body}) == std::make_pair(true, true));
+    CHECK(empty.validate(ValidationContext{"example.com", "/", 302, headers, body}) == std::make_pair(true, true));
+    CHECK(empty.validate(ValidationContext{"example.com", "/", 503, headers, body}) == std::make_pair(false, false));
+    
+    struct CountingVeto : Validator {
+        int calls = 0;
+        std::pair<bool, bool> validate(const ValidationContext& context) override {
+            ++calls;
+            return std::make_pair(true, context.path != "/blocked");
+        }
+    };
+    auto veto = std::make_shared<CountingVeto>();
+    auto after = std::make_shared<CountingVeto>();
+    Config config = Config::parse_json("{}");
+    ValidatorChain chain;
+    chain.add(std::make_shared<StatusValidator>(config));
+    chain.add(std::make_shared<BlockPageValidator>(config));
+    chain.add(veto);
+    chain.add(after);
+    CHECK(chain.validate(ValidationContext{"example.com", "/", 200, headers, body}) == std::make_pair(true, true));
+    CHECK(chain.validate(ValidationContext{"example.com", "/blocked", 200, headers, body}) ==
+          std::make_pair(true, false));
+    CHECK(veto->calls == 2);
+    CHECK(after->calls == 1);
+    
+    std::vector<uint8_t> denied = bytes("<html><h1>Access Denied</h1></html>");
+    CHECK(chain.validate(ValidationContext{"example.com", "/", 200, headers, denied}) == std::make_pair(true, false));
+    CHECK(veto->calls == 2); // Stopped at the block page
+}
+
+// fingerprint_file hash entries are FNV-1a 64 of the normalized ASCII body, as body_fingerprint
+// (and the fingerprint command) computes it: a matching entry is a block page, another passes
+static void test_fingerprint_hash() {
+    std::vector<uint8_t> page = bytes("  <HTML><title>Notice</title>\r\n\t Site   Unavailable</HTML>\n");
+    CHECK(FingerprintValidator::body_fingerprint(page) == "33fcdba8b1fc9f54");
+    CHECK(FingerprintValidator::body_fingerprint(bytes("<html><title>notice</title> site unavailable</html>")) ==
+          "33fcdba8b1fc9f54");
+    
+    std::string path = "validator_test_fingerprints.txt";
+    {
+        std::ofstream file(path);
+        file << "# kind|value|label\n" << "hash|33fcdba8b1fc9f54|Test filter\n";
+    }
+    HttpValidator validator(Config::parse_json("{\"fingerprint_file\": \"" + path + "\"}"));
+    CHECK(!passes(validator, std::string(page.begin(), page.end())));
+    CHECK(!passes(validator, "<HTML><TITLE>Notice</TITLE>\n site  UNAVAILABLE</HTML>")); // Same after normalization
+    CHECK(passes(validator, "<html><title>notice</title> site available</html>"));
+    std::remove(path.c_str());
+}
+
+int main() {
+    test_block_patterns();
+    test_text_content_only();
+    test_validation_deadline();
+    test_regex_deadline_within_one_pattern();
+    test_nested_quantifiers_rejected();
+    test_body_size_baseline_relearned();
+    test_foreign_redirect_blocked();
+    test_validator_chain();
+    test_fingerprint_hash();
+    return test_result("validator");
+}
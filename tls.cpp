@@ -0,0 +1,347 @@
+#include "tls.h"
+
+#ifdef SMARTPROXY_WITH_TLS
+
+#include "utils.h"
+#include <openssl/ssl.h>
+#include <openssl/err.h>
+#include <openssl/x509v3.h>
+#include <openssl/sha.h>
+#include <map>
+#include <mutex>
+
+namespace tls {
+
+// Sessions attached to sockets, looked up on every send/recv
+static std::map<socket_t, SSL*> sessions;
+static std::mutex sessions_mutex;
+
+static SSL* find_session(socket_t sock) {
+    std::lock_guard<std::mutex> lock(sessions_mutex);
+    auto it = sessions.find(sock);
+    return it != sessions.end() ? it->second : nullptr;
+}
+
+// One client context for all connections: system trust store, TLS 1.2 minimum
+static SSL_CTX* client_context() {
+    static std::once_flag once;
+    static SSL_CTX* ctx = nullptr;
+    std::call_once(once, []() {
+        ctx = SSL_CTX_new(TLS_client_method());
+        if (ctx) {
+            SSL_CTX_set_min_proto_version(ctx, TLS1_2_VERSION);
+            SSL_CTX_set_default_verify_paths(ctx);
+        }
+    });
+    return ctx;
+}
+
+// Verification callback that tolerates only self-signed certificates;
+// every other error (expiry, name mismatch, ...) still fails the handshake
+static int accept_self_signed(int preverify_ok, X509_STORE_CTX* store) {
+    if (preverify_ok) {
+        return 1;
+    }
+    int err = X509_STORE_CTX_get_error(store);
+    return err == X509_V_ERR_DEPTH_ZERO_SELF_SIGNED_CERT || err == X509_V_ERR_SELF_SIGNED_CERT_IN_CHAIN;
+}
+
+static std::string last_error() {
+    unsigned long code = ERR_get_error();
+    if (code == 0) {
+        return "handshake failed";
+    }
+    char buffer[256];
+    ERR_error_string_n(code, buffer, sizeof(buffer));
+    return buffer;
+}
+
+bool available() {
+    return true;
+}
+
+struct ServerContext {
+    SSL_CTX* ctx;
+    
+    explicit ServerContext(SSL_CTX* server_ctx) : ctx(server_ctx) {}
+    ~ServerContext() { SSL_CTX_free(ctx); }
+    ServerContext(const ServerContext&) = delete;
+    ServerContext& operator=(const ServerContext&) = delete;
+};
+
+// Context of its own for a connection with its own trust anchors or client certificate;
+// null with error set if a file cannot be loaded
+static SSL_CTX* custom_client_context(const ClientOptions& options, std::string& error) {
+    SSL_CTX* ctx = SSL_CTX_new(TLS_client_method());
+    if (!ctx) {
+        error = "cannot create TLS context";
+        return nullptr;
+    }
+    SSL_CTX_set_min_proto_version(ctx, TLS1_2_VERSION);
+    
+    ERR_clear_error();
+    if (options.ca_path.empty()) {
+        SSL_CTX_set_default_verify_paths(ctx);
+    } else if (SSL_CTX_load_verify_locations(ctx, options.ca_path.c_str(), nullptr) != 1) {
+        error = "cannot load " + options.ca_path + ": " + last_error();
+        SSL_CTX_free(ctx);
+        return nullptr;
+    } else {
+        // A trusted certificate need not be a root, so the server's own certificate can be pinned
+        X509_VERIFY_PARAM_set_flags(SSL_CTX_get0_param(ctx), X509_V_FLAG_PARTIAL_CHAIN);
+    }
+    if (!options.cert_path.empty() &&
+        (SSL_CTX_use_certificate_chain_file(ctx, options.cert_path.c_str()) != 1 ||
+         SSL_CTX_use_PrivateKey_file(ctx, options.key_path.c_str(), SSL_FILETYPE_PEM) != 1)) {
+        error = "cannot load client certificate " + options.cert_path + ": " + last_error();
+        SSL_CTX_free(ctx);
+        return nullptr;
+    }
+    return ctx;
+}
+
+bool connect(socket_t sock, const ClientOptions& options, std::string& error) {
+    bool custom = !options.ca_path.empty() || !options.cert_path.empty();
+    SSL_CTX* ctx = custom ? custom_client_context(options, error) : client_context();
+    if (!ctx) {
+        if (!custom) {
+            error = "cannot create TLS context";
+        }
+        return false;
+    }
+    
+    SSL* ssl = SSL_new(ctx);
+    if (custom) {
+        SSL_CTX_free(ctx); // The session holds its own reference
+    }
+    if (!ssl) {
+        error = last_error();
+        return false;
+    }
+    SSL_set_fd(ssl, static_cast<int>(sock));
+    if (options.accept_invalid_certs) {
+        SSL_set_verify(ssl, SSL_VERIFY_NONE, nullptr);
+    } else {
+        SSL_set_verify(ssl, SSL_VERIFY_PEER, options.allow_self_signed ? accept_self_signed : nullptr);
+    }
+    
+    // RFC 6066 Section 3 - SNI carries host names only, never IP literals
+    bool is_ip = utils::is_valid_ipv4(options.server_name) || network::is_ipv6_address(options.server_name);
+    if (is_ip) {
+        X509_VERIFY_PARAM_set1_ip_asc(SSL_get0_param(ssl), options.server_name.c_str());
+    } else if (!options.server_name.empty()) { // Empty: the trust anchors alone decide
+        SSL_set_tlsext_host_name(ssl, options.server_name.c_str());
+        SSL_set1_host(ssl, options.server_name.c_str());
+    }
+    
+    ERR_clear_error();
+    if (SSL_connect(ssl) != 1) {
+        long verify_result = SSL_get_verify_result(ssl);
+        error = verify_result != X509_V_OK ? X509_verify_cert_error_string(verify_result) : last_error();
+        SSL_free(ssl);
+        return false;
+    }
+    
+    std::lock_guard<std::mutex> lock(sessions_mutex);
+    sessions[sock] = ssl;
+    return true;
+}
+
+std::shared_ptr<ServerContext> create_server_context(const ServerOptions& options, std::string& error) {
+    SSL_CTX* ctx = SSL_CTX_new(TLS_server_method());
+    if (!ctx) {
+        error = "cannot create TLS context";
+        return nullptr;
+    }
+    auto context = std::make_shared<ServerContext>(ctx);
+    SSL_CTX_set_min_proto_version(ctx, TLS1_2_VERSION);
+    
+    ERR_clear_error();
+    if (SSL_CTX_use_certificate_chain_file(ctx, options.cert_path.c_str()) != 1) {
+        error = "cannot load certificate " + options.cert_path + ": " + last_error();
+        return nullptr;
+    }
+    if (SSL_CTX_use_PrivateKey_file(ctx, options.key_path.c_str(), SSL_FILETYPE_PEM) != 1) {
+        error = "cannot load private key " + options.key_path + ": " + last_error();
+        return nullptr;
+    }
+    if (SSL_CTX_check_private_key(ctx) != 1) {
+        error = options.key_path + " is not the key of " + options.cert_path;
+        return nullptr;
+    }
+    
+    if (!options.client_ca_path.empty()) {
+        STACK_OF(X509_NAME)* ca_names = SSL_load_client_CA_file(options.client_ca_path.c_str());
+        if (!ca_names || SSL_CTX_load_verify_locations(ctx, options.client_ca_path.c_str(), nullptr) != 1) {
+            if (ca_names) {
+                sk_X509_NAME_pop_free(ca_names, X509_NAME_free);
+            }
+            error = "cannot load client CA " + options.client_ca_path + ": " + last_error();
+            return nullptr;
+        }
+        SSL_CTX_set_client_CA_list(ctx, ca_names); // Sent in the CertificateRequest, so clients pick the right certificate
+        // Without require_client_cert, a certificate is only asked for; one that is presented must still verify
+        SSL_CTX_set_verify(ctx, SSL_VERIFY_PEER | (options.require_client_cert ? SSL_VERIFY_FAIL_IF_NO_PEER_CERT : 0), nullptr);
+    }
+    return context;
+}
+
+bool accept(socket_t sock, const ServerContext& context, std::string& error) {
+    SSL* ssl = SSL_new(context.ctx);
+    if (!ssl) {
+        error = last_error();
+        return false;
+    }
+    SSL_set_fd(ssl, static_cast<int>(sock));
+    
+    ERR_clear_error();
+    if (SSL_accept(ssl) != 1) {
+        long verify_result = SSL_get_verify_result(ssl); // Why a client certificate was refused
+        error = verify_result != X509_V_OK ? "client certificate: " + std::string(X509_verify_cert_error_string(verify_result))
+                                           : last_error();
+        SSL_free(ssl);
+        return false;
+    }
+    
+    std::lock_guard<std::mutex> lock(sessions_mutex);
+    sessions[sock] = ssl;
+    return true;
+}
+
+std::string peer_name(socket_t sock) {
+    SSL* ssl = find_session(sock);
+    X509* cert = ssl ? SSL_get_peer_certificate(ssl) : nullptr;
+    if (!cert) {
+        return "";
+    }
+    std::string name;
+    if (SSL_get_verify_result(ssl) == X509_V_OK) {
+        X509_NAME* subject = X509_get_subject_name(cert);
+        char buffer[256];
+        if (X509_NAME_get_text_by_NID(subject, NID_commonName, buffer, sizeof(buffer)) > 0) {
+            name = buffer;
+        } else {
+            char* text = X509_NAME_oneline(subject, nullptr, 0);
+            if (text) {
+                name = text;
+                OPENSSL_free(text);
+            }
+        }
+    }
+    X509_free(cert);
+    return name;
+}
+
+std::string peer_key_pin(socket_t sock) {
+    SSL* ssl = find_session(sock);
+    X509* cert = ssl ? SSL_get_peer_certificate(ssl) : nullptr;
+    if (!cert) {
+        return "";
+    }
+    std::string pin;
+    unsigned char* spki = nullptr;
+    int spki_len = i2d_X509_PUBKEY(X509_get_X509_PUBKEY(cert), &spki);
+    if (spki_len > 0) {
+        unsigned char digest[SHA256_DIGEST_LENGTH];
+        SHA256(spki, static_cast<size_t>(spki_len), digest);
+        pin = "sha256/" + utils::base64_encode(std::string(reinterpret_cast<const char*>(digest), sizeof(digest)));
+        OPENSSL_free(spki);
+    }
+    X509_free(cert);
+    return pin;
+}
+
+bool is_attached(socket_t sock) {
+    return find_session(sock) != nullptr;
+}
+
+ssize_t send(socket_t sock, const void* data, size_t len) {
+    SSL* ssl = find_session(sock);
+    if (!ssl) {
+        return -1;
+    }
+    int result = SSL_write(ssl, data, static_cast<int>(len));
+    return result > 0 ? result : -1;
+}
+
+ssize_t recv(socket_t sock, void* buffer, size_t len, bool peek) {
+    SSL* ssl = find_session(sock);
+    if (!ssl) {
+        return -1;
+    }
+    int result = peek ? SSL_peek(ssl, buffer, static_cast<int>(len)) : SSL_read(ssl, buffer, static_cast<int>(len));
+    if (result > 0) {
+        return result;
+    }
+    return SSL_get_error(ssl, result) == SSL_ERROR_ZERO_RETURN ? 0 : -1; // 0 = peer sent close_notify
+}
+
+void detach(socket_t sock) {
+    SSL* ssl = nullptr;
+    {
+        std::lock_guard<std::mutex> lock(sessions_mutex);
+        auto it = sessions.find(sock);
+        if (it == sessions.end()) {
+            return;
+        }
+        ssl = it->second;
+        sessions.erase(it);
+    }
+    SSL_shutdown(ssl); // One-way close_notify; the socket is closed right after
+    SSL_free(ssl);
+}
+
+} // namespace tls
+
+#else // !SMARTPROXY_WITH_TLS
+
+namespace tls {
+
+bool available() {
+    return false;
+}
+
+bool connect(socket_t /*sock*/, const ClientOptions& /*options*/, std::string& error) {
+    error = "built without TLS support (rebuild with -DSMARTPROXY_TLS=ON)";
+    return false;
+}
+
+struct ServerContext {
+};
+
+std::shared_ptr<ServerContext> create_server_context(const ServerOptions& /*options*/, std::string& error) {
+    error = "built without TLS support (rebuild with -DSMARTPROXY_TLS=ON)";
+    return nullptr;
+}
+
+bool accept(socket_t /*sock*/, const ServerContext& /*context*/, std::string& error) {
+    error = "built without TLS support (rebuild with -DSMARTPROXY_TLS=ON)";
+    return false;
+}
+
+std::string peer_name(socket_t /*sock*/) {
+    return "";
+}
+
+std::string peer_key_pin(socket_t /*sock*/) {
+    return "";
+}
+
+bool is_attached(socket_t /*sock*/) {
+    return false;
+}
+
+ssize_t send(socket_t /*sock*/, const void* /*data*/, size_t /*len*/) {
+    return -1;
+}
+
+ssize_t recv(socket_t /*sock*/, void* /*buffer*/, size_t /*len*/, bool /*peek*/) {
+    return -1;
+}
+
+void detach(socket_t /*sock*/) {
+}
+
+} // namespace tls
+
+#endif // SMARTPROXY_WITH_TLS
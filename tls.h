@@ -0,0 +1,74 @@
+#ifndef TLS_H
+#define TLS_H
+
+#include <string>
+#include <cstdint>
+#include <memory>
+#include "network.h"
+
+// Optional TLS layer (RFC 8446 - TLS 1.3, RFC 5246 - TLS 1.2)
+// Built only with the SMARTPROXY_TLS CMake option, which links OpenSSL;
+// the default build keeps zero external dependencies and reports TLS as unavailable.
+//
+// A TLS session is attached to an already connected socket. While attached,
+// network::send_data/recv_data transparently go through it, so protocol code
+// (SOCKS, CONNECT, HTTP) works unchanged; network::close_socket releases it.
+
+namespace tls {
+
+// True if this build includes TLS support
+bool available();
+
+struct ClientOptions {
+    std::string server_name; // SNI and the name the certificate must match (RFC 6125); empty = name not checked
+    bool allow_self_signed; // Accept a self-signed certificate (the name is still checked)
+    bool accept_invalid_certs; // Skip verification altogether: any certificate, for any name
+    std::string ca_path; // PEM certificates trusted instead of the system store; may be the server's own certificate
+    std::string cert_path; // PEM client certificate (and intermediates) presented if the server asks for one
+    std::string key_path; // PEM private key of cert_path
+    
+    ClientOptions() : allow_self_signed(false), accept_invalid_certs(false) {}
+};
+
+// TLS client handshake over a connected socket; on success the session is attached to sock
+// The peer certificate is verified against the system trust store (or ca_path) and server_name
+bool connect(socket_t sock, const ClientOptions& options, std::string& error);
+
+struct ServerOptions {
+    std::string cert_path; // PEM certificate presented to clients, followed by any intermediates
+    std::string key_path; // PEM private key of that certificate
+    std::string client_ca_path; // PEM CA certificates; if set, client certificates must be issued by one of them
+    bool require_client_cert; // With client_ca_path: fail the handshake of clients presenting no certificate at all
+    
+    ServerOptions() : require_client_cert(true) {}
+};
+
+// Certificate and key loaded once for every connection accepted with them
+struct ServerContext;
+
+// Load options into a server context; null with error set if a file is missing or the key does not match
+std::shared_ptr<ServerContext> create_server_context(const ServerOptions& options, std::string& error);
+
+// TLS server handshake over an accepted socket; on success the session is attached to sock
+bool accept(socket_t sock, const ServerContext& context, std::string& error);
+
+// Common name (or, lacking one, the subject) of the verified client certificate on sock; empty if none
+std::string peer_name(socket_t sock);
+
+// "sha256/<base64>" hash of the public key (SubjectPublicKeyInfo) of the peer certificate on sock,
+// as pinned in RFC 7469 Section 2.4; empty if there is none
+std::string peer_key_pin(socket_t sock);
+
+// True if a TLS session is attached to sock
+bool is_attached(socket_t sock);
+
+// Send/receive through the attached session (same return conventions as network::send_data/recv_data)
+ssize_t send(socket_t sock, const void* data, size_t len);
+ssize_t recv(socket_t sock, void* buffer, size_t len, bool peek);
+
+// Send close_notify and release the session attached to sock, if any
+void detach(socket_t sock);
+
+} // namespace tls
+
+#endif // TLS_H
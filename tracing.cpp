@@ -0,0 +1,326 @@
+#include "tracing.h"
+
+#ifdef SMARTPROXY_WITH_TRACING
+
+#include "admin.h"
+#include "network.h"
+#include "logger.h"
+#include "utils.h"
+#include "webui_json.h"
+#include <atomic>
+#include <mutex>
+#include <condition_variable>
+#include <thread>
+#include <deque>
+#include <vector>
+#include <random>
+#include <chrono>
+#include <sstream>
+#include <iomanip>
+
+namespace tracing {
+
+// Finished spans waiting for export; past this many, new ones are dropped
+static const size_t MAX_QUEUED_SPANS = 2048;
+
+// Spans per export request; a full batch is sent at once, a partial one every EXPORT_INTERVAL_SECS
+static const size_t EXPORT_BATCH_SPANS = 512;
+static const int EXPORT_INTERVAL_SECS = 5;
+
+struct FinishedSpan {
+    std::string trace_id; // 32 hex digits
+    std::string span_id; // 16 hex digits
+    std::string parent_span_id; // Empty for a root span
+    std::string name;
+    SpanKind kind;
+    uint64_t start_ns; // Unix time in nanoseconds
+    uint64_t end_ns;
+    std::vector<std::pair<std::string, std::string>> attributes; // Key -> OTLP AnyValue as JSON
+    bool error;
+    std::string error_message;
+};
+
+struct SpanData {
+    FinishedSpan span;
+    SpanData* parent; // Span that was open on this thread when this one started
+};
+
+static std::atomic<bool> g_started(false);
+static std::mutex g_mutex;
+static std::condition_variable g_wake;
+static std::deque<FinishedSpan> g_queue;
+static size_t g_dropped = 0;
+static bool g_stopping = false;
+static std::thread g_exporter;
+static std::string g_host;
+static uint16_t g_port = 80;
+static std::string g_path;
+static std::string g_service_name;
+
+// Innermost open span of this thread
+static thread_local SpanData* t_current = nullptr;
+
+// Unsampled spans open on this thread; while there are any, no span is recorded
+static thread_local int t_unsampled = 0;
+
+static uint64_t now_ns() {
+    return static_cast<uint64_t>(std::chrono::duration_cast<std::chrono::nanoseconds>(
+        std::chrono::system_clock::now().time_since_epoch()).count());
+}
+
+// Random id of words x 8 bytes, as lowercase hex (W3C Trace Context ids)
+static std::string random_id(size_t words) {
+    static thread_local std::mt19937_64 generator(std::random_device{}() ^ now_ns());
+    std::ostringstream oss;
+    oss << std::hex << std::setfill('0');
+    for (size_t i = 0; i < words; ++i) {
+        oss << std::setw(16) << generator();
+    }
+    return oss.str();
+}
+
+static std::string attribute(const std::string& key, const std::string& value) {
+    using namespace webui_json;
+    return build_object({{"key", encode_string(key)}, {"value", value}});
+}
+
+// OTLP/JSON ExportTraceServiceRequest; 64-bit integers are strings (proto3 JSON mapping)
+static std::string build_request(const std::vector<FinishedSpan>& batch) {
+    using namespace webui_json;
+    std::vector<std::string> spans;
+    for (const auto& span : batch) {
+        std::vector<std::string> attributes;
+        for (const auto& pair : span.attributes) {
+            attributes.push_back(attribute(pair.first, pair.second));
+        }
+        std::vector<std::pair<std::string, std::string>> fields = {
+            {"traceId", encode_string(span.trace_id)},
+            {"spanId", encode_string(span.span_id)},
+            {"name", encode_string(span.name)},
+            {"kind", encode_int(static_cast<int64_t>(span.kind))},
+            {"startTimeUnixNano", encode_string(std::to_string(span.start_ns))},
+            {"endTimeUnixNano", encode_string(std::to_string(span.end_ns))},
+            {"attributes", build_array(attributes)}
+        };
+        if (!span.parent_span_id.empty()) {
+            fields.push_back({"parentSpanId", encode_string(span.parent_span_id)});
+        }
+        if (span.error) {
+            fields.push_back({"status", build_object({{"code", encode_int(2)},
+                                                      {"message", encode_string(span.error_message)}})});
+        }
+        spans.push_back(build_object(fields));
+    }
+    
+    std::string resource = build_object({{"attributes", build_array({
+        attribute("service.name", build_object({{"stringValue", encode_string(g_service_name)}}))
+    })}});
+    std::string scope_spans = build_object({{"scope", build_object({{"name", encode_string("smartproxy")}})},
+                                            {"spans", build_array(spans)}});
+    return build_object({{"resourceSpans", build_array({
+        build_object({{"resource", resource}, {"scopeSpans", build_array({scope_spans})}})
+    })}});
+}
+
+static void export_batch(const std::vector<FinishedSpan>& batch) {
+    // Log the first failure of a run of failures, and the recovery, not every attempt
+    static bool failing = false;
+    
+    std::string ip = g_host;
+    int status = 0;
+    std::string reply;
+    bool sent = (utils::is_valid_ipv4(ip) || network::resolve_hostname(g_host, ip)) &&
+                admin::request(ip, g_port, "POST", g_path, build_request(batch), status, reply, 10) &&
+                status >= 200 && status < 300;
+    if (!sent && !failing) {
+        Logger::instance().log(LogLevel::WARN, "OTLP export to " + g_host + ":" + std::to_string(g_port) + g_path +
+                               " failed" + (status > 0 ? " (HTTP " + std::to_string(status) + ")" : "") +
+                               "; spans are dropped until it succeeds");
+    } else if (sent && failing) {
+        Logger::instance().log(LogLevel::INFO, "OTLP export is working again");
+    }
+    failing = !sent;
+}
+
+static void exporter_loop() {
+    while (true) {
+        std::vector<FinishedSpan> batch;
+        size_t dropped = 0;
+        {
+            std::unique_lock<std::mutex> lock(g_mutex);
+            g_wake.wait_for(lock, std::chrono::seconds(EXPORT_INTERVAL_SECS),
+                            []() { return g_stopping || g_queue.size() >= EXPORT_BATCH_SPANS; });
+            while (!g_queue.empty() && batch.size() < EXPORT_BATCH_SPANS) {
+                batch.push_back(std::move(g_queue.front()));
+                g_queue.pop_front();
+            }
+            dropped = g_dropped;
+            g_dropped = 0;
+            if (batch.empty() && g_stopping) {
+                return;
+            }
+        }
+        
+        if (dropped > 0) {
+            Logger::instance().log(LogLevel::WARN, "Tracing queue full, dropped " + std::to_string(dropped) + " spans");
+        }
+        if (!batch.empty()) {
+            export_batch(batch);
+        }
+    }
+}
+
+bool available() {
+    return true;
+}
+
+bool start(const std::string& endpoint, const std::string& service_name, std::string& error) {
+    // Plain HTTP only, like the alert webhook: collectors usually run next to the service
+    const std::string scheme = "http://";
+    if (utils::to_lower(endpoint.substr(0, scheme.length())) != scheme) {
+        error = "otlp_endpoint must start with http://";
+        return false;
+    }
+    std::string rest = endpoint.substr(scheme.length());
+    size_t path_pos = rest.find('/');
+    std::string authority = rest.substr(0, path_pos);
+    std::string path = path_pos == std::string::npos || rest.substr(path_pos) == "/" ? "/v1/traces" : rest.substr(path_pos);
+    std::string host = authority;
+    uint16_t port = 80;
+    size_t colon_pos = authority.find(':');
+    if (colon_pos != std::string::npos) {
+        host = authority.substr(0, colon_pos);
+        if (!utils::safe_str_to_uint16(authority.substr(colon_pos + 1), port) || port == 0) {
+            error = "otlp_endpoint has an invalid port";
+            return false;
+        }
+    }
+    if (host.empty()) {
+        error = "otlp_endpoint has no host";
+        return false;
+    }
+    
+    if (g_started) {
+        return true;
+    }
+    g_host = host;
+    g_port = port;
+    g_path = path;
+    g_service_name = service_name;
+    g_stopping = false;
+    g_exporter = std::thread(exporter_loop);
+    g_started = true;
+    return true;
+}
+
+void stop() {
+    if (!g_started) {
+        return;
+    }
+    g_started = false;
+    {
+        std::lock_guard<std::mutex> lock(g_mutex);
+        g_stopping = true;
+    }
+    g_wake.notify_all();
+    if (g_exporter.joinable()) {
+        g_exporter.join();
+    }
+}
+
+Span::Span(const char* name, SpanKind kind, bool sampled) : unsampled_(false) {
+    if (!g_started || t_unsampled > 0) {
+        return;
+    }
+    if (!sampled) {
+        unsampled_ = true;
+        t_unsampled++;
+        return;
+    }
+    data_.reset(new SpanData());
+    FinishedSpan& span = data_->span;
+    span.name = name;
+    span.kind = kind;
+    span.start_ns = now_ns();
+    span.end_ns = 0;
+    span.error = false;
+    data_->parent = t_current;
+    if (t_current) {
+        span.trace_id = t_current->span.trace_id;
+        span.parent_span_id = t_current->span.span_id;
+    } else {
+        span.trace_id = random_id(2);
+    }
+    span.span_id = random_id(1);
+    t_current = data_.get();
+}
+
+Span::~Span() {
+    if (unsampled_) {
+        t_unsampled--;
+        return;
+    }
+    if (!data_) {
+        return;
+    }
+    data_->span.end_ns = now_ns();
+    t_current = data_->parent;
+    
+    bool wake = false;
+    {
+        std::lock_guard<std::mutex> lock(g_mutex);
+        if (g_stopping) {
+            return;
+        }
+        if (g_queue.size() >= MAX_QUEUED_SPANS) {
+            g_dropped++;
+        } else {
+            g_queue.push_back(std::move(data_->span));
+            wake = g_queue.size() == EXPORT_BATCH_SPANS;
+        }
+    }
+    if (wake) {
+        g_wake.notify_one();
+    }
+}
+
+void Span::set_attribute(const char* key, const std::string& value) {
+    if (data_) {
+        data_->span.attributes.emplace_back(key, webui_json::build_object({{"stringValue", webui_json::encode_string(value)}}));
+    }
+}
+
+void Span::set_attribute(const char* key, int64_t value) {
+    if (data_) {
+        data_->span.attributes.emplace_back(key, webui_json::build_object({{"intValue", webui_json::encode_string(std::to_string(value))}}));
+    }
+}
+
+void Span::set_error(const std::string& message) {
+    if (data_) {
+        data_->span.error = true;
+        data_->span.error_message = message;
+    }
+}
+
+} // namespace tracing
+
+#else // !SMARTPROXY_WITH_TRACING
+
+namespace tracing {
+
+bool available() {
+    return false;
+}
+
+bool start(const std::string& /*endpoint*/, const std::string& /*service_name*/, std::string& error) {
+    error = "built without tracing support (rebuild with -DSMARTPROXY_TRACING=ON)";
+    return false;
+}
+
+void stop() {
+}
+
+} // namespace tracing
+
+#endif // SMARTPROXY_WITH_TRACING
@@ -0,0 +1,74 @@
+#ifndef TRACING_H
+#define TRACING_H
+
+#include <string>
+#include <cstdint>
+#include <memory>
+
+// Optional OpenTelemetry tracing: spans exported to an OTLP/HTTP collector (JSON encoding, POST /v1/traces)
+// Built only with the SMARTPROXY_TRACING CMake option; without it Span is an empty inline class
+// and tracing compiles away. With it, spans are only recorded once start() was called (otlp_endpoint).
+//
+// A Span covers the scope it lives in. Spans nest per thread: a span started while another is open on
+// the same thread becomes its child, so callers need not pass parents around. A span started with
+// sampled = false is not recorded, and neither is anything nested in it.
+
+namespace tracing {
+
+// True if this build includes tracing support
+bool available();
+
+// Export finished spans in the background to endpoint ("http://host[:port][/path]"; the path defaults
+// to /v1/traces), as service service_name; false with error set if the endpoint is unusable
+bool start(const std::string& endpoint, const std::string& service_name, std::string& error);
+
+// Send what is still queued and stop exporting
+void stop();
+
+// OTLP span kinds
+enum class SpanKind {
+    Internal = 1,
+    Server = 2, // Handling a client's request
+    Client = 3  // A request to another service (DNS server, upstream proxy, target)
+};
+
+#ifdef SMARTPROXY_WITH_TRACING
+
+struct SpanData;
+
+class Span {
+public:
+    explicit Span(const char* name, SpanKind kind = SpanKind::Internal, bool sampled = true);
+    ~Span();
+    Span(const Span&) = delete;
+    Span& operator=(const Span&) = delete;
+    
+    void set_attribute(const char* key, const std::string& value);
+    void set_attribute(const char* key, int64_t value);
+    
+    // Mark the span failed (OTLP status code ERROR)
+    void set_error(const std::string& message);
+
+private:
+    std::unique_ptr<SpanData> data_; // Null while tracing is not started, or the span is not recorded
+    bool unsampled_; // Started with sampled = false: spans nested in it are not recorded either
+};
+
+#else // !SMARTPROXY_WITH_TRACING
+
+class Span {
+public:
+    explicit Span(const char* /*name*/, SpanKind /*kind*/ = SpanKind::Internal, bool /*sampled*/ = true) {}
+    Span(const Span&) = delete;
+    Span& operator=(const Span&) = delete;
+    
+    void set_attribute(const char* /*key*/, const std::string& /*value*/) {}
+    void set_attribute(const char* /*key*/, int64_t /*value*/) {}
+    void set_error(const std::string& /*message*/) {}
+};
+
+#endif // SMARTPROXY_WITH_TRACING
+
+} // namespace tracing
+
+#endif // TRACING_H
@@ -1,6 +1,9 @@
 #include "tracker.h"
+#include "webui_json.h"
 #include <ctime>
 #include <algorithm>
+#include <sstream>
+#include <iomanip>
 
 #ifdef _WIN32
 #include <winsock2.h>
@@ -8,9 +11,15 @@
 #include <sys/time.h>
 #endif
 
-TargetAccessibilityTracker::TargetAccessibilityTracker(size_t success_rate_window, double success_rate_threshold)
+TargetAccessibilityTracker::TargetAccessibilityTracker(size_t success_rate_window, double success_rate_threshold,
+                                                       uint64_t uptime_window_secs, double latency_ema_alpha,
+                                                       uint64_t probe_backoff_base_secs, uint64_t probe_backoff_max_secs)
     : success_rate_window_(success_rate_window)
-    , success_rate_threshold_(success_rate_threshold) {
+    , success_rate_threshold_(success_rate_threshold)
+    , uptime_window_secs_(uptime_window_secs)
+    , latency_ema_alpha_(latency_ema_alpha)
+    , probe_backoff_base_secs_(probe_backoff_base_secs)
+    , probe_backoff_max_secs_(probe_backoff_max_secs) {
 }
 
 uint64_t TargetAccessibilityTracker::get_current_time() const {
@@ -30,22 +39,86 @@ TargetMetrics& TargetAccessibilityTracker::get_or_create_metrics(
 }
 
 void TargetMetrics::update_success_rate(size_t /*window*/) {
-    if (recent_attempts.empty()) {
-        success_rate = 0.0;
-        return;
+    auto rate = [](const std::vector<bool>& attempts) {
+        if (attempts.empty()) {
+            return 0.0;
+        }
+        size_t success_count = 0;
+        for (bool attempt : attempts) {
+            if (attempt) success_count++;
+        }
+        return static_cast<double>(success_count) / attempts.size();
+    };
+    
+    success_rate = rate(recent_attempts);
+    network_success_rate = rate(recent_network_attempts);
+}
+
+void TargetMetrics::record_state(uint64_t now, uint64_t period) {
+    if (state_history.empty() || state_history.back().second != state) {
+        state_history.push_back(std::make_pair(now, state));
     }
     
-    size_t success_count = 0;
-    for (bool attempt : recent_attempts) {
-        if (attempt) success_count++;
+    // Keep the last transition before the window start so the state at window start is known
+    uint64_t window_start = (now > period) ? now - period : 0;
+    while (state_history.size() >= 2 && state_history[1].first <= window_start) {
+        state_history.erase(state_history.begin());
     }
-    success_rate = static_cast<double>(success_count) / recent_attempts.size();
+}
+
+double TargetMetrics::uptime_ratio(uint64_t now, uint64_t period) const {
+    if (state_history.empty()) {
+        return 0.0;
+    }
+    
+    uint64_t window_start = (now > period) ? now - period : 0;
+    uint64_t observed_start = std::max(state_history.front().first, window_start);
+    if (now <= observed_start) {
+        return (state_history.back().second == RunwayState::Accessible) ? 1.0 : 0.0;
+    }
+    
+    uint64_t up_time = 0;
+    for (size_t i = 0; i < state_history.size(); ++i) {
+        uint64_t seg_start = std::max(state_history[i].first, window_start);
+        uint64_t seg_end = (i + 1 < state_history.size()) ? state_history[i + 1].first : now;
+        if (seg_end <= seg_start) continue;
+        if (state_history[i].second == RunwayState::Accessible) {
+            up_time += seg_end - seg_start;
+        }
+    }
+    
+    return static_cast<double>(up_time) / static_cast<double>(now - observed_start);
 }
 
 void TargetAccessibilityTracker::update(const std::string& target, const std::string& runway_id,
-                                         bool network_success, bool user_success, double response_time_secs) {
-    std::lock_guard<std::mutex> lock(mutex_);
-    TargetMetrics& metrics = metrics_[target][runway_id];
+                                         bool network_success, bool user_success, double response_time_secs,
+                                         double dns_time_secs) {
+    std::vector<TargetMetrics> down_runways;
+    TargetDownCallback callback;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        bool went_down = apply_update(target, runway_id, network_success, user_success,
+                                      response_time_secs, dns_time_secs);
+        if (went_down && target_down_callback_) {
+            callback = target_down_callback_;
+            for (const auto& pair : metrics_[target]) {
+                down_runways.push_back(pair.second);
+            }
+        }
+    }
+    
+    // Invoke outside the lock so the callback may query the tracker
+    if (callback) {
+        callback(target, down_runways);
+    }
+}
+
+bool TargetAccessibilityTracker::apply_update(const std::string& target, const std::string& runway_id,
+                                               bool network_success, bool user_success, double response_time_secs,
+                                               double dns_time_secs) {
+    std::map<std::string, TargetMetrics>& target_runways = metrics_[target];
+    bool was_accessible = has_accessible_runway(target_runways);
+    TargetMetrics& metrics = target_runways[runway_id];
     
     if (metrics.target.empty()) {
         metrics.target = target;
@@ -54,12 +127,28 @@ void TargetAccessibilityTracker::update(const std::string& target, const std::st
     
     metrics.total_attempts++;
     uint64_t current_time = get_current_time();
+    metrics.last_used = current_time;
+    metrics.last_evaluated = current_time;
+    
+    // DNS time is only reported for uncached lookups
+    if (dns_time_secs > 0.0) {
+        if (metrics.avg_dns_time == 0.0) {
+            metrics.avg_dns_time = dns_time_secs;
+        } else {
+            metrics.avg_dns_time = metrics.avg_dns_time * (1.0 - latency_ema_alpha_) +
+                                   dns_time_secs * latency_ema_alpha_;
+        }
+    }
     
     // Update recent attempts
     metrics.recent_attempts.push_back(user_success);
     if (metrics.recent_attempts.size() > success_rate_window_) {
         metrics.recent_attempts.erase(metrics.recent_attempts.begin());
     }
+    metrics.recent_network_attempts.push_back(network_success);
+    if (metrics.recent_network_attempts.size() > success_rate_window_) {
+        metrics.recent_network_attempts.erase(metrics.recent_network_attempts.begin());
+    }
     
     if (network_success && user_success) {
         metrics.network_success_count++;
@@ -67,17 +156,21 @@ void TargetAccessibilityTracker::update(const std::string& target, const std::st
         metrics.state = RunwayState::Accessible;
         metrics.last_success_time = current_time;
         metrics.consecutive_failures = 0;
+        metrics.next_probe_at = 0;
         
         // Update average response time (exponential moving average)
         if (metrics.avg_response_time == 0.0) {
             metrics.avg_response_time = response_time_secs;
         } else {
-            metrics.avg_response_time = metrics.avg_response_time * 0.7 + response_time_secs * 0.3;
+            metrics.avg_response_time = metrics.avg_response_time * (1.0 - latency_ema_alpha_) +
+                                        response_time_secs * latency_ema_alpha_;
         }
     } else if (network_success && !user_success) {
         metrics.network_success_count++;
         metrics.partial_success_count++;
         metrics.state = RunwayState::PartiallyAccessible;
+        metrics.next_probe_at = 0; // The path itself works again
+
     } else {
         metrics.failure_count++;
         metrics.last_failure_time = current_time;
@@ -85,6 +178,11 @@ void TargetAccessibilityTracker::update(const std::string& target, const std::st
         
         if (metrics.consecutive_failures > 3) {
             metrics.state = RunwayState::Inaccessible;
+            
+            // Circuit open: wait base, 2x base, 4x base, ... (capped) before the next half-open probe
+            uint32_t doublings = std::min<uint32_t>(metrics.consecutive_failures - 4, 20);
+            uint64_t backoff = std::min(probe_backoff_base_secs_ << doublings, probe_backoff_max_secs_);
+            metrics.next_probe_at = current_time + backoff;
         }
     }
     
@@ -95,6 +193,54 @@ void TargetAccessibilityTracker::update(const std::string& target, const std::st
     }
     
     metrics.update_success_rate(success_rate_window_);
+    metrics.record_state(current_time, uptime_window_secs_);
+    
+    return was_accessible && !has_accessible_runway(target_runways);
+}
+
+void TargetAccessibilityTracker::set_target_down_callback(TargetDownCallback callback) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    target_down_callback_ = callback;
+}
+
+bool TargetAccessibilityTracker::is_accessible(const TargetMetrics& metrics) const {
+    if (metrics.state == RunwayState::Accessible) {
+        return true;
+    }
+    return metrics.state == RunwayState::PartiallyAccessible &&
+           metrics.success_rate >= success_rate_threshold_;
+}
+
+bool TargetAccessibilityTracker::has_accessible_runway(const std::map<std::string, TargetMetrics>& runways) const {
+    for (const auto& pair : runways) {
+        if (is_accessible(pair.second)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+void TargetAccessibilityTracker::set_target_port(const std::string& target, uint16_t port) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    target_ports_[target] = port;
+}
+
+uint16_t TargetAccessibilityTracker::get_target_port(const std::string& target) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    auto it = target_ports_.find(target);
+    return it != target_ports_.end() ? it->second : 80;
+}
+
+void TargetAccessibilityTracker::record_bytes(const std::string& target, const std::string& runway_id,
+                                              uint64_t bytes) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    TargetMetrics& metrics = metrics_[target][runway_id];
+    if (metrics.target.empty()) {
+        metrics.target = target;
+        metrics.runway_id = runway_id;
+    }
+    metrics.bytes_transferred += bytes;
+    metrics.last_used = get_current_time();
 }
 
 std::vector<std::string> TargetAccessibilityTracker::get_accessible_runways(const std::string& target) {
@@ -107,13 +253,8 @@ std::vector<std::string> TargetAccessibilityTracker::get_accessible_runways(cons
     }
     
     for (const auto& pair : target_it->second) {
-        const TargetMetrics& metrics = pair.second;
-        if (metrics.state == RunwayState::Accessible) {
+        if (is_accessible(pair.second)) {
             accessible.push_back(pair.first);
-        } else if (metrics.state == RunwayState::PartiallyAccessible) {
-            if (metrics.success_rate >= success_rate_threshold_) {
-                accessible.push_back(pair.first);
-            }
         }
     }
     
@@ -146,6 +287,35 @@ std::vector<std::string> TargetAccessibilityTracker::get_all_targets() {
     return targets;
 }
 
+std::map<std::string, std::map<std::string, TargetMetrics>> TargetAccessibilityTracker::snapshot() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return metrics_;
+}
+
+std::vector<std::string> TargetAccessibilityTracker::get_targets_by_staleness() {
+    std::vector<std::pair<uint64_t, std::string>> evaluated;
+    {
+        std::lock_guard<std::mutex> lock(mutex_);
+        for (const auto& target_pair : metrics_) {
+            uint64_t last_evaluated = 0;
+            for (const auto& pair : target_pair.second) {
+                last_evaluated = std::max(last_evaluated, pair.second.last_evaluated);
+            }
+            evaluated.emplace_back(last_evaluated, target_pair.first);
+        }
+    }
+    
+    std::stable_sort(evaluated.begin(), evaluated.end(),
+                     [](const std::pair<uint64_t, std::string>& a, const std::pair<uint64_t, std::string>& b) {
+                         return a.first < b.first;
+                     });
+    std::vector<std::string> targets;
+    for (const auto& pair : evaluated) {
+        targets.push_back(pair.second);
+    }
+    return targets;
+}
+
 std::map<std::string, TargetMetrics> TargetAccessibilityTracker::get_target_metrics(const std::string& target) {
     std::lock_guard<std::mutex> lock(mutex_);
     
@@ -156,3 +326,193 @@ std::map<std::string, TargetMetrics> TargetAccessibilityTracker::get_target_metr
     
     return target_it->second;
 }
+
+double TargetAccessibilityTracker::get_uptime(const std::string& target, const std::string& runway_id) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    
+    auto target_it = metrics_.find(target);
+    if (target_it == metrics_.end()) {
+        return 0.0;
+    }
+    
+    auto runway_it = target_it->second.find(runway_id);
+    if (runway_it == target_it->second.end()) {
+        return 0.0;
+    }
+    
+    return runway_it->second.uptime_ratio(get_current_time(), uptime_window_secs_);
+}
+
+std::vector<TargetMetrics> TargetAccessibilityTracker::get_ranked_runways(const std::string& target) {
+    std::vector<TargetMetrics> ranked;
+    for (const auto& pair : get_target_metrics(target)) {
+        ranked.push_back(pair.second);
+    }
+    
+    auto state_rank = [](RunwayState state) {
+        switch (state) {
+            case RunwayState::Accessible: return 0;
+            case RunwayState::PartiallyAccessible: return 1;
+            case RunwayState::Unknown: return 2;
+            case RunwayState::Testing: return 2;
+            case RunwayState::Inaccessible: return 3;
+        }
+        return 3;
+    };
+    
+    std::stable_sort(ranked.begin(), ranked.end(), [&](const TargetMetrics& a, const TargetMetrics& b) {
+        if (state_rank(a.state) != state_rank(b.state)) {
+            return state_rank(a.state) < state_rank(b.state);
+        }
+        if (a.success_rate != b.success_rate) {
+            return a.success_rate > b.success_rate;
+        }
+        // Unmeasured latency (0) sorts after measured latency
+        if ((a.avg_response_time > 0.0) != (b.avg_response_time > 0.0)) {
+            return a.avg_response_time > 0.0;
+        }
+        return a.avg_response_time < b.avg_response_time;
+    });
+    
+    return ranked;
+}
+
+GlobalSummary TargetAccessibilityTracker::global_summary() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    GlobalSummary summary;
+    std::map<std::string, uint64_t> runway_requests;
+    
+    summary.total_targets = metrics_.size();
+    for (const auto& target_pair : metrics_) {
+        for (const auto& runway_pair : target_pair.second) {
+            const TargetMetrics& m = runway_pair.second;
+            summary.total_requests += m.total_attempts;
+            summary.total_successes += m.user_success_count;
+            summary.total_partial_successes += m.partial_success_count;
+            summary.total_failures += m.failure_count;
+            summary.total_bytes += m.bytes_transferred;
+            runway_requests[runway_pair.first] += m.total_attempts;
+        }
+    }
+    
+    summary.total_runways = runway_requests.size();
+    for (const auto& pair : runway_requests) {
+        if (pair.second > summary.busiest_runway_requests) {
+            summary.busiest_runway = pair.first;
+            summary.busiest_runway_requests = pair.second;
+        }
+    }
+    
+    if (summary.total_requests > 0) {
+        summary.overall_success_rate =
+            static_cast<double>(summary.total_successes) / static_cast<double>(summary.total_requests);
+    }
+    
+    return summary;
+}
+
+void TargetAccessibilityTracker::set_success_rate_threshold(double threshold) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    success_rate_threshold_ = threshold;
+}
+
+std::string TargetAccessibilityTracker::to_json() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    uint64_t now = get_current_time();
+    
+    std::ostringstream oss;
+    oss << std::fixed;
+    oss << "{\n";
+    oss << "  \"generated_at\": " << now << ",\n";
+    oss << "  \"uptime_window_secs\": " << uptime_window_secs_ << ",\n";
+    oss << "  \"targets\": {";
+    size_t i = 0;
+    for (const auto& target_pair : metrics_) {
+        oss << (i++ > 0 ? "," : "") << "\n    " << webui_json::encode_string(target_pair.first) << ": {";
+        size_t j = 0;
+        for (const auto& runway_pair : target_pair.second) {
+            const TargetMetrics& m = runway_pair.second;
+            std::string state;
+            switch (m.state) {
+                case RunwayState::Unknown: state = "unknown"; break;
+                case RunwayState::Accessible: state = "accessible"; break;
+                case RunwayState::PartiallyAccessible: state = "partially_accessible"; break;
+                case RunwayState::Inaccessible: state = "inaccessible"; break;
+                case RunwayState::Testing: state = "testing"; break;
+            }
+            oss << (j++ > 0 ? "," : "") << "\n      " << webui_json::encode_string(runway_pair.first) << ": {\n";
+            oss << "        \"state\": \"" << state << "\",\n";
+            oss << std::setprecision(3);
+            oss << "        \"success_rate\": " << m.success_rate << ",\n";
+            oss << "        \"network_success_rate\": " << m.network_success_rate << ",\n";
+            oss << "        \"uptime\": " << m.uptime_ratio(now, uptime_window_secs_) << ",\n";
+            oss << std::setprecision(6);
+            oss << "        \"avg_response_time\": " << m.avg_response_time << ",\n";
+            oss << "        \"avg_dns_time\": " << m.avg_dns_time << ",\n";
+            oss << "        \"total_attempts\": " << m.total_attempts << ",\n";
+            oss << "        \"network_success_count\": " << m.network_success_count << ",\n";
+            oss << "        \"user_success_count\": " << m.user_success_count << ",\n";
+            oss << "        \"partial_success_count\": " << m.partial_success_count << ",\n";
+            oss << "        \"failure_count\": " << m.failure_count << ",\n";
+            oss << "        \"consecutive_failures\": " << m.consecutive_failures << ",\n";
+            oss << "        \"recovery_count\": " << m.recovery_count << ",\n";
+            oss << "        \"bytes_transferred\": " << m.bytes_transferred << ",\n";
+            oss << "        \"last_success_time\": " << m.last_success_time << ",\n";
+            oss << "        \"last_failure_time\": " << m.last_failure_time << ",\n";
+            oss << "        \"last_used\": " << m.last_used << ",\n";
+            oss << "        \"last_evaluated\": " << m.last_evaluated << ",\n";
+            oss << "        \"next_probe_at\": " << m.next_probe_at << "\n";
+            oss << "      }";
+        }
+        oss << (j > 0 ? "\n    }" : "}");
+    }
+    oss << (i > 0 ? "\n  }\n" : "}\n");
+    oss << "}";
+    return oss.str();
+}
+
+void TargetAccessibilityTracker::remove_runway(const std::string& runway_id) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    for (auto& pair : metrics_) {
+        pair.second.erase(runway_id);
+    }
+}
+
+size_t TargetAccessibilityTracker::reset(const std::string& target) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    if (!target.empty()) {
+        target_ports_.erase(target);
+        return metrics_.erase(target);
+    }
+    size_t cleared = metrics_.size();
+    metrics_.clear();
+    target_ports_.clear();
+    return cleared;
+}
+
+size_t TargetAccessibilityTracker::evict_stale_targets(uint64_t ttl_secs) {
+    if (ttl_secs == 0) {
+        return 0;
+    }
+    
+    std::lock_guard<std::mutex> lock(mutex_);
+    uint64_t now = get_current_time();
+    size_t evicted = 0;
+    
+    for (auto it = metrics_.begin(); it != metrics_.end();) {
+        uint64_t last_used = 0;
+        for (const auto& pair : it->second) {
+            last_used = std::max(last_used, pair.second.last_used);
+        }
+        
+        if (now > last_used && now - last_used > ttl_secs) {
+            target_ports_.erase(it->first);
+            it = metrics_.erase(it);
+            evicted++;
+        } else {
+            ++it;
+        }
+    }
+    
+    return evicted;
+}
@@ -6,6 +6,7 @@
 #include <vector>
 #include <mutex>
 #include <cstdint>
+#include <functional>
 #include "runway.h"
 
 struct TargetMetrics {
@@ -18,12 +19,20 @@ struct TargetMetrics {
     uint64_t partial_success_count;
     uint64_t total_attempts;
     double avg_response_time;
+    double avg_dns_time; // Average DNS resolution time (seconds), excluded from avg_response_time
     uint64_t last_success_time; // Unix timestamp
     uint64_t last_failure_time; // Unix timestamp
+    uint64_t last_used; // Unix timestamp of the last recorded activity
+    uint64_t last_evaluated; // Unix timestamp of the last accessibility result (request or probe)
     uint32_t consecutive_failures;
+    uint64_t next_probe_at; // Circuit breaker: health probes wait until this Unix timestamp (0 = closed)
     uint64_t recovery_count;
-    double success_rate;
+    uint64_t bytes_transferred; // Request + response bytes proxied through this runway
+    double success_rate; // User-level success over recent attempts
+    double network_success_rate; // Network-level success (connected and got a response) over recent attempts
     std::vector<bool> recent_attempts; // Last N attempts (true=success, false=failure)
+    std::vector<bool> recent_network_attempts; // Last N attempts at network level
+    std::vector<std::pair<uint64_t, RunwayState>> state_history; // State transitions (timestamp, new state)
     
     TargetMetrics() 
         : state(RunwayState::Unknown)
@@ -33,11 +42,17 @@ struct TargetMetrics {
         , partial_success_count(0)
         , total_attempts(0)
         , avg_response_time(0.0)
+        , avg_dns_time(0.0)
         , last_success_time(0)
         , last_failure_time(0)
+        , last_used(0)
+        , last_evaluated(0)
         , consecutive_failures(0)
+        , next_probe_at(0)
         , recovery_count(0)
-        , success_rate(0.0) {}
+        , bytes_transferred(0)
+        , success_rate(0.0)
+        , network_success_rate(0.0) {}
     
     TargetMetrics(const std::string& target, const std::string& runway_id)
         : target(target)
@@ -49,21 +64,77 @@ struct TargetMetrics {
         , partial_success_count(0)
         , total_attempts(0)
         , avg_response_time(0.0)
+        , avg_dns_time(0.0)
         , last_success_time(0)
         , last_failure_time(0)
+        , last_used(0)
+        , last_evaluated(0)
         , consecutive_failures(0)
+        , next_probe_at(0)
         , recovery_count(0)
-        , success_rate(0.0) {}
+        , bytes_transferred(0)
+        , success_rate(0.0)
+        , network_success_rate(0.0) {}
     
     void update_success_rate(size_t window);
+    
+    // Record current state in the transition history, pruning entries older than period
+    void record_state(uint64_t now, uint64_t period);
+    
+    // Fraction of time spent Accessible over the last period seconds (0.0 - 1.0)
+    // Time with no traffic counts as the last known state
+    double uptime_ratio(uint64_t now, uint64_t period) const;
+};
+
+// Aggregate view across all targets and runways
+struct GlobalSummary {
+    size_t total_targets;
+    size_t total_runways; // Distinct runways with recorded metrics
+    uint64_t total_requests;
+    uint64_t total_successes;
+    uint64_t total_partial_successes;
+    uint64_t total_failures;
+    uint64_t total_bytes;
+    double overall_success_rate;
+    std::string busiest_runway;
+    uint64_t busiest_runway_requests;
+    
+    GlobalSummary()
+        : total_targets(0)
+        , total_runways(0)
+        , total_requests(0)
+        , total_successes(0)
+        , total_partial_successes(0)
+        , total_failures(0)
+        , total_bytes(0)
+        , overall_success_rate(0.0)
+        , busiest_runway_requests(0) {}
 };
 
+// Invoked when a target transitions from having accessible runways to having none
+using TargetDownCallback = std::function<void(const std::string& target,
+                                              const std::vector<TargetMetrics>& runways)>;
+
 class TargetAccessibilityTracker {
 public:
-    TargetAccessibilityTracker(size_t success_rate_window, double success_rate_threshold);
+    // probe_backoff_*: once a runway is Inaccessible for a target, probes back off exponentially
+    // from base_secs (doubling per further failure) up to max_secs
+    TargetAccessibilityTracker(size_t success_rate_window, double success_rate_threshold,
+                               uint64_t uptime_window_secs = 3600, double latency_ema_alpha = 0.3,
+                               uint64_t probe_backoff_base_secs = 60, uint64_t probe_backoff_max_secs = 3600);
     
     void update(const std::string& target, const std::string& runway_id,
-                bool network_success, bool user_success, double response_time_secs);
+                bool network_success, bool user_success, double response_time_secs,
+                double dns_time_secs = 0.0);
+    
+    // Record bytes proxied through a runway for a target
+    void record_bytes(const std::string& target, const std::string& runway_id, uint64_t bytes);
+    
+    // Remember the port target was last requested on, so health probes test that port
+    void set_target_port(const std::string& target, uint16_t port);
+    
+    // Port target was last requested on; 80 if it was never requested (e.g. only probed)
+    uint16_t get_target_port(const std::string& target);
     
     std::vector<std::string> get_accessible_runways(const std::string& target);
     
@@ -71,14 +142,63 @@ public:
     
     std::vector<std::string> get_all_targets();
     
+    // All targets, least recently evaluated first (a target counts as evaluated when any of its runways was)
+    std::vector<std::string> get_targets_by_staleness();
+    
     std::map<std::string, TargetMetrics> get_target_metrics(const std::string& target);
     
+    // Copy of every target's runway metrics (target -> runway_id -> metrics)
+    std::map<std::string, std::map<std::string, TargetMetrics>> snapshot();
+    
+    // All runways for a target ranked best-first: by state, then success rate, then latency
+    std::vector<TargetMetrics> get_ranked_runways(const std::string& target);
+    
+    // Drop a runway's metrics for every target (e.g. after the runway was removed)
+    void remove_runway(const std::string& runway_id);
+    
+    // Forget the metrics of one target (or of all, if target is empty) so they are learned afresh
+    // Returns number of targets cleared
+    size_t reset(const std::string& target = "");
+    
+    // Remove targets with no activity in the last ttl_secs (0 = never), returns number evicted
+    size_t evict_stale_targets(uint64_t ttl_secs);
+    
+    // Fold all targets and runways into a single summary
+    GlobalSummary global_summary();
+    
+    // Uptime of a runway for a target over the rolling uptime window (0.0 - 1.0)
+    double get_uptime(const std::string& target, const std::string& runway_id);
+    
+    uint64_t get_uptime_window() const { return uptime_window_secs_; }
+    
+    // Minimum recent success rate for Accessible; applies to the next update of each runway
+    void set_success_rate_threshold(double threshold);
+    
+    // Full snapshot of every target's runway metrics as a JSON object (for export and offline analysis)
+    std::string to_json();
+    
+    // Set callback fired (outside the tracker lock) when a target loses all accessible runways
+    void set_target_down_callback(TargetDownCallback callback);
+    
 private:
     std::map<std::string, std::map<std::string, TargetMetrics>> metrics_; // target -> runway_id -> metrics
+    std::map<std::string, uint16_t> target_ports_; // target -> port it was last requested on
     size_t success_rate_window_;
     double success_rate_threshold_;
+    uint64_t uptime_window_secs_;
+    double latency_ema_alpha_; // Weight of the newest sample in latency moving averages
+    uint64_t probe_backoff_base_secs_;
+    uint64_t probe_backoff_max_secs_;
     std::mutex mutex_;
+    TargetDownCallback target_down_callback_;
     
+    // Apply an attempt to the metrics (mutex_ must be held)
+    // Returns true if the target just lost its last accessible runway
+    bool apply_update(const std::string& target, const std::string& runway_id,
+                      bool network_success, bool user_success, double response_time_secs,
+                      double dns_time_secs);
+    bool is_accessible(const TargetMetrics& metrics) const;
+    bool has_accessible_runway(const std::map<std::string, TargetMetrics>& runways) const;
     TargetMetrics& get_or_create_metrics(const std::string& target, const std::string& runway_id);
     uint64_t get_current_time() const;
 };
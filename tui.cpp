@@ -2,6 +2,7 @@
 #include "utils.h"
 #include "logger.h"
 #include "routing.h"
+#include "upstream.h"
 #include <iostream>
 #include <sstream>
 #include <iomanip>
@@ -1569,10 +1570,12 @@ void TUI::draw_detail_view(std::stringstream& output, int cols, int rows) {
             output << "\n";
             
             if (runway->upstream_proxy) {
-                output << "  Upstream Proxy: " << runway->upstream_proxy->config.proxy_type 
-                       << "://" << runway->upstream_proxy->config.host 
-                       << ":" << runway->upstream_proxy->config.port << "\n";
+                output << "  Upstream Proxy: " << upstream::describe(runway->upstream_proxy->config) << "\n";
                 output << "  Proxy Accessible: " << (runway->upstream_proxy->accessible ? "Yes" : "No") << "\n";
+                if (runway->upstream_proxy->response_time > 0.0) {
+                    output << "  Proxy Latency: " << std::fixed << std::setprecision(1)
+                           << runway->upstream_proxy->response_time * 1000.0 << "ms\n";
+                }
             } else {
                 output << "  Upstream Proxy: None (direct)\n";
             }
@@ -3,6 +3,7 @@
 #include "logger.h"
 #include "routing.h"
 #include "config.h"
+#include "audit.h"
 #include <iostream>
 #include <cstring>
 #include <ctime>
@@ -632,6 +633,10 @@ void TUI::cycle_routing_mode() {
     
     routing_engine_->set_mode(next_mode);
     should_redraw_ = true;
+    {
+        audit::Actor actor("tui", "console");
+        audit::record("mode", "routing_mode", routing_mode_name(current_mode), routing_mode_name(next_mode));
+    }
     
     // Log the change
     std::string mode_str;
@@ -0,0 +1,301 @@
+#include "upstream.h"
+#include "utils.h"
+#include "tls.h"
+#include "logger.h"
+#include <vector>
+
+namespace upstream {
+
+// Read exactly len bytes (SOCKS replies may arrive in pieces)
+static bool recv_exact(socket_t sock, uint8_t* buffer, size_t len) {
+    size_t received = 0;
+    while (received < len) {
+        ssize_t n = network::recv_data(sock, buffer + received, len - received);
+        if (n <= 0) {
+            return false;
+        }
+        received += static_cast<size_t>(n);
+    }
+    return true;
+}
+
+static bool send_all(socket_t sock, const std::vector<uint8_t>& data) {
+    return network::send_data(sock, data.data(), data.size()) == static_cast<ssize_t>(data.size());
+}
+
+bool is_supported_type(const std::string& proxy_type) {
+    std::string type = utils::to_lower(proxy_type);
+    return is_http_type(type) || type == "socks4" || type == "socks4a" || type == "socks5" || type == "socks5h";
+}
+
+const char* supported_types() {
+    return "http, https, socks4, socks4a, socks5, socks5h";
+}
+
+bool is_http_type(const std::string& proxy_type) {
+    std::string type = utils::to_lower(proxy_type);
+    return type == "http" || type == "https";
+}
+
+bool resolves_remotely(const std::string& proxy_type) {
+    std::string type = utils::to_lower(proxy_type);
+    return is_http_type(type) || type == "socks4a" || type == "socks5h";
+}
+
+bool resolves_remotely(const UpstreamProxyConfig& proxy) {
+    std::string type = utils::to_lower(proxy.proxy_type);
+    return resolves_remotely(type) || (proxy.remote_dns && (type == "socks4" || type == "socks5"));
+}
+
+bool has_address(const UpstreamProxyConfig& proxy, int family) {
+    if (utils::is_valid_ipv4(proxy.host)) {
+        return family == AF_INET;
+    }
+    if (network::is_ipv6_address(proxy.host)) {
+        return family == AF_INET6;
+    }
+    std::string ip;
+    return network::resolve_hostname(proxy.host, ip, family);
+}
+
+bool has_literal_address(const UpstreamProxyConfig& proxy) {
+    return utils::is_valid_ipv4(proxy.host) || network::is_ipv6_address(proxy.host);
+}
+
+bool connect_to_proxy(socket_t sock, const UpstreamProxyConfig& proxy, int family, int timeout_ms) {
+    std::string proxy_ip = proxy.host;
+    bool is_literal = (family == AF_INET6) ? network::is_ipv6_address(proxy_ip) : utils::is_valid_ipv4(proxy_ip);
+    if (!is_literal && !network::resolve_hostname(proxy.host, proxy_ip, family)) {
+        return false;
+    }
+    if (!network::connect_socket(sock, proxy_ip, proxy.port, timeout_ms)) {
+        return false;
+    }
+    
+    if (utils::to_lower(proxy.proxy_type) == "https") {
+        tls::ClientOptions options;
+        options.server_name = proxy.tls_server_name.empty() ? proxy.host : proxy.tls_server_name;
+        options.allow_self_signed = proxy.tls_allow_self_signed;
+        std::string error;
+        if (!tls::connect(sock, options, error)) {
+            Logger::instance().log(LogLevel::WARN, "TLS to upstream proxy " + proxy.host + ":" +
+                                   std::to_string(proxy.port) + " failed: " + error);
+            return false;
+        }
+    }
+    return true;
+}
+
+bool socks5_connect(socket_t sock, const std::string& host, uint16_t port,
+                    const std::string& username, const std::string& password) {
+    // RFC 1928 Section 3 - version identifier/method selection: VER, NMETHODS, METHODS
+    // 0x00 = no authentication, 0x02 = username/password
+    std::vector<uint8_t> greeting = username.empty()
+        ? std::vector<uint8_t>{0x05, 0x01, 0x00}
+        : std::vector<uint8_t>{0x05, 0x02, 0x00, 0x02};
+    if (!send_all(sock, greeting)) {
+        return false;
+    }
+    
+    uint8_t method_reply[2];
+    if (!recv_exact(sock, method_reply, sizeof(method_reply)) || method_reply[0] != 0x05) {
+        return false;
+    }
+    
+    if (method_reply[1] == 0x02 && !username.empty()) {
+        // RFC 1929 Section 2 - VER (0x01), ULEN, UNAME, PLEN, PASSWD
+        if (username.length() > 255 || password.length() > 255) {
+            return false;
+        }
+        std::vector<uint8_t> auth = {0x01, static_cast<uint8_t>(username.length())};
+        auth.insert(auth.end(), username.begin(), username.end());
+        auth.push_back(static_cast<uint8_t>(password.length()));
+        auth.insert(auth.end(), password.begin(), password.end());
+        
+        uint8_t auth_reply[2];
+        if (!send_all(sock, auth) || !recv_exact(sock, auth_reply, sizeof(auth_reply)) || auth_reply[1] != 0x00) {
+            return false; // Rejected credentials
+        }
+    } else if (method_reply[1] != 0x00) {
+        return false; // 0xFF: no acceptable methods
+    }
+    
+    // RFC 1928 Section 4 - request: VER, CMD (CONNECT), RSV, ATYP, DST.ADDR, DST.PORT
+    std::vector<uint8_t> request = {0x05, 0x01, 0x00};
+    if (utils::is_valid_ipv4(host)) {
+        uint32_t addr = utils::ip_to_uint32(host);
+        request.push_back(0x01);
+        request.push_back(static_cast<uint8_t>(addr >> 24));
+        request.push_back(static_cast<uint8_t>(addr >> 16));
+        request.push_back(static_cast<uint8_t>(addr >> 8));
+        request.push_back(static_cast<uint8_t>(addr));
+    } else if (network::is_ipv6_address(host)) {
+        uint8_t addr6[16];
+        if (inet_pton(AF_INET6, host.c_str(), addr6) != 1) {
+            return false;
+        }
+        request.push_back(0x04);
+        request.insert(request.end(), addr6, addr6 + sizeof(addr6));
+    } else {
+        if (host.empty() || host.length() > 255) {
+            return false;
+        }
+        request.push_back(0x03);
+        request.push_back(static_cast<uint8_t>(host.length()));
+        request.insert(request.end(), host.begin(), host.end());
+    }
+    request.push_back(static_cast<uint8_t>(port >> 8));
+    request.push_back(static_cast<uint8_t>(port & 0xFF));
+    
+    if (!send_all(sock, request)) {
+        return false;
+    }
+    
+    // RFC 1928 Section 6 - reply: VER, REP, RSV, ATYP, BND.ADDR, BND.PORT
+    uint8_t reply[4];
+    if (!recv_exact(sock, reply, sizeof(reply)) || reply[0] != 0x05 || reply[1] != 0x00) {
+        return false;
+    }
+    
+    // Drain the bound address so the tunnel starts clean
+    size_t addr_len = 0;
+    if (reply[3] == 0x01) {
+        addr_len = 4;
+    } else if (reply[3] == 0x04) {
+        addr_len = 16;
+    } else if (reply[3] == 0x03) {
+        uint8_t name_len;
+        if (!recv_exact(sock, &name_len, 1)) {
+            return false;
+        }
+        addr_len = name_len;
+    } else {
+        return false;
+    }
+    
+    std::vector<uint8_t> bound(addr_len + 2);
+    return recv_exact(sock, bound.data(), bound.size());
+}
+
+bool socks4_connect(socket_t sock, const std::string& host, uint16_t port, bool socks4a,
+                    const std::string& user_id) {
+    // SOCKS4 request: VN (4), CD (1 = CONNECT), DSTPORT, DSTIP, USERID, NULL
+    bool send_name = !utils::is_valid_ipv4(host);
+    if (send_name && (!socks4a || host.empty() || host.length() > 255)) {
+        return false; // Plain SOCKS4 only carries IPv4 addresses
+    }
+    
+    std::vector<uint8_t> request = {0x04, 0x01,
+                                    static_cast<uint8_t>(port >> 8), static_cast<uint8_t>(port & 0xFF)};
+    if (send_name) {
+        // SOCKS4A: DSTIP 0.0.0.x (x != 0) tells the proxy a host name follows the USERID
+        request.insert(request.end(), {0x00, 0x00, 0x00, 0x01});
+    } else {
+        uint32_t addr = utils::ip_to_uint32(host);
+        request.push_back(static_cast<uint8_t>(addr >> 24));
+        request.push_back(static_cast<uint8_t>(addr >> 16));
+        request.push_back(static_cast<uint8_t>(addr >> 8));
+        request.push_back(static_cast<uint8_t>(addr));
+    }
+    request.insert(request.end(), user_id.begin(), user_id.end());
+    request.push_back(0x00);
+    if (send_name) {
+        request.insert(request.end(), host.begin(), host.end());
+        request.push_back(0x00);
+    }
+    
+    if (!send_all(sock, request)) {
+        return false;
+    }
+    
+    // Reply: VN (0), CD (90 = granted), DSTPORT, DSTIP
+    uint8_t reply[8];
+    return recv_exact(sock, reply, sizeof(reply)) && reply[0] == 0x00 && reply[1] == 90;
+}
+
+bool http_connect(socket_t sock, const std::string& host, uint16_t port,
+                  const std::string& authorization) {
+    // RFC 7230 Section 5.3.3 - authority-form request target (IPv6 literals in brackets, RFC 3986)
+    std::string authority = (network::is_ipv6_address(host) ? "[" + host + "]" : host) + ":" + std::to_string(port);
+    std::string request = "CONNECT " + authority + " HTTP/1.1\r\n";
+    request += "Host: " + authority + "\r\n";
+    if (!authorization.empty()) {
+        request += "Proxy-Authorization: " + authorization + "\r\n";
+    }
+    request += "\r\n";
+    if (!send_all(sock, std::vector<uint8_t>(request.begin(), request.end()))) {
+        return false;
+    }
+    
+    // Read the response head one byte at a time so no tunnelled data is consumed
+    const size_t max_head = 8192;
+    std::string head;
+    uint8_t c;
+    while (head.size() < max_head) {
+        if (!recv_exact(sock, &c, 1)) {
+            return false;
+        }
+        head += static_cast<char>(c);
+        if (head.size() >= 4 && head.compare(head.size() - 4, 4, "\r\n\r\n") == 0) {
+            break;
+        }
+    }
+    
+    // Status line: "HTTP/1.1 200 Connection established"
+    std::vector<std::string> parts = utils::split(head.substr(0, head.find("\r\n")), ' ');
+    return parts.size() >= 2 && parts[0].compare(0, 5, "HTTP/") == 0 &&
+           parts[1].length() == 3 && parts[1][0] == '2';
+}
+
+std::string proxy_authorization(const UpstreamProxyConfig& proxy) {
+    if (proxy.username.empty()) {
+        return "";
+    }
+    return "Basic " + utils::base64_encode(proxy.username + ":" + proxy.password);
+}
+
+bool open_tunnel(socket_t sock, const UpstreamProxyConfig& proxy, const std::string& host, uint16_t port) {
+    std::string type = utils::to_lower(proxy.proxy_type);
+    if (type == "socks5" || type == "socks5h") {
+        return socks5_connect(sock, host, port, proxy.username, proxy.password);
+    }
+    if (type == "socks4" || type == "socks4a") {
+        return socks4_connect(sock, host, port, resolves_remotely(proxy), proxy.username);
+    }
+    return is_http_type(type);
+}
+
+const UpstreamProxyConfig& exit_hop(const UpstreamProxyConfig& proxy) {
+    return proxy.chain.empty() ? proxy : proxy.chain.back();
+}
+
+bool open_chain(socket_t sock, const UpstreamProxyConfig& proxy) {
+    const UpstreamProxyConfig* current = &proxy;
+    for (const auto& next : proxy.chain) {
+        // Hops that cannot resolve names (socks4, socks5) get the next hop's address resolved here
+        std::string next_host = next.host;
+        if (!resolves_remotely(*current) && !utils::is_valid_ipv4(next_host) &&
+            !network::is_ipv6_address(next_host) && !network::resolve_hostname(next.host, next_host)) {
+            return false;
+        }
+        
+        bool tunnelled = is_http_type(current->proxy_type)
+            ? http_connect(sock, next_host, next.port, proxy_authorization(*current))
+            : open_tunnel(sock, *current, next_host, next.port);
+        if (!tunnelled) {
+            return false;
+        }
+        current = &next;
+    }
+    return true;
+}
+
+std::string describe(const UpstreamProxyConfig& proxy) {
+    std::string result = proxy.proxy_type + "://" + proxy.host + ":" + std::to_string(proxy.port);
+    for (const auto& hop : proxy.chain) {
+        result += " -> " + hop.proxy_type + "://" + hop.host + ":" + std::to_string(hop.port);
+    }
+    return result;
+}
+
+} // namespace upstream
@@ -0,0 +1,86 @@
+#ifndef UPSTREAM_H
+#define UPSTREAM_H
+
+#include <string>
+#include <cstdint>
+#include "config.h"
+#include "network.h"
+
+// Upstream proxy protocols
+// SOCKS 4 / SOCKS 4A (de facto protocol documents, no RFC)
+// RFC 1928 - SOCKS Protocol Version 5
+// RFC 1929 - Username/Password Authentication for SOCKS V5
+// RFC 7617 - Basic HTTP Authentication (Proxy-Authorization)
+// RFC 7230 Section 5.3.2 - absolute-form requests to HTTP proxies
+// RFC 7231 Section 4.3.6 - CONNECT
+
+namespace upstream {
+
+// True for proxy types that can carry runway traffic:
+// http/https (https = TLS to the proxy itself), socks4/socks5 (target resolved locally)
+// and socks4a/socks5h (target resolved by the proxy)
+bool is_supported_type(const std::string& proxy_type);
+
+// True for http and https proxies, which take absolute-form requests and CONNECT
+bool is_http_type(const std::string& proxy_type);
+
+// Comma-separated list of supported types, for error messages
+const char* supported_types();
+
+// True if the proxy resolves target host names itself (http, https, socks4a, socks5h)
+bool resolves_remotely(const std::string& proxy_type);
+
+// As above, but also true for socks4/socks5 proxies configured with remote_dns
+bool resolves_remotely(const UpstreamProxyConfig& proxy);
+
+// True if proxy's host has an address in family: an address literal of that family, or a name that
+// resolves to one (looked up with getaddrinfo). A runway of a family the proxy lacks cannot reach it,
+// which says nothing about the proxy itself
+bool has_address(const UpstreamProxyConfig& proxy, int family);
+
+// True if proxy's host is an IPv4 or IPv6 address rather than a name
+bool has_literal_address(const UpstreamProxyConfig& proxy);
+
+// Connect sock to the proxy itself (host may be a name; resolved with getaddrinfo)
+// family must match the socket: AF_INET or AF_INET6; timeout_ms bounds the TCP handshake (-1 = none)
+// https proxies also complete a verified TLS handshake, after which sock carries TLS
+bool connect_to_proxy(socket_t sock, const UpstreamProxyConfig& proxy, int family = AF_INET, int timeout_ms = -1);
+
+// SOCKS5 handshake and CONNECT over a socket already connected to the proxy
+// host may be an IPv4 address, a domain name or an IPv6 address (ATYP 0x01 / 0x03 / 0x04)
+// Non-empty username offers username/password authentication
+bool socks5_connect(socket_t sock, const std::string& host, uint16_t port,
+                    const std::string& username = "", const std::string& password = "");
+
+// HTTP CONNECT to host:port over a socket already connected to an HTTP proxy
+// Succeeds on a 2xx reply; the response head is consumed so the tunnel starts clean
+// authorization: Proxy-Authorization value, omitted if empty
+bool http_connect(socket_t sock, const std::string& host, uint16_t port,
+                  const std::string& authorization = "");
+
+// SOCKS4 CONNECT over a socket already connected to the proxy
+// host must be an IPv4 address, or any name when socks4a (sent for the proxy to resolve)
+// user_id is the SOCKS4 USERID field (may be empty)
+bool socks4_connect(socket_t sock, const std::string& host, uint16_t port, bool socks4a,
+                    const std::string& user_id = "");
+
+// "Basic <credentials>" for the Proxy-Authorization header; empty without a username
+std::string proxy_authorization(const UpstreamProxyConfig& proxy);
+
+// Establish a tunnel to host:port for tunnelling proxy types (socks4, socks4a, socks5, socks5h)
+// No-op for http/https, which instead receive absolute-form requests
+bool open_tunnel(socket_t sock, const UpstreamProxyConfig& proxy, const std::string& host, uint16_t port);
+
+// The hop that talks to the target: the last entry of proxy.chain, or proxy itself
+const UpstreamProxyConfig& exit_hop(const UpstreamProxyConfig& proxy);
+
+// Tunnel from the entry proxy (already connected) through each hop in proxy.chain
+// Every hop tunnels to the next, HTTP hops via CONNECT; afterwards sock talks to exit_hop(proxy)
+bool open_chain(socket_t sock, const UpstreamProxyConfig& proxy);
+
+// "type://host:port", with " -> type://host:port" per chain hop (never includes credentials)
+std::string describe(const UpstreamProxyConfig& proxy);
+
+} // namespace upstream
+
+#endif // UPSTREAM_H
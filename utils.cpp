@@ -2,6 +2,7 @@
 #include <cctype>
 #include <algorithm>
 #include <cstring>
+#include <cstdio>
 #include <iostream>
 #include <fstream>
 #include <sys/stat.h>
@@ -25,6 +26,7 @@
 #define fileno _fileno
 #else
 #include <unistd.h>
+#include <termios.h>
 #endif
 
 namespace utils {
@@ -110,6 +112,120 @@ std::string to_lower(const std::string& str) {
     return result;
 }
 
+bool match_host_pattern(const std::string& host, const std::string& pattern) {
+    std::string h = to_lower(host);
+    std::string p = to_lower(pattern);
+    
+    if (p.length() > 2 && p[0] == '*' && p[1] == '.') {
+        std::string suffix = p.substr(1); // ".example.com"
+        return h.length() > suffix.length() &&
+               h.compare(h.length() - suffix.length(), suffix.length(), suffix) == 0;
+    }
+    
+    return h == p;
+}
+
+bool match_wildcard(const std::string& str, const std::string& pattern) {
+    // Iterative matcher: backtrack to just after the last '*' on mismatch
+    size_t s = 0, p = 0;
+    size_t star = std::string::npos, star_match = 0;
+    while (s < str.length()) {
+        if (p < pattern.length() && pattern[p] == '*') {
+            star = p++;
+            star_match = s;
+        } else if (p < pattern.length() && pattern[p] == str[s]) {
+            ++p;
+            ++s;
+        } else if (star != std::string::npos) {
+            p = star + 1;
+            s = ++star_match;
+        } else {
+            return false;
+        }
+    }
+    while (p < pattern.length() && pattern[p] == '*') {
+        ++p;
+    }
+    return p == pattern.length();
+}
+
+bool has_nested_quantifier(const std::string& regex) {
+    // One entry per open group: whether a quantifier occurs inside it
+    std::vector<bool> groups;
+    bool closed_quantified_group = false; // The previous token closed a group containing a quantifier
+    for (size_t i = 0; i < regex.size(); ++i) {
+        char c = regex[i];
+        bool was_closed_quantified_group = closed_quantified_group;
+        closed_quantified_group = false;
+        if (c == '\\') {
+            ++i; // Escaped character
+        } else if (c == '[') {
+            // Character class: skip to the closing ']' (a leading ']' or '^]' is literal)
+            size_t j = i + 1;
+            if (j < regex.size() && regex[j] == '^') ++j;
+            if (j < regex.size() && regex[j] == ']') ++j;
+            while (j < regex.size() && regex[j] != ']') {
+                j += regex[j] == '\\' ? 2 : 1;
+            }
+            i = j;
+        } else if (c == '(') {
+            groups.push_back(false);
+            if (i + 1 < regex.size() && regex[i + 1] == '?') {
+                ++i; // "(?:", "(?=", "(?!": not a quantifier
+            }
+        } else if (c == ')') {
+            if (groups.empty()) {
+                continue;
+            }
+            bool quantified_inside = groups.back();
+            groups.pop_back();
+            closed_quantified_group = quantified_inside;
+            if (quantified_inside && !groups.empty()) {
+                groups.back() = true;
+            }
+        } else if (c == '*' || c == '+' || c == '{') {
+            // A bounded count ("{3}", "{2,4}") repeats a fixed number of times; only "{n,}" is open-ended
+            size_t close = c == '{' ? regex.find('}', i) : std::string::npos;
+            bool unbounded = c != '{' || (close != std::string::npos && regex[close - 1] == ',');
+            if (was_closed_quantified_group && unbounded) {
+                return true;
+            }
+            if (!groups.empty()) {
+                groups.back() = true;
+            }
+        }
+    }
+    return false;
+}
+
+std::string base64_encode(const std::string& data) {
+    static const char alphabet[] = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    std::string result;
+    result.reserve(((data.size() + 2) / 3) * 4);
+    
+    size_t i = 0;
+    while (i + 2 < data.size()) {
+        uint32_t n = (static_cast<uint8_t>(data[i]) << 16) | (static_cast<uint8_t>(data[i + 1]) << 8) |
+                     static_cast<uint8_t>(data[i + 2]);
+        result += alphabet[(n >> 18) & 0x3F];
+        result += alphabet[(n >> 12) & 0x3F];
+        result += alphabet[(n >> 6) & 0x3F];
+        result += alphabet[n & 0x3F];
+        i += 3;
+    }
+    
+    // Final quantum: pad with '='
+    if (i < data.size()) {
+        uint32_t n = static_cast<uint8_t>(data[i]) << 16;
+        if (i + 1 < data.size()) n |= static_cast<uint8_t>(data[i + 1]) << 8;
+        result += alphabet[(n >> 18) & 0x3F];
+        result += alphabet[(n >> 12) & 0x3F];
+        result += (i + 1 < data.size()) ? alphabet[(n >> 6) & 0x3F] : '=';
+        result += '=';
+    }
+    return result;
+}
+
 std::string format_bytes(uint64_t bytes) {
     const char* units[] = {"B", "KB", "MB", "GB", "TB"};
     double size = static_cast<double>(bytes);
@@ -164,6 +280,44 @@ bool is_terminal() {
     return isatty(fileno(stdout)) != 0;
 }
 
+bool is_stderr_terminal() {
+    return isatty(fileno(stderr)) != 0;
+}
+
+bool is_stdin_terminal() {
+    return isatty(fileno(stdin)) != 0;
+}
+
+bool read_secret(const std::string& prompt, std::string& secret) {
+    std::cerr << prompt << std::flush;
+#ifdef _WIN32
+    HANDLE input = GetStdHandle(STD_INPUT_HANDLE);
+    DWORD mode = 0;
+    bool echo_off = GetConsoleMode(input, &mode) && SetConsoleMode(input, mode & ~ENABLE_ECHO_INPUT);
+#else
+    struct termios term = {};
+    bool echo_off = tcgetattr(fileno(stdin), &term) == 0;
+    struct termios saved = term;
+    if (echo_off) {
+        term.c_lflag &= ~static_cast<tcflag_t>(ECHO);
+        echo_off = tcsetattr(fileno(stdin), TCSAFLUSH, &term) == 0;
+    }
+#endif
+    bool read = static_cast<bool>(std::getline(std::cin, secret));
+    if (echo_off) {
+#ifdef _WIN32
+        SetConsoleMode(input, mode);
+#else
+        tcsetattr(fileno(stdin), TCSAFLUSH, &saved);
+#endif
+    }
+    std::cerr << "\n"; // The typed newline was not echoed
+    if (!secret.empty() && secret.back() == '\r') {
+        secret.pop_back();
+    }
+    return read;
+}
+
 void safe_print(const std::string& message) {
     // Defensive: Only print if terminal is available or message is safe
     if (is_terminal() || message.find_first_of("\x1B\x07\x08") == std::string::npos) {
@@ -245,4 +399,27 @@ bool file_exists(const std::string& path) {
     return stat(path.c_str(), &info) == 0;
 }
 
+bool write_file_atomic(const std::string& path, const std::string& contents) {
+    std::string tmp_path = path + ".tmp";
+    {
+        std::ofstream out(tmp_path, std::ios::binary | std::ios::trunc);
+        out << contents;
+        out.flush();
+        if (!out) {
+            out.close();
+            std::remove(tmp_path.c_str());
+            return false;
+        }
+    }
+#ifdef _WIN32
+    bool renamed = MoveFileExA(tmp_path.c_str(), path.c_str(), MOVEFILE_REPLACE_EXISTING) != 0;
+#else
+    bool renamed = std::rename(tmp_path.c_str(), path.c_str()) == 0;
+#endif
+    if (!renamed) {
+        std::remove(tmp_path.c_str());
+    }
+    return renamed;
+}
+
 } // namespace utils
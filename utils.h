@@ -32,6 +32,21 @@ std::vector<std::string> split(const std::string& str, char delimiter);
 // Convert string to lowercase
 std::string to_lower(const std::string& str);
 
+// Match a host name against a pattern: exact ("example.com") or
+// wildcard subdomain ("*.example.com"), case-insensitive
+bool match_host_pattern(const std::string& host, const std::string& pattern);
+
+// Match a name against a glob-style pattern where '*' matches any run of characters
+// ("docker*", "*tun*"), case-sensitive
+bool match_wildcard(const std::string& str, const std::string& pattern);
+
+// True if a regex repeats a group that itself contains a repetition ("(a+)+", "(\w+\s?)*"):
+// such patterns can backtrack exponentially on a body that almost matches
+bool has_nested_quantifier(const std::string& regex);
+
+// Base64 encoding (RFC 4648 Section 4)
+std::string base64_encode(const std::string& data);
+
 // Format bytes to human-readable size
 std::string format_bytes(uint64_t bytes);
 
@@ -44,6 +59,15 @@ bool safe_str_to_double(const std::string& str, double& result);
 // Check if terminal is available (defensive terminal handling)
 bool is_terminal();
 
+// Check if stderr is a terminal
+bool is_stderr_terminal();
+
+// Check if stdin is a terminal
+bool is_stdin_terminal();
+
+// Write prompt to stderr and read one line from stdin without echoing it (for passwords)
+bool read_secret(const std::string& prompt, std::string& secret);
+
 // Safe output function (checks terminal state before writing)
 void safe_print(const std::string& message);
 
@@ -59,6 +83,10 @@ bool ensure_log_file(const std::string& log_file_path);
 // Check if file exists
 bool file_exists(const std::string& path);
 
+// Replace path's contents all at once: write <path>.tmp, flush, then rename it over path, so a
+// crash or full disk leaves either the old file or the new one, never a truncated mix
+bool write_file_atomic(const std::string& path, const std::string& contents);
+
 } // namespace utils
 
 #endif // UTILS_H
@@ -1,49 +1,865 @@
 #include "validator.h"
 #include "utils.h"
+#include "logger.h"
+#include "charset.h"
 #include <algorithm>
+#include <fstream>
+#include <sys/stat.h>
+#include <ctime>
 
-SuccessValidator::SuccessValidator() {
+// Regex search is bounded to the head of the body (block pages are small and
+// std::regex backtracking on multi-megabyte input is slow and stack-hungry), and runs over
+// overlapping windows so the deadline is checked between them: one search only ever sees
+// REGEX_WINDOW_BYTES, and matches up to REGEX_WINDOW_OVERLAP long are never split
+static const size_t MAX_REGEX_SCAN_BYTES = 64 * 1024;
+static const size_t REGEX_WINDOW_BYTES = 4 * 1024;
+static const size_t REGEX_WINDOW_OVERLAP = 1024;
+
+// Minimum seconds between fingerprint file modification checks
+static const uint64_t FINGERPRINT_CHECK_INTERVAL = 5;
+
+// Body size baselines: samples needed before flagging, averaging weight, flagged responses in a
+// row after which the small size is taken as the resource's new size, resource cap
+static const size_t BODY_BASELINE_MIN_SAMPLES = 5;
+static const double BODY_BASELINE_ALPHA = 0.2;
+static const size_t BODY_BASELINE_RELEARN_STREAK = 10;
+static const size_t MAX_BODY_BASELINES = 4096;
+
+// Collapse whitespace runs to a single space and trim (content is already lowercased)
+static std::string normalize_content(const std::string& content) {
+    std::string result;
+    result.reserve(content.size());
+    bool in_space = false;
+    for (char c : content) {
+        if (c == ' ' || c == '\t' || c == '\n' || c == '\r') {
+            in_space = true;
+            continue;
+        }
+        if (in_space && !result.empty()) {
+            result += ' ';
+        }
+        in_space = false;
+        result += c;
+    }
+    return result;
+}
+
+static std::string fnv1a_hex(const std::string& data) {
+    uint64_t hash = 14695981039346656037ULL;
+    for (char c : data) {
+        hash ^= static_cast<uint8_t>(c);
+        hash *= 1099511628211ULL;
+    }
+    std::ostringstream oss;
+    oss << std::hex << std::setw(16) << std::setfill('0') << hash;
+    return oss.str();
+}
+
+// Decode per the response charset (RFC 7231 Section 3.1.1.2) and lowercase, so
+// local-language block pages match local-language patterns
+static std::string body_to_text(const std::vector<uint8_t>& body, const std::string& content_type) {
+    return charset::to_lower_utf8(charset::to_utf8(body, charset::detect(content_type, body)));
+}
+
+// Fingerprint hashes cover ASCII only, so they do not depend on charset handling
+static std::string ascii_only(const std::string& text) {
+    std::string result;
+    result.reserve(text.size());
+    for (char c : text) {
+        if (static_cast<uint8_t>(c) < 0x80) {
+            result += c;
+        }
+    }
+    return result;
+}
+
+static bool deadline_passed(const std::chrono::steady_clock::time_point& deadline) {
+    return std::chrono::steady_clock::now() >= deadline;
+}
+
+// Minimal JSON navigation for pointer checks (RFC 7159 / RFC 6901); values are
+// located by skipping over siblings without building a document tree
+namespace {
+
+void skip_json_whitespace(const std::string& json, size_t& pos) {
+    while (pos < json.length() && (json[pos] == ' ' || json[pos] == '\t' || json[pos] == '\n' || json[pos] == '\r')) {
+        pos++;
+    }
+}
+
+bool read_json_string(const std::string& json, size_t& pos, std::string& result) {
+    if (pos >= json.length() || json[pos] != '"') return false;
+    pos++;
+    result.clear();
+    while (pos < json.length()) {
+        char c = json[pos++];
+        if (c == '"') return true;
+        if (c == '\\' && pos < json.length()) {
+            char e = json[pos++];
+            switch (e) {
+                case 'n': result += '\n'; break;
+                case 'r': result += '\r'; break;
+                case 't': result += '\t'; break;
+                case 'b': result += '\b'; break;
+                case 'f': result += '\f'; break;
+                case 'u': pos = std::min(pos + 4, json.length()); break; // Non-ASCII escapes are not compared
+                default: result += e; break;
+            }
+        } else {
+            result += c;
+        }
+    }
+    return false;
 }
 
-std::pair<bool, bool> SuccessValidator::validate_http(uint16_t status_code, const std::vector<uint8_t>& body) {
-    // Network success: response received
-    bool network_success = (status_code >= 200 && status_code < 400);
+bool skip_json_value(const std::string& json, size_t& pos, const std::chrono::steady_clock::time_point& deadline,
+                     int depth = 0) {
+    if (depth > 64 || deadline_passed(deadline)) return false; // Defensive: bound nesting and time
+    skip_json_whitespace(json, pos);
+    if (pos >= json.length()) return false;
     
-    if (!network_success) {
-        return std::make_pair(false, false);
+    std::string ignored;
+    char c = json[pos];
+    if (c == '"') {
+        return read_json_string(json, pos, ignored);
+    }
+    if (c == '{' || c == '[') {
+        char close = (c == '{') ? '}' : ']';
+        pos++;
+        skip_json_whitespace(json, pos);
+        if (pos < json.length() && json[pos] == close) {
+            pos++;
+            return true;
+        }
+        while (pos < json.length()) {
+            if (c == '{') {
+                skip_json_whitespace(json, pos);
+                if (!read_json_string(json, pos, ignored)) return false;
+                skip_json_whitespace(json, pos);
+                if (pos >= json.length() || json[pos] != ':') return false;
+                pos++;
+            }
+            if (!skip_json_value(json, pos, deadline, depth + 1)) return false;
+            skip_json_whitespace(json, pos);
+            if (pos >= json.length()) return false;
+            if (json[pos] == close) {
+                pos++;
+                return true;
+            }
+            if (json[pos] != ',') return false;
+            pos++;
+        }
+        return false;
+    }
+    
+    // Number, true, false, null
+    size_t start = pos;
+    while (pos < json.length() && json[pos] != ',' && json[pos] != '}' && json[pos] != ']' &&
+           json[pos] != ' ' && json[pos] != '\t' && json[pos] != '\n' && json[pos] != '\r') {
+        pos++;
+    }
+    return pos > start;
+}
+
+// Split "/a/b~1c" (RFC 6901) or "$.a.b[0]" into reference tokens
+std::vector<std::string> parse_json_pointer(const std::string& pointer) {
+    std::vector<std::string> tokens;
+    if (pointer.empty() || pointer == "$") {
+        return tokens;
+    }
+    
+    if (pointer[0] == '$') {
+        std::string token;
+        for (size_t i = 1; i < pointer.length(); ++i) {
+            char c = pointer[i];
+            if (c == '.' || c == '[' || c == ']') {
+                if (!token.empty()) tokens.push_back(token);
+                token.clear();
+            } else {
+                token += c;
+            }
+        }
+        if (!token.empty()) tokens.push_back(token);
+        return tokens;
     }
     
-    // User success: check for actual content vs error pages
-    bool user_success = false;
-    if (!body.empty()) {
-        // Convert to string (defensive: handle non-UTF8)
-        std::string content;
-        content.reserve(body.size());
-        for (uint8_t byte : body) {
-            if (byte >= 32 && byte < 127) { // Printable ASCII
-                content += static_cast<char>(byte);
-            } else if (byte == '\n' || byte == '\r' || byte == '\t') {
-                content += static_cast<char>(byte);
+    std::vector<std::string> parts = utils::split(pointer.substr(1), '/');
+    for (auto& part : parts) {
+        std::string token;
+        for (size_t i = 0; i < part.length(); ++i) {
+            if (part[i] == '~' && i + 1 < part.length()) {
+                token += (part[i + 1] == '1') ? '/' : '~';
+                i++;
+            } else {
+                token += part[i];
             }
         }
+        tokens.push_back(token);
+    }
+    return tokens;
+}
+
+// Resolve pointer tokens; value receives the string contents or raw JSON text
+bool lookup_json_value(const std::string& json, const std::vector<std::string>& tokens, std::string& value,
+                       const std::chrono::steady_clock::time_point& deadline) {
+    size_t pos = 0;
+    for (const auto& token : tokens) {
+        skip_json_whitespace(json, pos);
+        if (pos >= json.length()) return false;
         
-        content = utils::to_lower(content);
-        user_success = !contains_error_patterns(content);
+        if (json[pos] == '{') {
+            pos++;
+            bool found = false;
+            while (!found) {
+                skip_json_whitespace(json, pos);
+                std::string key;
+                if (!read_json_string(json, pos, key)) return false;
+                skip_json_whitespace(json, pos);
+                if (pos >= json.length() || json[pos] != ':') return false;
+                pos++;
+                if (key == token) {
+                    found = true;
+                    break;
+                }
+                if (!skip_json_value(json, pos, deadline)) return false;
+                skip_json_whitespace(json, pos);
+                if (pos >= json.length() || json[pos] != ',') return false;
+                pos++;
+            }
+        } else if (json[pos] == '[') {
+            uint64_t index;
+            if (!utils::safe_str_to_uint64(token, index)) return false;
+            pos++;
+            for (uint64_t i = 0; i < index; ++i) {
+                if (!skip_json_value(json, pos, deadline)) return false;
+                skip_json_whitespace(json, pos);
+                if (pos >= json.length() || json[pos] != ',') return false;
+                pos++;
+            }
+            skip_json_whitespace(json, pos);
+            if (pos < json.length() && json[pos] == ']') return false; // Empty array
+        } else {
+            return false; // Scalar has no children
+        }
+    }
+    
+    skip_json_whitespace(json, pos);
+    size_t start = pos;
+    if (pos < json.length() && json[pos] == '"') {
+        return read_json_string(json, pos, value);
+    }
+    if (!skip_json_value(json, pos, deadline)) return false;
+    value = json.substr(start, pos - start);
+    return true;
+}
+
+} // namespace
+
+// Approximate registrable domain: last two labels, or three for ccTLD second-level
+// registrations like example.co.uk / example.ac.ir; IP addresses are returned as-is
+static std::string registrable_domain(const std::string& host) {
+    if (utils::is_valid_ipv4(host)) {
+        return host;
+    }
+    
+    std::vector<std::string> labels = utils::split(host, '.');
+    size_t keep = 2;
+    if (labels.size() >= 3 && labels.back().length() == 2 && labels[labels.size() - 2].length() <= 3) {
+        keep = 3;
+    }
+    if (labels.size() <= keep) {
+        return host;
+    }
+    
+    std::string domain;
+    for (size_t i = labels.size() - keep; i < labels.size(); ++i) {
+        domain += (domain.empty() ? "" : ".") + labels[i];
+    }
+    return domain;
+}
+
+// Host of an absolute url when it is on a different site (registrable domain) than target; empty otherwise
+static std::string foreign_redirect_host(const std::string& target, const std::string& url) {
+    std::string lower_url = utils::to_lower(utils::trim(url));
+    
+    // Only absolute URLs leave the requested host: "scheme://host..." or "//host..."
+    size_t host_start = lower_url.find("//");
+    if (host_start == std::string::npos || lower_url.find_first_of("/?#") < host_start) {
+        return "";
     }
+    host_start += 2;
+    size_t host_end = lower_url.find_first_of(":/?#", host_start);
+    std::string host = lower_url.substr(host_start, host_end == std::string::npos ? std::string::npos : host_end - host_start);
     
+    // Same site: equal registrable domain (mail.example.com <-> accounts.example.com)
+    if (host.empty() || registrable_domain(host) == registrable_domain(utils::to_lower(target))) {
+        return "";
+    }
+    return host;
+}
+
+// True if url points to a different host than target and looks like a portal login
+static bool is_portal_redirect(const std::string& target, const std::string& url) {
+    std::string host = foreign_redirect_host(target, url);
+    if (host.empty()) {
+        return false;
+    }
+    
+    // Gateways commonly host the portal on a private address
+    if (utils::is_valid_ipv4(host) && utils::is_private_ip(host)) {
+        return true;
+    }
+    
+    static const std::vector<std::string> portal_keywords = {
+        "login", "signin", "sign-in", "portal", "hotspot", "captive", "wifi", "guest", "auth"
+    };
+    std::string lower_url = utils::to_lower(url);
+    size_t host_pos = lower_url.find(host);
+    for (const auto& keyword : portal_keywords) {
+        if (lower_url.find(keyword, host_pos) != std::string::npos) {
+            return true;
+        }
+    }
+    
+    return false;
+}
+
+// Exact host match wins, then the longest matching wildcard pattern; nullptr if none
+static const TargetValidationConfig* find_target_rule(const std::map<std::string, TargetValidationConfig>& rules,
+                                                      const std::string& target) {
+    auto exact = rules.find(utils::to_lower(target));
+    if (exact != rules.end()) {
+        return &exact->second;
+    }
+    
+    const TargetValidationConfig* best = nullptr;
+    size_t best_length = 0;
+    for (const auto& entry : rules) {
+        if (entry.first.length() > best_length && utils::match_host_pattern(target, entry.first)) {
+            best = &entry.second;
+            best_length = entry.first.length();
+        }
+    }
+    return best;
+}
+
+// True for HTML/text media types (RFC 7231 Section 3.1.1.1) and for a missing Content-Type
+static bool is_text_content(const std::map<std::string, std::string>& headers) {
+    auto it = headers.find("content-type");
+    if (it == headers.end()) {
+        return true; // Unknown: middlebox block pages often omit Content-Type
+    }
+    
+    // Media type without parameters: "text/html; charset=utf-8" -> "text/html"
+    std::string media_type = utils::to_lower(utils::trim(it->second.substr(0, it->second.find(';'))));
+    if (media_type.empty()) {
+        return true;
+    }
+    
+    return media_type.compare(0, 5, "text/") == 0 ||
+           media_type == "application/xhtml+xml";
+}
+
+// Statuses past StatusValidator that the other checks judge (the rest are its call alone)
+static bool reached_target(uint16_t status_code) {
+    return status_code >= 200 && status_code < 400;
+}
+
+// Whether the content checks judge this response: a 2xx, or a 3xx that RedirectValidator did not
+// settle (304 Not Modified has no content; redirects are judged by Location), with a text body,
+// unless the target's JSON rule judges it instead. Images, APIs and downloads cannot be block
+// pages; scanning them only risks false positives
+static bool scans_body(const ValidationContext& context, const TargetValidationConfig* rule) {
+    if (!reached_target(context.status_code) || context.status_code == 304) {
+        return false;
+    }
+    if (context.status_code >= 300 && context.headers.find("location") != context.headers.end()) {
+        return false;
+    }
+    if (rule && !rule->json_pointer.empty() && context.status_code < 300) {
+        return false;
+    }
+    return is_text_content(context.headers);
+}
+
+static std::string context_text(const ValidationContext& context) {
+    auto content_type = context.headers.find("content-type");
+    return body_to_text(context.body, content_type != context.headers.end() ? content_type->second : "");
+}
+
+// A user failure found after the deadline is (or may be) the timeout rather than a match
+static void log_if_timed_out(const ValidationContext& context) {
+    if (deadline_passed(context.deadline)) {
+        Logger::instance().log(LogLevel::WARN, "Validation timed out for " + context.target, {"", context.target});
+    }
+}
+
+// Target rules with their block patterns lowercased like the body text they are matched against
+static std::map<std::string, TargetValidationConfig> lowered_rules(const Config& config) {
+    std::map<std::string, TargetValidationConfig> rules = config.target_validation;
+    for (auto& entry : rules) {
+        for (auto& pattern : entry.second.block_patterns) {
+            pattern = charset::to_lower_utf8(pattern);
+        }
+    }
+    return rules;
+}
+
+ValidatorChain::ValidatorChain(uint64_t timeout_secs)
+    : timeout_(static_cast<std::chrono::seconds::rep>(timeout_secs)) {}
+
+void ValidatorChain::add(std::shared_ptr<Validator> validator) {
+    if (validator) {
+        validators_.push_back(validator);
+    }
+}
+
+std::pair<bool, bool> ValidatorChain::validate(const ValidationContext& context) {
+    if (validators_.empty()) {
+        bool network_success = reached_target(context.status_code);
+        return std::make_pair(network_success, network_success);
+    }
+    
+    ValidationContext bounded = context;
+    if (timeout_.count() > 0) {
+        bounded.deadline = std::min(context.deadline, std::chrono::steady_clock::now() + timeout_);
+    }
+    
+    bool network_success = true;
+    bool user_success = true;
+    for (const auto& validator : validators_) {
+        auto result = validator->validate(bounded);
+        network_success = network_success && result.first;
+        user_success = user_success && result.second;
+        if (!user_success) {
+            break;
+        }
+    }
     return std::make_pair(network_success, user_success);
 }
 
-bool SuccessValidator::contains_error_patterns(const std::string& content) const {
-    const std::vector<std::string> error_patterns = {
-        "blocked", "forbidden", "access denied", "error 403", "error 404"
+StatusValidator::StatusValidator(const Config& config)
+    : target_rules_(config.target_validation) {}
+
+std::pair<bool, bool> StatusValidator::validate(const ValidationContext& context) {
+    if (reached_target(context.status_code)) {
+        return std::make_pair(true, true);
+    }
+    
+    const TargetValidationConfig* rule = find_target_rule(target_rules_, context.target);
+    if (rule && std::find(rule->expected_statuses.begin(), rule->expected_statuses.end(), context.status_code) !=
+                rule->expected_statuses.end()) {
+        return std::make_pair(true, true);
+    }
+    return std::make_pair(false, false);
+}
+
+BlockHeaderValidator::BlockHeaderValidator(const Config& config) {
+    for (const auto& entry : config.block_headers) {
+        size_t colon = entry.find(':');
+        std::string name = utils::to_lower(utils::trim(entry.substr(0, colon)));
+        std::string value = colon == std::string::npos ? "" : utils::to_lower(utils::trim(entry.substr(colon + 1)));
+        if (!name.empty()) {
+            block_headers_.push_back(std::make_pair(name, value));
+        }
+    }
+}
+
+std::pair<bool, bool> BlockHeaderValidator::validate(const ValidationContext& context) {
+    if (!reached_target(context.status_code)) {
+        return std::make_pair(true, true);
+    }
+    
+    for (const auto& block_header : block_headers_) {
+        auto it = context.headers.find(block_header.first);
+        if (it == context.headers.end()) {
+            continue;
+        }
+        if (block_header.second.empty() || utils::to_lower(it->second).find(block_header.second) != std::string::npos) {
+            return std::make_pair(true, false);
+        }
+    }
+    return std::make_pair(true, true);
+}
+
+RedirectValidator::RedirectValidator(const Config& config)
+    : redirect_allow_(config.redirect_allow) {}
+
+std::pair<bool, bool> RedirectValidator::validate(const ValidationContext& context) {
+    if (context.status_code < 300 || context.status_code >= 400 || context.status_code == 304) {
+        return std::make_pair(true, true);
+    }
+    auto location = context.headers.find("location");
+    if (location == context.headers.end()) {
+        return std::make_pair(true, true);
+    }
+    
+    std::string host = foreign_redirect_host(context.target, location->second);
+    if (host.empty()) {
+        return std::make_pair(true, true); // Relative or same-site redirect
+    }
+    
+    // Block portals live on another site; only the sites the operator vouches for (e.g. a
+    // single sign-on host) pass, even with login-like URLs
+    for (const auto& pattern : redirect_allow_) {
+        if (utils::match_host_pattern(host, pattern)) {
+            return std::make_pair(true, true);
+        }
+    }
+    return std::make_pair(true, false);
+}
+
+JsonRuleValidator::JsonRuleValidator(const Config& config)
+    : target_rules_(config.target_validation) {}
+
+std::pair<bool, bool> JsonRuleValidator::validate(const ValidationContext& context) {
+    const TargetValidationConfig* rule = find_target_rule(target_rules_, context.target);
+    if (!rule || rule->json_pointer.empty() || context.status_code < 200 || context.status_code >= 300) {
+        return std::make_pair(true, true);
+    }
+    
+    std::string json(context.body.begin(), context.body.end());
+    std::string value;
+    bool json_ok = lookup_json_value(json, parse_json_pointer(rule->json_pointer), value, context.deadline) &&
+                   (rule->json_expected.empty() || value == rule->json_expected);
+    if (!json_ok) {
+        log_if_timed_out(context); // Otherwise not JSON, the pointer is absent, or the value differs
+    }
+    return std::make_pair(true, json_ok);
+}
+
+BlockPageValidator::BlockPageValidator(const Config& config)
+    : use_regex_(false)
+    , target_rules_(lowered_rules(config)) {
+    for (const auto& pattern : config.block_patterns) {
+        if (!pattern.empty()) {
+            block_patterns_.push_back(charset::to_lower_utf8(pattern));
+        }
+    }
+    
+    if (config.block_regex_enabled) {
+        for (const auto& pattern : config.block_regex_patterns) {
+            if (utils::has_nested_quantifier(pattern)) {
+                // Rejected by Config::validate(); skipped here too, as one search could run for hours
+                Logger::instance().log(LogLevel::WARN, "Ignoring block regex '" + pattern +
+                                       "': nested quantifiers can backtrack exponentially");
+                continue;
+            }
+            try {
+                block_regexes_.emplace_back(pattern, std::regex::ECMAScript | std::regex::icase | std::regex::optimize);
+            } catch (const std::regex_error& e) {
+                Logger::instance().log(LogLevel::WARN, "Ignoring invalid block regex '" + pattern + "': " + e.what());
+            }
+        }
+        use_regex_ = !block_regexes_.empty();
+    }
+}
+
+std::pair<bool, bool> BlockPageValidator::validate(const ValidationContext& context) {
+    const TargetValidationConfig* rule = find_target_rule(target_rules_, context.target);
+    if (!scans_body(context, rule)) {
+        return std::make_pair(true, true);
+    }
+    
+    // Stub block pages and truncated responses are tiny; 204/205 have no body by design (RFC 7231 Section 6.3)
+    uint16_t status_code = context.status_code;
+    if (rule && rule->min_body_bytes > 0 && status_code < 300 && status_code != 204 && status_code != 205 &&
+        context.body.size() < rule->min_body_bytes) {
+        return std::make_pair(true, false);
+    }
+    if (context.body.empty()) {
+        return std::make_pair(true, false);
+    }
+    
+    if (contains_error_patterns(context_text(context), rule, context.deadline)) {
+        log_if_timed_out(context);
+        return std::make_pair(true, false);
+    }
+    return std::make_pair(true, true);
+}
+
+bool BlockPageValidator::contains_error_patterns(const std::string& content, const TargetValidationConfig* rule,
+                                                 const std::chrono::steady_clock::time_point& deadline) const {
+    // Global regexes stand in for the global substrings; a target's own patterns take precedence
+    bool own_patterns = rule && !rule->block_patterns.empty();
+    if (use_regex_ && !own_patterns) {
+        size_t scan_length = std::min(content.size(), MAX_REGEX_SCAN_BYTES);
+        for (const auto& re : block_regexes_) {
+            for (size_t start = 0; start == 0 || start + REGEX_WINDOW_OVERLAP < scan_length;
+                 start += REGEX_WINDOW_BYTES - REGEX_WINDOW_OVERLAP) {
+                size_t end = std::min(start + REGEX_WINDOW_BYTES, scan_length);
+                if (deadline_passed(deadline) ||
+                    std::regex_search(content.begin() + start, content.begin() + end, re)) {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+    
+    for (const auto& pattern : own_patterns ? rule->block_patterns : block_patterns_) {
+        if (deadline_passed(deadline) || content.find(pattern) != std::string::npos) {
+            return true;
+        }
+    }
+    
+    return false;
+}
+
+CaptivePortalValidator::CaptivePortalValidator(const Config& config)
+    : target_rules_(config.target_validation) {}
+
+std::pair<bool, bool> CaptivePortalValidator::validate(const ValidationContext& context) {
+    if (!scans_body(context, find_target_rule(target_rules_, context.target)) || context.body.empty()) {
+        return std::make_pair(true, true);
+    }
+    if (is_captive_portal_page(context.target, context_text(context), context.deadline)) {
+        log_if_timed_out(context);
+        return std::make_pair(true, false);
+    }
+    return std::make_pair(true, true);
+}
+
+bool CaptivePortalValidator::is_captive_portal_page(const std::string& target, const std::string& content,
+                                                    const std::chrono::steady_clock::time_point& deadline) {
+    static const std::vector<std::string> portal_markers = {
+        "captive portal", "hotspot login", "wifi login", "wi-fi login",
+        "network login", "sign in to network", "accept the terms", "accept terms of use"
     };
     
-    for (const auto& pattern : error_patterns) {
-        if (content.find(pattern) != std::string::npos) {
+    for (const auto& marker : portal_markers) {
+        if (content.find(marker) != std::string::npos) {
             return true;
         }
     }
     
+    // Page that only bounces the client elsewhere: <meta http-equiv="refresh" content="0;url=...">
+    // or window.location / location.href / location.replace("...")
+    static const std::vector<std::string> redirect_markers = {
+        "http-equiv=\"refresh\"", "http-equiv='refresh'", "http-equiv=refresh",
+        "window.location", "location.href", "location.replace("
+    };
+    
+    for (const auto& marker : redirect_markers) {
+        size_t pos = content.find(marker);
+        while (pos != std::string::npos) {
+            if (deadline_passed(deadline)) {
+                return true;
+            }
+            size_t start = content.find("url=", pos);
+            if (marker.find("location") != std::string::npos) {
+                start = content.find_first_of("\"'", pos + marker.length());
+            } else if (start != std::string::npos) {
+                start += 3; // Points at '=' so the value begins at start + 1
+            }
+            
+            if (start != std::string::npos && start - pos < 256) {
+                size_t end = content.find_first_of("\"'> ;)", start + 1);
+                std::string url = content.substr(start + 1, end == std::string::npos ? std::string::npos : end - start - 1);
+                if (is_portal_redirect(target, url)) {
+                    return true;
+                }
+            }
+            pos = content.find(marker, pos + marker.length());
+        }
+    }
+    
     return false;
 }
+
+BodySizeValidator::BodySizeValidator(const Config& config)
+    : anomaly_ratio_(config.body_size_anomaly_ratio) {}
+
+std::pair<bool, bool> BodySizeValidator::validate(const ValidationContext& context) {
+    // A much smaller body than other runways delivered for the same resource suggests tampering
+    if (context.status_code == 200 && is_anomaly(context.target, context.path, context.body.size())) {
+        return std::make_pair(true, false);
+    }
+    return std::make_pair(true, true);
+}
+
+bool BodySizeValidator::is_anomaly(const std::string& target, const std::string& path, size_t body_size) {
+    if (anomaly_ratio_ <= 0.0) {
+        return false;
+    }
+    
+    std::string key = utils::to_lower(target) + path.substr(0, path.find('?'));
+    uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+    double size = static_cast<double>(body_size);
+    
+    std::lock_guard<std::mutex> lock(mutex_);
+    auto it = baselines_.find(key);
+    if (it == baselines_.end()) {
+        // Bounded: make room by dropping the least recently seen resource
+        if (baselines_.size() >= MAX_BODY_BASELINES) {
+            auto oldest = std::min_element(baselines_.begin(), baselines_.end(),
+                [](const std::pair<const std::string, BodySizeBaseline>& a,
+                   const std::pair<const std::string, BodySizeBaseline>& b) {
+                    return a.second.last_seen < b.second.last_seen;
+                });
+            baselines_.erase(oldest);
+        }
+        baselines_[key] = BodySizeBaseline{size, 1, now, 0, 0.0};
+        return false;
+    }
+    
+    BodySizeBaseline& baseline = it->second;
+    baseline.last_seen = now;
+    if (baseline.samples >= BODY_BASELINE_MIN_SAMPLES && size < baseline.avg_size * anomaly_ratio_) {
+        // Suspect responses never teach the baseline, but when nothing else arrives for long enough
+        // (every runway delivers the small body, e.g. the page was redesigned) it becomes the baseline
+        baseline.suspect_avg_size = baseline.suspect_streak == 0 ? size :
+            baseline.suspect_avg_size * (1.0 - BODY_BASELINE_ALPHA) + size * BODY_BASELINE_ALPHA;
+        if (++baseline.suspect_streak < BODY_BASELINE_RELEARN_STREAK) {
+            Logger::instance().log(LogLevel::DEBUG, "Body size anomaly for " + key + ": " + std::to_string(body_size) +
+                                   " bytes vs typical " + std::to_string(static_cast<uint64_t>(baseline.avg_size)));
+            return true;
+        }
+        Logger::instance().log(LogLevel::INFO, "Body size for " + key + " changed to about " +
+                               std::to_string(static_cast<uint64_t>(baseline.suspect_avg_size)) + " bytes, relearned");
+        baseline = BodySizeBaseline{baseline.suspect_avg_size, BODY_BASELINE_MIN_SAMPLES, now, 0, 0.0};
+        return false;
+    }
+    
+    baseline.suspect_streak = 0; // A normal-looking body: the small ones were the anomaly
+    baseline.avg_size = baseline.avg_size * (1.0 - BODY_BASELINE_ALPHA) + size * BODY_BASELINE_ALPHA;
+    baseline.samples++;
+    return false;
+}
+
+FingerprintValidator::FingerprintValidator(const Config& config)
+    : target_rules_(config.target_validation)
+    , fingerprint_file_(config.fingerprint_file)
+    , fingerprint_mtime_(-1)
+    , fingerprint_checked_at_(0) {}
+
+std::pair<bool, bool> FingerprintValidator::validate(const ValidationContext& context) {
+    if (!scans_body(context, find_target_rule(target_rules_, context.target)) || context.body.empty()) {
+        return std::make_pair(true, true);
+    }
+    
+    std::string label = match_fingerprint(context_text(context), context.deadline);
+    if (label.empty()) {
+        return std::make_pair(true, true);
+    }
+    Logger::instance().log(LogLevel::DEBUG, "Block page fingerprint matched for " + context.target + ": " + label,
+                           {"", context.target});
+    log_if_timed_out(context);
+    return std::make_pair(true, false);
+}
+
+std::string FingerprintValidator::body_fingerprint(const std::vector<uint8_t>& body) {
+    return fnv1a_hex(normalize_content(ascii_only(body_to_text(body, ""))));
+}
+
+void FingerprintValidator::refresh_fingerprints() {
+    uint64_t now = static_cast<uint64_t>(std::time(nullptr));
+    if (fingerprint_checked_at_ != 0 && now - fingerprint_checked_at_ < FINGERPRINT_CHECK_INTERVAL) {
+        return;
+    }
+    fingerprint_checked_at_ = now;
+    
+    struct stat info;
+    if (stat(fingerprint_file_.c_str(), &info) != 0) {
+        if (fingerprint_mtime_ != 0) {
+            Logger::instance().log(LogLevel::WARN, "Fingerprint file not found: " + fingerprint_file_);
+            fingerprint_mtime_ = 0; // Warn once; keep the last loaded set
+        }
+        return;
+    }
+    if (static_cast<int64_t>(info.st_mtime) == fingerprint_mtime_) {
+        return;
+    }
+    
+    std::ifstream file(fingerprint_file_);
+    if (!file.is_open()) {
+        return;
+    }
+    
+    // Line format: kind|value|label  ('#' starts a comment)
+    std::vector<BlockFingerprint> loaded;
+    std::string line;
+    while (std::getline(file, line)) {
+        line = utils::trim(line);
+        if (line.empty() || line[0] == '#') {
+            continue;
+        }
+        
+        std::vector<std::string> fields = utils::split(line, '|');
+        if (fields.size() < 2) {
+            continue;
+        }
+        
+        BlockFingerprint fp;
+        fp.kind = utils::to_lower(utils::trim(fields[0]));
+        fp.value = normalize_content(charset::to_lower_utf8(utils::trim(fields[1])));
+        fp.label = fields.size() >= 3 ? utils::trim(fields[2]) : fp.value;
+        if ((fp.kind == "hash" || fp.kind == "title" || fp.kind == "marker") && !fp.value.empty()) {
+            loaded.push_back(fp);
+        }
+    }
+    
+    fingerprints_ = loaded;
+    fingerprint_mtime_ = static_cast<int64_t>(info.st_mtime);
+    Logger::instance().log(LogLevel::INFO, "Loaded " + std::to_string(fingerprints_.size()) +
+                           " block page fingerprints from " + fingerprint_file_);
+}
+
+std::string FingerprintValidator::match_fingerprint(const std::string& content,
+                                                    const std::chrono::steady_clock::time_point& deadline) {
+    std::lock_guard<std::mutex> lock(fingerprint_mutex_);
+    refresh_fingerprints();
+    if (fingerprints_.empty()) {
+        return "";
+    }
+    
+    std::string normalized = normalize_content(content);
+    std::string hash = fnv1a_hex(normalize_content(ascii_only(content)));
+    
+    std::string title;
+    size_t title_start = normalized.find("<title");
+    if (title_start != std::string::npos) {
+        title_start = normalized.find('>', title_start);
+        size_t title_end = normalized.find("</title>", title_start);
+        if (title_start != std::string::npos && title_end != std::string::npos) {
+            title = utils::trim(normalized.substr(title_start + 1, title_end - title_start - 1));
+        }
+    }
+    
+    for (const auto& fp : fingerprints_) {
+        if (deadline_passed(deadline)) {
+            return "validation timeout";
+        }
+        if ((fp.kind == "hash" && fp.value == hash) ||
+            (fp.kind == "title" && !title.empty() && fp.value == title) ||
+            (fp.kind == "marker" && normalized.find(fp.value) != std::string::npos)) {
+            return fp.label;
+        }
+    }
+    return "";
+}
+
+
+HttpValidator::HttpValidator(const Config& config)
+    : ValidatorChain(config.user_validation_timeout) {
+    add(std::make_shared<StatusValidator>(config));
+    add(std::make_shared<BlockHeaderValidator>(config));
+    add(std::make_shared<RedirectValidator>(config));
+    add(std::make_shared<JsonRuleValidator>(config));
+    add(std::make_shared<BlockPageValidator>(config));
+    if (config.captive_portal_detection) {
+        add(std::make_shared<CaptivePortalValidator>(config));
+    }
+    if (!config.fingerprint_file.empty()) {
+        add(std::make_shared<FingerprintValidator>(config));
+    }
+    if (config.body_size_anomaly_ratio > 0.0) {
+        add(std::make_shared<BodySizeValidator>(config));
+    }
+}
+
+std::pair<bool, bool> HttpValidator::validate_for(const std::string& target, uint16_t status_code,
+                                                  const std::map<std::string, std::string>& headers,
+                                                  const std::vector<uint8_t>& body) {
+    return validate(ValidationContext{target, "/", status_code, headers, body});
+}
@@ -3,21 +3,209 @@
 
 #include <string>
 #include <vector>
+#include <map>
+#include <regex>
+#include <mutex>
+#include <memory>
+#include <chrono>
 #include <cstdint>
+#include "config.h"
 
 // Success validation for different protocols
 // Validates user-level success vs network-level success
 
-class SuccessValidator {
+// Known block page signature loaded from the fingerprint file
+struct BlockFingerprint {
+    std::string kind; // hash, title or marker
+    std::string value;
+    std::string label; // Free text, e.g. ISP/country
+};
+
+// Learned response size for one resource (target + path)
+struct BodySizeBaseline {
+    double avg_size; // Moving average of normal-looking body sizes
+    size_t samples;
+    uint64_t last_seen; // Unix timestamp, for eviction
+    size_t suspect_streak; // Flagged responses in a row, with no normal-looking one in between
+    double suspect_avg_size; // Moving average of the sizes in the current streak
+};
+
+// A response under validation
+struct ValidationContext {
+    std::string target; // Host name
+    std::string path; // Request target (RFC 7230 Section 5.3)
+    uint16_t status_code;
+    const std::map<std::string, std::string>& headers; // Lowercased names (RFC 7230 Section 3.2)
+    const std::vector<uint8_t>& body;
+    std::chrono::steady_clock::time_point deadline = std::chrono::steady_clock::time_point::max(); // Scanning budget
+};
+
+// Validator interface: decides network-level and user-level success of a response
+// Implementations must be thread-safe (called concurrently from connection threads)
+// Scanning validators report a user failure once context.deadline has passed
+class Validator {
 public:
-    SuccessValidator();
+    virtual ~Validator() = default;
     
-    // Validate HTTP/HTTPS response
     // Returns (network_success, user_success)
-    std::pair<bool, bool> validate_http(uint16_t status_code, const std::vector<uint8_t>& body);
+    virtual std::pair<bool, bool> validate(const ValidationContext& context) = 0;
+};
+
+// Runs validators in order; a response succeeds only if every validator agrees
+// Stops at the first user failure. Populate with add() before the proxy starts
+// An empty chain has no opinion of its own: the status code decides both results
+class ValidatorChain : public Validator {
+public:
+    // timeout_secs bounds one validate() across all validators (0 = no bound of its own)
+    explicit ValidatorChain(uint64_t timeout_secs = 0);
+    
+    void add(std::shared_ptr<Validator> validator);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+private:
+    std::chrono::seconds timeout_;
+    std::vector<std::shared_ptr<Validator>> validators_;
+};
+
+// Network-level success: 2xx/3xx reached the target, and so did a target's expected_statuses
+// (e.g. 401 from an auth-gated API), whose error bodies are by design and pass unscanned
+// Every other failed status is a network and user failure
+class StatusValidator : public Validator {
+public:
+    explicit StatusValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+private:
+    std::map<std::string, TargetValidationConfig> target_rules_; // Host pattern -> policy (patterns lowercased)
+};
+
+// block_headers: transparent proxies often leave the body untouched but add telltale headers
+class BlockHeaderValidator : public Validator {
+public:
+    explicit BlockHeaderValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+private:
+    std::vector<std::pair<std::string, std::string>> block_headers_; // Lowercased (name, value substring; empty = any)
+};
+
+// Redirects (RFC 7231 Section 6.4) are judged by their Location rather than their usually empty
+// body: leaving the requested site for a host outside redirect_allow is a user failure
+class RedirectValidator : public Validator {
+public:
+    explicit RedirectValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+private:
+    std::vector<std::string> redirect_allow_; // Host patterns redirects may leave the requested site for
+};
+
+// target_validation json_pointer/json_expected: API targets answering 2xx with an error envelope
+class JsonRuleValidator : public Validator {
+public:
+    explicit JsonRuleValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
     
 private:
-    bool contains_error_patterns(const std::string& content) const;
+    std::map<std::string, TargetValidationConfig> target_rules_;
+};
+
+// Block page content of text bodies: empty or below the target's min_body_bytes, or matching
+// block_patterns (the target's own when set) or, when enabled, block_regex_patterns
+class BlockPageValidator : public Validator {
+public:
+    // Regexes are compiled once here; invalid ones and nested quantifiers are logged and skipped
+    explicit BlockPageValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+private:
+    std::vector<std::string> block_patterns_; // Lowercased at construction
+    std::vector<std::regex> block_regexes_; // Compiled case-insensitive
+    bool use_regex_;
+    std::map<std::string, TargetValidationConfig> target_rules_; // Host pattern -> policy (patterns lowercased)
+    
+    // Reports a match once the deadline has passed (timeout = user failure)
+    bool contains_error_patterns(const std::string& content, const TargetValidationConfig* rule,
+                                 const std::chrono::steady_clock::time_point& deadline) const;
+};
+
+// captive_portal_detection: interception markers in the page, or a redirect (meta refresh /
+// script) to a login-like page on a foreign host
+class CaptivePortalValidator : public Validator {
+public:
+    explicit CaptivePortalValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+    static bool is_captive_portal_page(const std::string& target, const std::string& content,
+                                       const std::chrono::steady_clock::time_point& deadline);
+    
+private:
+    std::map<std::string, TargetValidationConfig> target_rules_;
+};
+
+// fingerprint_file: known block pages by hash, title or marker (hot-reloaded when the file's mtime changes)
+class FingerprintValidator : public Validator {
+public:
+    explicit FingerprintValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+    // FNV-1a 64-bit hash (hex) of a body after normalization: ASCII-only,
+    // lowercased, whitespace runs collapsed to one space, trimmed
+    static std::string body_fingerprint(const std::vector<uint8_t>& body);
+    
+private:
+    std::map<std::string, TargetValidationConfig> target_rules_;
+    std::string fingerprint_file_;
+    std::vector<BlockFingerprint> fingerprints_;
+    int64_t fingerprint_mtime_;
+    uint64_t fingerprint_checked_at_;
+    std::mutex fingerprint_mutex_;
+    
+    // Reload the fingerprint file if modified (checked at most every few seconds)
+    void refresh_fingerprints();
+    
+    // Returns the matching fingerprint's label, or empty if none match
+    std::string match_fingerprint(const std::string& content, const std::chrono::steady_clock::time_point& deadline);
+};
+
+// body_size_anomaly_ratio: cross-runway tamper signal for 200 responses
+class BodySizeValidator : public Validator {
+public:
+    explicit BodySizeValidator(const Config& config);
+    
+    std::pair<bool, bool> validate(const ValidationContext& context) override;
+    
+    // True if a body for target+path is dramatically smaller (below the anomaly ratio) than the
+    // size learned from earlier responses via any runway; normal-looking sizes update the baseline,
+    // and a long enough run of small ones (the resource itself shrank) replaces it
+    bool is_anomaly(const std::string& target, const std::string& path, size_t body_size);
+    
+private:
+    double anomaly_ratio_;
+    std::map<std::string, BodySizeBaseline> baselines_; // Bounded to the most recently seen resources
+    std::mutex mutex_;
+};
+
+// Default HTTP validator: the built-in checks above, in order, configured from the config
+// (captive portal, fingerprint and body-size checks only when enabled), bounded together by
+// user_validation_timeout
+class HttpValidator : public ValidatorChain {
+public:
+    explicit HttpValidator(const Config& config);
+    
+    // Validate a response to a request for "/" on target, e.g. outside the proxy
+    // Returns (network_success, user_success)
+    std::pair<bool, bool> validate_for(const std::string& target, uint16_t status_code,
+                                       const std::map<std::string, std::string>& headers,
+                                       const std::vector<uint8_t>& body);
 };
 
 #endif // VALIDATOR_H
@@ -0,0 +1,67 @@
+#include "version.h"
+#include "upstream.h"
+#include "tls.h"
+#include "tracing.h"
+#include "webui_json.h"
+#include "utils.h"
+
+#ifndef SMARTPROXY_VERSION
+#define SMARTPROXY_VERSION "unknown"
+#endif
+#ifndef SMARTPROXY_GIT_COMMIT
+#define SMARTPROXY_GIT_COMMIT "unknown"
+#endif
+#ifndef SMARTPROXY_BUILD_TIME
+#define SMARTPROXY_BUILD_TIME __DATE__ " " __TIME__
+#endif
+
+namespace version {
+
+static const char* ROUTING_MODES = "latency, first_accessible, round_robin";
+
+const char* number() {
+    return SMARTPROXY_VERSION;
+}
+
+const char* git_commit() {
+    return SMARTPROXY_GIT_COMMIT;
+}
+
+const char* build_time() {
+    return SMARTPROXY_BUILD_TIME;
+}
+
+std::string report(const std::string& running_version, bool json) {
+    if (json) {
+        using namespace webui_json;
+        auto list = [](const std::string& items) {
+            std::vector<std::string> values;
+            for (const auto& item : utils::split(items, ',')) {
+                values.push_back(encode_string(utils::trim(item)));
+            }
+            return build_array(values);
+        };
+        return build_object({{"version", encode_string(number())},
+                             {"git_commit", encode_string(git_commit())},
+                             {"build_time", encode_string(build_time())},
+                             {"tls", encode_bool(tls::available())},
+                             {"tracing", encode_bool(tracing::available())},
+                             {"proxy_types", list(upstream::supported_types())},
+                             {"routing_modes", list(ROUTING_MODES)},
+                             {"running_version", running_version.empty() ? encode_null() : encode_string(running_version)}}) + "\n";
+    }
+    
+    std::string text = std::string("smartproxy ") + number() + "\n";
+    text += std::string("Commit: ") + git_commit() + "\n";
+    text += std::string("Built: ") + build_time() + "\n";
+    text += std::string("TLS: ") + (tls::available() ? "yes" : "no (https upstream proxies unavailable)") + "\n";
+    text += std::string("Tracing: ") + (tracing::available() ? "yes" : "no (otlp_endpoint unavailable)") + "\n";
+    text += std::string("Proxy types: ") + upstream::supported_types() + "\n";
+    text += std::string("Routing modes: ") + ROUTING_MODES + "\n";
+    if (!running_version.empty()) {
+        text += "Running service: " + running_version + (running_version == number() ? "" : " (a different build)") + "\n";
+    }
+    return text;
+}
+
+} // namespace version
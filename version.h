@@ -0,0 +1,25 @@
+#ifndef VERSION_H
+#define VERSION_H
+
+#include <string>
+
+// Build identification, set by CMake from git at configure time (see CMakeLists.txt)
+
+namespace version {
+
+// `git describe` of the source tree, e.g. "v1.2.0" or "v1.2.0-3-gabc1234-dirty"; "unknown" without git
+const char* number();
+
+// Full commit hash; "unknown" without git
+const char* git_commit();
+
+// UTC time the build was configured, ISO 8601
+const char* build_time();
+
+// Version, commit and build time, then what this build supports (proxy types, routing modes, TLS)
+// running_version is the running service's version if it could be asked, otherwise empty
+std::string report(const std::string& running_version, bool json);
+
+} // namespace version
+
+#endif // VERSION_H
@@ -0,0 +1,200 @@
+#include "watch.h"
+#include "admin.h"
+#include "config.h"
+#include "utils.h"
+#include <iostream>
+#include <sstream>
+#include <ctime>
+#include <csignal>
+#include <thread>
+#include <chrono>
+#include <map>
+#include <vector>
+
+#ifdef _WIN32
+#include <windows.h>
+#include <conio.h>
+#else
+#include <unistd.h>
+#include <sys/ioctl.h>
+#include <termios.h>
+#endif
+
+namespace watch {
+
+static volatile sig_atomic_t g_stop = 0;
+
+static void stop_handler(int /*signal*/) {
+    g_stop = 1;
+}
+
+#ifndef _WIN32
+static struct termios saved_termios;
+#endif
+
+// Unbuffered, unechoed keys; alternate screen so the shell's scrollback is left alone
+static void setup_terminal() {
+#ifdef _WIN32
+    HANDLE hOut = GetStdHandle(STD_OUTPUT_HANDLE);
+    DWORD dwMode = 0;
+    GetConsoleMode(hOut, &dwMode);
+    SetConsoleMode(hOut, dwMode | ENABLE_VIRTUAL_TERMINAL_PROCESSING);
+#else
+    tcgetattr(STDIN_FILENO, &saved_termios);
+    struct termios term = saved_termios;
+    term.c_lflag &= ~(ICANON | ECHO);
+    term.c_cc[VMIN] = 0; // read() returns at once when no key is pending
+    term.c_cc[VTIME] = 0;
+    tcsetattr(STDIN_FILENO, TCSANOW, &term);
+#endif
+    std::cout << "\033[?1049h\033[?25l";
+    std::cout.flush();
+}
+
+static void restore_terminal() {
+    std::cout << "\033[?25h\033[?1049l";
+    std::cout.flush();
+#ifndef _WIN32
+    tcsetattr(STDIN_FILENO, TCSANOW, &saved_termios);
+#endif
+}
+
+// Pending key press, or 0 if none
+static int read_key() {
+#ifdef _WIN32
+    return _kbhit() ? _getch() : 0;
+#else
+    unsigned char c = 0;
+    return read(STDIN_FILENO, &c, 1) == 1 ? c : 0;
+#endif
+}
+
+static void terminal_size(int& rows, int& cols) {
+    rows = 24;
+    cols = 80;
+#ifdef _WIN32
+    CONSOLE_SCREEN_BUFFER_INFO csbi;
+    if (GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &csbi)) {
+        rows = csbi.srWindow.Bottom - csbi.srWindow.Top + 1;
+        cols = csbi.srWindow.Right - csbi.srWindow.Left + 1;
+    }
+#else
+    struct winsize w;
+    if (ioctl(STDOUT_FILENO, TIOCGWINSZ, &w) == 0 && w.ws_row > 0 && w.ws_col > 0) {
+        rows = w.ws_row;
+        cols = w.ws_col;
+    }
+#endif
+}
+
+// First line of a command's output, for the status line
+static std::string first_line(const std::string& output) {
+    return utils::trim(output.substr(0, output.find('\n')));
+}
+
+static std::string cycle_routing_mode(const std::string& host, uint16_t port, const std::string& token,
+                                      const tls::ClientOptions* tls_options) {
+    int exit_code = 1;
+    std::string output;
+    if (!admin::run_command(host, port, {"status", "--json"}, exit_code, output, 60, token, tls_options)) {
+        return "Cannot reach the running service";
+    }
+    
+    size_t pos = 0;
+    std::map<std::string, std::string> status;
+    std::string mode;
+    size_t mode_pos = 0;
+    if (!Config::parse_object(output, pos, status) ||
+        !Config::parse_string(utils::trim(status["routing_mode"]), mode_pos, mode)) {
+        return "Unexpected status reply";
+    }
+    
+    // Same order as the service TUI: latency -> first_accessible -> round_robin
+    std::string next = mode == "latency" ? "first_accessible" : mode == "first_accessible" ? "round_robin" : "latency";
+    if (!admin::run_command(host, port, {"mode", next}, exit_code, output, 60, token, tls_options)) {
+        return "Cannot reach the running service";
+    }
+    return first_line(output);
+}
+
+static void draw(const std::string& host, uint16_t port, uint64_t interval_secs,
+                 const std::string& body, const std::string& message) {
+    int rows = 0;
+    int cols = 0;
+    terminal_size(rows, cols);
+    
+    char clock[16];
+    std::time_t now = std::time(nullptr);
+    std::strftime(clock, sizeof(clock), "%H:%M:%S", std::localtime(&now));
+    
+    std::vector<std::string> lines;
+    lines.push_back("smartproxy watch - " + host + ":" + std::to_string(port) +
+                    " - every " + std::to_string(interval_secs) + "s - " + clock);
+    lines.push_back("");
+    for (const auto& line : utils::split(body, '\n')) {
+        lines.push_back(line);
+    }
+    
+    // Title and body at the top (cut to the screen), key help on the last row
+    std::ostringstream screen;
+    screen << "\033[H\033[2J";
+    for (int i = 0; i < static_cast<int>(lines.size()) && i < rows - 2; ++i) {
+        screen << lines[i].substr(0, static_cast<size_t>(cols)) << "\r\n";
+    }
+    std::string footer = (message.empty() ? "" : message + "  |  ") + "m: cycle mode  r: reset stats  q: quit";
+    screen << "\033[" << rows << ";1H" << footer.substr(0, static_cast<size_t>(cols));
+    std::cout << screen.str();
+    std::cout.flush();
+}
+
+int run(const std::string& host, uint16_t port, const std::string& token, const tls::ClientOptions* tls_options,
+        uint64_t interval_secs) {
+    if (!utils::is_terminal()) {
+        utils::safe_print("Error: watch needs a terminal\n");
+        return 1;
+    }
+    
+    signal(SIGINT, stop_handler);
+    signal(SIGTERM, stop_handler);
+    setup_terminal();
+    
+    std::string message;
+    while (!g_stop) {
+        int exit_code = 1;
+        std::string output;
+        std::string body;
+        if (admin::run_command(host, port, {"dashboard"}, exit_code, output, 60, token, tls_options)) {
+            body = output;
+        } else {
+            body = "Cannot reach the running service at " + host + ":" + std::to_string(port) + ", retrying...\n";
+        }
+        draw(host, port, interval_secs, body, message);
+        message.clear();
+        
+        // Wait out the interval, but act on a key at once
+        auto deadline = std::chrono::steady_clock::now() + std::chrono::seconds(interval_secs);
+        while (!g_stop && std::chrono::steady_clock::now() < deadline) {
+            int key = read_key();
+            if (key == 'q' || key == 'Q') {
+                g_stop = 1;
+            } else if (key == 'm' || key == 'M') {
+                message = cycle_routing_mode(host, port, token, tls_options);
+                break;
+            } else if (key == 'r' || key == 'R') {
+                if (admin::run_command(host, port, {"reset-stats"}, exit_code, output, 60, token, tls_options)) {
+                    message = first_line(output);
+                } else {
+                    message = "Cannot reach the running service";
+                }
+                break;
+            } else {
+                std::this_thread::sleep_for(std::chrono::milliseconds(100));
+            }
+        }
+    }
+    
+    restore_terminal();
+    return 0;
+}
+
+} // namespace watch
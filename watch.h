@@ -0,0 +1,21 @@
+#ifndef WATCH_H
+#define WATCH_H
+
+#include <string>
+#include <cstdint>
+#include "tls.h"
+
+// `smartproxy watch`: live dashboard of a running service, drawn from its `dashboard`
+// command through the admin endpoint (ANSI escape codes, zero dependencies)
+
+namespace watch {
+
+// Redraw every interval_secs until q or Ctrl+C; returns the process exit code
+// Keys: m cycles the routing mode, r resets statistics, q quits
+// token is the admin_token, if the service requires one; tls_options, if its admin endpoint serves TLS
+int run(const std::string& host, uint16_t port, const std::string& token, const tls::ClientOptions* tls_options,
+        uint64_t interval_secs);
+
+} // namespace watch
+
+#endif // WATCH_H
@@ -2,6 +2,7 @@
 #include "webui_json.h"
 #include "utils.h"
 #include "logger.h"
+#include "audit.h"
 #include "runway.h"
 #include <iostream>
 #include <sstream>
@@ -100,6 +101,7 @@ void WebUI::server_loop() {
         
         // Handle connection in same thread (simple implementation)
         // For production, could use thread pool
+        audit::Actor actor("webui", client_ip);
         handle_connection(client_sock);
         close_socket(client_sock);
     }
@@ -750,6 +752,7 @@ std::string WebUI::handle_api_action(const std::string& body) {
                 break;
         }
         routing_engine_->set_mode(next_mode);
+        audit::record("mode", "routing_mode", routing_mode_name(current_mode), routing_mode_name(next_mode));
     }
     
     std::vector<std::pair<std::string, std::string>> response_pairs;